@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::bridge::{Backup, BackupChunk, BackupContent, Bridge, IndexFile, ReadOptions};
+use crate::connector::{Connector, Engine};
+use crate::types::Bytes;
+
+/// bridge keeping its index file, its objects and the written parts in memory - the test double of the bridges
+#[derive(Default)]
+pub struct InMemoryBridge {
+    /// index file as JSON - no backups when empty
+    pub index_file: Arc<Mutex<Vec<u8>>>,
+    /// `check_write_access` fails
+    pub read_only: bool,
+    /// objects of the backup once decompressed - returned by `read`
+    pub objects: Vec<Bytes>,
+    /// objects of the backup as stored - returned by `read_objects` without `decompress`
+    pub stored_objects: Vec<Bytes>,
+    /// parts written by `write`
+    pub parts: Arc<Mutex<Vec<Bytes>>>,
+    pub aborted: Arc<Mutex<bool>>,
+    pub source_finished: Arc<AtomicBool>,
+    pub parts_written_before_source_finished: Arc<Mutex<usize>>,
+    pub content: Arc<Mutex<Option<BackupContent>>>,
+    pub upload_delay: Duration,
+    /// requests uploading a part - the progress is reported after each of them
+    pub upload_requests: usize,
+    /// holder of the lock shared by the bridges
+    pub run_lock: Arc<Mutex<Option<String>>>,
+    /// times the lock has been acquired or renewed
+    pub run_lock_acquisitions: Arc<Mutex<usize>>,
+    pub name: String,
+    /// chunks of the previous backup by unchanged table
+    pub unchanged_table_chunks: BTreeMap<String, Vec<BackupChunk>>,
+    pub shared_chunks: Arc<Mutex<Vec<(u16, Vec<BackupChunk>)>>>,
+}
+
+impl InMemoryBridge {
+    /// bridge holding `backups` in its index file
+    pub fn with_backups(backups: Vec<Backup>) -> Self {
+        InMemoryBridge {
+            index_file: Arc::new(Mutex::new(
+                serde_json::to_vec(&IndexFile { backups }).unwrap(),
+            )),
+            ..Default::default()
+        }
+    }
+
+    /// bridge holding a single backup made of `objects` - E.g. to restore it
+    pub fn with_objects(objects: Vec<Bytes>, engine: Option<Engine>) -> Self {
+        let backup = Backup {
+            directory_name: "backup-1".to_string(),
+            size: objects.iter().map(|object| object.len()).sum(),
+            created_at: 1,
+            compressed: false,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        InMemoryBridge {
+            objects,
+            ..InMemoryBridge::with_backups(vec![backup])
+        }
+    }
+}
+
+impl Connector for InMemoryBridge {
+    fn init(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Bridge for InMemoryBridge {
+    fn index_file(&self) -> Result<IndexFile, Error> {
+        let index_file = self.index_file.lock().unwrap();
+
+        match index_file.is_empty() {
+            true => Ok(IndexFile { backups: vec![] }),
+            false => Ok(serde_json::from_slice(index_file.as_slice())?),
+        }
+    }
+
+    fn write_index_file(&self, index_file: &IndexFile) -> Result<(), Error> {
+        *self.index_file.lock().unwrap() = serde_json::to_vec(index_file)?;
+        Ok(())
+    }
+
+    fn check_write_access(&self) -> Result<(), Error> {
+        match self.read_only {
+            true => Err(Error::new(ErrorKind::Other, "access denied")),
+            false => Ok(()),
+        }
+    }
+
+    fn write(&self, _: u16, data: Bytes) -> Result<(), Error> {
+        if !self.source_finished.load(Ordering::SeqCst) {
+            *self.parts_written_before_source_finished.lock().unwrap() += 1;
+        }
+
+        thread::sleep(self.upload_delay);
+        self.parts.lock().unwrap().push(data);
+        Ok(())
+    }
+
+    fn write_with_progress(
+        &self,
+        file_part: u16,
+        data: Bytes,
+        progress_callback: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), Error> {
+        let part_size = data.len();
+        let _ = self.write(file_part, data)?;
+
+        for request in 1..=self.upload_requests {
+            progress_callback(part_size * request / self.upload_requests, part_size);
+        }
+
+        Ok(())
+    }
+
+    fn abort_write(&self) -> Result<(), Error> {
+        self.parts.lock().unwrap().clear();
+        *self.aborted.lock().unwrap() = true;
+        Ok(())
+    }
+
+    fn delete_backup(&self, name: &str) -> Result<(), Error> {
+        let mut index_file = self.index_file()?;
+        let _ = index_file.check_backup_not_locked(name)?;
+        index_file
+            .backups
+            .retain(|backup| backup.directory_name != name);
+        self.write_index_file(&index_file)
+    }
+
+    fn acquire_run_lock(&self) -> Result<(), Error> {
+        let mut run_lock = self.run_lock.lock().unwrap();
+
+        match run_lock.as_ref() {
+            Some(holder) if *holder != self.name => Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "another backup is in progress - '{}' holds the lock",
+                    holder
+                ),
+            )),
+            _ => {
+                *run_lock = Some(self.name.clone());
+                *self.run_lock_acquisitions.lock().unwrap() += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn release_run_lock(&self) -> Result<(), Error> {
+        *self.run_lock.lock().unwrap() = None;
+        Ok(())
+    }
+
+    fn write_backup_content(&self, content: BackupContent) -> Result<(), Error> {
+        *self.content.lock().unwrap() = Some(content);
+        Ok(())
+    }
+
+    fn unchanged_table_chunks(
+        &self,
+        _: &BTreeMap<String, String>,
+        _: &[String],
+    ) -> Result<BTreeMap<String, Vec<BackupChunk>>, Error> {
+        Ok(self.unchanged_table_chunks.clone())
+    }
+
+    fn write_chunks(&self, first_part: u16, chunks: Vec<BackupChunk>) -> Result<(), Error> {
+        self.shared_chunks
+            .lock()
+            .unwrap()
+            .push((first_part, chunks));
+        Ok(())
+    }
+
+    fn read<F>(&self, options: &ReadOptions, data_callback: F) -> Result<(), Error>
+    where
+        F: FnMut(Bytes),
+    {
+        self.read_objects(options, true, true, data_callback)
+    }
+
+    fn read_objects<F>(
+        &self,
+        _: &ReadOptions,
+        _: bool,
+        decompress: bool,
+        mut data_callback: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Bytes),
+    {
+        let objects = match decompress {
+            true => &self.objects,
+            false => &self.stored_objects,
+        };
+
+        for object in objects {
+            data_callback(object.clone());
+        }
+
+        Ok(())
+    }
+
+    fn set_compression(&mut self, _: bool) {}
+
+    fn set_encryption_key(&mut self, _: Option<String>) {}
+}
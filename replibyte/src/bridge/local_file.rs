@@ -0,0 +1,212 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::bridge::{
+    decompress, decrypt_without_index, Backup, Bridge, EncryptionCipher, IndexFile, ReadOptions,
+};
+use crate::connector::Connector;
+use crate::types::Bytes;
+
+/// Read-only bridge reading a backup downloaded locally - a single part (E.g. `1.dump`)
+/// or a directory with all the parts of a backup.
+/// The compression and the encryption can't be read from the index file, they must be set -
+/// the backup is decrypted with the default cipher (AES-256-GCM).
+pub struct LocalFile {
+    path: PathBuf,
+    enable_compression: bool,
+    encryption_key: Option<String>,
+}
+
+impl LocalFile {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        LocalFile {
+            path: path.into(),
+            enable_compression: true,
+            encryption_key: None,
+        }
+    }
+
+    /// the backup parts ordered by part number - E.g. `1.dump`, `2.dump`... `10.dump`
+    fn parts(&self) -> Result<Vec<PathBuf>, Error> {
+        if !self.path.is_dir() {
+            return Ok(vec![self.path.clone()]);
+        }
+
+        let mut parts = fs::read_dir(self.path.as_path())?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .filter(|path| path.extension().map(|ext| ext == "dump").unwrap_or(false))
+            .collect::<Vec<_>>();
+
+        parts.sort_by_key(|path| part_number(path));
+
+        Ok(parts)
+    }
+}
+
+fn part_number(path: &Path) -> Option<u16> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.parse::<u16>().ok())
+}
+
+impl Connector for LocalFile {
+    fn init(&mut self) -> Result<(), Error> {
+        if !self.path.exists() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("backup file '{}' does not exist", self.path.display()),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Bridge for LocalFile {
+    fn index_file(&self) -> Result<IndexFile, Error> {
+        let mut size = 0usize;
+        for part in self.parts()? {
+            size += fs::metadata(part)?.len() as usize;
+        }
+
+        Ok(IndexFile {
+            backups: vec![Backup {
+                directory_name: self.path.display().to_string(),
+                size,
+                created_at: 0,
+                compressed: self.enable_compression,
+                encrypted: self.encryption_key.is_some(),
+                cipher: self
+                    .encryption_key
+                    .as_ref()
+                    .map(|_| EncryptionCipher::default()),
+                uncompressed_size: None,
+                engine: None,
+                storage_class: None,
+                compression_dictionary_id: None,
+                tables: None,
+                transformers: None,
+                chunks: None,
+                locked: false,
+                table_checksums: None,
+                table_parts: None,
+                key: None,
+                random_nonce: false,
+            }],
+        })
+    }
+
+    fn write_index_file(&self, _: &IndexFile) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn check_write_access(&self) -> Result<(), Error> {
+        // nothing is written - the backup file only needs to be readable
+        fs::metadata(self.path.as_path()).map(|_| ())
+    }
+
+    fn write(&self, _: u16, _: Bytes) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn abort_write(&self) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn read<F>(&self, _: &ReadOptions, mut data_callback: F) -> Result<(), Error>
+    where
+        F: FnMut(Bytes),
+    {
+        for part in self.parts()? {
+            let data = fs::read(part)?;
+
+            // decrypt data?
+            let data = match &self.encryption_key {
+                Some(key) => {
+                    decrypt_without_index(data, key.as_str(), EncryptionCipher::default())?
+                }
+                None => data,
+            };
+
+            // decompress data?
+            let data = if self.enable_compression {
+                decompress(data)?
+            } else {
+                data
+            };
+
+            data_callback(data);
+        }
+
+        Ok(())
+    }
+
+    fn set_compression(&mut self, enable: bool) {
+        self.enable_compression = enable;
+    }
+
+    fn set_encryption_key(&mut self, key: Option<String>) {
+        self.encryption_key = key;
+    }
+}
+
+fn read_only_error() -> Error {
+    Error::new(ErrorKind::Other, "a local backup file is read-only")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use fake::{Fake, Faker};
+
+    use crate::bridge::local_file::LocalFile;
+    use crate::bridge::{
+        compress, encrypt, Bridge, CompressionFormat, EncryptionCipher, ReadOptions,
+    };
+    use crate::connector::Connector;
+
+    fn backup_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("replibyte-backup-{}", Faker.fake::<u64>()));
+        fs::create_dir_all(dir.as_path()).unwrap();
+        dir
+    }
+
+    #[test]
+    fn read_parts_in_order() {
+        let dir = backup_dir();
+        let key = "this is my secret";
+
+        for (part, data) in [(10, "ten"), (2, "two"), (1, "one")] {
+            let data = compress(data.as_bytes().to_vec(), CompressionFormat::Gzip).unwrap();
+            let data = encrypt(data, key, EncryptionCipher::Aes256Gcm).unwrap();
+            fs::write(dir.join(format!("{}.dump", part)), data).unwrap();
+        }
+
+        let mut local_file = LocalFile::new(dir.as_path());
+        local_file.set_encryption_key(Some(key.to_string()));
+        assert!(local_file.init().is_ok());
+
+        let mut parts = vec![];
+        local_file
+            .read(&ReadOptions::Latest, |data| {
+                parts.push(String::from_utf8(data).unwrap())
+            })
+            .unwrap();
+
+        assert_eq!(parts, vec!["one", "two", "ten"]);
+        assert!(local_file.write(1, vec![]).is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn missing_file() {
+        let mut local_file = LocalFile::new("/does/not/exist.dump");
+        assert!(local_file.init().is_err());
+    }
+}
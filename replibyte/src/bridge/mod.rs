@@ -1,27 +1,154 @@
 use aes_gcm::aead::{Aead, NewAead};
 use aes_gcm::{Aes256Gcm, Key, Nonce};
+use chacha20poly1305::ChaCha20Poly1305;
+use std::collections::BTreeMap;
+use std::fs;
 use std::io::{Error, ErrorKind, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
-use flate2::read::ZlibDecoder;
-use flate2::write::ZlibEncoder;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
 use flate2::Compression;
 use serde::{Deserialize, Serialize};
 
-use crate::connector::Connector;
+use crate::connector::{Connector, Engine};
 use crate::types::Bytes;
 
+#[cfg(test)]
+pub mod in_memory;
+pub mod local_file;
 pub mod s3;
 
 pub trait Bridge: Connector + Send + Sync {
     /// Getting Index file with all the backups information
     fn index_file(&self) -> Result<IndexFile, Error>;
     fn write_index_file(&self, index_file: &IndexFile) -> Result<(), Error>;
+    /// Checking objects can be written and deleted
+    fn check_write_access(&self) -> Result<(), Error>;
     fn write(&self, file_part: u16, data: Bytes) -> Result<(), Error>;
+    /// Writing the part like `write` - `progress_callback` gets the uploaded bytes and the size of the part when the
+    /// bridge uploads it in several requests
+    fn write_with_progress(
+        &self,
+        file_part: u16,
+        data: Bytes,
+        _progress_callback: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), Error> {
+        self.write(file_part, data)
+    }
+    /// Deleting the objects written by `write` and removing the backup from the index file
+    fn abort_write(&self) -> Result<(), Error>;
+    /// Acquiring the lock held while a backup is written - fails when another backup is in progress
+    fn acquire_run_lock(&self) -> Result<(), Error> {
+        Ok(())
+    }
+    /// Extending the lock of the backup in progress by `RUN_LOCK_TTL` - the lock is acquired again by its holder
+    fn renew_run_lock(&self) -> Result<(), Error> {
+        self.acquire_run_lock()
+    }
+    /// Releasing the lock acquired by `acquire_run_lock`
+    fn release_run_lock(&self) -> Result<(), Error> {
+        Ok(())
+    }
+    /// Recording the content of the backup written by `write` into the index file - E.g. to compare two backups
+    fn write_backup_content(&self, _content: BackupContent) -> Result<(), Error> {
+        Ok(())
+    }
+    /// Finding the chunks of the last backup holding the rows of the tables with the same checksum - the bridges
+    /// storing the parts in the backup directory share nothing
+    fn unchanged_table_chunks(
+        &self,
+        _table_checksums: &BTreeMap<String, String>,
+        _transformers: &[String],
+    ) -> Result<BTreeMap<String, Vec<BackupChunk>>, Error> {
+        Ok(BTreeMap::new())
+    }
+    /// Adding the chunks of a previous backup to the backup being written - as parts `first_part`, `first_part + 1`...
+    fn write_chunks(&self, _first_part: u16, _chunks: Vec<BackupChunk>) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Other,
+            "sharing the parts of a previous backup is not supported by this bridge",
+        ))
+    }
     fn read<F>(&self, options: &ReadOptions, data_callback: F) -> Result<(), Error>
     where
         F: FnMut(Bytes);
+    /// Reading the objects of the backup in order - as stored (E.g. encrypted) unless `decrypt` and `decompress` are set
+    fn read_objects<F>(
+        &self,
+        _options: &ReadOptions,
+        _decrypt: bool,
+        _decompress: bool,
+        _data_callback: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Bytes),
+    {
+        Err(Error::new(
+            ErrorKind::Other,
+            "reading the backup objects as stored is not supported by this bridge",
+        ))
+    }
+    /// Reading the first `length` bytes of the first object of the backup as stored (E.g. encrypted) - without downloading it
+    fn read_object_header(&self, _options: &ReadOptions, _length: usize) -> Result<Bytes, Error> {
+        Err(Error::new(
+            ErrorKind::Other,
+            "reading the header of the backup objects is not supported by this bridge",
+        ))
+    }
     fn set_compression(&mut self, enable: bool);
     fn set_encryption_key(&mut self, key: Option<String>);
+    /// Renaming the backup `name` into `new_name` - its objects are moved and its index file entry is rewritten
+    fn rename_backup(&self, _name: &str, _new_name: &str) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Other,
+            "renaming a backup is not supported by this bridge",
+        ))
+    }
+    /// Deleting the objects of the backup `name` and removing it from the index file - the chunks shared with the
+    /// other backups are kept
+    fn delete_backup(&self, _name: &str) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Other,
+            "deleting a backup is not supported by this bridge",
+        ))
+    }
+    /// Locking or unlocking the backup `name` - a locked backup can't be renamed nor deleted
+    fn set_backup_locked(&self, name: &str, locked: bool) -> Result<(), Error> {
+        let mut index_file = self.index_file()?;
+        let _ = index_file.set_backup_locked(name, locked)?;
+        self.write_index_file(&index_file)
+    }
+    /// Writing the index file as JSON into `writer` - E.g. to move the backups to another bridge
+    fn export_index_file<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let index_file = self.index_file()?;
+        serde_json::to_writer_pretty(writer, &index_file).map_err(Error::from)
+    }
+    /// Adding the backups of the JSON index file read from `reader` - the backups already in the index file are kept.
+    /// Only the metadata is imported, the backup objects must be copied separately. Returns the number of added backups
+    fn import_index_file<R: Read>(&self, reader: R) -> Result<usize, Error> {
+        let imported_index_file: IndexFile = serde_json::from_reader(reader)?;
+        let mut index_file = self.index_file()?;
+        let backups_count = index_file.backups.len();
+
+        for backup in imported_index_file.backups {
+            if !index_file
+                .backups
+                .iter()
+                .any(|b| b.directory_name == backup.directory_name)
+            {
+                index_file.backups.push(backup);
+            }
+        }
+
+        let added_backups_count = index_file.backups.len() - backups_count;
+        let _ = self.write_index_file(&index_file)?;
+
+        Ok(added_backups_count)
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -30,6 +157,23 @@ pub struct IndexFile {
 }
 
 impl IndexFile {
+    /// the index file is stored as zstd compressed JSON - E.g. `zstd -d < metadata.json.zst` to read it
+    pub fn to_bytes(&self) -> Result<Bytes, Error> {
+        let json = serde_json::to_vec(self)?;
+        zstd::stream::encode_all(json.as_slice(), 0)
+    }
+
+    /// the index files written by the previous versions are plain JSON
+    pub fn from_bytes(data: Bytes) -> Result<Self, Error> {
+        let json = if data.starts_with(&ZSTD_MAGIC_BYTES) {
+            zstd::stream::decode_all(data.as_slice())?
+        } else {
+            data
+        };
+
+        serde_json::from_slice(json.as_slice()).map_err(Error::from)
+    }
+
     pub fn find_backup(&mut self, options: &ReadOptions) -> Result<&Backup, Error> {
         match options {
             ReadOptions::Latest => {
@@ -57,6 +201,295 @@ impl IndexFile {
             }
         }
     }
+
+    /// aggregate the size and the age of all the backups
+    pub fn stats(&self) -> BackupStats {
+        let total_size = self.backups.iter().map(|backup| backup.size).sum::<usize>();
+
+        BackupStats {
+            count: self.backups.len(),
+            total_size,
+            average_size: total_size.checked_div(self.backups.len()).unwrap_or(0),
+            oldest_created_at: self.backups.iter().map(|backup| backup.created_at).min(),
+            newest_created_at: self.backups.iter().map(|backup| backup.created_at).max(),
+        }
+    }
+
+    /// the backups from the newest to the oldest, skipping `offset` backups and keeping at most `limit` -
+    /// only the requested backups are sorted
+    pub fn page(mut self, offset: usize, limit: Option<usize>) -> Vec<Backup> {
+        let newest_first = |a: &Backup, b: &Backup| a.cmp(b).reverse();
+
+        let end = match limit {
+            Some(limit) => offset.saturating_add(limit).min(self.backups.len()),
+            None => self.backups.len(),
+        };
+
+        if offset >= end {
+            return vec![];
+        }
+
+        if end < self.backups.len() {
+            // partition around the last requested backup instead of sorting the whole index
+            let _ = self.backups.select_nth_unstable_by(end, newest_first);
+            self.backups.truncate(end);
+        }
+
+        self.backups.sort_by(newest_first);
+        self.backups.split_off(offset)
+    }
+
+    /// add a written part to its backup - the backup is added with its first part
+    pub fn add_part(&mut self, part: Backup) {
+        match self
+            .backups
+            .iter_mut()
+            .find(|backup| backup.directory_name == part.directory_name)
+        {
+            Some(backup) => {
+                backup.size += part.size;
+                backup.uncompressed_size = backup
+                    .uncompressed_size
+                    .zip(part.uncompressed_size)
+                    .map(|(a, b)| a + b);
+
+                if let Some(chunks) = part.chunks {
+                    backup.chunks.get_or_insert_with(Vec::new).extend(chunks);
+                }
+            }
+            None => self.backups.push(part),
+        }
+    }
+
+    /// the chunk stored with this hash - by any backup
+    pub fn chunk(&self, hash: &str) -> Option<&BackupChunk> {
+        self.backups
+            .iter()
+            .flat_map(|backup| backup.chunks.iter().flatten())
+            .find(|chunk| chunk.hash == hash)
+    }
+
+    /// the chunks of the backup no other backup shares - E.g. to delete them with the backup.
+    /// The chunks of a locked backup are never returned
+    pub fn unshared_chunks(&self, directory_name: &str) -> Vec<&BackupChunk> {
+        if self.check_backup_not_locked(directory_name).is_err() {
+            return vec![];
+        }
+
+        let (backups, other_backups): (Vec<_>, Vec<_>) = self
+            .backups
+            .iter()
+            .partition(|backup| backup.directory_name == directory_name);
+
+        let mut chunks = backups
+            .into_iter()
+            .flat_map(|backup| backup.chunks.iter().flatten())
+            .filter(|chunk| {
+                !other_backups
+                    .iter()
+                    .flat_map(|backup| backup.chunks.iter().flatten())
+                    .any(|other_chunk| other_chunk.hash == chunk.hash)
+            })
+            .collect::<Vec<_>>();
+
+        // a chunk appears once per identical part
+        chunks.sort_by(|a, b| a.hash.cmp(&b.hash));
+        chunks.dedup_by(|a, b| a.hash == b.hash);
+
+        chunks
+    }
+
+    /// the chunks of the tables whose checksum is the same as in the last backup stored like `like` (compression,
+    /// encryption and transformers) - E.g. `public.users` stored in the chunks of parts 3 and 4
+    pub fn unchanged_table_chunks(
+        &self,
+        like: &Backup,
+        table_checksums: &BTreeMap<String, String>,
+        transformers: &[String],
+    ) -> BTreeMap<String, Vec<BackupChunk>> {
+        let last_backup = self
+            .backups
+            .iter()
+            .filter(|backup| {
+                backup.directory_name != like.directory_name
+                    && backup.compressed == like.compressed
+                    && backup.encrypted == like.encrypted
+                    && backup.cipher == like.cipher
+                    && backup.random_nonce == like.random_nonce
+                    && backup.compression_dictionary_id == like.compression_dictionary_id
+                    && backup.transformers.as_deref() == Some(transformers)
+            })
+            .filter(|backup| backup.table_checksums.is_some() && backup.table_parts.is_some())
+            .max_by_key(|backup| backup.created_at);
+
+        let backup = match last_backup {
+            Some(backup) => backup,
+            None => return BTreeMap::new(),
+        };
+
+        let chunks = backup.chunks.as_deref().unwrap_or_default();
+        let last_checksums = backup.table_checksums.as_ref().unwrap();
+        let last_parts = backup.table_parts.as_ref().unwrap();
+
+        table_checksums
+            .iter()
+            .filter(|(table_name, checksum)| last_checksums.get(*table_name) == Some(checksum))
+            .filter_map(|(table_name, _)| {
+                let parts = last_parts
+                    .get(table_name)
+                    .filter(|parts| !parts.is_empty())?;
+
+                // every part of the table must be a chunk
+                let table_chunks = parts
+                    .iter()
+                    .map(|part| chunks.iter().find(|chunk| chunk.part == *part).cloned())
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some((table_name.clone(), table_chunks))
+            })
+            .collect()
+    }
+
+    /// fails when the backup `name` is locked - E.g. before renaming or deleting it
+    pub fn check_backup_not_locked(&self, name: &str) -> Result<(), Error> {
+        match self
+            .backups
+            .iter()
+            .any(|backup| backup.directory_name == name && backup.locked)
+        {
+            true => Err(Error::new(
+                ErrorKind::Other,
+                format!("backup '{}' is locked - unlock it first", name),
+            )),
+            false => Ok(()),
+        }
+    }
+
+    /// the backups older than the `keep_last` newest ones, from the oldest - the locked backups are kept and not counted
+    pub fn prunable_backups(&self, keep_last: usize) -> Vec<String> {
+        let mut backups = self
+            .backups
+            .iter()
+            .filter(|backup| !backup.locked)
+            .collect::<Vec<_>>();
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        backups
+            .into_iter()
+            .skip(keep_last)
+            .rev()
+            .map(|backup| backup.directory_name.clone())
+            .collect()
+    }
+
+    /// lock or unlock the backup `name`
+    pub fn set_backup_locked(&mut self, name: &str, locked: bool) -> Result<&Backup, Error> {
+        match self
+            .backups
+            .iter_mut()
+            .find(|backup| backup.directory_name == name)
+        {
+            Some(backup) => {
+                backup.locked = locked;
+                Ok(backup)
+            }
+            None => Err(Error::new(
+                ErrorKind::Other,
+                format!("backup '{}' not found", name),
+            )),
+        }
+    }
+
+    /// rename the backup `name` into `new_name` - fails when `new_name` is already taken or is not a valid directory name
+    pub fn rename_backup(&mut self, name: &str, new_name: &str) -> Result<&Backup, Error> {
+        let _ = self.check_backup_not_locked(name)?;
+
+        if new_name.is_empty() || new_name.contains('/') {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "'{}' is not a valid backup name - it must not be empty nor contain '/'",
+                    new_name
+                ),
+            ));
+        }
+
+        if self
+            .backups
+            .iter()
+            .any(|backup| backup.directory_name == new_name)
+        {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("backup '{}' already exists", new_name),
+            ));
+        }
+
+        match self
+            .backups
+            .iter_mut()
+            .find(|backup| backup.directory_name == name)
+        {
+            Some(backup) => {
+                // the templated key ends with the backup name - E.g. `2024/01/15/backup-1705276800000`
+                if let Some(key) = &mut backup.key {
+                    if let Some(key_prefix) = key.strip_suffix(name) {
+                        *key = format!("{}{}", key_prefix, new_name);
+                    }
+                }
+
+                backup.directory_name = new_name.to_string();
+                Ok(backup)
+            }
+            None => Err(Error::new(
+                ErrorKind::Other,
+                format!("backup '{}' not found", name),
+            )),
+        }
+    }
+}
+
+/// sizes of the parts written by a bridge - before (`uncompressed_size`) and after compression and encryption (`size`)
+#[derive(Default)]
+pub struct WrittenSizes {
+    uncompressed_size: AtomicUsize,
+    size: AtomicUsize,
+    deduplicated_size: AtomicUsize,
+}
+
+impl WrittenSizes {
+    pub fn add(&self, uncompressed_size: usize, size: usize) {
+        self.uncompressed_size
+            .fetch_add(uncompressed_size, Ordering::Relaxed);
+        self.size.fetch_add(size, Ordering::Relaxed);
+    }
+
+    /// a part already stored - it is not uploaded again
+    pub fn add_deduplicated(&self, uncompressed_size: usize) {
+        self.deduplicated_size
+            .fetch_add(uncompressed_size, Ordering::Relaxed);
+    }
+
+    pub fn deduplicated_size(&self) -> usize {
+        self.deduplicated_size.load(Ordering::Relaxed)
+    }
+
+    pub fn uncompressed_size(&self) -> usize {
+        self.uncompressed_size.load(Ordering::Relaxed)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BackupStats {
+    pub count: usize,
+    pub total_size: usize,
+    pub average_size: usize,
+    pub oldest_created_at: Option<u128>,
+    pub newest_created_at: Option<u128>,
 }
 
 #[derive(Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq)]
@@ -66,6 +499,160 @@ pub struct Backup {
     pub created_at: u128,
     pub compressed: bool,
     pub encrypted: bool,
+    /// cipher used to encrypt the backup - missing for the backups encrypted before it was configurable (AES-256-GCM)
+    #[serde(default)]
+    pub cipher: Option<EncryptionCipher>,
+    /// size of the dump before compression and encryption - missing for the backups written before it was recorded
+    #[serde(default)]
+    pub uncompressed_size: Option<usize>,
+    /// engine of the source database - missing for the backups written before it was recorded
+    #[serde(default)]
+    pub engine: Option<Engine>,
+    /// S3 storage class of the backup objects (E.g. `STANDARD_IA`) - missing when not configured
+    #[serde(default)]
+    pub storage_class: Option<String>,
+    /// id of the zstd dictionary the backup objects are compressed with - missing when compressed without dictionary
+    #[serde(default)]
+    pub compression_dictionary_id: Option<u32>,
+    /// tables of the dump (E.g. `public.users`) - missing for the backups written before it was recorded and for MongoDB
+    #[serde(default)]
+    pub tables: Option<Vec<String>>,
+    /// transformed columns and their transformer (E.g. `public.users.email: email`) - missing for the backups
+    /// written before it was recorded
+    #[serde(default)]
+    pub transformers: Option<Vec<String>>,
+    /// parts of the backup stored once for all the backups - missing when the parts are stored in the backup directory
+    #[serde(default)]
+    pub chunks: Option<Vec<BackupChunk>>,
+    /// a locked backup can't be deleted - E.g. kept for compliance
+    #[serde(default)]
+    pub locked: bool,
+    /// checksum of the rows of each table (E.g. `public.users`) - only recorded by `backup run --only-changed`
+    #[serde(default)]
+    pub table_checksums: Option<BTreeMap<String, String>>,
+    /// parts holding the rows of each table - recorded with `table_checksums`, the next backups share the parts of
+    /// the unchanged tables
+    #[serde(default)]
+    pub table_parts: Option<BTreeMap<String, Vec<u16>>>,
+    /// key of the directory of the backup objects built from the bridge `key_template` (E.g. `2024/01/15/backup-1705276800000`)
+    /// - missing when it is the directory name
+    #[serde(default)]
+    pub key: Option<String>,
+    /// the AES-256-GCM objects start with their random nonce - false for the backups encrypted with the static nonce
+    /// before the nonce was random
+    #[serde(default)]
+    pub random_nonce: bool,
+}
+
+impl Backup {
+    /// key of the directory of the backup objects - relative to the bridge prefix
+    pub fn directory_key(&self) -> &str {
+        self.key.as_deref().unwrap_or(self.directory_name.as_str())
+    }
+}
+
+/// part of a deduplicated backup - the backups with the same part share its object
+#[derive(Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq, Clone, Debug)]
+pub struct BackupChunk {
+    pub part: u16,
+    /// SHA-256 of the part before compression and encryption, and of the way it is compressed and encrypted
+    pub hash: String,
+    /// size of the stored object
+    pub size: usize,
+    /// size of the part before compression and encryption - missing for the chunks written before it was recorded
+    #[serde(default)]
+    pub uncompressed_size: Option<usize>,
+}
+
+/// a backup holding the lock for longer without renewing it is considered crashed - see `Bridge::renew_run_lock`
+pub const RUN_LOCK_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// advisory lock stored in the bridge while a backup is written - the lock of a crashed backup expires by itself
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunLock {
+    /// E.g. the name of the backup being written
+    pub holder: String,
+    pub acquired_at: u128,
+    pub expires_at: u128,
+}
+
+impl RunLock {
+    /// fails when the lock is held by another holder and not expired
+    pub fn check(&self, holder: &str, now: u128) -> Result<(), Error> {
+        if self.holder == holder || self.expires_at <= now {
+            return Ok(());
+        }
+
+        Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "another backup is in progress - '{}' holds the bridge lock for {} more minutes",
+                self.holder,
+                (self.expires_at - now) / 60_000 + 1
+            ),
+        ))
+    }
+}
+
+/// what a backup contains - recorded once all its parts are written
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BackupContent {
+    /// None when the tables can't be read from the dump (E.g. MongoDB)
+    pub tables: Option<Vec<String>>,
+    pub transformers: Vec<String>,
+    /// None when the backup does not share the parts of its unchanged tables
+    pub table_checksums: Option<BTreeMap<String, String>>,
+    pub table_parts: Option<BTreeMap<String, Vec<u16>>>,
+}
+
+/// differences between two backups - from `a` to `b`
+#[derive(Debug, PartialEq)]
+pub struct BackupComparison {
+    pub size_delta: i128,
+    /// milliseconds between the creation of both backups
+    pub created_at_delta: i128,
+    pub added_tables: Vec<String>,
+    pub removed_tables: Vec<String>,
+    pub added_transformers: Vec<String>,
+    pub removed_transformers: Vec<String>,
+}
+
+impl BackupComparison {
+    /// the tables and the transformers not recorded in one of the backups are not compared
+    pub fn new(a: &Backup, b: &Backup) -> Self {
+        let (added_tables, removed_tables) = diff(a.tables.as_deref(), b.tables.as_deref());
+        let (added_transformers, removed_transformers) =
+            diff(a.transformers.as_deref(), b.transformers.as_deref());
+
+        BackupComparison {
+            size_delta: b.size as i128 - a.size as i128,
+            created_at_delta: b.created_at as i128 - a.created_at as i128,
+            added_tables,
+            removed_tables,
+            added_transformers,
+            removed_transformers,
+        }
+    }
+}
+
+/// the values only in `b` and the values only in `a`
+fn diff(a: Option<&[String]>, b: Option<&[String]>) -> (Vec<String>, Vec<String>) {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return (vec![], vec![]),
+    };
+
+    let only_in = |values: &[String], others: &[String]| {
+        let mut only_in = values
+            .iter()
+            .filter(|value| !others.contains(value))
+            .cloned()
+            .collect::<Vec<_>>();
+        only_in.sort();
+        only_in
+    };
+
+    (only_in(b, a), only_in(a, b))
 }
 
 #[derive(Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq, Clone)]
@@ -74,16 +661,270 @@ pub enum ReadOptions {
     Backup { name: String },
 }
 
-fn compress(data: Bytes) -> Result<Bytes, Error> {
-    let mut enc = ZlibEncoder::new(Vec::new(), Compression::default());
-    let _ = enc.write_all(data.as_slice());
-    enc.flush_finish()
+/// Compression format of the backup objects
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionFormat {
+    /// zlib stream (default) - E.g. `zlib-flate -uncompress < 1.dump`
+    #[default]
+    Zlib,
+    /// gzip stream - the objects can be opened with `gunzip` when they are not encrypted
+    Gzip,
+    /// zstd stream - better ratio and faster than zlib, required to use a compression dictionary
+    Zstd,
+}
+
+/// zstd dictionary improving the compression ratio of small similar backups -
+/// trained with `backup train-compression-dict` or `zstd --train`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionDictionary {
+    /// id written in the dictionary header - recorded in the index file to restore the backup
+    pub id: u32,
+    pub data: Bytes,
+}
+
+impl CompressionDictionary {
+    /// fail when `data` is not a zstd dictionary - the raw content dictionaries have no id
+    pub fn try_from_bytes(data: Bytes) -> Result<Self, Error> {
+        let id = match data.get(..8) {
+            Some(header) if header[..4] == ZSTD_DICTIONARY_MAGIC_BYTES => {
+                u32::from_le_bytes([header[4], header[5], header[6], header[7]])
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    "not a zstd dictionary - train it with `backup train-compression-dict` or `zstd --train`",
+                ))
+            }
+        };
+
+        Ok(CompressionDictionary { id, data })
+    }
+
+    /// train a dictionary of at most `max_size` bytes from `samples` - E.g. the dumps of previous backups
+    pub fn train(samples: &[Bytes], max_size: usize) -> Result<Self, Error> {
+        let data = zstd::dict::from_samples(samples, max_size)?;
+        Self::try_from_bytes(data)
+    }
+}
+
+/// Cipher used to encrypt the backup objects
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub enum EncryptionCipher {
+    /// AES-256 in Galois/Counter Mode (default)
+    #[default]
+    #[serde(rename = "aes-256-gcm")]
+    Aes256Gcm,
+    /// ChaCha20-Poly1305 - faster than AES on CPUs without AES instructions
+    #[serde(rename = "chacha20-poly1305")]
+    ChaCha20Poly1305,
 }
 
+impl EncryptionCipher {
+    pub fn name(&self) -> &str {
+        match self {
+            EncryptionCipher::Aes256Gcm => "aes-256-gcm",
+            EncryptionCipher::ChaCha20Poly1305 => "chacha20-poly1305",
+        }
+    }
+}
+
+impl FromStr for EncryptionCipher {
+    type Err = String;
+
+    /// parse the cipher name - E.g. `aes-256-gcm`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [
+            EncryptionCipher::Aes256Gcm,
+            EncryptionCipher::ChaCha20Poly1305,
+        ]
+        .into_iter()
+        .find(|cipher| cipher.name() == value)
+        .ok_or_else(|| {
+            format!(
+                "unknown cipher '{}' - expected aes-256-gcm or chacha20-poly1305",
+                value
+            )
+        })
+    }
+}
+
+/// bytes of the backup objects read to check they are encrypted - enough to recognize a compression header or text
+pub const OBJECT_HEADER_LENGTH: usize = 64;
+
+/// what the first bytes of a backup object look like
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectHeader {
+    Gzip,
+    Zstd,
+    Zlib,
+    /// uncompressed dump - E.g. SQL queries
+    Text,
+    /// none of the above - the ciphertext of the encrypted objects looks random
+    Opaque,
+}
+
+impl ObjectHeader {
+    pub fn of(header: &[u8]) -> Self {
+        if header.starts_with(&GZIP_MAGIC_BYTES) {
+            ObjectHeader::Gzip
+        } else if header.starts_with(&ZSTD_MAGIC_BYTES) {
+            ObjectHeader::Zstd
+        } else if is_zlib_header(header) {
+            ObjectHeader::Zlib
+        } else if !header.is_empty()
+            && header
+                .iter()
+                .all(|byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace())
+        {
+            ObjectHeader::Text
+        } else {
+            ObjectHeader::Opaque
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            ObjectHeader::Gzip => "gzip",
+            ObjectHeader::Zstd => "zstd",
+            ObjectHeader::Zlib => "zlib",
+            ObjectHeader::Text => "text",
+            ObjectHeader::Opaque => "opaque",
+        }
+    }
+}
+
+/// deflate with a 32K window, no preset dictionary and a valid header checksum (RFC 1950) -
+/// random bytes have about 1 chance in 16000 to match
+fn is_zlib_header(header: &[u8]) -> bool {
+    match header {
+        [cmf, flg, ..] => {
+            *cmf == 0x78 && flg & 0x20 == 0 && (u16::from(*cmf) * 256 + u16::from(*flg)) % 31 == 0
+        }
+        _ => false,
+    }
+}
+
+/// write the objects of the backup `name` into `path` as `1.dump`, `2.dump`... - each object is written as soon as it is read.
+/// Returns the number of objects
+pub fn download_backup<B: Bridge>(
+    bridge: &B,
+    name: &str,
+    path: &Path,
+    decrypt: bool,
+    decompress: bool,
+) -> Result<usize, Error> {
+    fs::create_dir_all(path)?;
+
+    let read_options = ReadOptions::Backup {
+        name: name.to_string(),
+    };
+    let mut objects_count = 0usize;
+    let mut write_result = Ok(());
+
+    let _ = bridge.read_objects(&read_options, decrypt, decompress, |object| {
+        if write_result.is_err() {
+            return;
+        }
+
+        objects_count += 1;
+        write_result = fs::write(path.join(format!("{}.dump", objects_count)), object);
+    })?;
+
+    write_result.map(|_| objects_count)
+}
+
+/// delete the backups of `bridge` older than the `keep_last` newest ones - the locked backups are kept.
+/// Returns the names of the deleted backups
+pub fn prune_backups<B: Bridge>(bridge: &B, keep_last: usize) -> Result<Vec<String>, Error> {
+    let backups = bridge.index_file()?.prunable_backups(keep_last);
+
+    for backup in &backups {
+        let _ = bridge.delete_backup(backup.as_str())?;
+    }
+
+    Ok(backups)
+}
+
+/// check that `backup` is encrypted from the first bytes of one of its objects - return the cipher it is encrypted with.
+/// The ciphertext has no framing: an object is encrypted when it has no compression header and is not text
+pub fn verify_encryption(backup: &Backup, header: &[u8]) -> Result<EncryptionCipher, Error> {
+    if !backup.encrypted {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("backup '{}' is not encrypted", backup.directory_name),
+        ));
+    }
+
+    match ObjectHeader::of(header) {
+        ObjectHeader::Opaque => Ok(backup.cipher.unwrap_or_default()),
+        object_header => Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "backup '{}' is recorded as encrypted but its objects start with a {} header - they are not encrypted",
+                backup.directory_name,
+                object_header.name()
+            ),
+        )),
+    }
+}
+
+const GZIP_MAGIC_BYTES: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC_BYTES: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const ZSTD_DICTIONARY_MAGIC_BYTES: [u8; 4] = [0x37, 0xa4, 0x30, 0xec];
+
+fn compress(data: Bytes, format: CompressionFormat) -> Result<Bytes, Error> {
+    match format {
+        CompressionFormat::Zlib => {
+            let mut enc = ZlibEncoder::new(Vec::new(), Compression::default());
+            let _ = enc.write_all(data.as_slice());
+            enc.flush_finish()
+        }
+        CompressionFormat::Gzip => {
+            let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+            enc.write_all(data.as_slice())?;
+            enc.finish()
+        }
+        CompressionFormat::Zstd => zstd::stream::encode_all(data.as_slice(), 0),
+    }
+}
+
+/// the data is always compressed with zstd - the dictionary is required to decompress it
+fn compress_with_dictionary(
+    data: Bytes,
+    dictionary: &CompressionDictionary,
+) -> Result<Bytes, Error> {
+    let mut enc =
+        zstd::stream::Encoder::with_dictionary(Vec::new(), 0, dictionary.data.as_slice())?;
+    enc.write_all(data.as_slice())?;
+    enc.finish()
+}
+
+/// the compression format is detected from the data - gzip has a magic header, zlib otherwise
 fn decompress(data: Bytes) -> Result<Bytes, Error> {
-    let mut dec = ZlibDecoder::new(data.as_slice());
     let mut decoded_data = Vec::new();
-    let _ = dec.read_to_end(&mut decoded_data);
+
+    if data.starts_with(&GZIP_MAGIC_BYTES) {
+        let mut dec = GzDecoder::new(data.as_slice());
+        let _ = dec.read_to_end(&mut decoded_data)?;
+    } else if data.starts_with(&ZSTD_MAGIC_BYTES) {
+        decoded_data = zstd::stream::decode_all(data.as_slice())?;
+    } else {
+        let mut dec = ZlibDecoder::new(data.as_slice());
+        let _ = dec.read_to_end(&mut decoded_data);
+    }
+
+    Ok(decoded_data)
+}
+
+fn decompress_with_dictionary(
+    data: Bytes,
+    dictionary: &CompressionDictionary,
+) -> Result<Bytes, Error> {
+    let mut decoded_data = Vec::new();
+    let mut dec =
+        zstd::stream::Decoder::with_dictionary(data.as_slice(), dictionary.data.as_slice())?;
+    let _ = dec.read_to_end(&mut decoded_data)?;
     Ok(decoded_data)
 }
 
@@ -100,61 +941,1107 @@ fn get_encryption_key_with_correct_length(key: &str) -> String {
     key_string
 }
 
-fn encrypt(data: Bytes, encryption_key: &str) -> Result<Bytes, Error> {
+/// bytes of the random nonce written before the ciphertext of an object
+const NONCE_LENGTH: usize = 12;
+
+/// nonce of the AES-256-GCM objects written before the nonce was random - only used to read them
+const AES_256_GCM_STATIC_NONCE: &[u8; NONCE_LENGTH] = b"unique nonce";
+
+/// a random nonce for each object - written before the ciphertext
+fn encrypt(data: Bytes, encryption_key: &str, cipher: EncryptionCipher) -> Result<Bytes, Error> {
     let key = get_encryption_key_with_correct_length(encryption_key);
     let key = Key::from_slice(key.as_bytes());
-    let cipher = Aes256Gcm::new(key);
-    let nonce = Nonce::from_slice(b"unique nonce");
+    let nonce = rand::random::<[u8; NONCE_LENGTH]>();
+    let nonce_slice = Nonce::from_slice(&nonce);
 
-    let encrypted_data = match cipher.encrypt(nonce, data.as_slice()) {
-        Ok(data) => data,
-        Err(err) => return Err(Error::new(ErrorKind::Other, format!("{:?}", err))),
+    let encrypted_data = match cipher {
+        EncryptionCipher::Aes256Gcm => Aes256Gcm::new(key).encrypt(nonce_slice, data.as_slice()),
+        EncryptionCipher::ChaCha20Poly1305 => {
+            ChaCha20Poly1305::new(key).encrypt(nonce_slice, data.as_slice())
+        }
     };
 
-    Ok(encrypted_data)
+    match encrypted_data {
+        Ok(encrypted_data) => Ok([nonce.as_slice(), encrypted_data.as_slice()].concat()),
+        Err(err) => Err(Error::new(ErrorKind::Other, format!("{:?}", err))),
+    }
+}
+
+#[cfg(test)]
+fn decrypt(
+    encrypted_data: Bytes,
+    encryption_key: &str,
+    cipher: EncryptionCipher,
+) -> Result<Bytes, Error> {
+    decrypt_data(encrypted_data.as_slice(), encryption_key, cipher, false)
+}
+
+/// decrypt an object of `backup` - the index file tells if its AES-256-GCM objects use the static nonce
+fn decrypt_backup_object(
+    encrypted_data: Bytes,
+    encryption_key: &str,
+    backup: &Backup,
+) -> Result<Bytes, Error> {
+    decrypt_data(
+        encrypted_data.as_slice(),
+        encryption_key,
+        backup.cipher.unwrap_or_default(),
+        !backup.random_nonce,
+    )
+}
+
+/// decrypt an object read without index file - E.g. a local backup. The AES-256-GCM objects written before the
+/// nonce was random fail to decrypt with their first bytes as nonce, they are decrypted with the static nonce
+fn decrypt_without_index(
+    encrypted_data: Bytes,
+    encryption_key: &str,
+    cipher: EncryptionCipher,
+) -> Result<Bytes, Error> {
+    decrypt_data(encrypted_data.as_slice(), encryption_key, cipher, false).or_else(|err| {
+        match cipher {
+            EncryptionCipher::Aes256Gcm => {
+                decrypt_data(encrypted_data.as_slice(), encryption_key, cipher, true)
+            }
+            EncryptionCipher::ChaCha20Poly1305 => Err(err),
+        }
+    })
 }
 
-fn decrypt(encrypted_data: Bytes, encryption_key: &str) -> Result<Bytes, Error> {
+fn decrypt_data(
+    encrypted_data: &[u8],
+    encryption_key: &str,
+    cipher: EncryptionCipher,
+    static_nonce: bool,
+) -> Result<Bytes, Error> {
     let key = get_encryption_key_with_correct_length(encryption_key);
     let key = Key::from_slice(key.as_bytes());
-    let cipher = Aes256Gcm::new(key);
-    let nonce = Nonce::from_slice(b"unique nonce");
 
-    let data = match cipher.decrypt(nonce, encrypted_data.as_slice()) {
-        Ok(data) => data,
-        Err(err) => return Err(Error::new(ErrorKind::Other, format!("{:?}", err))),
+    let (nonce, encrypted_data) = match (cipher, static_nonce) {
+        (EncryptionCipher::Aes256Gcm, true) => {
+            (AES_256_GCM_STATIC_NONCE.as_slice(), encrypted_data)
+        }
+        _ if encrypted_data.len() < NONCE_LENGTH => {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "encrypted data is shorter than its nonce",
+            ))
+        }
+        _ => encrypted_data.split_at(NONCE_LENGTH),
+    };
+
+    let data = match cipher {
+        EncryptionCipher::Aes256Gcm => {
+            Aes256Gcm::new(key).decrypt(Nonce::from_slice(nonce), encrypted_data)
+        }
+        EncryptionCipher::ChaCha20Poly1305 => {
+            ChaCha20Poly1305::new(key).decrypt(Nonce::from_slice(nonce), encrypted_data)
+        }
     };
 
-    Ok(data)
+    match data {
+        Ok(data) => Ok(data),
+        Err(err) => Err(Error::new(ErrorKind::Other, format!("{:?}", err))),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::bridge::{compress, decompress, decrypt, encrypt};
+    use std::collections::BTreeMap;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    use crate::bridge::in_memory::InMemoryBridge;
+    use aes_gcm::aead::{Aead, NewAead};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    use crate::bridge::{
+        compress, compress_with_dictionary, decompress, decompress_with_dictionary, decrypt,
+        decrypt_backup_object, decrypt_without_index, download_backup, encrypt,
+        get_encryption_key_with_correct_length, prune_backups, verify_encryption, Backup,
+        BackupChunk, BackupComparison, BackupStats, Bridge, CompressionDictionary,
+        CompressionFormat, EncryptionCipher, IndexFile, ObjectHeader, RunLock, WrittenSizes,
+        AES_256_GCM_STATIC_NONCE, OBJECT_HEADER_LENGTH,
+    };
+    use crate::connector::Engine;
 
     #[test]
     fn test_compression() {
         let data = b"hello w0rld - this is a long sentence right?".to_vec();
-        let compressed_data = compress(data.clone()).unwrap();
+        let compressed_data = compress(data.clone(), CompressionFormat::Zlib).unwrap();
+        assert_ne!(data, compressed_data);
+        assert_eq!(decompress(compressed_data).unwrap(), data);
+    }
+
+    #[test]
+    fn test_zstd_compression() {
+        let data = b"hello w0rld - this is a long sentence right?".to_vec();
+        let compressed_data = compress(data.clone(), CompressionFormat::Zstd).unwrap();
         assert_ne!(data, compressed_data);
         assert_eq!(decompress(compressed_data).unwrap(), data);
     }
 
+    /// small dumps of the same schema - like the backups of a small database
+    fn dictionary_samples() -> Vec<Vec<u8>> {
+        (0..1_000)
+            .map(|i| {
+                format!(
+                    "INSERT INTO public.users (id, first_name, last_name, email) VALUES ({}, 'first name {}', 'last name {}', 'user{}@acme.com');\n",
+                    i, i, i * 7, i * 13
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dictionary_compression() {
+        let dictionary = CompressionDictionary::train(&dictionary_samples(), 4_096).unwrap();
+        assert_ne!(dictionary.id, 0);
+        assert_eq!(
+            CompressionDictionary::try_from_bytes(dictionary.data.clone()).unwrap(),
+            dictionary
+        );
+
+        let data = b"INSERT INTO public.users (id, first_name, last_name, email) VALUES (1001, 'first name 1001', 'last name 7007', 'user13013@acme.com');\n".to_vec();
+        let compressed_data = compress_with_dictionary(data.clone(), &dictionary).unwrap();
+        assert!(
+            compressed_data.len()
+                < compress(data.clone(), CompressionFormat::Zstd)
+                    .unwrap()
+                    .len()
+        );
+
+        assert_eq!(
+            decompress_with_dictionary(compressed_data.clone(), &dictionary).unwrap(),
+            data
+        );
+        // the dictionary is required to restore the backup
+        assert!(decompress(compressed_data).is_err());
+
+        assert!(CompressionDictionary::try_from_bytes(b"not a dictionary".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_gzip_compression() {
+        let data = b"hello w0rld - this is a long sentence right?".to_vec();
+        let compressed_data = compress(data.clone(), CompressionFormat::Gzip).unwrap();
+        assert_ne!(data, compressed_data);
+        assert_eq!(decompress(compressed_data.clone()).unwrap(), data);
+
+        // the object can be opened by gunzip
+        let mut process = Command::new("gzip")
+            .args(["-d", "-c"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("can't run gzip");
+
+        process
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(compressed_data.as_slice())
+            .unwrap();
+
+        let output = process.wait_with_output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, data);
+    }
+
     #[test]
     fn test_encryption_1() {
         let key = "this is my secret";
         let data = b"hello w0rld hello w0rld hello w0rld hello w0rld hello w0rld".to_vec();
-        let encrypted_data = encrypt(data.clone(), key).unwrap();
+        let encrypted_data = encrypt(data.clone(), key, EncryptionCipher::Aes256Gcm).unwrap();
         assert_ne!(encrypted_data, data);
-        assert_eq!(decrypt(encrypted_data, key).unwrap(), data);
+        assert_eq!(
+            decrypt(encrypted_data, key, EncryptionCipher::Aes256Gcm).unwrap(),
+            data
+        );
     }
 
     #[test]
     fn test_encryption_2() {
         let key = "this is my secret very very very long and greater than 32 chars";
         let data = b"hello w0rld hello w0rld hello w0rld hello w0rld hello w0rld".to_vec();
-        let encrypted_data = encrypt(data.clone(), key).unwrap();
+        let encrypted_data = encrypt(data.clone(), key, EncryptionCipher::Aes256Gcm).unwrap();
+        assert_ne!(encrypted_data, data);
+        assert_eq!(
+            decrypt(encrypted_data, key, EncryptionCipher::Aes256Gcm).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_encryption_chacha20_poly1305() {
+        let key = "this is my secret";
+        let data = b"hello w0rld hello w0rld hello w0rld hello w0rld hello w0rld".to_vec();
+        let encrypted_data =
+            encrypt(data.clone(), key, EncryptionCipher::ChaCha20Poly1305).unwrap();
         assert_ne!(encrypted_data, data);
-        assert_eq!(decrypt(encrypted_data, key).unwrap(), data);
+        assert_eq!(
+            decrypt(
+                encrypted_data.clone(),
+                key,
+                EncryptionCipher::ChaCha20Poly1305
+            )
+            .unwrap(),
+            data
+        );
+
+        // the data can't be decrypted with another cipher
+        assert!(decrypt(encrypted_data, key, EncryptionCipher::Aes256Gcm).is_err());
+    }
+
+    #[test]
+    fn test_encryption_chacha20_poly1305_uses_a_random_nonce() {
+        let key = "this is my secret";
+        let data = b"hello w0rld hello w0rld hello w0rld hello w0rld hello w0rld".to_vec();
+        let encrypted_data =
+            encrypt(data.clone(), key, EncryptionCipher::ChaCha20Poly1305).unwrap();
+        let other_encrypted_data =
+            encrypt(data.clone(), key, EncryptionCipher::ChaCha20Poly1305).unwrap();
+
+        assert_ne!(encrypted_data, other_encrypted_data);
+        assert_eq!(
+            decrypt(
+                other_encrypted_data,
+                key,
+                EncryptionCipher::ChaCha20Poly1305
+            )
+            .unwrap(),
+            data
+        );
+        assert!(decrypt(vec![0; 4], key, EncryptionCipher::ChaCha20Poly1305).is_err());
+    }
+
+    #[test]
+    fn test_encryption_aes_256_gcm_uses_a_random_nonce() {
+        let key = "this is my secret";
+        let data = b"hello w0rld hello w0rld hello w0rld hello w0rld hello w0rld".to_vec();
+        let encrypted_data = encrypt(data.clone(), key, EncryptionCipher::Aes256Gcm).unwrap();
+        let other_encrypted_data = encrypt(data.clone(), key, EncryptionCipher::Aes256Gcm).unwrap();
+
+        assert_ne!(encrypted_data, other_encrypted_data);
+        assert_eq!(
+            decrypt(other_encrypted_data, key, EncryptionCipher::Aes256Gcm).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_decryption_of_objects_encrypted_with_the_static_nonce() {
+        let key = "this is my secret";
+        let data = b"hello w0rld hello w0rld hello w0rld hello w0rld hello w0rld".to_vec();
+        // objects written before the nonce was random
+        let aes_key = get_encryption_key_with_correct_length(key);
+        let encrypted_data = Aes256Gcm::new(Key::from_slice(aes_key.as_bytes()))
+            .encrypt(Nonce::from_slice(AES_256_GCM_STATIC_NONCE), data.as_slice())
+            .unwrap();
+
+        // index files written before the nonce was random
+        let backup: Backup = serde_json::from_str(
+            r#"{"directory_name":"backup-1","size":10,"created_at":1,"compressed":true,"encrypted":true}"#,
+        )
+        .unwrap();
+        assert!(!backup.random_nonce);
+
+        assert_eq!(
+            decrypt_backup_object(encrypted_data.clone(), key, &backup).unwrap(),
+            data
+        );
+        assert!(decrypt(encrypted_data.clone(), key, EncryptionCipher::Aes256Gcm).is_err());
+        assert_eq!(
+            decrypt_without_index(encrypted_data, key, EncryptionCipher::Aes256Gcm).unwrap(),
+            data
+        );
+
+        let encrypted_data = encrypt(data.clone(), key, EncryptionCipher::Aes256Gcm).unwrap();
+        assert_eq!(
+            decrypt_without_index(encrypted_data, key, EncryptionCipher::Aes256Gcm).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_backup_without_cipher() {
+        // index files written before the cipher was configurable
+        let backup: Backup = serde_json::from_str(
+            r#"{"directory_name":"backup-1","size":10,"created_at":1,"compressed":true,"encrypted":true}"#,
+        )
+        .unwrap();
+
+        assert_eq!(backup.cipher, None);
+        assert_eq!(
+            backup.cipher.unwrap_or_default(),
+            EncryptionCipher::Aes256Gcm
+        );
+    }
+
+    #[test]
+    fn test_verify_encryption() {
+        let data = (0..100)
+            .flat_map(|i| {
+                format!(
+                    "INSERT INTO public.users (id, name) VALUES ({}, 'user {}');\n",
+                    i,
+                    i * 7
+                )
+                .into_bytes()
+            })
+            .collect::<Vec<_>>();
+        let key = "this is my secret";
+
+        let backup = |encrypted: bool, cipher: Option<EncryptionCipher>| Backup {
+            directory_name: "backup-1".to_string(),
+            size: 1024,
+            created_at: 1,
+            compressed: true,
+            encrypted,
+            cipher,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+        let header = |data: &[u8]| data[..OBJECT_HEADER_LENGTH.min(data.len())].to_vec();
+
+        // encrypted fixtures
+        for format in [
+            CompressionFormat::Zlib,
+            CompressionFormat::Gzip,
+            CompressionFormat::Zstd,
+        ] {
+            for cipher in [
+                EncryptionCipher::Aes256Gcm,
+                EncryptionCipher::ChaCha20Poly1305,
+            ] {
+                let object = encrypt(compress(data.clone(), format).unwrap(), key, cipher).unwrap();
+                assert_eq!(ObjectHeader::of(&header(&object)), ObjectHeader::Opaque);
+                assert_eq!(
+                    verify_encryption(&backup(true, Some(cipher)), &header(&object)).unwrap(),
+                    cipher
+                );
+            }
+        }
+
+        let object = encrypt(data.clone(), key, EncryptionCipher::Aes256Gcm).unwrap();
+        // backups encrypted before the cipher was recorded
+        assert_eq!(
+            verify_encryption(&backup(true, None), &header(&object)).unwrap(),
+            EncryptionCipher::Aes256Gcm
+        );
+        assert!(verify_encryption(&backup(false, None), &header(&object)).is_err());
+
+        // unencrypted fixtures
+        for (object, object_header) in [
+            (
+                compress(data.clone(), CompressionFormat::Zlib).unwrap(),
+                ObjectHeader::Zlib,
+            ),
+            (
+                compress(data.clone(), CompressionFormat::Gzip).unwrap(),
+                ObjectHeader::Gzip,
+            ),
+            (
+                compress(data.clone(), CompressionFormat::Zstd).unwrap(),
+                ObjectHeader::Zstd,
+            ),
+            (data.clone(), ObjectHeader::Text),
+        ] {
+            assert_eq!(ObjectHeader::of(&header(&object)), object_header);
+            // the index file says it is encrypted but the objects are not
+            assert!(verify_encryption(
+                &backup(true, Some(EncryptionCipher::Aes256Gcm)),
+                &header(&object)
+            )
+            .is_err());
+            assert!(verify_encryption(&backup(false, None), &header(&object)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_index_file_bytes() {
+        let backups = (0..1_000)
+            .map(|i| Backup {
+                directory_name: format!("backup-{}", 1647706359405u128 + i),
+                size: 1024,
+                created_at: 1647706359405 + i,
+                compressed: true,
+                encrypted: false,
+                cipher: None,
+                uncompressed_size: Some(8192),
+                engine: Some(Engine::PostgreSQL),
+                storage_class: None,
+                compression_dictionary_id: None,
+                tables: Some(vec!["public.users".to_string()]),
+                transformers: None,
+                chunks: None,
+                locked: false,
+                table_checksums: None,
+                table_parts: None,
+                key: None,
+                random_nonce: false,
+            })
+            .collect::<Vec<_>>();
+
+        let index_file = IndexFile { backups };
+        let legacy_json = serde_json::to_vec(&index_file).unwrap();
+        let data = index_file.to_bytes().unwrap();
+
+        assert!(data.len() < legacy_json.len() / 5);
+        assert!(IndexFile::from_bytes(data).unwrap().backups == index_file.backups);
+
+        // written as plain JSON by the previous versions
+        assert!(IndexFile::from_bytes(legacy_json).unwrap().backups == index_file.backups);
+        assert_eq!(
+            IndexFile::from_bytes(br#"{"backups":[{"directory_name":"backup-1647706359405","size":1024,"created_at":1647706359405,"compressed":true,"encrypted":false}]}"#.to_vec())
+                .unwrap()
+                .backups[0]
+                .directory_name,
+            "backup-1647706359405"
+        );
+
+        assert!(IndexFile::from_bytes(b"not an index file".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_backup_comparison() {
+        let backup =
+            |size: usize, created_at: u128, tables: Option<&[&str]>, transformers: &[&str]| {
+                Backup {
+                    directory_name: format!("backup-{}", created_at),
+                    size,
+                    created_at,
+                    compressed: true,
+                    encrypted: false,
+                    cipher: None,
+                    uncompressed_size: None,
+                    engine: Some(Engine::PostgreSQL),
+                    storage_class: None,
+                    compression_dictionary_id: None,
+                    tables: tables.map(|tables| tables.iter().map(|t| t.to_string()).collect()),
+                    transformers: Some(transformers.iter().map(|t| t.to_string()).collect()),
+                    chunks: None,
+                    locked: false,
+                    table_checksums: None,
+                    table_parts: None,
+                    key: None,
+                    random_nonce: false,
+                }
+            };
+
+        let a = backup(
+            3_000,
+            1647706359405,
+            Some(&["public.users", "public.orders", "public.legacy"]),
+            &["public.users.email: email"],
+        );
+        let b = backup(
+            2_000,
+            1647731334517,
+            Some(&["public.users", "public.orders", "public.items"]),
+            &["public.users.email: email", "public.users.name: first-name"],
+        );
+
+        assert_eq!(
+            BackupComparison::new(&a, &b),
+            BackupComparison {
+                size_delta: -1_000,
+                created_at_delta: 1647731334517 - 1647706359405,
+                added_tables: vec!["public.items".to_string()],
+                removed_tables: vec!["public.legacy".to_string()],
+                added_transformers: vec!["public.users.name: first-name".to_string()],
+                removed_transformers: vec![],
+            }
+        );
+
+        // the tables were not recorded in the oldest backup
+        let a = backup(3_000, 1647706359405, None, &[]);
+        let comparison = BackupComparison::new(&a, &b);
+        assert!(comparison.added_tables.is_empty());
+        assert!(comparison.removed_tables.is_empty());
+        assert_eq!(comparison.added_transformers.len(), 2);
+    }
+
+    #[test]
+    fn test_index_file_stats() {
+        let backup = |directory_name: &str, size: usize, created_at: u128| Backup {
+            directory_name: directory_name.to_string(),
+            size,
+            created_at,
+            compressed: true,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        let index_file = IndexFile {
+            backups: vec![
+                backup("backup-1647731334517", 2_000, 1647731334517),
+                backup("backup-1647706359405", 1_000, 1647706359405),
+                backup("backup-1647734369306", 6_000, 1647734369306),
+            ],
+        };
+
+        assert_eq!(
+            index_file.stats(),
+            BackupStats {
+                count: 3,
+                total_size: 9_000,
+                average_size: 3_000,
+                oldest_created_at: Some(1647706359405),
+                newest_created_at: Some(1647734369306),
+            }
+        );
+
+        let empty_index_file = IndexFile { backups: vec![] };
+        let stats = empty_index_file.stats();
+        assert_eq!(stats.count, 0);
+        assert_eq!(stats.average_size, 0);
+        assert_eq!(stats.oldest_created_at, None);
+    }
+
+    #[test]
+    fn test_index_file_page() {
+        let index_file = || IndexFile {
+            // shuffled - E.g. backups uploaded by several machines
+            backups: (0..5_000u128)
+                .map(|i| (i * 7_919) % 5_000)
+                .map(|i| Backup {
+                    directory_name: format!("backup-{}", 1647706359405 + i),
+                    size: 1_000,
+                    created_at: 1647706359405 + i,
+                    compressed: true,
+                    encrypted: false,
+                    cipher: None,
+                    uncompressed_size: None,
+                    engine: None,
+                    storage_class: None,
+                    compression_dictionary_id: None,
+                    tables: None,
+                    transformers: None,
+                    chunks: None,
+                    locked: false,
+                    table_checksums: None,
+                    table_parts: None,
+                    key: None,
+                    random_nonce: false,
+                })
+                .collect(),
+        };
+
+        let names = |backups: Vec<Backup>| {
+            backups
+                .into_iter()
+                .map(|backup| backup.created_at - 1647706359405)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(index_file().page(0, Some(3))),
+            vec![4_999, 4_998, 4_997]
+        );
+        assert_eq!(
+            names(index_file().page(10, Some(3))),
+            vec![4_989, 4_988, 4_987]
+        );
+        assert_eq!(names(index_file().page(4_998, Some(10))), vec![1, 0]);
+        assert!(index_file().page(5_000, Some(10)).is_empty());
+        assert!(index_file().page(0, Some(0)).is_empty());
+
+        let all = names(index_file().page(0, None));
+        assert_eq!(all.len(), 5_000);
+        assert_eq!(all[0], 4_999);
+        assert_eq!(all[4_999], 0);
+        assert_eq!(names(index_file().page(4_997, None)), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_index_file_add_part() {
+        let data = (0..100)
+            .flat_map(|i| {
+                format!(
+                    "INSERT INTO public.users (id, name) VALUES ({}, 'user {}');\n",
+                    i,
+                    i * 7
+                )
+                .into_bytes()
+            })
+            .collect::<Vec<_>>();
+        let compressed_data = compress(data.clone(), CompressionFormat::Gzip).unwrap();
+        let encrypted_data = encrypt(
+            compressed_data,
+            "this is my secret",
+            EncryptionCipher::default(),
+        )
+        .unwrap();
+
+        let part = || Backup {
+            directory_name: "backup-1".to_string(),
+            size: encrypted_data.len(),
+            created_at: 1647706359405,
+            compressed: true,
+            encrypted: true,
+            cipher: Some(EncryptionCipher::default()),
+            uncompressed_size: Some(data.len()),
+            engine: Some(Engine::PostgreSQL),
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        let mut index_file = IndexFile { backups: vec![] };
+        index_file.add_part(part());
+        index_file.add_part(part());
+
+        assert_eq!(index_file.backups.len(), 1);
+
+        let backup = &index_file.backups[0];
+        assert_eq!(backup.size, 2 * encrypted_data.len());
+        assert_eq!(backup.uncompressed_size, Some(2 * data.len()));
+        // the repeated queries compress well - even with the encryption overhead
+        assert!(backup.size < backup.uncompressed_size.unwrap());
+
+        // a backup written before the uncompressed size was recorded
+        index_file.backups[0].uncompressed_size = None;
+        index_file.add_part(part());
+        assert_eq!(index_file.backups[0].size, 3 * encrypted_data.len());
+        assert_eq!(index_file.backups[0].uncompressed_size, None);
+    }
+
+    #[test]
+    fn test_index_file_chunks() {
+        let chunk = |part: u16, hash: &str| BackupChunk {
+            part,
+            hash: hash.to_string(),
+            size: 100,
+            uncompressed_size: Some(1000),
+        };
+
+        let backup = |directory_name: &str, chunks: Vec<BackupChunk>| Backup {
+            directory_name: directory_name.to_string(),
+            size: 100 * chunks.len(),
+            created_at: 1647706359405,
+            compressed: true,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: Some(chunks),
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        let mut index_file = IndexFile { backups: vec![] };
+        index_file.add_part(backup("backup-1", vec![chunk(1, "a")]));
+        index_file.add_part(backup("backup-1", vec![chunk(2, "b")]));
+        index_file.add_part(backup("backup-2", vec![chunk(1, "a")]));
+        index_file.add_part(backup("backup-2", vec![chunk(2, "c")]));
+        // the same part twice
+        index_file.add_part(backup("backup-2", vec![chunk(3, "c")]));
+
+        assert_eq!(
+            index_file.backups[0].chunks,
+            Some(vec![chunk(1, "a"), chunk(2, "b")])
+        );
+        assert_eq!(index_file.chunk("a"), Some(&chunk(1, "a")));
+        assert_eq!(index_file.chunk("d"), None);
+
+        assert_eq!(index_file.unshared_chunks("backup-1"), vec![&chunk(2, "b")]);
+        assert_eq!(index_file.unshared_chunks("backup-2"), vec![&chunk(2, "c")]);
+        assert!(index_file.unshared_chunks("backup-3").is_empty());
+    }
+
+    #[test]
+    fn test_index_file_unchanged_table_chunks() {
+        let chunk = |part: u16| BackupChunk {
+            part,
+            hash: format!("hash-{}", part),
+            size: 100,
+            uncompressed_size: Some(1000),
+        };
+
+        let backup = |directory_name: &str, created_at: u128, users_checksum: &str| Backup {
+            directory_name: directory_name.to_string(),
+            size: 300,
+            created_at,
+            compressed: true,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: Some(3000),
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: Some(vec!["public.users.email: email".to_string()]),
+            chunks: Some(vec![chunk(1), chunk(2), chunk(3)]),
+            locked: false,
+            table_checksums: Some(BTreeMap::from([
+                ("public.orders".to_string(), "orders-1".to_string()),
+                ("public.users".to_string(), users_checksum.to_string()),
+            ])),
+            table_parts: Some(BTreeMap::from([
+                ("public.orders".to_string(), vec![2]),
+                ("public.users".to_string(), vec![3]),
+            ])),
+            key: None,
+            random_nonce: false,
+        };
+
+        let index_file = IndexFile {
+            backups: vec![
+                backup("backup-2", 1647706359406, "users-2"),
+                backup("backup-1", 1647706359405, "users-1"),
+            ],
+        };
+
+        let like = backup("backup-3", 1647706359407, "");
+        let transformers = vec!["public.users.email: email".to_string()];
+        let table_checksums = BTreeMap::from([
+            ("public.orders".to_string(), "orders-1".to_string()),
+            ("public.users".to_string(), "users-1".to_string()),
+            ("public.items".to_string(), "items-1".to_string()),
+        ]);
+
+        // compared to the last backup only - the users have changed since `backup-1`
+        assert_eq!(
+            index_file.unchanged_table_chunks(&like, &table_checksums, &transformers),
+            BTreeMap::from([("public.orders".to_string(), vec![chunk(2)])])
+        );
+
+        // the parts of a backup transformed differently are not shared
+        assert!(index_file
+            .unchanged_table_chunks(&like, &table_checksums, &[])
+            .is_empty());
+
+        let mut encrypted_like = backup("backup-3", 1647706359407, "");
+        encrypted_like.encrypted = true;
+        assert!(index_file
+            .unchanged_table_chunks(&encrypted_like, &table_checksums, &transformers)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_index_file_rename_backup() {
+        let backup = |directory_name: &str| Backup {
+            directory_name: directory_name.to_string(),
+            size: 100,
+            created_at: 1647706359405,
+            compressed: true,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        let mut index_file = IndexFile {
+            backups: vec![backup("backup-1"), backup("backup-2")],
+        };
+
+        assert_eq!(
+            index_file
+                .rename_backup("backup-1", "before-migration")
+                .unwrap()
+                .directory_name,
+            "before-migration"
+        );
+        assert_eq!(index_file.backups[0].directory_name, "before-migration");
+
+        // the name is taken
+        assert!(index_file
+            .rename_backup("backup-2", "before-migration")
+            .is_err());
+        assert!(index_file.rename_backup("backup-2", "backup-2").is_err());
+        assert_eq!(index_file.backups[1].directory_name, "backup-2");
+
+        assert!(index_file
+            .rename_backup("backup-1", "after-migration")
+            .is_err());
+        assert!(index_file.rename_backup("backup-2", "").is_err());
+        assert!(index_file.rename_backup("backup-2", "a/b").is_err());
+
+        // the templated key follows the name
+        index_file.backups[1].key = Some("2022/03/19/backup-2".to_string());
+        assert_eq!(index_file.backups[1].directory_key(), "2022/03/19/backup-2");
+        let backup = index_file.rename_backup("backup-2", "weekly").unwrap();
+        assert_eq!(backup.directory_key(), "2022/03/19/weekly");
+        assert_eq!(index_file.backups[0].directory_key(), "before-migration");
+    }
+
+    #[test]
+    fn test_written_sizes() {
+        let written_sizes = WrittenSizes::default();
+        written_sizes.add(1_000, 100);
+        written_sizes.add(2_000, 200);
+        written_sizes.add_deduplicated(1_000);
+
+        assert_eq!(written_sizes.uncompressed_size(), 3_000);
+        assert_eq!(written_sizes.size(), 300);
+        assert_eq!(written_sizes.deduplicated_size(), 1_000);
+    }
+
+    #[test]
+    fn test_index_file_lock_backup() {
+        // backups written before the lock
+        let mut index_file: IndexFile = serde_json::from_str(
+            r#"{"backups":[{"directory_name":"backup-1","size":100,"created_at":1647706359405,"compressed":true,"encrypted":false}]}"#,
+        )
+        .unwrap();
+        assert!(!index_file.backups[0].locked);
+
+        assert!(
+            index_file
+                .set_backup_locked("backup-1", true)
+                .unwrap()
+                .locked
+        );
+        assert!(index_file.backups[0].locked);
+        assert!(
+            !index_file
+                .set_backup_locked("backup-1", false)
+                .unwrap()
+                .locked
+        );
+        assert!(index_file.set_backup_locked("backup-2", true).is_err());
+
+        // a locked backup can't be renamed nor deleted
+        index_file.backups[0].chunks = Some(vec![BackupChunk {
+            part: 1,
+            hash: "hash-1".to_string(),
+            size: 10,
+            uncompressed_size: None,
+        }]);
+        let _ = index_file.set_backup_locked("backup-1", true).unwrap();
+        assert!(index_file.check_backup_not_locked("backup-1").is_err());
+        assert!(index_file.rename_backup("backup-1", "backup-2").is_err());
+        assert_eq!(index_file.backups[0].directory_name, "backup-1");
+        assert!(index_file.unshared_chunks("backup-1").is_empty());
+
+        let _ = index_file.set_backup_locked("backup-1", false).unwrap();
+        assert!(index_file.check_backup_not_locked("backup-1").is_ok());
+        assert_eq!(index_file.unshared_chunks("backup-1").len(), 1);
+        assert!(index_file.rename_backup("backup-1", "backup-2").is_ok());
+    }
+
+    #[test]
+    fn test_run_lock() {
+        let lock = RunLock {
+            holder: "backup-1".to_string(),
+            acquired_at: 1_000,
+            expires_at: 1_000 + 3_600_000,
+        };
+
+        // held by another backup
+        let err = lock.check("backup-2", 2_000).unwrap_err();
+        assert!(err.to_string().contains("another backup is in progress"));
+        assert!(err.to_string().contains("60 more minutes"));
+
+        // held by the same backup
+        assert!(lock.check("backup-1", 2_000).is_ok());
+        // expired - E.g. a crashed backup
+        assert!(lock.check("backup-2", 1_000 + 3_600_000).is_ok());
+    }
+
+    #[test]
+    fn test_export_and_import_index_file() {
+        let backup = |directory_name: &str, created_at: u128| Backup {
+            directory_name: directory_name.to_string(),
+            size: 1_000,
+            created_at,
+            compressed: true,
+            encrypted: true,
+            cipher: Some(EncryptionCipher::ChaCha20Poly1305),
+            uncompressed_size: Some(4_000),
+            engine: Some(Engine::PostgreSQL),
+            storage_class: Some("STANDARD_IA".to_string()),
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        let source = InMemoryBridge::with_backups(vec![
+            backup("backup-1647706359405", 1647706359405),
+            backup("backup-1647706359406", 1647706359406),
+        ]);
+        // the backups already in the destination are kept
+        let destination =
+            InMemoryBridge::with_backups(vec![backup("backup-1647706359405", 1647706359405)]);
+
+        let mut exported_index_file = vec![];
+        source.export_index_file(&mut exported_index_file).unwrap();
+
+        assert_eq!(
+            destination
+                .import_index_file(exported_index_file.as_slice())
+                .unwrap(),
+            1
+        );
+        assert!(source.index_file().unwrap().backups == destination.index_file().unwrap().backups);
+
+        // importing twice adds nothing
+        assert_eq!(
+            destination
+                .import_index_file(exported_index_file.as_slice())
+                .unwrap(),
+            0
+        );
+        assert!(destination
+            .import_index_file(b"not json".as_slice())
+            .is_err());
+    }
+
+    const STORED_OBJECTS: [&[u8]; 2] = [b"compressed part 1", b"compressed part 2"];
+    const DECOMPRESSED_OBJECTS: [&[u8]; 2] = [
+        b"INSERT INTO public.users (id) VALUES (1);\n",
+        b"INSERT INTO public.users (id) VALUES (2);\n",
+    ];
+
+    /// a backup of two objects - stored compressed, E.g. `1.dump` and `2.dump`
+    fn fixture_bridge() -> InMemoryBridge {
+        InMemoryBridge {
+            objects: DECOMPRESSED_OBJECTS.map(|object| object.to_vec()).to_vec(),
+            stored_objects: STORED_OBJECTS.map(|object| object.to_vec()).to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prune_backups_keeps_the_newest_and_the_locked_ones() {
+        let backup = |name: &str, created_at: u128, locked: bool| Backup {
+            directory_name: name.to_string(),
+            size: 1_000,
+            created_at,
+            compressed: true,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        let bridge = InMemoryBridge::default();
+        let index_file = IndexFile {
+            backups: vec![
+                backup("backup-3", 3, false),
+                backup("backup-1", 1, false),
+                backup("backup-2", 2, true),
+                backup("backup-4", 4, false),
+                backup("backup-0", 0, false),
+            ],
+        };
+        bridge.write_index_file(&index_file).unwrap();
+
+        // from the oldest
+        assert_eq!(
+            index_file.prunable_backups(2),
+            vec!["backup-0".to_string(), "backup-1".to_string()]
+        );
+        assert!(index_file.prunable_backups(4).is_empty());
+
+        assert_eq!(prune_backups(&bridge, 2).unwrap().len(), 2);
+        let mut names = bridge
+            .index_file()
+            .unwrap()
+            .backups
+            .into_iter()
+            .map(|backup| backup.directory_name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["backup-2", "backup-3", "backup-4"]);
+    }
+
+    #[test]
+    fn download_backup_writes_the_objects() {
+        let dir =
+            std::env::temp_dir().join(format!("replibyte-download-{}", rand::random::<u64>()));
+
+        assert_eq!(
+            download_backup(&fixture_bridge(), "backup-1", &dir, false, false).unwrap(),
+            2
+        );
+        assert_eq!(
+            std::fs::read(dir.join("1.dump")).unwrap(),
+            STORED_OBJECTS[0]
+        );
+        assert_eq!(
+            std::fs::read(dir.join("2.dump")).unwrap(),
+            STORED_OBJECTS[1]
+        );
+
+        // the objects of a previous download are overwritten
+        assert_eq!(
+            download_backup(&fixture_bridge(), "backup-1", &dir, false, true).unwrap(),
+            2
+        );
+        assert_eq!(
+            std::fs::read(dir.join("1.dump")).unwrap(),
+            DECOMPRESSED_OBJECTS[0]
+        );
+        assert_eq!(
+            std::fs::read(dir.join("2.dump")).unwrap(),
+            DECOMPRESSED_OBJECTS[1]
+        );
+        assert!(!dir.join("3.dump").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
     }
 }
@@ -1,78 +1,541 @@
-use std::io::{Error, ErrorKind};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::future::{poll_fn, Future};
+use std::io::{BufReader, Error, ErrorKind};
+use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use aws_config::provider_config::ProviderConfig;
-use aws_sdk_s3::model::{BucketLocationConstraint, CreateBucketConfiguration, Object};
-use aws_sdk_s3::types::ByteStream;
+use aws_sdk_s3::model::{
+    BucketLocationConstraint, CompletedMultipartUpload, CompletedPart, CreateBucketConfiguration,
+    Object, StorageClass,
+};
+use aws_sdk_s3::types::{ByteStream, SdkError};
 use aws_sdk_s3::{Client, Endpoint as SdkEndpoint};
+use aws_smithy_client::erase::DynConnector;
+use aws_smithy_client::hyper_ext::Adapter;
 use aws_types::os_shim_internal::Env;
-use log::{error, info};
+use aws_types::region::Region;
+use aws_types::SdkConfig;
+use futures_core::Stream;
+use hyper_rustls::{HttpsConnectorBuilder, MaybeHttpsStream};
+use log::{error, info, warn};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
 
 use crate::bridge::s3::S3Error::FailedObjectUpload;
 use crate::bridge::{
-    compress, decompress, decrypt, encrypt, Backup, Bridge, IndexFile, ReadOptions,
+    compress, compress_with_dictionary, decompress, decompress_with_dictionary,
+    decrypt_backup_object, decrypt_without_index, encrypt, Backup, BackupChunk, BackupContent,
+    Bridge, CompressionDictionary, CompressionFormat, EncryptionCipher, IndexFile, ReadOptions,
+    RunLock, WrittenSizes, RUN_LOCK_TTL,
 };
 use crate::config::Endpoint;
-use crate::connector::Connector;
+use crate::connector::{Connector, Engine};
 use crate::runtime::block_on;
+use crate::timings::{Stage, Timings};
 use crate::types::Bytes;
-use crate::utils::epoch_millis;
+use crate::utils::{epoch_millis, utc_date};
+
+const INDEX_FILE_NAME: &str = "metadata.json.zst";
+/// plain JSON index file of the previous versions - read when there is no compressed index file yet
+const LEGACY_INDEX_FILE_NAME: &str = "metadata.json";
+const PREFLIGHT_FILE_NAME: &str = ".replibyte-preflight";
+/// held while a backup is written - see `RunLock`
+const RUN_LOCK_FILE_NAME: &str = "backup.lock";
+/// the compression dictionaries are stored next to the index file - shared by all the backups compressed with them
+const COMPRESSION_DICTIONARIES_DIRECTORY: &str = "dictionaries";
+/// the parts of the deduplicated backups - shared by all the backups
+const CHUNKS_DIRECTORY: &str = "chunks";
+const DOWNLOAD_MAX_RETRIES: u32 = 5;
+const DOWNLOAD_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+/// objects larger than the multipart part size are uploaded in parts - a single PUT is limited to 5GB
+const DEFAULT_MULTIPART_PART_SIZE: usize = 100 * 1024 * 1024;
+/// S3 rejects the parts smaller than 5MB (except the last one)
+const MIN_MULTIPART_PART_SIZE: usize = 5 * 1024 * 1024;
+const UPLOAD_PART_MAX_RETRIES: u32 = 3;
+const UPLOAD_PART_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+/// the objects of these storage classes must be restored from the archive before being downloaded
+const ARCHIVE_STORAGE_CLASSES: [&str; 2] = ["GLACIER", "DEEP_ARCHIVE"];
+/// a proxy answering the `CONNECT` request with larger headers is not an HTTP proxy
+const MAX_CONNECT_RESPONSE_LENGTH: usize = 8 * 1024;
+
+/// storage class, content type and tags set on the uploaded backup objects
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjectOptions {
+    pub storage_class: Option<String>,
+    pub content_type: Option<String>,
+    /// URL-encoded tags - E.g. `team=data&env=staging`
+    pub tagging: Option<String>,
+}
+
+/// proxy and certificate authorities of the HTTP client - E.g. behind a corporate proxy with a custom CA
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpClientOptions {
+    /// HTTP proxy tunneling the requests with `CONNECT` - E.g. `http://proxy.acme.com:3128`
+    pub proxy: Option<String>,
+    /// hosts reached without the proxy - E.g. `localhost`, `.acme.com` for its subdomains or `*` for all
+    pub no_proxy: Vec<String>,
+    /// path of a PEM file with the certificate authorities trusted on top of the system ones
+    pub ca_bundle: Option<String>,
+}
 
-const INDEX_FILE_NAME: &str = "metadata.json";
+impl HttpClientOptions {
+    /// the proxy of `host` - None without proxy or when `host` is in `no_proxy`
+    pub fn proxy_for(&self, host: &str) -> Option<&str> {
+        let is_excluded = self.no_proxy.iter().any(|no_proxy| {
+            let no_proxy = no_proxy.trim_start_matches("*.").trim_start_matches('.');
+            no_proxy == "*"
+                || host.eq_ignore_ascii_case(no_proxy)
+                || host
+                    .to_lowercase()
+                    .ends_with(format!(".{}", no_proxy.to_lowercase()).as_str())
+        });
+
+        match is_excluded {
+            true => None,
+            false => self.proxy.as_deref(),
+        }
+    }
+}
 
+#[derive(Clone)]
 pub struct S3 {
     bucket: String,
+    prefix: Option<String>,
     root_key: String,
+    /// creation time of the backup written by this bridge
+    created_at: u128,
+    key_template: Option<String>,
     region: String,
     client: Client,
     enable_compression: bool,
+    compression_format: CompressionFormat,
+    compression_dictionary: Option<CompressionDictionary>,
     encryption_key: Option<String>,
+    encryption_cipher: EncryptionCipher,
+    timings: Arc<Timings>,
+    written_sizes: Arc<WrittenSizes>,
+    engine: Option<Engine>,
+    object_options: ObjectOptions,
+    multipart_part_size: usize,
+    deduplication: bool,
+    /// the custom endpoints (E.g. MinIO) ignore the region
+    check_region: bool,
+    credentials: Option<(String, String)>,
+    /// environment variables read by the AWS default credential chain
+    env: Env,
+    endpoint: Endpoint,
+    http_client_options: HttpClientOptions,
 }
 
 impl S3 {
+    /// `access_key_id` and `secret_access_key` are optional -
+    /// the AWS default credential chain (env vars, profile, instance role...) is used when they are both missing.
+    /// Fail when only one of them is set
     pub fn new<S: Into<String>>(
         bucket: S,
         region: S,
-        access_key_id: S,
-        secret_access_key: S,
+        access_key_id: Option<S>,
+        secret_access_key: Option<S>,
         endpoint: Endpoint,
-    ) -> Self {
-        let access_key_id = access_key_id.into();
-        let secret_access_key = secret_access_key.into();
-        let region = region.into();
-
-        let sdk_config = block_on(
-            aws_config::from_env()
-                .configure(ProviderConfig::default().with_env(Env::from_slice(&[
-                    ("AWS_ACCESS_KEY_ID", access_key_id.as_str()),
-                    ("AWS_SECRET_ACCESS_KEY", secret_access_key.as_str()),
-                    ("AWS_REGION", region.as_str()),
-                ])))
-                .load(),
-        );
+    ) -> Result<Self, Error> {
+        Self::new_with_env(
+            bucket,
+            region,
+            access_key_id,
+            secret_access_key,
+            endpoint,
+            Env::real(),
+        )
+    }
 
-        let s3_config_builder = aws_sdk_s3::config::Builder::from(&sdk_config);
+    /// same as `new` - the default credential chain reads the environment variables from `env`
+    fn new_with_env<S: Into<String>>(
+        bucket: S,
+        region: S,
+        access_key_id: Option<S>,
+        secret_access_key: Option<S>,
+        endpoint: Endpoint,
+        env: Env,
+    ) -> Result<Self, Error> {
+        let region = region.into();
 
-        let s3_config = match endpoint {
-            Endpoint::Default => s3_config_builder.build(),
-            Endpoint::Custom(url) => match http::Uri::from_str(url.as_str()) {
-                Ok(uri) => s3_config_builder
-                    .endpoint_resolver(SdkEndpoint::immutable(uri))
-                    .build(),
-                Err(err) => s3_config_builder.build(),
-            },
+        let credentials = match (access_key_id, secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => {
+                Some((access_key_id.into(), secret_access_key.into()))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "the bridge access_key_id and secret_access_key must be set together - \
+                    remove both to use the AWS default credential chain",
+                ))
+            }
         };
 
-        S3 {
+        let sdk_config = sdk_config(region.as_str(), credentials.clone(), env.clone());
+        let check_region = endpoint == Endpoint::Default;
+        let client = s3_client(&sdk_config, &endpoint, None);
+        let created_at = epoch_millis();
+
+        Ok(S3 {
             bucket: bucket.into().to_string(),
-            root_key: format!("backup-{}", epoch_millis()),
+            prefix: None,
+            root_key: format!("backup-{}", created_at),
+            created_at,
+            key_template: None,
             region,
-            client: Client::from_conf(s3_config),
+            client,
             enable_compression: true,
+            compression_format: CompressionFormat::default(),
+            compression_dictionary: None,
             encryption_key: None,
+            encryption_cipher: EncryptionCipher::default(),
+            timings: Arc::new(Timings::default()),
+            written_sizes: Arc::new(WrittenSizes::default()),
+            engine: None,
+            object_options: ObjectOptions::default(),
+            multipart_part_size: DEFAULT_MULTIPART_PART_SIZE,
+            deduplication: false,
+            check_region,
+            credentials,
+            env,
+            endpoint,
+            http_client_options: HttpClientOptions::default(),
+        })
+    }
+
+    /// send the requests through a proxy and trust more certificate authorities - E.g. behind a corporate proxy.
+    /// The client is rebuilt - it fails when the proxy URL is invalid or when the CA bundle has no certificate
+    pub fn set_http_client_options(&mut self, options: HttpClientOptions) -> Result<(), Error> {
+        let connector = match (options.proxy_for(self.host().as_str()), &options.ca_bundle) {
+            (None, None) => None,
+            (proxy, ca_bundle) => Some(http_connector(proxy, ca_bundle.as_deref())?),
+        };
+
+        let sdk_config = sdk_config(
+            self.region.as_str(),
+            self.credentials.clone(),
+            self.env.clone(),
+        );
+        self.client = s3_client(&sdk_config, &self.endpoint, connector);
+        self.http_client_options = options;
+        Ok(())
+    }
+
+    pub fn http_client_options(&self) -> &HttpClientOptions {
+        &self.http_client_options
+    }
+
+    /// the proxy of the requests - None when they go straight to S3
+    pub fn proxy(&self) -> Option<&str> {
+        self.http_client_options.proxy_for(self.host().as_str())
+    }
+
+    /// host of the requests - the custom endpoint one or the regional AWS one
+    fn host(&self) -> String {
+        match &self.endpoint {
+            Endpoint::Default => format!("s3.{}.amazonaws.com", self.region),
+            Endpoint::Custom(url) => http::Uri::from_str(url.as_str())
+                .ok()
+                .and_then(|uri| uri.host().map(|host| host.to_string()))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// name of the backup written by this bridge - E.g. `backup-1647706359405`
+    pub fn backup_name(&self) -> &str {
+        self.root_key.as_str()
+    }
+
+    /// same bridge writing a new backup - E.g. to back up on a schedule
+    pub fn next_backup(&self) -> Self {
+        let created_at = epoch_millis();
+
+        S3 {
+            root_key: format!("backup-{}", created_at),
+            created_at,
+            ..self.clone()
+        }
+    }
+
+    /// store the objects of the backups under the key built from `key_template` - E.g. `{year}/{month}/{day}/{name}`
+    /// for lifecycle rules by date. Default to the backup name
+    pub fn set_key_template(&mut self, key_template: Option<String>) -> Result<(), Error> {
+        if let Some(key_template) = &key_template {
+            check_key_template(key_template.as_str())?;
+        }
+
+        self.key_template = key_template;
+        Ok(())
+    }
+
+    /// key of the directory of the backup written by this bridge - relative to the prefix
+    fn directory_key(&self) -> String {
+        match &self.key_template {
+            Some(key_template) => resolve_key_template(
+                key_template.as_str(),
+                self.root_key.as_str(),
+                self.created_at,
+            ),
+            None => self.root_key.clone(),
+        }
+    }
+
+    /// engine of the source database - recorded in the index file to check the restore destination
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = Some(engine);
+    }
+
+    /// record the size of the written parts before and after compression and encryption into `written_sizes`
+    pub fn set_written_sizes(&mut self, written_sizes: Arc<WrittenSizes>) {
+        self.written_sizes = written_sizes;
+    }
+
+    /// record the time spent compressing, encrypting and uploading into `timings`
+    pub fn set_timings(&mut self, timings: Arc<Timings>) {
+        self.timings = timings;
+    }
+
+    /// namespace the index file and all the backups under `prefix` in the bucket
+    pub fn set_prefix(&mut self, prefix: Option<String>) {
+        self.prefix = prefix
+            .map(|prefix| prefix.trim_matches('/').to_string())
+            .filter(|prefix| !prefix.is_empty());
+    }
+
+    /// compression format of the uploaded objects - the format is detected when they are downloaded
+    pub fn set_compression_format(&mut self, format: CompressionFormat) {
+        self.compression_format = format;
+    }
+
+    /// compress the uploaded objects with zstd and `dictionary` - the dictionary id is recorded in the index file
+    /// and the dictionary is uploaded by `init` to restore the backups
+    pub fn set_compression_dictionary(&mut self, dictionary: Option<CompressionDictionary>) {
+        self.compression_dictionary = dictionary;
+    }
+
+    /// upload the compression dictionary next to the index file - nothing to do without dictionary
+    fn write_compression_dictionary(&self) -> Result<(), Error> {
+        let dictionary = match &self.compression_dictionary {
+            Some(dictionary) => dictionary,
+            None => return Ok(()),
+        };
+
+        let key = self.compression_dictionary_key(dictionary.id);
+        create_object(
+            &self.client,
+            self.bucket.as_str(),
+            key.as_str(),
+            dictionary.data.clone(),
+            &ObjectOptions::default(),
+        )
+        .map_err(Error::from)
+    }
+
+    fn compression_dictionary(&self, id: u32) -> Result<CompressionDictionary, Error> {
+        let key = self.compression_dictionary_key(id);
+        let data = get_object(&self.client, self.bucket.as_str(), key.as_str()).map_err(|err| {
+            Error::new(
+                ErrorKind::Other,
+                format!(
+                    "can't read the compression dictionary {}: {}",
+                    id,
+                    Error::from(err)
+                ),
+            )
+        })?;
+
+        CompressionDictionary::try_from_bytes(data)
+    }
+
+    fn compression_dictionary_key(&self, id: u32) -> String {
+        self.key(format!("{}/{}.dict", COMPRESSION_DICTIONARIES_DIRECTORY, id).as_str())
+    }
+
+    /// cipher used to encrypt the uploaded objects - the backups are decrypted with the cipher stored in the index file
+    pub fn set_encryption_cipher(&mut self, cipher: EncryptionCipher) {
+        self.encryption_cipher = cipher;
+    }
+
+    /// storage class, content type and tags of the uploaded backup objects - the storage class is recorded in the index file
+    pub fn set_object_options(&mut self, object_options: ObjectOptions) {
+        self.object_options = object_options;
+    }
+
+    /// the objects larger than `part_size` are uploaded in parts of `part_size` - at least 5MB
+    pub fn set_multipart_part_size(&mut self, part_size: usize) {
+        self.multipart_part_size = part_size.max(MIN_MULTIPART_PART_SIZE);
+    }
+
+    /// store each part once for all the backups - a part already stored (E.g. the rows of an unchanged table) is not uploaded again
+    pub fn set_deduplication(&mut self, enable: bool) {
+        self.deduplication = enable;
+    }
+
+    pub fn deduplication(&self) -> bool {
+        self.deduplication
+    }
+
+    /// the hash of the part and of the way it is stored - the parts compressed or encrypted differently are not shared.
+    /// The encryption key is hashed with the part to not reveal the content of the encrypted parts
+    fn chunk_hash(&self, data: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+
+        if self.enable_compression {
+            hasher.update(format!("{:?}", self.compression_format));
+            if let Some(dictionary) = &self.compression_dictionary {
+                hasher.update(dictionary.id.to_be_bytes());
+            }
+        }
+
+        if let Some(key) = &self.encryption_key {
+            hasher.update(self.encryption_cipher.name());
+            // the chunks encrypted with the static nonce are never shared with the new backups
+            hasher.update("random-nonce");
+            hasher.update(key);
+        }
+
+        hasher.update(data);
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    fn chunk_key(&self, hash: &str) -> String {
+        self.key(format!("{}/{}", CHUNKS_DIRECTORY, hash).as_str())
+    }
+
+    /// delete the objects of the backup `name` stored under `directory_key` and the chunks no other backup shares,
+    /// then remove it from `index_file`
+    fn delete_backup_objects(
+        &self,
+        mut index_file: IndexFile,
+        name: &str,
+        directory_key: &str,
+    ) -> Result<(), Error> {
+        let _ = index_file.check_backup_not_locked(name)?;
+
+        let backup_key = self.key(format!("{}/", directory_key).as_str());
+
+        for object in list_objects(
+            &self.client,
+            self.bucket.as_str(),
+            Some(backup_key.as_str()),
+        )? {
+            delete_object(
+                &self.client,
+                self.bucket.as_str(),
+                object.key().unwrap_or(""),
+            )?;
+        }
+
+        // the chunks of the other backups are kept
+        for chunk in index_file.unshared_chunks(name) {
+            let key = self.chunk_key(chunk.hash.as_str());
+            delete_object(&self.client, self.bucket.as_str(), key.as_str())?;
+        }
+        index_file
+            .backups
+            .retain(|backup| backup.directory_name.as_str() != name);
+
+        self.write_index_file(&index_file)
+    }
+
+    /// the keys of the backup objects in the order of the parts
+    fn object_keys(&self, backup: &Backup) -> Result<Vec<String>, Error> {
+        if let Some(chunks) = &backup.chunks {
+            let mut chunks = chunks.iter().collect::<Vec<_>>();
+            chunks.sort_by_key(|chunk| chunk.part);
+
+            return Ok(chunks
+                .into_iter()
+                .map(|chunk| self.chunk_key(chunk.hash.as_str()))
+                .collect());
+        }
+
+        let backup_key = self.key(backup.directory_key());
+
+        Ok(list_objects(
+            &self.client,
+            self.bucket.as_str(),
+            Some(backup_key.as_str()),
+        )?
+        .into_iter()
+        .filter_map(|object| object.key)
+        .collect())
+    }
+
+    /// the index file entry of a part written by this bridge - the backup size before compression is unknown when the
+    /// part `uncompressed_size` is
+    fn backup_part(
+        &self,
+        size: usize,
+        uncompressed_size: Option<usize>,
+        chunk: Option<BackupChunk>,
+    ) -> Backup {
+        Backup {
+            directory_name: self.root_key.clone(),
+            size,
+            created_at: epoch_millis(),
+            compressed: self.enable_compression,
+            encrypted: self.encryption_key.is_some(),
+            cipher: self.encryption_key.as_ref().map(|_| self.encryption_cipher),
+            uncompressed_size,
+            engine: self.engine,
+            storage_class: self.object_options.storage_class.clone(),
+            compression_dictionary_id: self
+                .compression_dictionary
+                .as_ref()
+                .filter(|_| self.enable_compression)
+                .map(|dictionary| dictionary.id),
+            tables: None,
+            transformers: None,
+            chunks: chunk.map(|chunk| vec![chunk]),
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: self.key_template.as_ref().map(|_| self.directory_key()),
+            random_nonce: self.encryption_key.is_some(),
         }
     }
 
+    /// same bridge reading and writing under `prefix` - E.g. to read the index file of another project
+    pub fn with_prefix(&self, prefix: Option<String>) -> Self {
+        let mut s3 = self.clone();
+        s3.set_prefix(prefix);
+        s3
+    }
+
+    /// the index files of every prefix of the bucket, whatever the configured prefix -
+    /// the prefix is None for the index file at the root of the bucket
+    pub fn all_index_files(&self) -> Result<Vec<(Option<String>, IndexFile)>, Error> {
+        let objects = list_objects(&self.client, self.bucket.as_str(), None)?;
+
+        index_file_prefixes(objects.iter().filter_map(|object| object.key()))
+            .into_iter()
+            .map(|prefix| {
+                let index_file = self.with_prefix(prefix.clone()).index_file()?;
+                Ok((prefix, index_file))
+            })
+            .collect()
+    }
+
+    fn key(&self, key: &str) -> String {
+        prefixed_key(self.prefix.as_deref(), key)
+    }
+
     fn create_index_file(&self) -> Result<IndexFile, Error> {
         match self.index_file() {
             Ok(index_file) => Ok(index_file),
@@ -87,108 +550,400 @@ impl S3 {
 
 impl Connector for S3 {
     fn init(&mut self) -> Result<(), Error> {
+        if self.check_region {
+            let bucket_region = get_bucket_region(&self.client, self.bucket.as_str());
+
+            if let Some(warning) =
+                region_mismatch_warning(self.bucket.as_str(), self.region.as_str(), bucket_region)
+            {
+                warn!("{}", warning);
+            }
+        }
+
         let _ = create_bucket(&self.client, self.bucket.as_str(), self.region.as_str())?;
-        self.create_index_file().map(|_| ())
+        let _ = self.create_index_file()?;
+        self.write_compression_dictionary()
     }
 }
 
 impl Bridge for S3 {
     fn index_file(&self) -> Result<IndexFile, Error> {
-        let object = get_object(&self.client, self.bucket.as_str(), INDEX_FILE_NAME)?;
-        let index_file: IndexFile = serde_json::from_slice(object.as_slice())?;
-        Ok(index_file)
+        let key = self.key(INDEX_FILE_NAME);
+        let legacy_key = self.key(LEGACY_INDEX_FILE_NAME);
+
+        let object = match get_object(&self.client, self.bucket.as_str(), key.as_str()) {
+            Err(S3Error::ObjectDoesNotExist { .. }) => {
+                get_object(&self.client, self.bucket.as_str(), legacy_key.as_str())?
+            }
+            object => object?,
+        };
+
+        IndexFile::from_bytes(object)
     }
 
     fn write_index_file(&self, index_file: &IndexFile) -> Result<(), Error> {
-        let index_file_json = serde_json::to_vec(index_file)?;
+        let key = self.key(INDEX_FILE_NAME);
 
         create_object(
             &self.client,
             self.bucket.as_str(),
-            INDEX_FILE_NAME,
-            index_file_json,
+            key.as_str(),
+            index_file.to_bytes()?,
+            &ObjectOptions::default(),
         )
         .map_err(|err| Error::from(err))
     }
 
+    fn check_write_access(&self) -> Result<(), Error> {
+        let key = self.key(PREFLIGHT_FILE_NAME);
+
+        create_object(
+            &self.client,
+            self.bucket.as_str(),
+            key.as_str(),
+            b"preflight".to_vec(),
+            &ObjectOptions::default(),
+        )?;
+
+        delete_object(&self.client, self.bucket.as_str(), key.as_str()).map_err(Error::from)
+    }
+
+    fn acquire_run_lock(&self) -> Result<(), Error> {
+        let key = self.key(RUN_LOCK_FILE_NAME);
+        let now = epoch_millis();
+
+        // S3 has no conditional write - the lock is advisory and two backups starting at once can both take it
+        let current_lock =
+            run_lock_of(get_object(&self.client, self.bucket.as_str(), key.as_str()))?;
+
+        if let Some(lock) = &current_lock {
+            lock.check(self.root_key.as_str(), now)?;
+        }
+
+        let lock = RunLock {
+            holder: self.root_key.clone(),
+            // a renewed lock keeps the time it was acquired
+            acquired_at: current_lock
+                .filter(|lock| lock.holder == self.root_key)
+                .map_or(now, |lock| lock.acquired_at),
+            expires_at: now + RUN_LOCK_TTL.as_millis(),
+        };
+
+        create_object(
+            &self.client,
+            self.bucket.as_str(),
+            key.as_str(),
+            serde_json::to_vec(&lock)?,
+            &ObjectOptions::default(),
+        )
+        .map_err(Error::from)
+    }
+
+    fn release_run_lock(&self) -> Result<(), Error> {
+        let key = self.key(RUN_LOCK_FILE_NAME);
+
+        let object = match get_object(&self.client, self.bucket.as_str(), key.as_str()) {
+            Ok(object) => object,
+            Err(_) => return Ok(()),
+        };
+
+        // the lock has expired and been taken by another backup
+        match serde_json::from_slice::<RunLock>(object.as_slice()) {
+            Ok(lock) if lock.holder != self.root_key => Ok(()),
+            _ => {
+                delete_object(&self.client, self.bucket.as_str(), key.as_str()).map_err(Error::from)
+            }
+        }
+    }
+
     fn write(&self, file_part: u16, data: Bytes) -> Result<(), Error> {
+        self.write_with_progress(file_part, data, &mut |_, _| {})
+    }
+
+    fn write_with_progress(
+        &self,
+        file_part: u16,
+        data: Bytes,
+        progress_callback: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), Error> {
+        let uncompressed_size = data.len();
+
+        let chunk_hash = match self.deduplication {
+            true => Some(self.chunk_hash(data.as_slice())),
+            false => None,
+        };
+
+        if let Some(hash) = &chunk_hash {
+            let mut index_file = self.index_file()?;
+
+            // the part is already stored - by this backup or a previous one
+            if let Some(chunk) = index_file.chunk(hash.as_str()).cloned() {
+                info!(
+                    "part {} is already stored in '{}'",
+                    file_part,
+                    self.chunk_key(hash)
+                );
+
+                let chunk_size = chunk.size;
+                index_file.add_part(self.backup_part(
+                    chunk_size,
+                    Some(uncompressed_size),
+                    Some(BackupChunk {
+                        part: file_part,
+                        uncompressed_size: Some(uncompressed_size),
+                        ..chunk
+                    }),
+                ));
+
+                self.written_sizes.add_deduplicated(uncompressed_size);
+                return self.write_index_file(&index_file);
+            }
+        }
+
         // compress data?
-        let data = if self.enable_compression {
-            compress(data)?
-        } else {
-            data
+        let data = match (self.enable_compression, &self.compression_dictionary) {
+            (true, Some(dictionary)) => self.timings.measure(Stage::Compress, || {
+                compress_with_dictionary(data, dictionary)
+            })?,
+            (true, None) => self
+                .timings
+                .measure(Stage::Compress, || compress(data, self.compression_format))?,
+            (false, _) => data,
         };
 
         // encrypt data?
         let data = match &self.encryption_key {
-            Some(key) => encrypt(data, key.as_str())?,
+            Some(key) => self.timings.measure(Stage::Encrypt, || {
+                encrypt(data, key.as_str(), self.encryption_cipher)
+            })?,
             None => data,
         };
 
         let data_size = data.len();
-        let key = format!("{}/{}.dump", self.root_key.as_str(), file_part);
+        let key = match &chunk_hash {
+            Some(hash) => self.chunk_key(hash.as_str()),
+            None => self.key(format!("{}/{}.dump", self.directory_key(), file_part).as_str()),
+        };
 
         info!("upload object '{}' part {} on", key.as_str(), file_part);
 
-        let _ = create_object(&self.client, self.bucket.as_str(), key.as_str(), data)?;
+        let upload_start = Instant::now();
+
+        let _ = if data_size > self.multipart_part_size {
+            let mut uploader = S3MultipartUploader {
+                client: &self.client,
+                bucket: self.bucket.as_str(),
+                key: key.as_str(),
+                options: &self.object_options,
+            };
+
+            multipart_upload(
+                &mut uploader,
+                data,
+                self.multipart_part_size,
+                UPLOAD_PART_MAX_RETRIES,
+                UPLOAD_PART_RETRY_BACKOFF,
+                |uploaded_bytes| {
+                    info!(
+                        "object '{}' part {}: {}/{} bytes uploaded",
+                        key.as_str(),
+                        file_part,
+                        uploaded_bytes,
+                        data_size
+                    );
+                    progress_callback(uploaded_bytes, data_size);
+                },
+            )
+            .map_err(|err| {
+                error!("{}", err);
+                Error::from(FailedObjectUpload {
+                    bucket: self.bucket.as_str(),
+                    key: key.as_str(),
+                })
+            })?
+        } else {
+            create_object(
+                &self.client,
+                self.bucket.as_str(),
+                key.as_str(),
+                data,
+                &self.object_options,
+            )?
+        };
 
         // update index file
         let mut index_file = self.index_file()?;
 
-        let mut new_backup = Backup {
-            directory_name: self.root_key.clone(),
-            size: 0,
-            created_at: epoch_millis(),
-            compressed: self.enable_compression,
-            encrypted: self.encryption_key.is_some(),
-        };
+        index_file.add_part(self.backup_part(
+            data_size,
+            Some(uncompressed_size),
+            chunk_hash.map(|hash| BackupChunk {
+                part: file_part,
+                hash,
+                size: data_size,
+                uncompressed_size: Some(uncompressed_size),
+            }),
+        ));
+
+        // save index file
+        let result = self.write_index_file(&index_file);
+        self.timings.add(Stage::Upload, upload_start.elapsed());
+        self.written_sizes.add(uncompressed_size, data_size);
+
+        result
+    }
 
-        // find or create Backup
-        let mut backup = index_file
+    fn write_backup_content(&self, content: BackupContent) -> Result<(), Error> {
+        let mut index_file = self.index_file()?;
+
+        match index_file
             .backups
             .iter_mut()
-            .find(|b| b.directory_name.as_str() == self.root_key.as_str())
-            .unwrap_or(&mut new_backup);
-
-        if backup.size == 0 {
-            // it means it's a new backup.
-            // We need to add it into the index_file.backups
-            new_backup.size = data_size;
-            index_file.backups.push(new_backup);
-        } else {
-            // update total backup size
-            backup.size = backup.size + data_size;
+            .find(|backup| backup.directory_name == self.root_key)
+        {
+            Some(backup) => {
+                backup.tables = content.tables;
+                backup.transformers = Some(content.transformers);
+                backup.table_checksums = content.table_checksums;
+                backup.table_parts = content.table_parts;
+                self.write_index_file(&index_file)
+            }
+            // nothing has been written
+            None => Ok(()),
+        }
+    }
+
+    fn unchanged_table_chunks(
+        &self,
+        table_checksums: &BTreeMap<String, String>,
+        transformers: &[String],
+    ) -> Result<BTreeMap<String, Vec<BackupChunk>>, Error> {
+        // the parts are only shared once stored as chunks
+        if !self.deduplication {
+            return Ok(BTreeMap::new());
+        }
+
+        Ok(self.index_file()?.unchanged_table_chunks(
+            &self.backup_part(0, None, None),
+            table_checksums,
+            transformers,
+        ))
+    }
+
+    fn write_chunks(&self, first_part: u16, chunks: Vec<BackupChunk>) -> Result<(), Error> {
+        let mut index_file = self.index_file()?;
+
+        for (idx, chunk) in chunks.into_iter().enumerate() {
+            let part = first_part + idx as u16;
+            info!(
+                "part {} is shared with a previous backup in '{}'",
+                part,
+                self.chunk_key(chunk.hash.as_str())
+            );
+
+            if let Some(uncompressed_size) = chunk.uncompressed_size {
+                self.written_sizes.add_deduplicated(uncompressed_size);
+            }
+
+            index_file.add_part(self.backup_part(
+                chunk.size,
+                chunk.uncompressed_size,
+                Some(BackupChunk { part, ..chunk }),
+            ));
         }
 
-        // save index file
         self.write_index_file(&index_file)
     }
 
-    fn read<'a, F>(&self, options: &ReadOptions, mut data_callback: F) -> Result<(), Error>
+    fn abort_write(&self) -> Result<(), Error> {
+        self.delete_backup_objects(
+            self.index_file()?,
+            self.root_key.as_str(),
+            self.directory_key().as_str(),
+        )
+    }
+
+    fn delete_backup(&self, name: &str) -> Result<(), Error> {
+        let index_file = self.index_file()?;
+        let directory_key = match index_file
+            .backups
+            .iter()
+            .find(|backup| backup.directory_name == name)
+        {
+            Some(backup) => backup.directory_key().to_string(),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("backup '{}' not found", name),
+                ))
+            }
+        };
+
+        self.delete_backup_objects(index_file, name, directory_key.as_str())
+    }
+
+    fn read<'a, F>(&self, options: &ReadOptions, data_callback: F) -> Result<(), Error>
+    where
+        F: FnMut(Bytes),
+    {
+        self.read_objects(options, true, true, data_callback)
+    }
+
+    fn read_objects<F>(
+        &self,
+        options: &ReadOptions,
+        decrypt_objects: bool,
+        decompress_objects: bool,
+        mut data_callback: F,
+    ) -> Result<(), Error>
     where
         F: FnMut(Bytes),
     {
         let mut index_file = self.index_file()?;
         let backup = index_file.find_backup(options)?;
+        let _ = check_not_archived(backup)?;
 
-        for object in list_objects(
-            &self.client,
-            self.bucket.as_str(),
-            Some(backup.directory_name.as_str()),
-        )? {
-            let data = get_object(&self.client, self.bucket.as_str(), object.key().unwrap())?;
+        if decompress_objects && !decrypt_objects && backup.encrypted {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "backup '{}' is encrypted - it can't be decompressed without being decrypted",
+                    backup.directory_name
+                ),
+            ));
+        }
 
-            // decrypt data?
-            let data = match &self.encryption_key {
-                Some(key) => decrypt(data, key.as_str())?,
-                None => data,
+        let compression_dictionary = match (decompress_objects, backup.compression_dictionary_id) {
+            (true, Some(id)) => Some(self.compression_dictionary(id)?),
+            _ => None,
+        };
+
+        for key in self.object_keys(backup)? {
+            let data = get_object_with_resume(&self.client, self.bucket.as_str(), key.as_str())?;
+
+            // decrypt data? - the index file tells how the backup was written
+            let data = match (decrypt_objects && backup.encrypted, &self.encryption_key) {
+                (true, Some(key)) => decrypt_backup_object(data, key.as_str(), backup)?,
+                (true, None) => {
+                    return Err(Error::new(
+                        ErrorKind::Other,
+                        format!(
+                            "backup '{}' is encrypted - missing <encryption_key> in the configuration file",
+                            backup.directory_name
+                        ),
+                    ))
+                }
+                (false, _) => data,
             };
 
             // decompress data?
-            let data = if self.enable_compression {
-                decompress(data)?
-            } else {
-                data
+            let data = match (
+                decompress_objects && backup.compressed,
+                &compression_dictionary,
+            ) {
+                (true, Some(dictionary)) => decompress_with_dictionary(data, dictionary)?,
+                (true, None) => decompress(data)?,
+                (false, _) => data,
             };
 
             data_callback(data);
@@ -197,13 +952,601 @@ impl Bridge for S3 {
         Ok(())
     }
 
-    fn set_encryption_key(&mut self, key: Option<String>) {
-        self.encryption_key = key;
+    fn read_object_header(&self, options: &ReadOptions, length: usize) -> Result<Bytes, Error> {
+        let mut index_file = self.index_file()?;
+        let backup = index_file.find_backup(options)?;
+        let _ = check_not_archived(backup)?;
+
+        // every object is encrypted on its own - the first one tells how they all are written
+        let key = self
+            .object_keys(backup)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("backup '{}' has no objects", backup.directory_name),
+                )
+            })?;
+
+        get_object_header(&self.client, self.bucket.as_str(), key.as_str(), length)
     }
 
-    fn set_compression(&mut self, enable: bool) {
-        self.enable_compression = enable;
-    }
+    fn rename_backup(&self, name: &str, new_name: &str) -> Result<(), Error> {
+        if [
+            INDEX_FILE_NAME,
+            LEGACY_INDEX_FILE_NAME,
+            COMPRESSION_DICTIONARIES_DIRECTORY,
+            CHUNKS_DIRECTORY,
+            RUN_LOCK_FILE_NAME,
+        ]
+        .contains(&new_name)
+        {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("'{}' is reserved by RepliByte", new_name),
+            ));
+        }
+
+        let mut index_file = self.index_file()?;
+        // E.g. `2024/01/15/backup-1705276800000` for a templated key
+        let old_directory_key = index_file
+            .backups
+            .iter()
+            .find(|backup| backup.directory_name == name)
+            .map(|backup| backup.directory_key().to_string())
+            .unwrap_or_else(|| name.to_string());
+        let backup = index_file.rename_backup(name, new_name)?;
+
+        // the parts of a deduplicated backup are not stored in its directory
+        let keys = match backup.chunks {
+            Some(_) => vec![],
+            None => {
+                let _ = check_not_archived(backup)?;
+
+                let backup_key = self.key(format!("{}/", old_directory_key).as_str());
+                list_objects(
+                    &self.client,
+                    self.bucket.as_str(),
+                    Some(backup_key.as_str()),
+                )?
+                .into_iter()
+                .filter_map(|object| object.key)
+                .collect::<Vec<_>>()
+            }
+        };
+
+        let old_prefix = self.key(format!("{}/", old_directory_key).as_str());
+        let new_prefix = self.key(format!("{}/", backup.directory_key()).as_str());
+
+        for key in &keys {
+            let new_key = format!("{}{}", new_prefix, &key[old_prefix.len()..]);
+            copy_object(
+                &self.client,
+                self.bucket.as_str(),
+                key.as_str(),
+                new_key.as_str(),
+                backup.storage_class.as_deref(),
+            )?;
+        }
+
+        // the backup is readable under its new name before its old objects are deleted
+        let _ = self.write_index_file(&index_file)?;
+
+        for key in &keys {
+            delete_object(&self.client, self.bucket.as_str(), key.as_str())?;
+        }
+
+        Ok(())
+    }
+
+    fn set_encryption_key(&mut self, key: Option<String>) {
+        self.encryption_key = key;
+    }
+
+    fn set_compression(&mut self, enable: bool) {
+        self.enable_compression = enable;
+    }
+}
+
+fn s3_client(
+    sdk_config: &SdkConfig,
+    endpoint: &Endpoint,
+    connector: Option<DynConnector>,
+) -> Client {
+    let s3_config_builder = aws_sdk_s3::config::Builder::from(sdk_config);
+
+    let s3_config = match endpoint {
+        Endpoint::Default => s3_config_builder.build(),
+        Endpoint::Custom(url) => match http::Uri::from_str(url.as_str()) {
+            Ok(uri) => s3_config_builder
+                .endpoint_resolver(SdkEndpoint::immutable(uri))
+                .build(),
+            Err(err) => s3_config_builder.build(),
+        },
+    };
+
+    match connector {
+        Some(connector) => Client::from_conf_conn(s3_config, connector),
+        None => Client::from_conf(s3_config),
+    }
+}
+
+/// HTTPS connector trusting the system certificate authorities and the ones of `ca_bundle` - through `proxy` when there is one
+fn http_connector(proxy: Option<&str>, ca_bundle: Option<&str>) -> Result<DynConnector, Error> {
+    let tls_config = tls_config(ca_bundle)?;
+
+    let proxy = match proxy {
+        Some(proxy) => proxy,
+        None => {
+            let connector = HttpsConnectorBuilder::new()
+                .with_tls_config(tls_config)
+                .https_or_http()
+                .enable_http1()
+                .build();
+
+            return Ok(DynConnector::new(Adapter::builder().build(connector)));
+        }
+    };
+
+    let proxy_uri = http::Uri::from_str(proxy).map_err(|err| {
+        Error::new(
+            ErrorKind::Other,
+            format!("invalid proxy '{}': {}", proxy, err),
+        )
+    })?;
+
+    if proxy_uri.host().is_none() || proxy_uri.scheme_str() == Some("https") {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "invalid proxy '{}' - use an HTTP proxy, E.g. http://proxy.acme.com:3128",
+                proxy
+            ),
+        ));
+    }
+
+    let connector = ProxyConnector {
+        proxy: proxy_uri,
+        tls: TlsConnector::from(Arc::new(tls_config)),
+    };
+
+    Ok(DynConnector::new(Adapter::builder().build(connector)))
+}
+
+fn tls_config(ca_bundle: Option<&str>) -> Result<rustls::ClientConfig, Error> {
+    let mut root_store = rustls::RootCertStore::empty();
+
+    for certificate in rustls_native_certs::load_native_certs()? {
+        let _ = root_store.add(&rustls::Certificate(certificate.0));
+    }
+
+    if let Some(ca_bundle) = ca_bundle {
+        let file = File::open(ca_bundle).map_err(|err| {
+            Error::new(
+                ErrorKind::Other,
+                format!("can't read the CA bundle '{}': {}", ca_bundle, err),
+            )
+        })?;
+
+        let certificates = rustls_pemfile::certs(&mut BufReader::new(file))?;
+        let (added, _) = root_store.add_parsable_certificates(certificates.as_slice());
+
+        if added == 0 {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("no certificate found in the CA bundle '{}'", ca_bundle),
+            ));
+        }
+    }
+
+    Ok(rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth())
+}
+
+/// connector opening a `CONNECT` tunnel through an HTTP proxy, then a TLS session with the host for the `https` URIs
+#[derive(Clone)]
+struct ProxyConnector {
+    proxy: http::Uri,
+    tls: TlsConnector,
+}
+
+impl ProxyConnector {
+    async fn connect(self, uri: http::Uri) -> Result<MaybeHttpsStream<TcpStream>, Error> {
+        let host = uri
+            .host()
+            .ok_or_else(|| Error::new(ErrorKind::Other, format!("no host in '{}'", uri)))?
+            .to_string();
+        let is_https = uri.scheme_str() == Some("https");
+        let port = uri.port_u16().unwrap_or(if is_https { 443 } else { 80 });
+
+        let proxy_host = self.proxy.host().unwrap_or_default();
+        let proxy_port = self.proxy.port_u16().unwrap_or(80);
+        let mut stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+
+        let authority = format!("{}:{}", host, port);
+        let request = format!(
+            "CONNECT {} HTTP/1.1\r\nHost: {}\r\n\r\n",
+            authority, authority
+        );
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut response = vec![];
+        let mut buf = [0u8; 1024];
+        while !response.windows(4).any(|window| window == b"\r\n\r\n") {
+            let len = stream.read(&mut buf).await?;
+            if len == 0 || response.len() > MAX_CONNECT_RESPONSE_LENGTH {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("proxy '{}' closed the tunnel to {}", self.proxy, authority),
+                ));
+            }
+
+            response.extend_from_slice(&buf[..len]);
+        }
+
+        match connect_status(response.as_slice()) {
+            Some(status) if (200..300).contains(&status) => {}
+            status => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!(
+                        "proxy '{}' refused the tunnel to {} (status {:?})",
+                        self.proxy, authority, status
+                    ),
+                ))
+            }
+        }
+
+        if !is_https {
+            return Ok(MaybeHttpsStream::Http(stream));
+        }
+
+        let server_name = rustls::ServerName::try_from(host.as_str())
+            .map_err(|err| Error::new(ErrorKind::Other, format!("{}: {}", host, err)))?;
+        let stream = self.tls.connect(server_name, stream).await?;
+
+        Ok(MaybeHttpsStream::Https(stream))
+    }
+}
+
+impl hyper::service::Service<http::Uri> for ProxyConnector {
+    type Response = MaybeHttpsStream<TcpStream>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: http::Uri) -> Self::Future {
+        Box::pin(self.clone().connect(uri))
+    }
+}
+
+/// status code of the proxy response to `CONNECT` - E.g. 200 for `HTTP/1.1 200 Connection established`
+fn connect_status(response: &[u8]) -> Option<u16> {
+    let status_line = response.split(|byte| *byte == b'\n').next()?;
+    let status_line = std::str::from_utf8(status_line).ok()?;
+    let mut parts = status_line.split_whitespace();
+
+    match parts.next() {
+        Some(version) if version.starts_with("HTTP/") => parts.next()?.parse().ok(),
+        _ => None,
+    }
+}
+
+fn sdk_config(region: &str, credentials: Option<(String, String)>, env: Env) -> SdkConfig {
+    match credentials {
+        Some((access_key_id, secret_access_key)) => block_on(
+            aws_config::from_env()
+                .configure(ProviderConfig::default().with_env(Env::from_slice(&[
+                    ("AWS_ACCESS_KEY_ID", access_key_id.as_str()),
+                    ("AWS_SECRET_ACCESS_KEY", secret_access_key.as_str()),
+                    ("AWS_REGION", region),
+                ])))
+                .load(),
+        ),
+        None => {
+            info!("no access key provided, use the AWS default credential chain");
+            block_on(
+                aws_config::from_env()
+                    .configure(ProviderConfig::default().with_env(env))
+                    .region(Region::new(region.to_string()))
+                    .load(),
+            )
+        }
+    }
+}
+
+/// Read-only bridge reading the objects under a raw key of the bucket without the index file - E.g. when it is lost or corrupt.
+/// The key is a single object (E.g. `backup-1647706359405/1.dump`) or a directory with all the parts of a backup.
+/// The compression, the encryption key and the cipher can't be read from the index file, they must be set on the `S3` bridge
+pub struct S3Object {
+    s3: S3,
+    key: String,
+}
+
+impl S3Object {
+    pub fn new<S: Into<String>>(s3: S3, key: S) -> Self {
+        S3Object {
+            s3,
+            key: key.into(),
+        }
+    }
+
+    /// the keys of the objects and their size ordered by part number - E.g. `1.dump`, `2.dump`... `10.dump`
+    fn objects(&self) -> Result<Vec<(String, usize)>, Error> {
+        let directory = format!("{}/", self.key.trim_end_matches('/'));
+
+        let mut objects = list_objects(
+            &self.s3.client,
+            self.s3.bucket.as_str(),
+            Some(self.key.as_str()),
+        )?
+        .into_iter()
+        .filter_map(|object| {
+            let size = object.size() as usize;
+            object.key.map(|key| (key, size))
+        })
+        .filter(|(key, _)| *key == self.key || key.starts_with(directory.as_str()))
+        .collect::<Vec<_>>();
+
+        objects.sort_by_key(|(key, _)| object_part_number(key.as_str()));
+
+        match objects.is_empty() {
+            true => Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "no object found under '{}/{}'",
+                    self.s3.bucket.as_str(),
+                    self.key
+                ),
+            )),
+            false => Ok(objects),
+        }
+    }
+}
+
+/// placeholders of the bridge `key_template` - `{name}` keeps the keys of the backups apart
+const KEY_TEMPLATE_PLACEHOLDERS: [&str; 4] = ["{year}", "{month}", "{day}", "{name}"];
+
+/// fails when the template has an unknown placeholder or does not end with `{name}` - E.g. `{year}/{month}/{day}/{name}`
+fn check_key_template(key_template: &str) -> Result<(), Error> {
+    if !key_template.ends_with("{name}") || key_template.starts_with('/') {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "invalid key_template '{}' - it must end with {{name}} and not start with '/', E.g. {{year}}/{{month}}/{{day}}/{{name}}",
+                key_template
+            ),
+        ));
+    }
+
+    let mut rest = key_template;
+    while let Some(start_idx) = rest.find('{') {
+        let placeholder = match rest[start_idx..].find('}') {
+            Some(end_idx) => &rest[start_idx..start_idx + end_idx + 1],
+            None => &rest[start_idx..],
+        };
+
+        if !KEY_TEMPLATE_PLACEHOLDERS.contains(&placeholder) {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "unknown placeholder '{}' in key_template - use {}",
+                    placeholder,
+                    KEY_TEMPLATE_PLACEHOLDERS.join(", ")
+                ),
+            ));
+        }
+
+        rest = &rest[start_idx + placeholder.len()..];
+    }
+
+    Ok(())
+}
+
+/// the key of the backup `name` created at `created_at` (UTC) - E.g. `2024/01/15/backup-1705276800000`
+fn resolve_key_template(key_template: &str, name: &str, created_at: u128) -> String {
+    let (year, month, day) = utc_date(created_at);
+
+    key_template
+        .replace("{year}", year.to_string().as_str())
+        .replace("{month}", format!("{:02}", month).as_str())
+        .replace("{day}", format!("{:02}", day).as_str())
+        .replace("{name}", name)
+}
+
+/// the part number of a backup object - E.g. 10 for `backup-1647706359405/10.dump`
+fn object_part_number(key: &str) -> Option<u16> {
+    let name = key.rsplit('/').next().unwrap_or(key);
+    name.strip_suffix(".dump")?.parse::<u16>().ok()
+}
+
+impl Connector for S3Object {
+    fn init(&mut self) -> Result<(), Error> {
+        self.objects().map(|_| ())
+    }
+}
+
+impl Bridge for S3Object {
+    fn index_file(&self) -> Result<IndexFile, Error> {
+        let size = self.objects()?.iter().map(|(_, size)| size).sum();
+
+        Ok(IndexFile {
+            backups: vec![Backup {
+                directory_name: self.key.clone(),
+                size,
+                created_at: 0,
+                compressed: self.s3.enable_compression,
+                encrypted: self.s3.encryption_key.is_some(),
+                cipher: self
+                    .s3
+                    .encryption_key
+                    .as_ref()
+                    .map(|_| self.s3.encryption_cipher),
+                uncompressed_size: None,
+                engine: None,
+                storage_class: None,
+                compression_dictionary_id: None,
+                tables: None,
+                transformers: None,
+                chunks: None,
+                locked: false,
+                table_checksums: None,
+                table_parts: None,
+                key: None,
+                random_nonce: false,
+            }],
+        })
+    }
+
+    fn write_index_file(&self, _: &IndexFile) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn check_write_access(&self) -> Result<(), Error> {
+        // nothing is written - the objects only need to be readable
+        self.objects().map(|_| ())
+    }
+
+    fn write(&self, _: u16, _: Bytes) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn abort_write(&self) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn read<F>(&self, _: &ReadOptions, mut data_callback: F) -> Result<(), Error>
+    where
+        F: FnMut(Bytes),
+    {
+        for (key, _) in self.objects()? {
+            let data =
+                get_object_with_resume(&self.s3.client, self.s3.bucket.as_str(), key.as_str())?;
+
+            // decrypt data?
+            let data = match &self.s3.encryption_key {
+                Some(encryption_key) => {
+                    decrypt_without_index(data, encryption_key.as_str(), self.s3.encryption_cipher)?
+                }
+                None => data,
+            };
+
+            // decompress data?
+            let data = match self.s3.enable_compression {
+                true => decompress(data)?,
+                false => data,
+            };
+
+            data_callback(data);
+        }
+
+        Ok(())
+    }
+
+    fn set_compression(&mut self, enable: bool) {
+        self.s3.set_compression(enable);
+    }
+
+    fn set_encryption_key(&mut self, key: Option<String>) {
+        self.s3.set_encryption_key(key);
+    }
+}
+
+fn read_only_error() -> Error {
+    Error::new(
+        ErrorKind::Other,
+        "a backup read from its object key is read-only",
+    )
+}
+
+/// an archived backup (E.g. GLACIER) can't be downloaded until it is restored from the archive
+fn check_not_archived(backup: &Backup) -> Result<(), Error> {
+    match &backup.storage_class {
+        Some(storage_class) if ARCHIVE_STORAGE_CLASSES.contains(&storage_class.as_str()) => {
+            Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "backup '{}' is stored in the {} storage class - it needs to be restored from the archive (E.g. `aws s3api restore-object`) before being restored",
+                    backup.directory_name, storage_class
+                ),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn prefixed_key(prefix: Option<&str>, key: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}/{}", prefix, key),
+        None => key.to_string(),
+    }
+}
+
+/// the prefixes of the index files found in `keys` - E.g. `project-a/metadata.json.zst` -> `project-a`
+fn index_file_prefixes<'a, I>(keys: I) -> Vec<Option<String>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut prefixes = keys
+        .into_iter()
+        .filter_map(|key| {
+            key.strip_suffix(INDEX_FILE_NAME)
+                .or_else(|| key.strip_suffix(LEGACY_INDEX_FILE_NAME))
+        })
+        .filter_map(|prefix| match prefix {
+            "" => Some(None),
+            prefix => prefix
+                .strip_suffix('/')
+                .map(|prefix| Some(prefix.to_string())),
+        })
+        .collect::<Vec<_>>();
+
+    prefixes.sort();
+    // a prefix with both a compressed and a legacy index file
+    prefixes.dedup();
+    prefixes
+}
+
+/// region of a bucket from its location constraint - E.g. there is no constraint for `us-east-1`
+fn bucket_region(location_constraint: Option<&str>) -> String {
+    match location_constraint {
+        None | Some("") => "us-east-1".to_string(),
+        // legacy constraint of the buckets created in Ireland
+        Some("EU") => "eu-west-1".to_string(),
+        Some(region) => region.to_string(),
+    }
+}
+
+/// None when the bucket does not exist yet or its location can't be read
+fn get_bucket_region(client: &Client, bucket: &str) -> Option<String> {
+    let output = block_on(client.get_bucket_location().bucket(bucket).send()).ok()?;
+
+    Some(bucket_region(
+        output
+            .location_constraint()
+            .map(|location_constraint| location_constraint.as_str()),
+    ))
+}
+
+/// the S3 calls fail with opaque errors when the bucket is in another region than the configured one
+fn region_mismatch_warning(
+    bucket: &str,
+    region: &str,
+    bucket_region: Option<String>,
+) -> Option<String> {
+    match bucket_region {
+        Some(bucket_region) if bucket_region != region => Some(format!(
+            "bucket '{}' is in the '{}' region but the configured region is '{}' - set `region: {}` in the bridge configuration",
+            bucket, bucket_region, region, bucket_region
+        )),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -215,6 +1558,7 @@ enum S3Error<'a> {
     FailedObjectDownload { bucket: &'a str, key: &'a str },
     FailedObjectUpload { bucket: &'a str, key: &'a str },
     FailedToDeleteObject { bucket: &'a str, key: &'a str },
+    FailedObjectCopy { bucket: &'a str, key: &'a str },
 }
 
 impl<'a> From<S3Error<'a>> for Error {
@@ -260,6 +1604,13 @@ impl<'a> From<S3Error<'a>> for Error {
                 ErrorKind::Other,
                 format!("failed to delete object '{}/{}'", bucket, object),
             ),
+            S3Error::FailedObjectCopy {
+                bucket,
+                key: object,
+            } => Error::new(
+                ErrorKind::Other,
+                format!("failed to copy object '{}/{}'", bucket, object),
+            ),
         }
     }
 }
@@ -327,6 +1678,7 @@ fn create_object<'a>(
     bucket: &'a str,
     key: &'a str,
     object: Vec<u8>,
+    options: &ObjectOptions,
 ) -> Result<(), S3Error<'a>> {
     let result = block_on(
         client
@@ -334,6 +1686,9 @@ fn create_object<'a>(
             .bucket(bucket)
             .key(key)
             .body(ByteStream::from(object))
+            .set_storage_class(options.storage_class.as_deref().map(StorageClass::from))
+            .set_content_type(options.content_type.clone())
+            .set_tagging(options.tagging.clone())
             // TODO: set metadata etag to validate upload on the S3 side
             .send(),
     );
@@ -345,77 +1700,466 @@ fn create_object<'a>(
     Ok(())
 }
 
-fn get_object<'a>(client: &Client, bucket: &'a str, key: &'a str) -> Result<Vec<u8>, S3Error<'a>> {
-    let result = block_on(client.get_object().bucket(bucket).key(key).send());
+/// copy the object `key` into `new_key` in the same bucket - the storage class is not copied by S3
+fn copy_object<'a>(
+    client: &Client,
+    bucket: &'a str,
+    key: &'a str,
+    new_key: &str,
+    storage_class: Option<&str>,
+) -> Result<(), S3Error<'a>> {
+    let result = block_on(
+        client
+            .copy_object()
+            .bucket(bucket)
+            .copy_source(format!("{}/{}", bucket, key))
+            .key(new_key)
+            .set_storage_class(storage_class.map(StorageClass::from))
+            .send(),
+    );
 
     match result {
-        Ok(file) => match block_on(file.body.collect()) {
-            Ok(data) => Ok(data.into_bytes().to_vec()),
-            Err(_) => Err(S3Error::FailedObjectDownload { bucket, key }),
-        },
-        Err(_) => Err(S3Error::ObjectDoesNotExist { bucket, key }),
+        Ok(_) => Ok(()),
+        Err(_) => Err(S3Error::FailedObjectCopy { bucket, key }),
     }
 }
 
-fn list_objects<'a>(
-    client: &Client,
+/// the calls of a multipart upload - abstracted to check the upload without S3
+trait MultipartUploader {
+    /// start the multipart upload and return its id
+    fn create(&mut self) -> Result<String, Error>;
+    /// upload the part and return its ETag
+    fn upload_part(
+        &mut self,
+        upload_id: &str,
+        part_number: i32,
+        data: &[u8],
+    ) -> Result<String, Error>;
+    /// `parts` are the part numbers and ETags ordered by part number
+    fn complete(&mut self, upload_id: &str, parts: Vec<(i32, String)>) -> Result<(), Error>;
+    /// delete the uploaded parts
+    fn abort(&mut self, upload_id: &str) -> Result<(), Error>;
+}
+
+struct S3MultipartUploader<'a> {
+    client: &'a Client,
     bucket: &'a str,
-    path: Option<&'a str>,
-) -> Result<Vec<Object>, S3Error<'a>> {
-    let objects = block_on(client.list_objects_v2().bucket(bucket).send());
-    let objects = match objects {
-        Ok(objects) => objects,
-        Err(err) => {
-            error!("{}", err);
-            return Err(S3Error::FailedToListObjects { bucket });
-        }
-    };
+    key: &'a str,
+    options: &'a ObjectOptions,
+}
 
-    // FIXME max objects listed is 1000 -> pagination?
+impl<'a> MultipartUploader for S3MultipartUploader<'a> {
+    fn create(&mut self) -> Result<String, Error> {
+        let output = block_on(
+            self.client
+                .create_multipart_upload()
+                .bucket(self.bucket)
+                .key(self.key)
+                .set_storage_class(
+                    self.options
+                        .storage_class
+                        .as_deref()
+                        .map(StorageClass::from),
+                )
+                .set_content_type(self.options.content_type.clone())
+                .set_tagging(self.options.tagging.clone())
+                .send(),
+        )
+        .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
 
-    let objects = objects.contents.unwrap_or(Vec::new());
-    if path.is_none() {
-        return Ok(objects);
+        output
+            .upload_id()
+            .map(|upload_id| upload_id.to_string())
+            .ok_or_else(|| Error::new(ErrorKind::Other, "missing multipart upload id"))
     }
 
-    let path = path.unwrap();
-    let mut objects = objects
-        .into_iter()
-        .filter(|object| match object.key() {
-            Some(key) => key.starts_with(path),
-            None => false,
-        })
-        .collect::<Vec<_>>();
-
-    // sort by key
-    objects.sort_by(|a, b| a.key.cmp(&b.key));
-
-    Ok(objects)
-}
+    fn upload_part(
+        &mut self,
+        upload_id: &str,
+        part_number: i32,
+        data: &[u8],
+    ) -> Result<String, Error> {
+        let output = block_on(
+            self.client
+                .upload_part()
+                .bucket(self.bucket)
+                .key(self.key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(data.to_vec()))
+                .send(),
+        )
+        .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
 
-fn delete_object<'a>(client: &Client, bucket: &'a str, key: &'a str) -> Result<(), S3Error<'a>> {
-    let _ = get_object(client, bucket, key)?;
+        output
+            .e_tag()
+            .map(|e_tag| e_tag.to_string())
+            .ok_or_else(|| Error::new(ErrorKind::Other, "missing ETag of the uploaded part"))
+    }
 
-    let result = block_on(client.delete_object().bucket(bucket).key(key).send());
+    fn complete(&mut self, upload_id: &str, parts: Vec<(i32, String)>) -> Result<(), Error> {
+        let parts = parts
+            .into_iter()
+            .map(|(part_number, e_tag)| {
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .e_tag(e_tag)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        block_on(
+            self.client
+                .complete_multipart_upload()
+                .bucket(self.bucket)
+                .key(self.key)
+                .upload_id(upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .send(),
+        )
+        .map(|_| ())
+        .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))
+    }
 
-    match result {
-        Ok(_) => Ok(()),
-        Err(_) => Err(S3Error::FailedToDeleteObject { bucket, key }),
+    fn abort(&mut self, upload_id: &str) -> Result<(), Error> {
+        block_on(
+            self.client
+                .abort_multipart_upload()
+                .bucket(self.bucket)
+                .key(self.key)
+                .upload_id(upload_id)
+                .send(),
+        )
+        .map(|_| ())
+        .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::time::SystemTime;
+/// upload `data` in parts of `part_size` - each part is retried up to `max_retries` times and the
+/// multipart upload is aborted when a part can't be uploaded. `on_part_uploaded` is called with the uploaded bytes
+fn multipart_upload<U, F>(
+    uploader: &mut U,
+    data: Vec<u8>,
+    part_size: usize,
+    max_retries: u32,
+    backoff: Duration,
+    mut on_part_uploaded: F,
+) -> Result<(), Error>
+where
+    U: MultipartUploader,
+    F: FnMut(usize),
+{
+    let upload_id = uploader.create()?;
+    let mut parts = vec![];
+    let mut uploaded_bytes = 0usize;
+
+    for (idx, part) in data.chunks(part_size.max(1)).enumerate() {
+        // part numbers start at 1
+        let part_number = idx as i32 + 1;
+        let mut retries = 0u32;
+
+        let e_tag = loop {
+            match uploader.upload_part(upload_id.as_str(), part_number, part) {
+                Ok(e_tag) => break e_tag,
+                Err(err) if retries < max_retries => {
+                    let wait = backoff * 2u32.pow(retries);
+                    retries += 1;
+                    warn!(
+                        "upload of part {} failed ({}) - retry in {:?}",
+                        part_number, err, wait
+                    );
+                    thread::sleep(wait);
+                }
+                Err(err) => {
+                    if let Err(abort_err) = uploader.abort(upload_id.as_str()) {
+                        error!("failed to abort the multipart upload: {}", abort_err);
+                    }
+
+                    return Err(err);
+                }
+            }
+        };
+
+        parts.push((part_number, e_tag));
+        uploaded_bytes += part.len();
+        on_part_uploaded(uploaded_bytes);
+    }
+
+    if let Err(err) = uploader.complete(upload_id.as_str(), parts) {
+        if let Err(abort_err) = uploader.abort(upload_id.as_str()) {
+            error!("failed to abort the multipart upload: {}", abort_err);
+        }
+
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+fn get_object<'a>(client: &Client, bucket: &'a str, key: &'a str) -> Result<Vec<u8>, S3Error<'a>> {
+    let result = block_on(client.get_object().bucket(bucket).key(key).send());
+
+    match result {
+        Ok(file) => match block_on(file.body.collect()) {
+            Ok(data) => Ok(data.into_bytes().to_vec()),
+            Err(_) => Err(S3Error::FailedObjectDownload { bucket, key }),
+        },
+        Err(SdkError::ServiceError { err, .. }) if err.is_no_such_key() => {
+            Err(S3Error::ObjectDoesNotExist { bucket, key })
+        }
+        // E.g. access denied or a network error - the object may exist
+        Err(_) => Err(S3Error::FailedObjectDownload { bucket, key }),
+    }
+}
+
+/// the lock stored in the bridge - none when there is no lock object. The lock can't be ignored when it can't be read
+/// or parsed: another backup may hold it
+fn run_lock_of(object: Result<Vec<u8>, S3Error>) -> Result<Option<RunLock>, Error> {
+    match object {
+        Ok(object) => serde_json::from_slice::<RunLock>(object.as_slice())
+            .map(Some)
+            .map_err(|err| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("invalid bridge lock '{}': {}", RUN_LOCK_FILE_NAME, err),
+                )
+            }),
+        Err(S3Error::ObjectDoesNotExist { .. }) => Ok(None),
+        Err(err) => Err(Error::from(err)),
+    }
+}
+
+/// download the first `length` bytes of the object with a range request
+fn get_object_header(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    length: usize,
+) -> Result<Vec<u8>, Error> {
+    block_on(async {
+        let output = client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .range(format!("bytes=0-{}", length.saturating_sub(1)))
+            .send()
+            .await
+            .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+
+        let data = output
+            .body
+            .collect()
+            .await
+            .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+
+        // the whole object is returned when the server ignores the range
+        let mut data = data.into_bytes().to_vec();
+        data.truncate(length);
+        Ok(data)
+    })
+}
+
+/// download the object - an interrupted download is resumed from the last received byte
+fn get_object_with_resume<'a>(
+    client: &Client,
+    bucket: &'a str,
+    key: &'a str,
+) -> Result<Vec<u8>, S3Error<'a>> {
+    let mut object_size = None;
+
+    download_with_resume(DOWNLOAD_MAX_RETRIES, DOWNLOAD_RETRY_BACKOFF, |data| {
+        get_object_range(client, bucket, key, &mut object_size, data)
+    })
+    .map_err(|err| {
+        error!("{}", err);
+        S3Error::FailedObjectDownload { bucket, key }
+    })
+}
+
+/// call `download` until it succeeds, waiting longer after each failure -
+/// `data` keeps the bytes received by the previous attempts to resume the download from there
+fn download_with_resume<F>(
+    max_retries: u32,
+    backoff: Duration,
+    mut download: F,
+) -> Result<Vec<u8>, Error>
+where
+    F: FnMut(&mut Vec<u8>) -> Result<(), Error>,
+{
+    let mut data = vec![];
+    let mut retries = 0u32;
+
+    loop {
+        let received_bytes = data.len();
+
+        match download(&mut data) {
+            Ok(_) => return Ok(data),
+            Err(err) => {
+                // the download moved forward - the network is flaky but working
+                if data.len() > received_bytes {
+                    retries = 0;
+                }
+
+                if retries >= max_retries {
+                    return Err(err);
+                }
+
+                let wait = backoff * 2u32.pow(retries);
+                retries += 1;
+
+                warn!(
+                    "download interrupted after {} bytes ({}) - resume in {:?}",
+                    data.len(),
+                    err,
+                    wait
+                );
+
+                thread::sleep(wait);
+            }
+        }
+    }
+}
+
+/// download the object from the byte `data.len()` and append the received bytes to `data`
+fn get_object_range(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    object_size: &mut Option<usize>,
+    data: &mut Vec<u8>,
+) -> Result<(), Error> {
+    if *object_size == Some(data.len()) {
+        return Ok(());
+    }
+
+    let mut request = client.get_object().bucket(bucket).key(key);
+    if !data.is_empty() {
+        request = request.range(format!("bytes={}-", data.len()));
+    }
+
+    block_on(async {
+        let output = request
+            .send()
+            .await
+            .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+
+        if object_size.is_none() && data.is_empty() {
+            *object_size = Some(output.content_length() as usize);
+        }
+
+        let mut body = output.body;
+        while let Some(bytes) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+            let bytes = bytes.map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+            data.extend_from_slice(&bytes);
+        }
+
+        match *object_size {
+            Some(object_size) if data.len() < object_size => Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("received {} of {} bytes", data.len(), object_size),
+            )),
+            _ => Ok(()),
+        }
+    })
+}
+
+fn list_objects<'a>(
+    client: &Client,
+    bucket: &'a str,
+    path: Option<&'a str>,
+) -> Result<Vec<Object>, S3Error<'a>> {
+    let mut objects = vec![];
+    let mut continuation_token = None;
+
+    // at most 1000 objects are listed at once
+    loop {
+        let output = block_on(
+            client
+                .list_objects_v2()
+                .bucket(bucket)
+                .set_continuation_token(continuation_token.take())
+                .send(),
+        );
+
+        let output = match output {
+            Ok(output) => output,
+            Err(err) => {
+                error!("{}", err);
+                return Err(S3Error::FailedToListObjects { bucket });
+            }
+        };
+
+        objects.extend(output.contents.unwrap_or_default());
+
+        match output.next_continuation_token {
+            Some(token) if output.is_truncated => continuation_token = Some(token),
+            _ => break,
+        }
+    }
+
+    if path.is_none() {
+        return Ok(objects);
+    }
+
+    let path = path.unwrap();
+    let mut objects = objects
+        .into_iter()
+        .filter(|object| match object.key() {
+            Some(key) => key.starts_with(path),
+            None => false,
+        })
+        .collect::<Vec<_>>();
+
+    // sort by key
+    objects.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(objects)
+}
+
+fn delete_object<'a>(client: &Client, bucket: &'a str, key: &'a str) -> Result<(), S3Error<'a>> {
+    let _ = get_object(client, bucket, key)?;
+
+    let result = block_on(client.delete_object().bucket(bucket).key(key).send());
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(_) => Err(S3Error::FailedToDeleteObject { bucket, key }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error, ErrorKind};
+    use std::sync::Arc;
+    use std::time::Duration;
 
     use fake::{Fake, Faker};
 
-    use crate::bridge::s3::{create_object, delete_bucket, delete_object, get_object, S3Error};
-    use crate::bridge::{Backup, Bridge};
+    use aws_types::credentials::ProvideCredentials;
+    use aws_types::os_shim_internal::Env;
+
+    use crate::bridge::s3::S3;
+    use crate::bridge::s3::{
+        bucket_region, check_key_template, check_not_archived, connect_status, create_object,
+        delete_bucket, delete_object, download_with_resume, get_object, index_file_prefixes,
+        list_objects, multipart_upload, object_part_number, prefixed_key, region_mismatch_warning,
+        resolve_key_template, run_lock_of, sdk_config, HttpClientOptions, MultipartUploader,
+        ObjectOptions, S3Error, S3Object, CHUNKS_DIRECTORY, INDEX_FILE_NAME,
+        LEGACY_INDEX_FILE_NAME,
+    };
+    use crate::bridge::{
+        decompress, decrypt, verify_encryption, Backup, Bridge, CompressionDictionary,
+        EncryptionCipher, ReadOptions, RunLock, WrittenSizes, OBJECT_HEADER_LENGTH,
+    };
     use crate::config::Endpoint;
     use crate::connector::Connector;
-    use crate::utils::epoch_millis;
-    use crate::S3;
+    use crate::runtime::block_on;
+    use crate::utils::{epoch_millis, utc_date};
+    use aws_sdk_s3::model::StorageClass;
 
     const BUCKET_NAME: &str = "replibyte-test";
     const REGION: &str = "us-east-2";
@@ -439,10 +2183,71 @@ mod tests {
         S3::new(
             bucket.to_string(),
             "us-east-2".to_string(),
-            access_key_id,
-            secret_access_key,
+            Some(access_key_id),
+            Some(secret_access_key),
             Endpoint::Custom(MINIO_ENDPOINT.to_string()),
         )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bucket_region() {
+        assert_eq!(bucket_region(None), "us-east-1");
+        assert_eq!(bucket_region(Some("")), "us-east-1");
+        assert_eq!(bucket_region(Some("EU")), "eu-west-1");
+        assert_eq!(bucket_region(Some("eu-west-3")), "eu-west-3");
+    }
+
+    #[test]
+    fn test_region_mismatch_warning() {
+        // the bucket location returned by S3
+        let get_bucket_region =
+            |location_constraint: Option<&str>| Some(bucket_region(location_constraint));
+
+        let warning =
+            region_mismatch_warning(BUCKET_NAME, REGION, get_bucket_region(Some("eu-west-3")))
+                .unwrap();
+        assert!(warning.contains("'eu-west-3'"));
+        assert!(warning.contains("'us-east-2'"));
+
+        let warning = region_mismatch_warning(BUCKET_NAME, REGION, get_bucket_region(None));
+        assert!(warning.unwrap().contains("'us-east-1'"));
+
+        assert!(
+            region_mismatch_warning(BUCKET_NAME, REGION, get_bucket_region(Some(REGION))).is_none()
+        );
+        // the bucket does not exist yet
+        assert!(region_mismatch_warning(BUCKET_NAME, REGION, None).is_none());
+    }
+
+    #[test]
+    fn test_run_lock_of() {
+        let lock = RunLock {
+            holder: "backup-1".to_string(),
+            acquired_at: 1,
+            expires_at: 2,
+        };
+
+        assert_eq!(
+            run_lock_of(Ok(serde_json::to_vec(&lock).unwrap())).unwrap(),
+            Some(lock)
+        );
+        // no lock object - the lock is free
+        assert_eq!(
+            run_lock_of(Err(S3Error::ObjectDoesNotExist {
+                bucket: BUCKET_NAME,
+                key: "backup.lock",
+            }))
+            .unwrap(),
+            None
+        );
+        // the lock may be held - E.g. access denied
+        assert!(run_lock_of(Err(S3Error::FailedObjectDownload {
+            bucket: BUCKET_NAME,
+            key: "backup.lock",
+        }))
+        .is_err());
+        assert!(run_lock_of(Ok(b"not a lock".to_vec())).is_err());
     }
 
     #[test]
@@ -480,6 +2285,7 @@ mod tests {
             bucket.as_str(),
             key.as_str(),
             b"hello w0rld".to_vec(),
+            &ObjectOptions::default(),
         )
         .is_ok());
 
@@ -494,6 +2300,7 @@ mod tests {
             bucket.as_str(),
             key.as_str(),
             b"hello w0rld updated".to_vec(),
+            &ObjectOptions::default(),
         )
         .is_ok());
 
@@ -527,6 +2334,67 @@ mod tests {
         assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
     }
 
+    #[test]
+    fn test_s3_check_write_access() {
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+
+        s3.init().expect("s3 init failed");
+
+        assert!(s3.check_write_access().is_ok());
+        // the probe object is deleted
+        assert_eq!(s3.index_file().unwrap().backups.len(), 0);
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_download_with_resume() {
+        let object = (0..=255u8).cycle().take(10_000).collect::<Vec<_>>();
+        let mut attempts = 0;
+
+        // the connection drops in the middle of the first two attempts
+        let data = download_with_resume(3, Duration::from_millis(1), |data| {
+            attempts += 1;
+
+            match attempts {
+                1 => {
+                    data.extend_from_slice(&object[..4_000]);
+                    Err(Error::new(ErrorKind::ConnectionReset, "connection reset"))
+                }
+                2 => {
+                    // resume from the last received byte
+                    assert_eq!(data.len(), 4_000);
+                    data.extend_from_slice(&object[4_000..7_000]);
+                    Err(Error::new(ErrorKind::ConnectionReset, "connection reset"))
+                }
+                _ => {
+                    assert_eq!(data.len(), 7_000);
+                    data.extend_from_slice(&object[7_000..]);
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+        assert_eq!(attempts, 3);
+        assert_eq!(data, object);
+    }
+
+    #[test]
+    fn test_download_with_resume_gives_up() {
+        let mut attempts = 0;
+
+        let result = download_with_resume(2, Duration::from_millis(1), |_| {
+            attempts += 1;
+            Err(Error::new(ErrorKind::ConnectionReset, "connection reset"))
+        });
+
+        assert!(result.is_err());
+        // first attempt + 2 retries
+        assert_eq!(attempts, 3);
+    }
+
     #[test]
     fn test_s3_index_file() {
         let bucket = bucket();
@@ -546,6 +2414,19 @@ mod tests {
             created_at: epoch_millis(),
             compressed: true,
             encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
         });
 
         assert!(s3.write_index_file(&index_file).is_ok());
@@ -554,4 +2435,927 @@ mod tests {
 
         assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
     }
+
+    #[test]
+    fn test_s3_legacy_index_file() {
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+
+        let _ = s3.init().expect("s3 init failed");
+
+        // a bucket written by a previous version
+        let legacy_index_file = br#"{"backups":[{"directory_name":"backup-1","size":0,"created_at":1,"compressed":true,"encrypted":false}]}"#;
+        assert!(delete_object(&s3.client, bucket.as_str(), INDEX_FILE_NAME).is_ok());
+        assert!(create_object(
+            &s3.client,
+            bucket.as_str(),
+            LEGACY_INDEX_FILE_NAME,
+            legacy_index_file.to_vec(),
+            &ObjectOptions::default(),
+        )
+        .is_ok());
+
+        let mut index_file = s3.index_file().unwrap();
+        assert_eq!(index_file.backups.len(), 1);
+
+        // the compressed index file is written next to the legacy one, which is kept for the previous versions
+        index_file.backups.clear();
+        assert!(s3.write_index_file(&index_file).is_ok());
+        assert!(s3.index_file().unwrap().backups.is_empty());
+        assert_eq!(
+            get_object(&s3.client, bucket.as_str(), LEGACY_INDEX_FILE_NAME).unwrap(),
+            legacy_index_file.to_vec()
+        );
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_s3_write_with_object_options() {
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        s3.set_compression(false);
+        // MinIO only supports the STANDARD and REDUCED_REDUNDANCY storage classes
+        s3.set_object_options(ObjectOptions {
+            storage_class: Some("REDUCED_REDUNDANCY".to_string()),
+            content_type: Some("application/octet-stream".to_string()),
+            tagging: Some("team=data".to_string()),
+        });
+
+        let _ = s3.init().expect("s3 init failed");
+        assert!(s3.write(1, b"hello w0rld".to_vec()).is_ok());
+
+        let key = format!("{}/1.dump", s3.backup_name());
+        let object = block_on(
+            s3.client
+                .head_object()
+                .bucket(bucket.as_str())
+                .key(key.as_str())
+                .send(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            object.storage_class(),
+            Some(&StorageClass::ReducedRedundancy)
+        );
+        assert_eq!(object.content_type(), Some("application/octet-stream"));
+
+        // the storage class is recorded in the index file
+        assert_eq!(
+            s3.index_file().unwrap().backups[0].storage_class.as_deref(),
+            Some("REDUCED_REDUNDANCY")
+        );
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_s3_read_object_header() {
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        s3.set_encryption_key(Some("this is my secret".to_string()));
+
+        let _ = s3.init().expect("s3 init failed");
+
+        let data = b"INSERT INTO public.users (id, name) VALUES (1, 'John Doe');\n".repeat(100);
+        assert!(s3.write(1, data).is_ok());
+
+        let read_options = ReadOptions::Backup {
+            name: s3.backup_name().to_string(),
+        };
+        let header = s3
+            .read_object_header(&read_options, OBJECT_HEADER_LENGTH)
+            .unwrap();
+        assert_eq!(header.len(), OBJECT_HEADER_LENGTH);
+
+        let mut index_file = s3.index_file().unwrap();
+        let backup = index_file.find_backup(&read_options).unwrap();
+        assert_eq!(
+            verify_encryption(backup, header.as_slice()).unwrap(),
+            EncryptionCipher::Aes256Gcm
+        );
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_s3_read_objects() {
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        s3.set_encryption_key(Some("this is my secret".to_string()));
+
+        let _ = s3.init().expect("s3 init failed");
+
+        let data = b"INSERT INTO public.users (id, name) VALUES (1, 'John Doe');\n".repeat(100);
+        assert!(s3.write(1, data.clone()).is_ok());
+        assert!(s3.write(2, data.clone()).is_ok());
+
+        let read_options = ReadOptions::Backup {
+            name: s3.backup_name().to_string(),
+        };
+        let read_objects = |decrypt_objects: bool, decompress_objects: bool| {
+            let mut objects = vec![];
+            s3.read_objects(
+                &read_options,
+                decrypt_objects,
+                decompress_objects,
+                |object| objects.push(object),
+            )
+            .map(|_| objects)
+        };
+
+        // as stored - compressed then encrypted
+        let objects = read_objects(false, false).unwrap();
+        assert_eq!(objects.len(), 2);
+        for object in objects {
+            assert_ne!(object, data);
+            let object = decrypt(object, "this is my secret", EncryptionCipher::Aes256Gcm).unwrap();
+            assert_eq!(decompress(object).unwrap(), data);
+        }
+
+        for object in read_objects(true, false).unwrap() {
+            assert_eq!(decompress(object).unwrap(), data);
+        }
+
+        assert_eq!(read_objects(true, true).unwrap(), vec![data.clone(), data]);
+
+        // the encryption is around the compression
+        assert!(read_objects(false, true).is_err());
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_s3_write_and_read_with_compression_dictionary() {
+        let samples = (0..1_000)
+            .map(|i| {
+                format!(
+                    "INSERT INTO public.users (id, name) VALUES ({}, 'user {}');\n",
+                    i,
+                    i * 7
+                )
+                .into_bytes()
+            })
+            .collect::<Vec<_>>();
+        let dictionary = CompressionDictionary::train(&samples, 4_096).unwrap();
+
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        s3.set_compression_dictionary(Some(dictionary.clone()));
+
+        let _ = s3.init().expect("s3 init failed");
+
+        let data = b"INSERT INTO public.users (id, name) VALUES (1001, 'user 7007');\n".to_vec();
+        assert!(s3.write(1, data.clone()).is_ok());
+
+        assert_eq!(
+            s3.index_file().unwrap().backups[0].compression_dictionary_id,
+            Some(dictionary.id)
+        );
+
+        // the dictionary is read from the bucket - the restoring bridge does not need it
+        let mut restore_s3 = s3.next_backup();
+        restore_s3.set_compression_dictionary(None);
+
+        let mut restored_data = vec![];
+        restore_s3
+            .read(&ReadOptions::Latest, |data| restored_data.extend(data))
+            .unwrap();
+        assert_eq!(restored_data, data);
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_s3_write_deduplicated_backups() {
+        let rows = |table: &str, count: usize| {
+            (0..count)
+                .flat_map(|i| {
+                    format!("INSERT INTO public.{} (id) VALUES ({});\n", table, i).into_bytes()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let tables = ["users", "orders", "products", "invoices"];
+
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        s3.set_encryption_key(Some("this is my secret".to_string()));
+        s3.set_deduplication(true);
+
+        let _ = s3.init().expect("s3 init failed");
+
+        let written_sizes = Arc::new(WrittenSizes::default());
+        s3.set_written_sizes(written_sizes.clone());
+        for (part, table) in tables.iter().enumerate() {
+            assert!(s3.write(part as u16 + 1, rows(table, 10_000)).is_ok());
+        }
+        let first_backup_size = written_sizes.size();
+
+        // only one row has been added since the first backup
+        let mut next_s3 = s3.next_backup();
+        let next_written_sizes = Arc::new(WrittenSizes::default());
+        next_s3.set_written_sizes(next_written_sizes.clone());
+
+        let mut data = vec![];
+        for (part, table) in tables.iter().enumerate() {
+            let count = if *table == "orders" { 10_001 } else { 10_000 };
+            let part_data = rows(table, count);
+            data.extend(part_data.clone());
+            assert!(next_s3.write(part as u16 + 1, part_data).is_ok());
+        }
+
+        assert!(next_written_sizes.size() * 3 < first_backup_size);
+        let unchanged_size = ["users", "products", "invoices"]
+            .iter()
+            .map(|table| rows(table, 10_000).len())
+            .sum::<usize>();
+        assert_eq!(next_written_sizes.deduplicated_size(), unchanged_size);
+
+        let index_file = s3.index_file().unwrap();
+        assert_eq!(index_file.backups.len(), 2);
+        assert_eq!(index_file.backups[0].size, first_backup_size);
+
+        let mut restored_data = vec![];
+        next_s3
+            .read(&ReadOptions::Latest, |data| restored_data.extend(data))
+            .unwrap();
+        assert_eq!(restored_data, data);
+
+        // the parts shared with the first backup are kept
+        assert!(next_s3.abort_write().is_ok());
+
+        let mut restored_data = vec![];
+        s3.read(&ReadOptions::Latest, |data| restored_data.extend(data))
+            .unwrap();
+        let first_backup_data = tables
+            .iter()
+            .flat_map(|table| rows(table, 10_000))
+            .collect::<Vec<_>>();
+        assert_eq!(restored_data, first_backup_data);
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_s3_rename_backup() {
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        let _ = s3.init().expect("s3 init failed");
+
+        let data = b"INSERT INTO public.users (id) VALUES (1);\n".to_vec();
+        assert!(s3.write(1, data.clone()).is_ok());
+        assert!(s3.write(2, data.clone()).is_ok());
+
+        let next_s3 = s3.next_backup();
+        assert!(next_s3.write(1, data.clone()).is_ok());
+
+        let name = s3.backup_name().to_string();
+        assert!(s3.rename_backup(name.as_str(), "before-migration").is_ok());
+
+        let mut restored_data = vec![];
+        s3.read(
+            &ReadOptions::Backup {
+                name: "before-migration".to_string(),
+            },
+            |data| restored_data.extend(data),
+        )
+        .unwrap();
+        assert_eq!(restored_data, [data.clone(), data].concat());
+
+        // the old objects are deleted
+        let old_key = format!("{}/1.dump", name);
+        assert!(get_object(&s3.client, bucket.as_str(), old_key.as_str()).is_err());
+        assert!(s3.read(&ReadOptions::Backup { name }, |_| {}).is_err());
+
+        // the name is taken
+        assert!(s3
+            .rename_backup(next_s3.backup_name(), "before-migration")
+            .is_err());
+        assert!(s3.rename_backup(next_s3.backup_name(), "chunks").is_err());
+        assert_eq!(s3.index_file().unwrap().backups.len(), 2);
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_s3_delete_backup() {
+        let data = b"INSERT INTO public.users (id) VALUES (1);\n".to_vec();
+
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        s3.set_deduplication(true);
+        let _ = s3.init().expect("s3 init failed");
+        assert!(s3.write(1, data.clone()).is_ok());
+
+        // the part is shared with the first backup
+        let next_s3 = s3.next_backup();
+        assert!(next_s3.write(1, data.clone()).is_ok());
+
+        assert!(s3.set_backup_locked(s3.backup_name(), true).is_ok());
+        assert!(s3.delete_backup(s3.backup_name()).is_err());
+        assert!(s3.set_backup_locked(s3.backup_name(), false).is_ok());
+
+        assert!(s3.delete_backup(s3.backup_name()).is_ok());
+        assert!(s3.delete_backup(s3.backup_name()).is_err());
+        assert_eq!(s3.index_file().unwrap().backups.len(), 1);
+
+        let mut restored_data = vec![];
+        next_s3
+            .read(&ReadOptions::Latest, |data| restored_data.extend(data))
+            .unwrap();
+        assert_eq!(restored_data, data);
+
+        // the last backup deletes the chunk
+        assert!(next_s3.delete_backup(next_s3.backup_name()).is_ok());
+        let chunks_key = s3.key(format!("{}/", CHUNKS_DIRECTORY).as_str());
+        assert!(
+            list_objects(&s3.client, bucket.as_str(), Some(chunks_key.as_str()))
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_key_template() {
+        assert!(check_key_template("{year}/{month}/{day}/{name}").is_ok());
+        assert!(check_key_template("daily/{year}-{month}/{name}").is_ok());
+        assert!(check_key_template("{name}").is_ok());
+        // the backups would share their objects
+        assert!(check_key_template("{year}/{month}/{day}").is_err());
+        assert!(check_key_template("{name}/{year}").is_err());
+        assert!(check_key_template("/{year}/{name}").is_err());
+        assert!(check_key_template("{hour}/{name}").is_err());
+        assert!(check_key_template("{year/{name}").is_err());
+
+        assert_eq!(
+            resolve_key_template(
+                "{year}/{month}/{day}/{name}",
+                "backup-1705276800000",
+                1705276800000
+            ),
+            "2024/01/15/backup-1705276800000"
+        );
+        assert_eq!(
+            resolve_key_template("daily/{year}-{month}/{name}", "weekly", 1647706359405),
+            "daily/2022-03/weekly"
+        );
+    }
+
+    #[test]
+    fn test_s3_write_with_key_template() {
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        assert!(s3
+            .set_key_template(Some("{year}/{name}/{unknown}".to_string()))
+            .is_err());
+        assert!(s3
+            .set_key_template(Some("{year}/{month}/{day}/{name}".to_string()))
+            .is_ok());
+        let _ = s3.init().expect("s3 init failed");
+
+        let data = b"INSERT INTO public.users (id) VALUES (1);\n".to_vec();
+        assert!(s3.write(1, data.clone()).is_ok());
+
+        // E.g. `2024/01/15/backup-1705276800000/1.dump`
+        let (year, month, day) = utc_date(s3.created_at);
+        let name = s3.backup_name().to_string();
+        let directory_key = format!("{}/{:02}/{:02}/{}", year, month, day, name);
+        let key = format!("{}/1.dump", directory_key);
+        assert!(get_object(&s3.client, bucket.as_str(), key.as_str()).is_ok());
+
+        let index_file = s3.index_file().unwrap();
+        assert_eq!(index_file.backups[0].key, Some(directory_key.clone()));
+
+        let mut restored_data = vec![];
+        s3.read(&ReadOptions::Latest, |data| restored_data.extend(data))
+            .unwrap();
+        assert_eq!(restored_data, data);
+
+        // the objects stay under the date of the backup
+        assert!(s3.rename_backup(name.as_str(), "before-migration").is_ok());
+        let key = format!("{}/{:02}/{:02}/before-migration/1.dump", year, month, day);
+        assert!(get_object(&s3.client, bucket.as_str(), key.as_str()).is_ok());
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_s3_object_read_without_index_file() {
+        let bucket = bucket();
+        let mut s3 = s3(bucket.as_str());
+        s3.set_encryption_key(Some("this is my secret".to_string()));
+        s3.set_encryption_cipher(EncryptionCipher::ChaCha20Poly1305);
+        let _ = s3.init().expect("s3 init failed");
+
+        let parts = (1..=11)
+            .map(|part| format!("INSERT INTO public.users (id) VALUES ({});\n", part).into_bytes())
+            .collect::<Vec<_>>();
+
+        for (idx, part) in parts.iter().enumerate() {
+            assert!(s3.write(idx as u16 + 1, part.clone()).is_ok());
+        }
+
+        // the index file is lost
+        assert!(delete_object(&s3.client, bucket.as_str(), INDEX_FILE_NAME).is_ok());
+
+        let mut object_s3 = s3.clone();
+        object_s3.set_compression(true);
+        object_s3.set_encryption_key(Some("this is my secret".to_string()));
+        object_s3.set_encryption_cipher(EncryptionCipher::ChaCha20Poly1305);
+
+        // the whole backup - the parts are read in order, `10.dump` after `9.dump`
+        let mut object = S3Object::new(object_s3.clone(), s3.backup_name());
+        assert!(object.init().is_ok());
+        let index_file = object.index_file().unwrap();
+        assert_eq!(
+            index_file.backups[0].cipher,
+            Some(EncryptionCipher::ChaCha20Poly1305)
+        );
+
+        let mut restored_data = vec![];
+        object
+            .read(&ReadOptions::Latest, |data| restored_data.extend(data))
+            .unwrap();
+        assert_eq!(restored_data, parts.concat());
+
+        // a single part
+        let key = format!("{}/2.dump", s3.backup_name());
+        let object = S3Object::new(object_s3.clone(), key);
+        let mut restored_data = vec![];
+        object
+            .read(&ReadOptions::Latest, |data| restored_data.extend(data))
+            .unwrap();
+        assert_eq!(restored_data, parts[1]);
+
+        // wrong hints
+        let mut wrong_cipher_s3 = object_s3.clone();
+        wrong_cipher_s3.set_encryption_cipher(EncryptionCipher::Aes256Gcm);
+        let object = S3Object::new(wrong_cipher_s3, s3.backup_name());
+        assert!(object.read(&ReadOptions::Latest, |_| {}).is_err());
+
+        let mut object = S3Object::new(object_s3, "backup-0");
+        assert!(object.init().is_err());
+
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_object_part_number() {
+        assert_eq!(object_part_number("backup-1647706359405/10.dump"), Some(10));
+        assert_eq!(object_part_number("1.dump"), Some(1));
+        assert_eq!(
+            object_part_number("backup-1647706359405/metadata.json"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_check_not_archived() {
+        let backup = |storage_class: Option<&str>| Backup {
+            directory_name: "backup-1".to_string(),
+            size: 0,
+            created_at: epoch_millis(),
+            compressed: true,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: storage_class.map(|storage_class| storage_class.to_string()),
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        assert!(check_not_archived(&backup(None)).is_ok());
+        assert!(check_not_archived(&backup(Some("STANDARD_IA"))).is_ok());
+        assert!(check_not_archived(&backup(Some("GLACIER_IR"))).is_ok());
+
+        let err = check_not_archived(&backup(Some("GLACIER"))).unwrap_err();
+        assert!(err.to_string().contains("restored from the archive"));
+        assert!(check_not_archived(&backup(Some("DEEP_ARCHIVE"))).is_err());
+    }
+
+    /// multipart uploader keeping the calls in memory - `failing_part` always fails, the others fail once every `flaky_every` calls
+    #[derive(Default)]
+    struct InMemoryMultipartUploader {
+        parts: Vec<(i32, Vec<u8>)>,
+        completed_parts: Option<Vec<(i32, String)>>,
+        aborted: bool,
+        calls: usize,
+        flaky_every: Option<usize>,
+        failing_part: Option<i32>,
+    }
+
+    impl MultipartUploader for InMemoryMultipartUploader {
+        fn create(&mut self) -> Result<String, Error> {
+            Ok("upload-1".to_string())
+        }
+
+        fn upload_part(
+            &mut self,
+            upload_id: &str,
+            part_number: i32,
+            data: &[u8],
+        ) -> Result<String, Error> {
+            assert_eq!(upload_id, "upload-1");
+            self.calls += 1;
+
+            if self.failing_part == Some(part_number)
+                || self
+                    .flaky_every
+                    .map(|n| self.calls % n == 0)
+                    .unwrap_or(false)
+            {
+                return Err(Error::new(ErrorKind::Other, "connection reset"));
+            }
+
+            self.parts.push((part_number, data.to_vec()));
+            Ok(format!("etag-{}", part_number))
+        }
+
+        fn complete(&mut self, _: &str, parts: Vec<(i32, String)>) -> Result<(), Error> {
+            self.completed_parts = Some(parts);
+            Ok(())
+        }
+
+        fn abort(&mut self, _: &str) -> Result<(), Error> {
+            self.aborted = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_multipart_upload() {
+        let data = (0..=255u8).cycle().take(10_000).collect::<Vec<_>>();
+        let mut uploader = InMemoryMultipartUploader {
+            flaky_every: Some(3),
+            ..Default::default()
+        };
+        let mut uploaded_bytes = vec![];
+
+        assert!(multipart_upload(
+            &mut uploader,
+            data.clone(),
+            3_000,
+            2,
+            Duration::from_millis(1),
+            |bytes| uploaded_bytes.push(bytes),
+        )
+        .is_ok());
+
+        // the parts are uploaded and completed in order
+        assert_eq!(
+            uploader
+                .parts
+                .iter()
+                .map(|(part_number, _)| *part_number)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(
+            uploader
+                .parts
+                .iter()
+                .flat_map(|(_, data)| data.clone())
+                .collect::<Vec<_>>(),
+            data
+        );
+        assert_eq!(
+            uploader.completed_parts,
+            Some(vec![
+                (1, "etag-1".to_string()),
+                (2, "etag-2".to_string()),
+                (3, "etag-3".to_string()),
+                (4, "etag-4".to_string()),
+            ])
+        );
+        assert_eq!(uploaded_bytes, vec![3_000, 6_000, 9_000, 10_000]);
+        assert!(!uploader.aborted);
+    }
+
+    #[test]
+    fn test_multipart_upload_aborts_on_failure() {
+        let mut uploader = InMemoryMultipartUploader {
+            failing_part: Some(2),
+            ..Default::default()
+        };
+
+        assert!(multipart_upload(
+            &mut uploader,
+            vec![0u8; 10_000],
+            3_000,
+            2,
+            Duration::from_millis(1),
+            |_| {},
+        )
+        .is_err());
+
+        // the failing part is tried 3 times
+        assert_eq!(uploader.calls, 4);
+        assert!(uploader.completed_parts.is_none());
+        assert!(uploader.aborted);
+    }
+
+    #[test]
+    fn test_prefixed_key() {
+        assert_eq!(prefixed_key(None, "metadata.json"), "metadata.json");
+        assert_eq!(
+            prefixed_key(Some("project-a"), "metadata.json"),
+            "project-a/metadata.json"
+        );
+        assert_eq!(
+            prefixed_key(Some("team/project-a"), "backup-1/1.dump"),
+            "team/project-a/backup-1/1.dump"
+        );
+    }
+
+    #[test]
+    fn test_s3_index_file_with_prefix() {
+        let bucket = bucket();
+
+        let mut s3_a = s3(bucket.as_str());
+        s3_a.set_prefix(Some("/project-a/".to_string()));
+        s3_a.init().expect("s3 init failed");
+
+        let mut s3_b = s3(bucket.as_str());
+        s3_b.set_prefix(Some("project-b".to_string()));
+        s3_b.init().expect("s3 init failed");
+
+        let mut index_file = s3_a.index_file().unwrap();
+        index_file.backups.push(Backup {
+            directory_name: "backup-1".to_string(),
+            size: 0,
+            created_at: epoch_millis(),
+            compressed: true,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        });
+
+        assert!(s3_a.write_index_file(&index_file).is_ok());
+
+        // each prefix has its own index file
+        assert_eq!(s3_a.index_file().unwrap().backups.len(), 1);
+        assert!(s3_b.index_file().unwrap().backups.is_empty());
+        assert!(get_object(&s3_a.client, bucket.as_str(), "project-a/metadata.json.zst").is_ok());
+        assert!(get_object(&s3_a.client, bucket.as_str(), "metadata.json.zst").is_err());
+
+        assert!(delete_bucket(&s3_a.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn test_index_file_prefixes() {
+        // a bucket shared by several projects
+        let keys = [
+            "metadata.json.zst",
+            "metadata.json",
+            "backup-1/1.dump",
+            "project-b/metadata.json",
+            "project-b/backup-2/1.dump",
+            "project-a/metadata.json.zst",
+            "team/project-c/metadata.json",
+            "project-d/backup-3/metadata.json.dump",
+            "not-a-metadata.json",
+        ];
+
+        assert_eq!(
+            index_file_prefixes(keys),
+            vec![
+                None,
+                Some("project-a".to_string()),
+                Some("project-b".to_string()),
+                Some("team/project-c".to_string()),
+            ]
+        );
+        assert!(index_file_prefixes([]).is_empty());
+    }
+
+    #[test]
+    fn test_s3_all_index_files() {
+        let bucket = bucket();
+
+        let mut s3_a = s3(bucket.as_str());
+        s3_a.set_prefix(Some("project-a".to_string()));
+        s3_a.init().expect("s3 init failed");
+        assert!(s3_a.write(1, b"hello w0rld".to_vec()).is_ok());
+
+        let s3_b = s3_a.with_prefix(Some("project-b".to_string()));
+        s3_b.create_index_file().expect("s3 index file failed");
+
+        let index_files = s3_b.all_index_files().unwrap();
+        let backups_count = index_files
+            .iter()
+            .map(|(prefix, index_file)| (prefix.as_deref(), index_file.backups.len()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            backups_count,
+            vec![(Some("project-a"), 1), (Some("project-b"), 0)]
+        );
+
+        assert!(delete_bucket(&s3_a.client, bucket.as_str(), true).is_ok());
+    }
+
+    /// self-signed certificate authority
+    const TEST_CA_BUNDLE: &str = "-----BEGIN CERTIFICATE-----
+MIIBjzCCATWgAwIBAgIUKmVuQa0GnZSgd7UWzkAfqEx9B1UwCgYIKoZIzj0EAwIw
+HDEaMBgGA1UEAwwRUmVwbGlieXRlIFRlc3QgQ0EwIBcNMjYxMDE1MDMzOTUzWhgP
+MjEyNjA5MjEwMzM5NTNaMBwxGjAYBgNVBAMMEVJlcGxpYnl0ZSBUZXN0IENBMFkw
+EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAERog48L8X7y3DGlYmmyn8H1CYP4QtRSkn
+D2dlO8fANun9/xZ2ubNmgXmcgGs9WcfNDQHiIXj8g5tn80Phs0Ly0qNTMFEwHQYD
+VR0OBBYEFBzBFywTCUusvd9WmBdkNU8dPlkeMB8GA1UdIwQYMBaAFBzBFywTCUus
+vd9WmBdkNU8dPlkeMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIh
+AN3TOmWAgTmGSV6KUDzaHI36AB26dyMarzRKB084Jr5DAiBf2w71a6LtVGypQbL8
+UZNSwk6XJoVQPeW7/OILbGQxlA==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_proxy_for() {
+        let options = HttpClientOptions {
+            proxy: Some("http://proxy.acme.com:3128".to_string()),
+            no_proxy: vec!["localhost".to_string(), ".internal.acme.com".to_string()],
+            ca_bundle: None,
+        };
+
+        assert_eq!(
+            options.proxy_for("s3.us-east-2.amazonaws.com"),
+            Some("http://proxy.acme.com:3128")
+        );
+        assert_eq!(options.proxy_for("localhost"), None);
+        assert_eq!(options.proxy_for("internal.acme.com"), None);
+        assert_eq!(options.proxy_for("minio.Internal.acme.com"), None);
+        assert_eq!(
+            options.proxy_for("minio.acme.com"),
+            Some("http://proxy.acme.com:3128")
+        );
+
+        let options = HttpClientOptions {
+            no_proxy: vec!["*".to_string()],
+            ..options
+        };
+        assert_eq!(options.proxy_for("s3.us-east-2.amazonaws.com"), None);
+        assert_eq!(HttpClientOptions::default().proxy_for("localhost"), None);
+    }
+
+    #[test]
+    fn test_connect_status() {
+        assert_eq!(
+            connect_status(b"HTTP/1.1 200 Connection established\r\n\r\n"),
+            Some(200)
+        );
+        assert_eq!(
+            connect_status(
+                b"HTTP/1.0 407 Proxy Authentication Required\r\nProxy-Authenticate: Basic\r\n\r\n"
+            ),
+            Some(407)
+        );
+        assert_eq!(connect_status(b"SSH-2.0-OpenSSH_8.9\r\n"), None);
+        assert_eq!(connect_status(b""), None);
+    }
+
+    #[test]
+    fn s3_with_a_proxy_and_a_ca_bundle() {
+        let dir = std::env::temp_dir().join(format!("replibyte-ca-{}", Faker.fake::<u64>()));
+        std::fs::create_dir_all(dir.as_path()).unwrap();
+        let ca_bundle = dir.join("ca.pem");
+        std::fs::write(ca_bundle.as_path(), TEST_CA_BUNDLE).unwrap();
+
+        let options = HttpClientOptions {
+            proxy: Some("http://proxy.acme.com:3128".to_string()),
+            no_proxy: vec!["localhost".to_string()],
+            ca_bundle: Some(ca_bundle.to_str().unwrap().to_string()),
+        };
+
+        let mut bridge = S3::new(
+            BUCKET_NAME,
+            REGION,
+            None,
+            None,
+            Endpoint::Custom("https://minio.acme.com:9000".to_string()),
+        )
+        .unwrap();
+        assert!(bridge.set_http_client_options(options.clone()).is_ok());
+        assert_eq!(bridge.http_client_options(), &options);
+        assert_eq!(bridge.proxy(), Some("http://proxy.acme.com:3128"));
+
+        let mut bridge = S3::new(BUCKET_NAME, REGION, None, None, Endpoint::Default).unwrap();
+        assert!(bridge.set_http_client_options(options.clone()).is_ok());
+        assert_eq!(bridge.proxy(), Some("http://proxy.acme.com:3128"));
+
+        // MinIO is reached without the proxy - the requests still work
+        let bucket = bucket();
+        let mut bridge = s3(bucket.as_str());
+        assert!(bridge.set_http_client_options(options.clone()).is_ok());
+        assert_eq!(bridge.proxy(), None);
+        assert!(bridge.init().is_ok());
+        assert!(delete_bucket(&bridge.client, bucket.as_str(), true).is_ok());
+
+        // the invalid options are rejected
+        let empty_ca_bundle = dir.join("empty.pem");
+        std::fs::write(empty_ca_bundle.as_path(), "").unwrap();
+        for options in [
+            HttpClientOptions {
+                ca_bundle: Some(dir.join("missing.pem").to_str().unwrap().to_string()),
+                ..HttpClientOptions::default()
+            },
+            HttpClientOptions {
+                ca_bundle: Some(empty_ca_bundle.to_str().unwrap().to_string()),
+                ..HttpClientOptions::default()
+            },
+            HttpClientOptions {
+                proxy: Some("https://proxy.acme.com".to_string()),
+                ..HttpClientOptions::default()
+            },
+        ] {
+            let mut bridge = S3::new(BUCKET_NAME, REGION, None, None, Endpoint::Default).unwrap();
+            assert!(bridge.set_http_client_options(options).is_err());
+        }
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_sdk_config_with_default_credential_chain() {
+        let (access_key_id, secret_access_key) = credentials();
+        let env = Env::from_slice(&[
+            ("AWS_ACCESS_KEY_ID", access_key_id.as_str()),
+            ("AWS_SECRET_ACCESS_KEY", secret_access_key.as_str()),
+        ]);
+
+        // no explicit keys -> the credentials come from the environment
+        let sdk_config = sdk_config(REGION, None, env);
+        let credentials = block_on(
+            sdk_config
+                .credentials_provider()
+                .unwrap()
+                .provide_credentials(),
+        )
+        .unwrap();
+
+        assert_eq!(credentials.access_key_id(), access_key_id.as_str());
+        assert_eq!(credentials.secret_access_key(), secret_access_key.as_str());
+        assert_eq!(sdk_config.region().unwrap().as_ref(), REGION);
+    }
+
+    #[test]
+    fn init_s3_with_default_credential_chain() {
+        let (access_key_id, secret_access_key) = credentials();
+        let env = Env::from_slice(&[
+            ("AWS_ACCESS_KEY_ID", access_key_id.as_str()),
+            ("AWS_SECRET_ACCESS_KEY", secret_access_key.as_str()),
+        ]);
+
+        let bucket = bucket();
+        let mut s3 = S3::new_with_env(
+            bucket.to_string(),
+            REGION.to_string(),
+            None,
+            None,
+            Endpoint::Custom(MINIO_ENDPOINT.to_string()),
+            env,
+        )
+        .unwrap();
+
+        assert!(s3.init().is_ok());
+        assert!(delete_bucket(&s3.client, bucket.as_str(), true).is_ok());
+    }
+
+    #[test]
+    fn only_one_of_the_keys_is_an_error() {
+        for (access_key_id, secret_access_key) in [
+            (Some("access_key_id"), None),
+            (None, Some("secret_access_key")),
+        ] {
+            let result = S3::new(
+                BUCKET_NAME,
+                REGION,
+                access_key_id,
+                secret_access_key,
+                Endpoint::Default,
+            );
+            assert_eq!(result.err().unwrap().kind(), ErrorKind::InvalidInput);
+        }
+    }
 }
@@ -1,15 +1,38 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgEnum, Args, Parser, Subcommand};
+
+use crate::bridge::EncryptionCipher;
+use crate::destination::postgres::{ColumnMapping, SchemaMapping};
+use crate::utils::{from_human_readable_duration, from_human_readable_unit};
 
 /// RepliByte is a tool to synchronize cloud databases and fake sensitive data, just pass `-h`
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 #[clap(propagate_version = true)]
 pub struct CLI {
-    /// replibyte configuration file
+    /// replibyte configuration file - YAML, TOML (`.toml`) or JSON (`.json`)
     #[clap(short, long, parse(from_os_str), value_name = "configuration file")]
     pub config: PathBuf,
+    /// profile of the configuration file to use - default to the `REPLIBYTE_PROFILE` environment variable
+    #[clap(long, value_name = "profile")]
+    pub profile: Option<String>,
+    /// max number of workers running at the same time (upload, parallel restore...) - default to the number of CPUs
+    #[clap(long, value_name = "number of threads")]
+    pub threads: Option<usize>,
+    /// format of the logs (enabled with `RUST_LOG`) - `json` is meant for log platforms
+    #[clap(long, arg_enum, default_value = "text")]
+    pub log_format: LogFormat,
+    /// how often the progress is refreshed, in milliseconds
+    #[clap(long, parse(try_from_str = parse_millis), value_name = "ms", default_value = "100")]
+    pub progress_interval: Duration,
+    /// Unix socket or named pipe receiving the progress as JSON lines instead of the terminal - E.g. for a GUI
+    #[clap(long, parse(from_os_str), value_name = "path")]
+    pub progress_socket: Option<PathBuf>,
+    /// JSON file summarizing the backup or the restore (status, bytes, duration, rows per table...) - written even when it fails
+    #[clap(long, parse(from_os_str), value_name = "summary file")]
+    pub summary_file: Option<PathBuf>,
     #[clap(subcommand)]
     pub sub_commands: SubCommand,
 }
@@ -25,33 +48,271 @@ pub enum SubCommand {
     Transformer(TransformerCommand),
     /// all restore commands
     Restore(RestoreArgs),
+    /// all source commands
+    #[clap(subcommand)]
+    Source(SourceCommand),
 }
 
 /// all backup commands
 #[derive(Subcommand, Debug)]
 pub enum BackupCommand {
-    /// list available backups
-    List,
+    /// list available backups -- use `-h` to show all the options
+    List(BackupListArgs),
+    /// summarize the backups stored in the bridge (count, size, oldest and newest)
+    Stats,
+    /// compare two backups - size, age, tables and transformers
+    Compare(BackupCompareArgs),
     /// launch backup -- use `-h` to show all the options
     Run(BackupRunArgs),
+    /// rename a backup - E.g. to a friendlier name than `backup-<timestamp>`
+    Rename(BackupRenameArgs),
+    /// lock a backup - a locked backup can't be renamed nor deleted, E.g. kept for compliance
+    Lock(BackupLockArgs),
+    /// unlock a backup locked by `backup lock`
+    Unlock(BackupLockArgs),
+    /// restore a backup into the scratch destination to check it, then wipe it -- use `-h` to show all the options
+    VerifyRestore(BackupVerifyRestoreArgs),
+    /// check that a backup is encrypted from the first bytes of its objects - without downloading nor decrypting it
+    VerifyEncryption(BackupVerifyEncryptionArgs),
+    /// download the objects of a backup into a local directory without restoring it - as stored unless `--decrypt` or `--decompress` is set
+    Download(BackupDownloadArgs),
+    /// write the index file (the backups metadata) into a JSON file - E.g. to move the backups to another bucket
+    ExportIndex(BackupIndexFileArgs),
+    /// add the backups of a JSON file written by `export-index` to the index file - the backup objects must be copied separately
+    ImportIndex(BackupIndexFileArgs),
+    /// train a zstd compression dictionary from sample dumps - to use with `backup run --compression-dict`
+    TrainCompressionDict(BackupTrainCompressionDictArgs),
+}
+
+/// all source commands
+#[derive(Subcommand, Debug)]
+pub enum SourceCommand {
+    /// dump the source through the transformers without storing it - print the throughput to estimate how long a backup takes
+    Ping,
 }
 
 /// all transformer commands
 #[derive(Subcommand, Debug)]
 pub enum TransformerCommand {
-    /// list available transformers
-    List,
+    /// list available transformers -- use `-h` to show all the options
+    List(TransformerListArgs),
+}
+
+/// all transformer list commands
+#[derive(Args, Debug)]
+pub struct TransformerListArgs {
+    /// output format - `json` is meant for tooling (E.g. editor integrations)
+    #[clap(long, arg_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RestoreOutputFormat {
+    Sql,
+    Jsonl,
+    Csv,
 }
 
 /// all restore commands
 #[derive(Args, Debug)]
 pub struct RestoreArgs {
     /// restore backup -- set `latest` or `<backup name>` - use `backup list` command to list all backups available
-    #[clap(short, long, value_name = "[latest | backup name]")]
-    pub value: String,
+    #[clap(
+        short,
+        long,
+        value_name = "[latest | backup name]",
+        required_unless_present_any = &["from-file", "object-key"]
+    )]
+    pub value: Option<String>,
+    /// restore a backup part (E.g. `1.dump`) or a directory with all the parts downloaded locally instead of reading the bridge -
+    /// the destination `compression` and `encryption_key` must match the backup ones
+    #[clap(
+        long,
+        parse(from_os_str),
+        value_name = "backup file",
+        conflicts_with = "value"
+    )]
+    pub from_file: Option<PathBuf>,
+    /// restore the objects under this key of the bucket without reading the index file (E.g. when it is lost) - a backup part
+    /// (E.g. `backup-1647706359405/1.dump`) or a backup directory. The destination `compression` and `encryption_key` and
+    /// `--cipher` must match the backup ones
+    #[clap(
+        long,
+        value_name = "object key",
+        conflicts_with_all = &["value", "from-file"]
+    )]
+    pub object_key: Option<String>,
+    /// cipher the objects of `--object-key` are encrypted with - `aes-256-gcm` (default) or `chacha20-poly1305`
+    #[clap(long, value_name = "cipher", requires = "object-key")]
+    pub cipher: Option<EncryptionCipher>,
     /// stream output on stdout
     #[clap(short, long)]
     pub output: bool,
+    /// format of the output (default: sql) - `jsonl` streams one JSON object per row on stdout, `csv` writes one file per
+    /// table into `--output-dir`
+    #[clap(long, arg_enum, requires = "output")]
+    pub format: Option<RestoreOutputFormat>,
+    /// directory of the CSV files - one `<database>.<table>.csv` file per table
+    #[clap(
+        long,
+        parse(from_os_str),
+        value_name = "directory",
+        required_if_eq("format", "csv")
+    )]
+    pub output_dir: Option<PathBuf>,
+    /// do not ask to confirm the destination database wipe
+    #[clap(short, long)]
+    pub yes: bool,
+    /// restore into the PostgreSQL destination without wiping it - the existing tables and rows are kept
+    #[clap(long, conflicts_with = "output")]
+    pub if_not_exists: bool,
+    /// rename a column of the restored PostgreSQL queries - E.g. `public.users.name=full_name`. Can be repeated
+    #[clap(
+        long,
+        multiple_occurrences = true,
+        value_name = "database.table.column=new column"
+    )]
+    pub column_map: Vec<ColumnMapping>,
+    /// restore the objects of the public schema into another PostgreSQL schema - E.g. `tenant_x`, or `tenant_a=tenant_x` to move
+    /// the objects of another schema. Only the target schema is wiped
+    #[clap(long, value_name = "[schema=]new schema")]
+    pub target_schema: Option<SchemaMapping>,
+    /// restore the rows of the tables listed in this file only - one `<database>.<table>` per line (E.g. `public.users`),
+    /// the empty lines and `#` comments are ignored. The schema of all the tables is restored
+    #[clap(long, parse(from_os_str), value_name = "tables file")]
+    pub tables_from_file: Option<PathBuf>,
+    /// warn when the latest backup is older than this age - E.g. 1d or 12h. Ignored when restoring a named backup
+    #[clap(long, parse(try_from_str = from_human_readable_duration), value_name = "age")]
+    pub max_age: Option<Duration>,
+    /// fail instead of restoring when the latest backup is older than `--max-age` - E.g. for alerting
+    #[clap(long, requires = "max-age")]
+    pub check_fresh: bool,
+}
+
+/// all backup list commands
+#[derive(Args, Debug)]
+pub struct BackupListArgs {
+    /// max number of backups to list (newest first)
+    #[clap(long, value_name = "number of backups")]
+    pub limit: Option<usize>,
+    /// number of backups to skip (newest first)
+    #[clap(long, value_name = "number of backups", default_value_t = 0)]
+    pub offset: usize,
+    /// list the backups of every prefix of the bucket
+    #[clap(long)]
+    pub all: bool,
+    /// warn when the newest backup is older than this age - E.g. 1d or 12h
+    #[clap(
+        long,
+        parse(try_from_str = from_human_readable_duration),
+        value_name = "age",
+        conflicts_with = "all"
+    )]
+    pub max_age: Option<Duration>,
+    /// exit with an error when the newest backup is older than `--max-age` - E.g. for alerting
+    #[clap(long, requires = "max-age")]
+    pub check_fresh: bool,
+}
+
+/// all backup compare commands
+#[derive(Args, Debug)]
+pub struct BackupCompareArgs {
+    /// name of the first backup - E.g. the oldest one
+    #[clap(value_name = "backup name")]
+    pub a: String,
+    /// name of the second backup
+    #[clap(value_name = "backup name")]
+    pub b: String,
+}
+
+/// all backup rename commands
+#[derive(Args, Debug)]
+pub struct BackupRenameArgs {
+    /// name of the backup - use `backup list` command to list all backups available
+    #[clap(value_name = "backup name")]
+    pub name: String,
+    /// new name of the backup - it must not be taken by another backup
+    #[clap(value_name = "new name")]
+    pub new_name: String,
+}
+
+/// all backup lock and unlock commands
+#[derive(Args, Debug)]
+pub struct BackupLockArgs {
+    /// name of the backup - use `backup list` command to list all backups available
+    #[clap(value_name = "backup name")]
+    pub name: String,
+}
+
+/// all backup verify-encryption commands
+#[derive(Args, Debug)]
+pub struct BackupVerifyEncryptionArgs {
+    /// name of the backup - use `backup list` command to list all backups available
+    #[clap(value_name = "backup name")]
+    pub name: String,
+}
+
+/// all backup download commands
+#[derive(Args, Debug)]
+pub struct BackupDownloadArgs {
+    /// name of the backup - use `backup list` command to list all backups available
+    #[clap(value_name = "backup name")]
+    pub name: String,
+    /// directory the objects are written to as `1.dump`, `2.dump`... - E.g. to restore them with `restore --from-file`
+    #[clap(parse(from_os_str), value_name = "directory")]
+    pub path: PathBuf,
+    /// decrypt the objects with the `encryption_key` of the configuration file
+    #[clap(long)]
+    pub decrypt: bool,
+    /// decompress the objects - an encrypted backup must be decrypted too
+    #[clap(long)]
+    pub decompress: bool,
+}
+
+/// all backup export-index and import-index commands
+#[derive(Args, Debug)]
+pub struct BackupIndexFileArgs {
+    /// JSON file holding the index file
+    #[clap(parse(from_os_str), value_name = "file")]
+    pub file: PathBuf,
+}
+
+/// all backup train-compression-dict commands
+#[derive(Args, Debug)]
+pub struct BackupTrainCompressionDictArgs {
+    /// dumps similar to the backups to compress - E.g. the dumps of a few previous backups
+    #[clap(parse(from_os_str), value_name = "sample files", required = true)]
+    pub samples: Vec<PathBuf>,
+    /// file the dictionary is written to
+    #[clap(short, long, parse(from_os_str), value_name = "dictionary file")]
+    pub output: PathBuf,
+    /// max size of the dictionary
+    #[clap(long, parse(try_from_str = from_human_readable_unit), value_name = "size", default_value = "112KB")]
+    pub max_size: usize,
+}
+
+/// all backup verify-restore commands
+#[derive(Args, Debug)]
+pub struct BackupVerifyRestoreArgs {
+    /// backup to verify -- set `latest` or `<backup name>` - use `backup list` command to list all backups available
+    #[clap(short, long, value_name = "[latest | backup name]")]
+    pub value: String,
+    /// known-good backup to compare with - both backups are restored into the scratch destination and the rows and the
+    /// checksum of their tables are compared
+    #[clap(long, value_name = "backup name")]
+    pub since_backup: Option<String>,
 }
 
 /// all backup run commands
@@ -66,4 +327,206 @@ pub struct BackupRunArgs {
     #[clap(short, long, parse(from_os_str), value_name = "dump file")]
     /// dump file
     pub file: Option<PathBuf>,
+    /// abort and delete the backup when it exceeds this size - E.g. 500MB or 50GB
+    #[clap(long, parse(try_from_str = from_human_readable_unit), value_name = "size")]
+    pub max_size: Option<usize>,
+    /// print the time spent in each stage (source dump, transform, compress, encrypt, upload) at the end
+    #[clap(long)]
+    pub timings: bool,
+    /// print the values replaced and kept by each transformer at the end - E.g. to find a transformer matching no column
+    #[clap(long)]
+    pub transformer_metrics: bool,
+    /// print the time spent in each transformer and its throughput at the end - from the slowest transformer
+    #[clap(long)]
+    pub profile_transformers: bool,
+    /// compress the backup whatever the configuration says
+    #[clap(long, conflicts_with = "no-compress")]
+    pub compress: bool,
+    /// do not compress the backup whatever the configuration says
+    #[clap(long)]
+    pub no_compress: bool,
+    /// do not encrypt the backup even if an encryption key is configured
+    #[clap(long)]
+    pub no_encrypt: bool,
+    /// back up on a schedule until interrupted - E.g. 30m or 6h. The old backups are deleted with the `keep_last` of the bridge
+    #[clap(long, parse(try_from_str = from_human_readable_duration), value_name = "interval")]
+    pub every: Option<Duration>,
+    /// compress the backup with zstd and this dictionary - E.g. written by `backup train-compression-dict`.
+    /// The dictionary is stored in the bridge to restore the backup
+    #[clap(long, parse(from_os_str), value_name = "dictionary file")]
+    pub compression_dict: Option<PathBuf>,
+    /// only upload the rows of the tables changed since the last backup - the parts of the unchanged tables are
+    /// shared with it. PostgreSQL only, the bridge deduplication must be enabled
+    #[clap(long)]
+    pub only_changed: bool,
+}
+
+impl BackupRunArgs {
+    /// `--compress` and `--no-compress` override the configuration - compressed by default
+    pub fn compression(&self, config_compression: Option<bool>) -> bool {
+        match (self.compress, self.no_compress) {
+            (true, _) => true,
+            (_, true) => false,
+            _ => config_compression.unwrap_or(true),
+        }
+    }
+
+    /// `--no-encrypt` drops the configured encryption key
+    pub fn encryption_key(&self, config_encryption_key: Option<String>) -> Option<String> {
+        match self.no_encrypt {
+            true => None,
+            false => config_encryption_key,
+        }
+    }
+}
+
+fn parse_millis(millis: &str) -> Result<Duration, String> {
+    match millis.parse::<u64>() {
+        Ok(0) => Err("it must be greater than 0".to_string()),
+        Ok(millis) => Ok(Duration::from_millis(millis)),
+        Err(_) => Err(format!("invalid number of milliseconds '{}'", millis)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use clap::Parser;
+
+    use crate::cli::{BackupCommand, SubCommand, CLI};
+    use crate::destination::postgres::ColumnMapping;
+
+    fn backup_run_args(args: &[&str]) -> crate::cli::BackupRunArgs {
+        let cli = CLI::try_parse_from(
+            [
+                &["replibyte", "-c", "replibyte.yaml", "backup", "run"],
+                args,
+            ]
+            .concat(),
+        )
+        .unwrap();
+
+        match cli.sub_commands {
+            SubCommand::Backup(BackupCommand::Run(args)) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn backup_run_compression_overrides_config() {
+        assert!(backup_run_args(&[]).compression(None));
+        assert!(!backup_run_args(&[]).compression(Some(false)));
+        assert!(backup_run_args(&["--compress"]).compression(Some(false)));
+        assert!(!backup_run_args(&["--no-compress"]).compression(Some(true)));
+
+        assert!(CLI::try_parse_from([
+            "replibyte",
+            "-c",
+            "replibyte.yaml",
+            "backup",
+            "run",
+            "--compress",
+            "--no-compress"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn backup_run_encryption_overrides_config() {
+        let key = Some("secret".to_string());
+
+        assert_eq!(backup_run_args(&[]).encryption_key(key.clone()), key);
+        assert_eq!(backup_run_args(&["--no-encrypt"]).encryption_key(key), None);
+    }
+
+    #[test]
+    fn backup_run_every() {
+        assert_eq!(backup_run_args(&[]).every, None);
+        assert_eq!(
+            backup_run_args(&["--every", "6h"]).every,
+            Some(Duration::from_secs(6 * 60 * 60))
+        );
+
+        assert!(CLI::try_parse_from([
+            "replibyte",
+            "-c",
+            "replibyte.yaml",
+            "backup",
+            "run",
+            "--every",
+            "sometimes"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn progress_interval() {
+        let cli = |args: &[&str]| {
+            CLI::try_parse_from(
+                [
+                    &["replibyte", "-c", "replibyte.yaml"],
+                    args,
+                    &["backup", "list"],
+                ]
+                .concat(),
+            )
+        };
+
+        assert_eq!(
+            cli(&[]).unwrap().progress_interval,
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            cli(&["--progress-interval", "500"])
+                .unwrap()
+                .progress_interval,
+            Duration::from_millis(500)
+        );
+
+        assert!(cli(&["--progress-interval", "0"]).is_err());
+        assert!(cli(&["--progress-interval", "1s"]).is_err());
+    }
+
+    #[test]
+    fn restore_column_map() {
+        let cli = CLI::try_parse_from([
+            "replibyte",
+            "-c",
+            "replibyte.yaml",
+            "restore",
+            "-v",
+            "latest",
+            "--column-map",
+            "public.users.name=full_name",
+            "--column-map",
+            "public.orders.user=user_id",
+        ])
+        .unwrap();
+
+        match cli.sub_commands {
+            SubCommand::Restore(args) => assert_eq!(
+                args.column_map,
+                vec![
+                    "public.users.name=full_name"
+                        .parse::<ColumnMapping>()
+                        .unwrap(),
+                    "public.orders.user=user_id".parse().unwrap(),
+                ]
+            ),
+            _ => unreachable!(),
+        }
+
+        assert!(CLI::try_parse_from([
+            "replibyte",
+            "-c",
+            "replibyte.yaml",
+            "restore",
+            "-v",
+            "latest",
+            "--column-map",
+            "name=full_name"
+        ])
+        .is_err());
+    }
 }
@@ -0,0 +1,456 @@
+use std::fs::{self, File};
+use std::io::{BufReader, Error, ErrorKind};
+use std::path::Path;
+use std::time::Duration;
+
+use log::warn;
+use timeago::Formatter;
+
+use replibyte::bridge::s3::S3;
+use replibyte::bridge::{
+    download_backup, verify_encryption, Backup, BackupComparison, Bridge, CompressionDictionary,
+    ReadOptions, OBJECT_HEADER_LENGTH,
+};
+use replibyte::cli::{
+    BackupCommand, BackupCompareArgs, BackupListArgs, BackupVerifyEncryptionArgs,
+};
+use replibyte::config::Config;
+use replibyte::connector::Connector;
+use replibyte::runtime::Runtime;
+use replibyte::source::SourceRegistry;
+use replibyte::utils::{epoch_millis, table, to_human_readable_unit};
+
+use crate::commands::{backup_run, verify_restore};
+
+fn list_backups(s3: &mut S3, args: &BackupListArgs) -> Result<(), Error> {
+    if args.all {
+        return list_all_backups(s3, args);
+    }
+
+    let _ = s3.init()?;
+    let index_file = s3.index_file()?;
+
+    if index_file.backups.is_empty() {
+        println!("<empty> no backups available\n");
+
+        return match args.max_age {
+            Some(max_age) => check_backup_freshness(&[], max_age, args.check_fresh, epoch_millis()),
+            None => Ok(()),
+        };
+    }
+
+    // checked on all the backups - the newest one can be out of the page
+    let freshness = args.max_age.map(|max_age| {
+        check_backup_freshness(
+            &index_file.backups,
+            max_age,
+            args.check_fresh,
+            epoch_millis(),
+        )
+    });
+
+    let backups = index_file.page(args.offset, args.limit);
+
+    let mut table = table();
+    table.set_titles(row![
+        "name",
+        "size",
+        "when",
+        "compressed",
+        "encrypted",
+        "locked"
+    ]);
+    let formatter = Formatter::new();
+    let now = epoch_millis();
+
+    for backup in backups {
+        table.add_row(row![
+            backup.directory_name.as_str(),
+            to_human_readable_unit(backup.size),
+            formatter.convert(Duration::from_millis((now - backup.created_at) as u64)),
+            backup.compressed,
+            backup.encrypted,
+            backup.locked,
+        ]);
+    }
+
+    let _ = table.printstd();
+
+    freshness.unwrap_or(Ok(()))
+}
+
+/// warn when the newest backup is older than `max_age` - or fail with `check_fresh`, to exit non-zero and alert
+pub fn check_backup_freshness(
+    backups: &[Backup],
+    max_age: Duration,
+    check_fresh: bool,
+    now: u128,
+) -> Result<(), Error> {
+    let mut formatter = Formatter::new();
+    let _ = formatter.ago("");
+
+    let message = match backups.iter().max_by_key(|backup| backup.created_at) {
+        Some(backup) => {
+            let age = Duration::from_millis(now.saturating_sub(backup.created_at) as u64);
+            if age <= max_age {
+                return Ok(());
+            }
+
+            format!(
+                "the newest backup '{}' is {} old - older than the max age of {}",
+                backup.directory_name,
+                formatter.convert(age),
+                formatter.convert(max_age)
+            )
+        }
+        None => "no backups available - the newest backup can't be fresh".to_string(),
+    };
+
+    if check_fresh {
+        return Err(Error::new(ErrorKind::Other, message));
+    }
+
+    warn!("{}", message);
+
+    Ok(())
+}
+
+/// the backups of every prefix of the bucket - no index file is created
+fn list_all_backups(s3: &S3, args: &BackupListArgs) -> Result<(), Error> {
+    let mut backups = s3
+        .all_index_files()?
+        .into_iter()
+        .flat_map(|(prefix, index_file)| {
+            index_file
+                .backups
+                .into_iter()
+                .map(move |backup| (prefix.clone(), backup))
+        })
+        .collect::<Vec<_>>();
+
+    if backups.is_empty() {
+        println!("<empty> no backups available\n");
+        return Ok(());
+    }
+
+    backups.sort_by(|(_, a), (_, b)| a.cmp(b).reverse());
+
+    let backups = backups
+        .into_iter()
+        .skip(args.offset)
+        .take(args.limit.unwrap_or(usize::MAX));
+
+    let mut table = table();
+    table.set_titles(row![
+        "prefix",
+        "name",
+        "size",
+        "when",
+        "compressed",
+        "encrypted",
+        "locked"
+    ]);
+    let formatter = Formatter::new();
+    let now = epoch_millis();
+
+    for (prefix, backup) in backups {
+        table.add_row(row![
+            prefix.as_deref().unwrap_or("/"),
+            backup.directory_name.as_str(),
+            to_human_readable_unit(backup.size),
+            formatter.convert(Duration::from_millis((now - backup.created_at) as u64)),
+            backup.compressed,
+            backup.encrypted,
+            backup.locked,
+        ]);
+    }
+
+    let _ = table.printstd();
+
+    Ok(())
+}
+
+fn backup_stats(s3: &mut S3) -> Result<(), Error> {
+    let _ = s3.init()?;
+    let stats = s3.index_file()?.stats();
+
+    let formatter = Formatter::new();
+    let now = epoch_millis();
+    let when = |created_at: Option<u128>| match created_at {
+        Some(created_at) => {
+            formatter.convert(Duration::from_millis(now.saturating_sub(created_at) as u64))
+        }
+        None => "-".to_string(),
+    };
+
+    let mut table = table();
+    table.set_titles(row!["stat", "value"]);
+    table.add_row(row!["backups", stats.count]);
+    table.add_row(row!["total size", to_human_readable_unit(stats.total_size)]);
+    table.add_row(row![
+        "average size",
+        to_human_readable_unit(stats.average_size)
+    ]);
+    table.add_row(row!["oldest", when(stats.oldest_created_at)]);
+    table.add_row(row!["newest", when(stats.newest_created_at)]);
+
+    let _ = table.printstd();
+
+    Ok(())
+}
+
+/// check the encryption of a backup from the first bytes of its objects - fail when it is not encrypted
+fn verify_backup_encryption(s3: &mut S3, args: &BackupVerifyEncryptionArgs) -> Result<(), Error> {
+    let _ = s3.init()?;
+    let mut index_file = s3.index_file()?;
+
+    let read_options = ReadOptions::Backup {
+        name: args.name.clone(),
+    };
+    let backup = index_file.find_backup(&read_options)?;
+    let header = s3.read_object_header(&read_options, OBJECT_HEADER_LENGTH)?;
+    let cipher = verify_encryption(backup, header.as_slice())?;
+
+    println!(
+        "Backup '{}' is encrypted - cipher: {}",
+        backup.directory_name,
+        cipher.name()
+    );
+
+    Ok(())
+}
+
+fn compare_backups(s3: &mut S3, args: &BackupCompareArgs) -> Result<(), Error> {
+    let _ = s3.init()?;
+    let index_file = s3.index_file()?;
+
+    let find_backup = |name: &str| {
+        index_file
+            .backups
+            .iter()
+            .find(|backup| backup.directory_name == name)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("Can't find backup with name '{}'", name),
+                )
+            })
+    };
+
+    let a = find_backup(args.a.as_str())?;
+    let b = find_backup(args.b.as_str())?;
+    let comparison = BackupComparison::new(a, b);
+
+    let formatter = Formatter::new();
+    let now = epoch_millis();
+    let when = |created_at: u128| {
+        formatter.convert(Duration::from_millis(now.saturating_sub(created_at) as u64))
+    };
+    let engine = |backup: &Backup| backup.engine.map(|engine| engine.name().to_string());
+    let count = |values: &Option<Vec<String>>| match values {
+        Some(values) => values.len().to_string(),
+        None => "-".to_string(),
+    };
+    let size_delta = match comparison.size_delta {
+        delta if delta < 0 => format!("-{}", to_human_readable_unit(delta.unsigned_abs() as usize)),
+        delta => format!("+{}", to_human_readable_unit(delta as usize)),
+    };
+
+    let mut table = table();
+    table.set_titles(row!["", a.directory_name, b.directory_name, "diff"]);
+    table.add_row(row!["when", when(a.created_at), when(b.created_at), ""]);
+    table.add_row(row![
+        "size",
+        to_human_readable_unit(a.size),
+        to_human_readable_unit(b.size),
+        size_delta
+    ]);
+    table.add_row(row![
+        "engine",
+        engine(a).unwrap_or_else(|| "-".to_string()),
+        engine(b).unwrap_or_else(|| "-".to_string()),
+        ""
+    ]);
+    table.add_row(row!["compressed", a.compressed, b.compressed, ""]);
+    table.add_row(row!["encrypted", a.encrypted, b.encrypted, ""]);
+    table.add_row(row![
+        "tables",
+        count(&a.tables),
+        count(&b.tables),
+        format!(
+            "+{} -{}",
+            comparison.added_tables.len(),
+            comparison.removed_tables.len()
+        )
+    ]);
+    table.add_row(row![
+        "transformers",
+        count(&a.transformers),
+        count(&b.transformers),
+        format!(
+            "+{} -{}",
+            comparison.added_transformers.len(),
+            comparison.removed_transformers.len()
+        )
+    ]);
+
+    for (kind, added, removed) in [
+        (
+            "table",
+            &comparison.added_tables,
+            &comparison.removed_tables,
+        ),
+        (
+            "transformer",
+            &comparison.added_transformers,
+            &comparison.removed_transformers,
+        ),
+    ] {
+        for value in removed {
+            table.add_row(row![kind, value, "", "removed"]);
+        }
+        for value in added {
+            table.add_row(row![kind, "", value, "added"]);
+        }
+    }
+
+    let _ = table.printstd();
+
+    Ok(())
+}
+
+/// run the `backup` commands - `backup run` and `backup verify-restore` have their own module
+pub fn run(
+    cmd: &BackupCommand,
+    mut bridge: S3,
+    config: Config,
+    source_registry: &SourceRegistry,
+    runtime: &Runtime,
+    summary_file: Option<&Path>,
+) -> anyhow::Result<()> {
+    match cmd {
+        BackupCommand::List(args) => {
+            let _ = list_backups(&mut bridge, args)?;
+        }
+        BackupCommand::Stats => {
+            let _ = backup_stats(&mut bridge)?;
+        }
+        BackupCommand::Compare(args) => {
+            let _ = compare_backups(&mut bridge, args)?;
+        }
+        BackupCommand::VerifyEncryption(args) => {
+            let _ = verify_backup_encryption(&mut bridge, args)?;
+        }
+        BackupCommand::Download(args) => {
+            let _ = bridge.init()?;
+            let objects_count = download_backup(
+                &bridge,
+                args.name.as_str(),
+                args.path.as_path(),
+                args.decrypt,
+                args.decompress,
+            )?;
+            println!(
+                "Backup '{}' downloaded to '{}' ({} objects)",
+                args.name,
+                args.path.display(),
+                objects_count
+            );
+        }
+        BackupCommand::Rename(args) => {
+            let _ = bridge.init()?;
+            let _ = bridge.rename_backup(args.name.as_str(), args.new_name.as_str())?;
+            println!("Backup '{}' renamed to '{}'", args.name, args.new_name);
+        }
+        BackupCommand::Lock(args) => {
+            let _ = bridge.init()?;
+            let _ = bridge.set_backup_locked(args.name.as_str(), true)?;
+            println!("Backup '{}' locked", args.name);
+        }
+        BackupCommand::Unlock(args) => {
+            let _ = bridge.init()?;
+            let _ = bridge.set_backup_locked(args.name.as_str(), false)?;
+            println!("Backup '{}' unlocked", args.name);
+        }
+        BackupCommand::ExportIndex(args) => {
+            let _ = bridge.init()?;
+            let _ = bridge.export_index_file(File::create(args.file.as_path())?)?;
+            println!("Index file exported to '{}'", args.file.display());
+        }
+        BackupCommand::ImportIndex(args) => {
+            let _ = bridge.init()?;
+            let added_backups_count =
+                bridge.import_index_file(BufReader::new(File::open(args.file.as_path())?))?;
+            println!("{} backups imported", added_backups_count);
+        }
+        BackupCommand::TrainCompressionDict(args) => {
+            let samples = args
+                .samples
+                .iter()
+                .map(fs::read)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let dictionary = CompressionDictionary::train(&samples, args.max_size)?;
+            let _ = fs::write(args.output.as_path(), dictionary.data)?;
+            println!(
+                "Compression dictionary {} written to '{}'",
+                dictionary.id,
+                args.output.display()
+            );
+        }
+        BackupCommand::Run(args) => {
+            backup_run::run(args, bridge, config, source_registry, runtime, summary_file)?
+        }
+        BackupCommand::VerifyRestore(args) => verify_restore::run(args, bridge, config, runtime)?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use replibyte::bridge::Backup;
+
+    use crate::commands::backup::check_backup_freshness;
+
+    #[test]
+    fn stale_backup_fails_the_freshness_check() {
+        let backup = |created_at: u128| Backup {
+            directory_name: format!("backup-{}", created_at),
+            size: 1_000,
+            created_at,
+            compressed: true,
+            encrypted: false,
+            cipher: None,
+            uncompressed_size: None,
+            engine: None,
+            storage_class: None,
+            compression_dictionary_id: None,
+            tables: None,
+            transformers: None,
+            chunks: None,
+            locked: false,
+            table_checksums: None,
+            table_parts: None,
+            key: None,
+            random_nonce: false,
+        };
+
+        let day = Duration::from_secs(24 * 60 * 60);
+        let now = 1647734369306;
+        let backups = vec![
+            backup(now - 3 * day.as_millis()),
+            backup(now - 2 * day.as_millis()),
+        ];
+
+        // the newest backup is 2 days old
+        assert!(check_backup_freshness(&backups, 3 * day, true, now).is_ok());
+        assert!(check_backup_freshness(&backups, day, true, now).is_err());
+        // only a warning without --check-fresh
+        assert!(check_backup_freshness(&backups, day, false, now).is_ok());
+
+        assert!(check_backup_freshness(&[], day, true, now).is_err());
+    }
+}
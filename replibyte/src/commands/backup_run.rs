@@ -0,0 +1,497 @@
+use std::fs::{self, File};
+use std::io::{stdin, BufReader, Error, ErrorKind, Read};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+
+use replibyte::bridge::s3::S3;
+use replibyte::bridge::{prune_backups, Bridge, CompressionDictionary, WrittenSizes};
+use replibyte::cli::BackupRunArgs;
+use replibyte::config::{Config, ConnectionUri, HooksConfig, SourceConfig, TransformerConfig};
+use replibyte::connector::Engine;
+use replibyte::hooks::Hooks;
+use replibyte::logger;
+use replibyte::metrics::{CountedTransformer, TableRows, TransformerMetrics};
+use replibyte::runtime::Runtime;
+use replibyte::schedule::{run_every, SystemClock};
+use replibyte::source::mongodb::MongoDB as SourceMongoDB;
+use replibyte::source::postgres::Postgres as SourcePostgres;
+use replibyte::source::postgres_stdin::PostgresStdin;
+use replibyte::source::{SourceOptions, SourceRegistry};
+use replibyte::tasks::full_backup::FullBackupTask;
+use replibyte::tasks::{MaxBytes, Task, TransferredBytes};
+use replibyte::timings::{Stage, TimedTransformer, Timings};
+use replibyte::transformer::Transformer;
+use replibyte::utils::{table, to_human_readable_unit};
+
+use crate::commands::{check_source_columns, column_transformers, with_summary, RunSummary};
+
+/// display the time spent in each backup stage
+fn print_timings(timings: &Timings) {
+    let mut table = table();
+    table.set_titles(row!["stage", "duration"]);
+
+    for stage in Stage::ALL {
+        table.add_row(row![
+            stage.name(),
+            format!("{:.2?}", timings.duration(stage))
+        ]);
+    }
+
+    let _ = table.printstd();
+}
+
+/// display the values replaced and kept by each transformer - a transformer without any value matches no column
+fn print_transformer_metrics(metrics: &TransformerMetrics) {
+    let counts = metrics.counts();
+    if counts.is_empty() {
+        return;
+    }
+
+    let mut table = table();
+    table.set_titles(row!["column", "transformer", "transformed", "skipped"]);
+
+    for count in counts {
+        table.add_row(row![count.name, count.id, count.transformed, count.skipped]);
+
+        if count.transformed + count.skipped == 0 {
+            warn!(
+                "transformer '{}' of '{}' matched no value - check the database, table and column names",
+                count.id, count.name
+            );
+        }
+    }
+
+    let _ = table.printstd();
+}
+
+/// display the time spent in each transformer from the slowest one - E.g. to find the transformer to optimize
+fn print_transformer_profile(metrics: &TransformerMetrics) {
+    let profile = metrics.profile();
+    if profile.is_empty() {
+        return;
+    }
+
+    let total_duration = profile.iter().map(|count| count.duration).sum::<Duration>();
+
+    let mut table = table();
+    table.set_titles(row![
+        "rank",
+        "column",
+        "transformer",
+        "duration",
+        "values/sec",
+        "share"
+    ]);
+
+    for (idx, count) in profile.iter().enumerate() {
+        let values_per_sec = match count.values_per_sec() {
+            Some(values_per_sec) => format!("{:.0}", values_per_sec),
+            None => "-".to_string(),
+        };
+
+        let share = match total_duration.is_zero() {
+            true => "-".to_string(),
+            false => format!(
+                "{:.1}%",
+                count.duration.as_secs_f64() * 100.0 / total_duration.as_secs_f64()
+            ),
+        };
+
+        table.add_row(row![
+            idx + 1,
+            count.name,
+            count.id,
+            format!("{:.2?}", count.duration),
+            values_per_sec,
+            share
+        ]);
+    }
+
+    let _ = table.printstd();
+}
+
+/// display the rows dumped for each table - a table of the configuration without any row is either empty or missing
+fn print_table_rows(table_rows: &TableRows, transformers: &[TransformerConfig]) {
+    let rows = table_rows.rows();
+    if rows.is_empty() {
+        return;
+    }
+
+    let mut table = table();
+    table.set_titles(row!["table", "rows"]);
+
+    for (table_name, rows) in rows {
+        table.add_row(row![table_name, rows]);
+    }
+
+    let _ = table.printstd();
+
+    for warning in empty_table_warnings(table_rows, transformers) {
+        warn!("{}", warning);
+    }
+}
+
+/// a warning for each table of the configuration without any row
+fn empty_table_warnings(table_rows: &TableRows, transformers: &[TransformerConfig]) -> Vec<String> {
+    let table_names = transformers
+        .iter()
+        .map(|transformer| format!("{}.{}", transformer.database, transformer.table))
+        .collect::<Vec<_>>();
+
+    table_rows
+        .empty_tables(table_names.iter().map(|x| x.as_str()))
+        .into_iter()
+        .map(|table_name| {
+            format!(
+                "table '{}' has transformers but no row was dumped - check that it is the right table",
+                table_name
+            )
+        })
+        .collect()
+}
+
+fn print_written_sizes(written_sizes: &WrittenSizes) {
+    let uncompressed_size = written_sizes.uncompressed_size();
+    let size = written_sizes.size();
+
+    let ratio = match size {
+        0 => "-".to_string(),
+        size => format!("{:.2}", uncompressed_size as f64 / size as f64),
+    };
+
+    println!(
+        "{} dumped, {} stored (compression and encryption ratio: {})",
+        to_human_readable_unit(uncompressed_size),
+        to_human_readable_unit(size),
+        ratio
+    );
+
+    let deduplicated_size = written_sizes.deduplicated_size();
+    if deduplicated_size > 0 {
+        println!(
+            "{} deduplicated - the identical parts were already stored",
+            to_human_readable_unit(deduplicated_size)
+        );
+    }
+}
+
+/// back up the `source` database into a new backup of `bridge`
+fn run_backup<F>(
+    mut bridge: S3,
+    source: &SourceConfig,
+    source_registry: &SourceRegistry,
+    hooks_config: Option<&HooksConfig>,
+    args: &BackupRunArgs,
+    summary: &mut RunSummary,
+    interrupted: Arc<AtomicBool>,
+    progress_callback: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(TransferredBytes, MaxBytes),
+{
+    summary.backup_name = Some(bridge.backup_name().to_string());
+
+    // the command line flags override the configuration
+    bridge.set_compression(args.compression(source.compression));
+    bridge.set_encryption_key(args.encryption_key(source.encryption_key()?));
+
+    if let Some(path) = &args.compression_dict {
+        let dictionary = CompressionDictionary::try_from_bytes(fs::read(path)?)?;
+        bridge.set_compression_dictionary(Some(dictionary));
+    }
+
+    // the parts of the unchanged tables are shared as chunks
+    if args.only_changed && !bridge.deduplication() {
+        return Err(anyhow::Error::from(Error::new(
+            ErrorKind::Other,
+            "--only-changed requires the bridge deduplication - set `deduplication: true` in the bridge configuration",
+        )));
+    }
+
+    let transformers =
+        column_transformers(&source.transformers, source.pseudonym_secret()?.as_deref())?;
+
+    // time the transformers, the source and the bridge
+    let timings = Arc::new(Timings::default());
+    bridge.set_timings(timings.clone());
+
+    let written_sizes = Arc::new(WrittenSizes::default());
+    bridge.set_written_sizes(written_sizes.clone());
+
+    // count the values going through each transformer and the rows of each table
+    let mut transformer_metrics = TransformerMetrics::default();
+    transformer_metrics.set_profiling(args.profile_transformers);
+    let table_rows = Arc::new(TableRows::default());
+
+    // the values are only counted when the metrics are printed - counting copies every value
+    let count_values = args.transformer_metrics || args.profile_transformers;
+
+    let transformers = transformers
+        .into_iter()
+        .map(|transformer| -> Box<dyn Transformer> {
+            let transformer: Box<dyn Transformer> = match count_values {
+                true => Box::new(CountedTransformer::new(transformer, &transformer_metrics)),
+                false => transformer,
+            };
+            Box::new(TimedTransformer::new(transformer, timings.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    let empty_config = vec![];
+    let skip_config = match &source.skip {
+        Some(config) => config,
+        None => &empty_config,
+    };
+
+    let options = SourceOptions {
+        transformers: &transformers,
+        skip_config: &skip_config,
+    };
+
+    let backup_name = bridge.backup_name().to_string();
+    logger::set_backup_name(Some(backup_name.clone()));
+    let hooks = Hooks::backup(hooks_config);
+
+    let mut column_type_warnings = vec![];
+    let result = hooks.run(backup_name.as_str(), || -> anyhow::Result<()> {
+        match args.source_type.as_ref().map(|x| x.as_str()) {
+            None if source.source_type.is_some() => {
+                let source_type = source.source_type.as_deref().unwrap_or_default();
+                let registered_source = source_registry.source(source_type, source)?;
+
+                let mut task = FullBackupTask::new(registered_source, bridge, options);
+                task.set_max_size(args.max_size);
+                task.set_interrupted(interrupted.clone());
+                task.set_buffered_parts(source.buffered_parts);
+                task.set_source_retries(source.source_retries);
+                task.set_timings(timings.clone());
+                task.set_table_rows(table_rows.clone());
+                task.run(progress_callback)?
+            }
+            None => match source.connection_uri()? {
+                ConnectionUri::Postgres(host, port, username, password, database) => {
+                    let mut postgres = SourcePostgres::new(
+                        host.as_str(),
+                        port,
+                        database.as_str(),
+                        username.as_str(),
+                        password.as_str(),
+                    );
+                    postgres.set_dump_args(source.dump_args.clone().unwrap_or_default())?;
+                    postgres.set_tls(source.tls.clone())?;
+                    postgres.set_parallel_dump(source.parallel_dump.clone())?;
+
+                    // first - an unchanged table is backed up as it was when its checksum was computed, and the rows
+                    // of the unchanged tables are excluded from the dump
+                    let table_checksums = match args.only_changed {
+                        true => Some(postgres.table_checksums()?),
+                        false => None,
+                    };
+
+                    // E.g. a `credit-card` transformer on an integer column
+                    if !transformers.is_empty() {
+                        let columns = postgres.column_types()?;
+                        for warning in
+                            check_source_columns(&source.transformers, &transformers, &columns)?
+                        {
+                            warn!("{}", warning);
+                            column_type_warnings.push(warning);
+                        }
+                    }
+
+                    bridge.set_engine(Engine::PostgreSQL);
+                    let part_per_table = bridge.deduplication();
+                    let mut task = FullBackupTask::new(postgres, bridge, options);
+                    task.set_max_size(args.max_size);
+                    task.set_interrupted(interrupted.clone());
+                    task.set_buffered_parts(source.buffered_parts);
+                    task.set_source_retries(source.source_retries);
+                    task.set_part_per_table(part_per_table);
+                    task.set_table_checksums(table_checksums);
+                    task.set_timings(timings.clone());
+                    task.set_table_rows(table_rows.clone());
+                    task.run(progress_callback)?
+                }
+                ConnectionUri::Mysql(_host, _port, _username, _password, _database) => {
+                    todo!() // FIXME
+                }
+                ConnectionUri::MongoDB(
+                    host,
+                    port,
+                    username,
+                    password,
+                    database,
+                    authentication_db,
+                    connection_string,
+                ) => {
+                    let mut mongodb = SourceMongoDB::new(
+                        host.as_str(),
+                        port,
+                        database.as_str(),
+                        username.as_str(),
+                        password.as_str(),
+                        authentication_db.as_str(),
+                    );
+                    mongodb.set_connection_string(connection_string.as_deref());
+                    mongodb.set_dump_args(source.dump_args.clone().unwrap_or_default())?;
+
+                    bridge.set_engine(Engine::MongoDB);
+                    let mut task = FullBackupTask::new(mongodb, bridge, options);
+                    task.set_max_size(args.max_size);
+                    task.set_interrupted(interrupted.clone());
+                    task.set_buffered_parts(source.buffered_parts);
+                    task.set_source_retries(source.source_retries);
+                    task.set_timings(timings.clone());
+                    task.set_table_rows(table_rows.clone());
+                    task.run(progress_callback)?
+                }
+            },
+            // some user use "postgres" and "postgresql" both are valid
+            Some(v) if v == "postgres" || v == "postgresql" => {
+                if args.file.is_some() {
+                    let dump_file = File::open(args.file.as_ref().unwrap())?;
+                    let mut stdin = stdin(); // FIXME
+                    let reader = BufReader::new(dump_file);
+                    let _ = stdin.read_to_end(&mut reader.buffer().to_vec())?;
+                }
+
+                let postgres = PostgresStdin::default();
+                bridge.set_engine(Engine::PostgreSQL);
+                let mut task = FullBackupTask::new(postgres, bridge, options);
+                task.set_max_size(args.max_size);
+                task.set_interrupted(interrupted.clone());
+                task.set_buffered_parts(source.buffered_parts);
+                task.set_timings(timings.clone());
+                task.set_table_rows(table_rows.clone());
+                task.run(progress_callback)?
+            }
+            Some(v) => {
+                return Err(anyhow::Error::from(Error::new(
+                    ErrorKind::Other,
+                    format!("source type '{}' not recognized", v),
+                )));
+            }
+        }
+
+        Ok(())
+    });
+
+    // the partial counts are recorded when the backup fails
+    summary.bytes = written_sizes.uncompressed_size();
+    summary.tables = table_rows.rows();
+    summary.warnings = column_type_warnings;
+    summary
+        .warnings
+        .extend(empty_table_warnings(&table_rows, &source.transformers));
+    result?;
+
+    println!("Backup successful!");
+    print_written_sizes(&written_sizes);
+
+    if args.transformer_metrics {
+        print_transformer_metrics(&transformer_metrics);
+    }
+
+    print_table_rows(&table_rows, &source.transformers);
+
+    if args.timings {
+        print_timings(&timings);
+    }
+
+    if args.profile_transformers {
+        print_transformer_profile(&transformer_metrics);
+    }
+
+    Ok(())
+}
+
+/// run `backup run` - once, or every `--every` until Ctrl-C
+pub fn run(
+    args: &BackupRunArgs,
+    bridge: S3,
+    config: Config,
+    source_registry: &SourceRegistry,
+    runtime: &Runtime,
+    summary_file: Option<&Path>,
+) -> anyhow::Result<()> {
+    let source = match config.source {
+        Some(source) => source,
+        None => {
+            return Err(anyhow::Error::from(Error::new(
+                ErrorKind::Other,
+                "missing <source> object in the configuration file",
+            )));
+        }
+    };
+
+    // checked before backing up
+    let keep_last = config.bridge.keep_last()?;
+
+    match args.every {
+        None => {
+            with_summary(summary_file, "backup", |summary| {
+                run_backup(
+                    bridge.clone(),
+                    &source,
+                    source_registry,
+                    config.hooks.as_ref(),
+                    args,
+                    summary,
+                    runtime.shutdown_signal(),
+                    runtime.progress_callback(),
+                )
+            })?;
+
+            prune(&bridge, keep_last)?
+        }
+        Some(every) => {
+            // stop on Ctrl-C - the backup in progress is aborted
+            runtime.shutdown_on_interrupt();
+
+            run_every(
+                &SystemClock,
+                every,
+                || runtime.is_shutdown(),
+                |run_number| {
+                    let bridge = bridge.next_backup();
+                    println!("Backup #{} '{}' started", run_number, bridge.backup_name());
+
+                    // the summary of the last backup is kept
+                    with_summary(summary_file, "backup", |summary| {
+                        run_backup(
+                            bridge.clone(),
+                            &source,
+                            source_registry,
+                            config.hooks.as_ref(),
+                            args,
+                            summary,
+                            runtime.shutdown_signal(),
+                            runtime.progress_callback(),
+                        )
+                    })
+                    .and_then(|_| prune(&bridge, keep_last))
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// delete the backups older than the `keep_last` newest ones - after each successful backup
+fn prune(bridge: &S3, keep_last: Option<usize>) -> anyhow::Result<()> {
+    if let Some(keep_last) = keep_last {
+        for backup in prune_backups(bridge, keep_last)? {
+            println!(
+                "Backup '{}' deleted - older than the {} newest backups",
+                backup, keep_last
+            );
+        }
+    }
+
+    Ok(())
+}
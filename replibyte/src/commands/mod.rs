@@ -0,0 +1,473 @@
+//! the commands of the command line - `main` dispatches the parsed subcommand to the `run` of its module
+
+pub mod backup;
+pub mod backup_run;
+pub mod restore;
+pub mod source;
+pub mod transformer;
+pub mod verify_restore;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use log::warn;
+use serde::Serialize;
+
+use replibyte::config::{check_column_types, check_key_columns, TransformerConfig};
+use replibyte::error::ReplibyteError;
+use replibyte::source::postgres::TableColumn;
+use replibyte::tasks::{MaxBytes, TransferredBytes};
+use replibyte::transformer::keep_null_and_empty::KeepNullAndEmptyTransformer;
+use replibyte::transformer::preserve_case::PreserveCaseTransformer;
+use replibyte::transformer::Transformer;
+
+/// a JSON progress record is written at most every PROGRESS_JSON_INTERVAL
+pub const PROGRESS_JSON_INTERVAL: Duration = Duration::from_secs(1);
+
+/// write the progress as newline-delimited JSON records - for non-TTY consumers (E.g. CI logs)
+/// E.g. `{"bytes":1024,"total":4096,"pct":25.0,"elapsed":3}`
+pub fn progress_json<W: Write + Send>(
+    mut writer: W,
+    interval: Duration,
+) -> impl FnMut(TransferredBytes, MaxBytes) + Send {
+    let started_at = Instant::now();
+    let mut last_record: Option<(Instant, TransferredBytes, MaxBytes)> = None;
+
+    move |transferred_bytes: TransferredBytes, max_bytes: MaxBytes| {
+        let now = Instant::now();
+
+        if let Some((written_at, bytes, total)) = last_record {
+            let unchanged = bytes == transferred_bytes && total == max_bytes;
+            if unchanged || now.duration_since(written_at) < interval {
+                return;
+            }
+        }
+
+        // the percentage is unknown until the max bytes are known
+        let pct = match max_bytes {
+            0 => None,
+            max_bytes => Some(transferred_bytes as f64 * 100.0 / max_bytes as f64),
+        };
+
+        let record = serde_json::json!({
+            "bytes": transferred_bytes,
+            "total": max_bytes,
+            "pct": pct,
+            "elapsed": now.duration_since(started_at).as_secs(),
+        });
+
+        // the progress is best effort - never fail the command on it
+        let _ = writeln!(writer, "{}", record).and_then(|_| writer.flush());
+        last_record = Some((now, transferred_bytes, max_bytes));
+    }
+}
+
+/// writer of the progress records - a Unix socket listened to by the consumer (E.g. a GUI) or a named pipe
+#[cfg(unix)]
+pub fn progress_socket(path: &Path) -> Result<Box<dyn Write + Send>, Error> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixStream;
+
+    let file_type = fs::metadata(path)?.file_type();
+
+    if file_type.is_socket() {
+        Ok(Box::new(UnixStream::connect(path)?))
+    } else if file_type.is_fifo() {
+        // blocks until the consumer opens the pipe for reading
+        Ok(Box::new(fs::OpenOptions::new().write(true).open(path)?))
+    } else {
+        Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "'{}' is neither a Unix socket nor a named pipe",
+                path.display()
+            ),
+        ))
+    }
+}
+
+#[cfg(not(unix))]
+pub fn progress_socket(_: &Path) -> Result<Box<dyn Write + Send>, Error> {
+    Err(Error::new(
+        ErrorKind::Other,
+        "--progress-socket is only supported on Unix",
+    ))
+}
+
+/// display the progress as a spinner until the max bytes are known, then as a progress bar
+pub fn progress_bar() -> impl FnMut(TransferredBytes, MaxBytes) + Send {
+    let pb = ProgressBar::new(0);
+    pb.set_style(ProgressStyle::default_spinner());
+
+    let mut style_is_progress_bar = false;
+    let mut _max_bytes = 0usize;
+
+    move |transferred_bytes: TransferredBytes, max_bytes: MaxBytes| {
+        if _max_bytes == 0 && style_is_progress_bar {
+            // show spinner if there is no max_bytes indicated
+            pb.set_style(ProgressStyle::default_spinner());
+            style_is_progress_bar = false;
+        } else if _max_bytes > 0 && !style_is_progress_bar {
+            pb.set_style(ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.green/blue}] {bytes}/{total_bytes} ({eta})")
+                .progress_chars("#>-"));
+            style_is_progress_bar = true;
+        }
+
+        if max_bytes != _max_bytes {
+            pb.set_length(max_bytes as u64);
+            _max_bytes = max_bytes;
+        }
+
+        pb.set_position(transferred_bytes as u64);
+    }
+}
+
+/// machine-readable summary of a backup or a restore - written into `--summary-file`
+#[derive(Debug, Default, Serialize)]
+pub struct RunSummary {
+    /// `backup` or `restore`
+    pub command: String,
+    /// `success` or `failure`
+    pub status: String,
+    pub backup_name: Option<String>,
+    /// bytes dumped by a backup - size of the backup read by a restore
+    pub bytes: usize,
+    pub duration_ms: u64,
+    /// rows dumped for each table - PostgreSQL backups only
+    pub tables: BTreeMap<String, usize>,
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// run `task` and write its summary into `summary_file` - even when it fails, with its error
+pub fn with_summary<F>(summary_file: Option<&Path>, command: &str, task: F) -> anyhow::Result<()>
+where
+    F: FnOnce(&mut RunSummary) -> anyhow::Result<()>,
+{
+    let started_at = Instant::now();
+    let mut summary = RunSummary {
+        command: command.to_string(),
+        ..RunSummary::default()
+    };
+
+    let result = task(&mut summary);
+
+    let summary_file = match summary_file {
+        Some(summary_file) => summary_file,
+        None => return result,
+    };
+
+    summary.duration_ms = started_at.elapsed().as_millis() as u64;
+    match &result {
+        Ok(_) => summary.status = "success".to_string(),
+        Err(err) => {
+            summary.status = "failure".to_string();
+            summary.error = Some(format!("{:#}", err));
+        }
+    }
+
+    let written = serde_json::to_string_pretty(&summary)
+        .map_err(Error::from)
+        .and_then(|summary| fs::write(summary_file, summary));
+
+    match (result, written) {
+        (Ok(_), Err(err)) => Err(anyhow::Error::from(Error::new(
+            ErrorKind::Other,
+            format!(
+                "can't write the summary file '{}': {}",
+                summary_file.display(),
+                err
+            ),
+        ))),
+        (Err(err), Err(write_err)) => {
+            warn!(
+                "can't write the summary file '{}': {}",
+                summary_file.display(),
+                write_err
+            );
+            Err(err)
+        }
+        (result, Ok(_)) => result,
+    }
+}
+
+/// the transformers of the configured columns - their pseudonyms are keyed with the `pseudonym_secret`
+pub fn column_transformers(
+    transformer_configs: &[TransformerConfig],
+    pseudonym_secret: Option<&str>,
+) -> Result<Vec<Box<dyn Transformer>>, ReplibyteError> {
+    transformer_configs
+        .iter()
+        .flat_map(|transformer| {
+            transformer.columns.iter().map(
+                |column| -> Result<Box<dyn Transformer>, ReplibyteError> {
+                    let mut column_transformer = column.transformer.transformer(
+                        transformer.database.as_str(),
+                        transformer.table.as_str(),
+                        column.name.as_str(),
+                    )?;
+
+                    if let Some(secret) = pseudonym_secret {
+                        column_transformer.set_secret(secret);
+                    }
+
+                    let column_transformer: Box<dyn Transformer> =
+                        if column.preserve_case.unwrap_or(false) {
+                            Box::new(PreserveCaseTransformer::new(column_transformer))
+                        } else {
+                            column_transformer
+                        };
+
+                    if column.keep_null_and_empty.unwrap_or(true) {
+                        Ok(Box::new(KeepNullAndEmptyTransformer::new(
+                            column_transformer,
+                        )))
+                    } else {
+                        Ok(column_transformer)
+                    }
+                },
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+}
+
+/// check the `transformers` configured by `transformer_configs` against the `columns` of the source - a transformed key
+/// column is an error, the warnings are the columns of another type than the one their transformer expects
+pub fn check_source_columns(
+    transformer_configs: &[TransformerConfig],
+    transformers: &Vec<Box<dyn Transformer>>,
+    columns: &[TableColumn],
+) -> Result<Vec<String>, ReplibyteError> {
+    // a transformed primary key breaks the foreign keys
+    check_key_columns(transformer_configs, columns)?;
+
+    Ok(check_column_types(transformers, columns))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::thread;
+    use std::time::Duration;
+
+    use replibyte::config::TransformerConfig;
+    use replibyte::runtime::Runtime;
+    use replibyte::source::postgres::TableColumn;
+    use replibyte::types::Column;
+
+    use crate::commands::{
+        check_source_columns, column_transformers, progress_json, progress_socket, with_summary,
+    };
+
+    #[test]
+    fn source_columns_are_checked_against_the_transformers() {
+        let column = |name: &str, data_type: &str, key: Option<&str>| TableColumn {
+            database: "public".to_string(),
+            table: "users".to_string(),
+            column: name.to_string(),
+            data_type: data_type.to_string(),
+            key: key.map(|key| key.to_string()),
+        };
+        let columns = vec![
+            column("id", "integer", Some("PRIMARY KEY")),
+            column("card_number", "bigint", None),
+        ];
+
+        let transformer_configs = |columns: &str| {
+            serde_yaml::from_str::<Vec<TransformerConfig>>(&format!(
+                "\
+- database: public
+  table: users
+  columns:
+{}",
+                columns
+            ))
+            .unwrap()
+        };
+
+        let rejected = transformer_configs(
+            "    - name: id
+      transformer_name: random",
+        );
+        let transformers = column_transformers(&rejected, None).unwrap();
+        assert!(check_source_columns(&rejected, &transformers, &columns).is_err());
+
+        let accepted = transformer_configs(
+            "    - name: card_number
+      transformer_name: credit-card",
+        );
+        let transformers = column_transformers(&accepted, None).unwrap();
+        assert_eq!(
+            check_source_columns(&accepted, &transformers, &columns).unwrap(),
+            vec!["transformer 'credit-card' expects a text column but 'public.users.card_number' is of type 'bigint'"]
+        );
+    }
+
+    #[test]
+    fn column_transformers_keep_null_and_empty_by_default() {
+        let transformer_configs = |keep_null_and_empty: &str| {
+            serde_yaml::from_str::<Vec<TransformerConfig>>(&format!(
+                "\
+- database: public
+  table: users
+  columns:
+    - name: card_number
+      transformer_name: credit-card
+{}",
+                keep_null_and_empty
+            ))
+            .unwrap()
+        };
+        let empty_string = || Column::StringValue("card_number".to_string(), "".to_string());
+
+        let transformers = column_transformers(&transformer_configs(""), None).unwrap();
+        let transformed_column = transformers[0].transform(empty_string());
+        assert_eq!(transformed_column.string_value(), Some(""));
+
+        let transformers = column_transformers(
+            &transformer_configs("      keep_null_and_empty: false"),
+            None,
+        )
+        .unwrap();
+        let transformed_column = transformers[0].transform(empty_string());
+        assert_ne!(transformed_column.string_value(), Some(""));
+    }
+
+    #[test]
+    fn progress_as_json_lines() {
+        let mut output = vec![];
+
+        {
+            let mut progress = progress_json(&mut output, Duration::ZERO);
+            progress(0, 0);
+            progress(512, 2048);
+            // unchanged progress is not written again
+            progress(512, 2048);
+            progress(2048, 2048);
+        }
+
+        let records = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["bytes"], 0);
+        assert!(records[0]["pct"].is_null());
+        assert_eq!(records[1]["bytes"], 512);
+        assert_eq!(records[1]["total"], 2048);
+        assert_eq!(records[1]["pct"], 25.0);
+        assert_eq!(records[2]["pct"], 100.0);
+        assert!(records[2]["elapsed"].is_u64());
+    }
+
+    #[test]
+    fn progress_is_written_to_a_socket() {
+        use std::os::unix::net::UnixListener;
+
+        let path =
+            std::env::temp_dir().join(format!("replibyte-progress-{}.sock", rand::random::<u64>()));
+        let listener = UnixListener::bind(&path).unwrap();
+
+        // the consumer - E.g. a GUI
+        let reader = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            BufReader::new(stream)
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(&line.unwrap()).unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let mut runtime = Runtime::new(None);
+        runtime.start_progress(
+            Duration::from_millis(1),
+            progress_json(progress_socket(&path).unwrap(), Duration::ZERO),
+        );
+
+        // the progress of a backup of 3 parts of 1 KB
+        let mut progress_callback = runtime.progress_callback();
+        for transferred_bytes in [1024, 2048, 3072] {
+            progress_callback(transferred_bytes, 0);
+            thread::sleep(Duration::from_millis(20));
+        }
+        progress_callback(3072, 3072);
+
+        // the socket is closed once the progress thread stops
+        runtime.shutdown();
+        let records = reader.join().unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert!(records
+            .iter()
+            .any(|record| record["bytes"] == 1024 && record["pct"].is_null()));
+        let last_record = records.last().unwrap();
+        assert_eq!(last_record["bytes"], 3072);
+        assert_eq!(last_record["total"], 3072);
+        assert_eq!(last_record["pct"], 100.0);
+
+        // a regular file is neither a socket nor a pipe
+        let file =
+            std::env::temp_dir().join(format!("replibyte-progress-{}", rand::random::<u64>()));
+        fs::write(&file, "").unwrap();
+        assert!(progress_socket(&file).is_err());
+        let _ = fs::remove_file(&file);
+    }
+
+    #[test]
+    fn summary_file_of_a_successful_run() {
+        let path =
+            std::env::temp_dir().join(format!("replibyte-summary-{}.json", rand::random::<u64>()));
+
+        let result = with_summary(Some(path.as_path()), "backup", |summary| {
+            summary.backup_name = Some("dump-1647734369306".to_string());
+            summary.bytes = 1_000;
+            let _ = summary.tables.insert("public.users".to_string(), 3);
+            Ok(())
+        });
+        assert!(result.is_ok());
+
+        let summary: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().as_str()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(summary["command"], "backup");
+        assert_eq!(summary["status"], "success");
+        assert_eq!(summary["backup_name"], "dump-1647734369306");
+        assert_eq!(summary["bytes"], 1_000);
+        assert!(summary["duration_ms"].is_u64());
+        assert_eq!(summary["tables"]["public.users"], 3);
+        assert_eq!(summary["warnings"], serde_json::json!([]));
+        assert!(summary["error"].is_null());
+    }
+
+    #[test]
+    fn summary_file_of_a_failed_run() {
+        let path =
+            std::env::temp_dir().join(format!("replibyte-summary-{}.json", rand::random::<u64>()));
+
+        let result = with_summary(Some(path.as_path()), "restore", |summary| {
+            summary.backup_name = Some("latest".to_string());
+            Err(anyhow::Error::msg("connection refused"))
+        });
+        // the error of the run is kept
+        assert_eq!(result.unwrap_err().to_string(), "connection refused");
+
+        let summary: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().as_str()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(summary["command"], "restore");
+        assert_eq!(summary["status"], "failure");
+        assert_eq!(summary["backup_name"], "latest");
+        assert_eq!(summary["bytes"], 0);
+        assert!(summary["tables"].as_object().unwrap().is_empty());
+        assert_eq!(summary["error"], "connection refused");
+    }
+}
@@ -0,0 +1,388 @@
+use std::cell::Cell;
+use std::io::{BufRead, Error, ErrorKind, Write};
+use std::path::Path;
+
+use replibyte::bridge::local_file::LocalFile;
+use replibyte::bridge::s3::{S3Object, S3};
+use replibyte::bridge::{Bridge, ReadOptions};
+use replibyte::cli::{RestoreArgs, RestoreOutputFormat};
+use replibyte::config::{Config, ConnectionUri, DestinationConfig};
+use replibyte::destination::csv::Csv;
+use replibyte::destination::jsonl::Jsonl;
+use replibyte::destination::mongodb::MongoDB as DestinationMongoDB;
+use replibyte::destination::postgres::{column_map, Postgres as DestinationPostgres};
+use replibyte::destination::postgres_stdout::PostgresStdout;
+use replibyte::destination::{Destination, DestinationRegistry};
+use replibyte::hooks::Hooks;
+use replibyte::logger;
+use replibyte::runtime::Runtime;
+use replibyte::tasks::full_restore::{read_tables_file, FullRestoreTask};
+use replibyte::tasks::{MaxBytes, Task, TransferredBytes};
+use replibyte::utils::epoch_millis;
+
+use crate::commands::backup::check_backup_freshness;
+use crate::commands::{column_transformers, with_summary};
+
+/// ask the user to type the database name before wiping it
+pub fn confirm_database_wipe<R: BufRead, W: Write>(
+    database: &str,
+    interactive: bool,
+    mut input: R,
+    mut output: W,
+) -> Result<(), Error> {
+    if !interactive {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "restore aborted: database '{}' will be wiped - use `--yes` to confirm in non-interactive mode",
+                database
+            ),
+        ));
+    }
+
+    write!(
+        output,
+        "The database '{}' will be wiped. Type its name to confirm: ",
+        database
+    )?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    let _ = input.read_line(&mut answer)?;
+
+    if answer.trim() != database {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "restore aborted: the database name does not match",
+        ));
+    }
+
+    Ok(())
+}
+
+/// stream the backup from `bridge` into an output `destination` (E.g. stdout) - with `--output`
+fn restore_output<D, B>(
+    output: D,
+    bridge: B,
+    destination: &DestinationConfig,
+    options: ReadOptions,
+    args: &RestoreArgs,
+) -> anyhow::Result<()>
+where
+    D: Destination + Sync,
+    B: Bridge + 'static,
+{
+    let mut task = FullRestoreTask::new(output, bridge, options);
+    task.set_column_map(column_map(&args.column_map));
+    task.set_schema_mapping(args.target_schema.clone());
+    task.set_tables(restore_tables(args)?);
+    if destination.transform_on_restore.unwrap_or(false) {
+        task.set_transformers(
+            column_transformers(
+                destination.transformers.as_deref().unwrap_or_default(),
+                destination.pseudonym_secret()?.as_deref(),
+            )?,
+            destination.skip.clone().unwrap_or_default(),
+        );
+    }
+    let _ = task.run(|_, _| {})?; // do not display the progress bar
+    Ok(())
+}
+
+/// the tables of `--tables-from-file` - empty to restore all the tables
+fn restore_tables(args: &RestoreArgs) -> Result<Vec<String>, Error> {
+    match &args.tables_from_file {
+        Some(path) => read_tables_file(path.as_path()),
+        None => Ok(vec![]),
+    }
+}
+
+/// restore the backup from `bridge` into the destination - or stream it on stdout with `--output`
+fn restore<B, F>(
+    bridge: B,
+    destination: &DestinationConfig,
+    destination_registry: &DestinationRegistry,
+    options: ReadOptions,
+    args: &RestoreArgs,
+    progress_callback: F,
+) -> anyhow::Result<()>
+where
+    B: Bridge + 'static,
+    F: FnMut(TransferredBytes, MaxBytes),
+{
+    if args.output {
+        return match args.format.unwrap_or(RestoreOutputFormat::Sql) {
+            RestoreOutputFormat::Sql => restore_output(
+                PostgresStdout::default(),
+                bridge,
+                destination,
+                options,
+                args,
+            ),
+            RestoreOutputFormat::Jsonl => {
+                restore_output(Jsonl::default(), bridge, destination, options, args)
+            }
+            RestoreOutputFormat::Csv => {
+                // `--output-dir` is required by `--format csv`
+                let output_dir = args.output_dir.clone().unwrap_or_default();
+                restore_output(Csv::new(output_dir), bridge, destination, options, args)
+            }
+        };
+    }
+
+    if let Some(destination_type) = &destination.destination_type {
+        let registered_destination =
+            destination_registry.destination(destination_type.as_str(), destination)?;
+
+        let mut task = FullRestoreTask::new(registered_destination, bridge, options);
+        task.set_column_map(column_map(&args.column_map));
+        task.set_schema_mapping(args.target_schema.clone());
+        task.set_tables(restore_tables(args)?);
+        if destination.transform_on_restore.unwrap_or(false) {
+            task.set_transformers(
+                column_transformers(
+                    destination.transformers.as_deref().unwrap_or_default(),
+                    destination.pseudonym_secret()?.as_deref(),
+                )?,
+                destination.skip.clone().unwrap_or_default(),
+            );
+        }
+        task.run(progress_callback)?;
+
+        return Ok(());
+    }
+
+    match destination.connection_uri()? {
+        ConnectionUri::Postgres(host, port, username, password, database) => {
+            let mut postgres = DestinationPostgres::new(
+                host.as_str(),
+                port,
+                database.as_str(),
+                username.as_str(),
+                password.as_str(),
+                !args.if_not_exists,
+            );
+            postgres.set_if_not_exists(args.if_not_exists);
+            postgres.set_tls(destination.tls.clone())?;
+            postgres.set_target_schema(
+                args.target_schema
+                    .as_ref()
+                    .map(|schema_mapping| schema_mapping.new_schema_name.clone()),
+            );
+            let preserve_tables = destination.preserve_tables.clone().unwrap_or_default();
+            postgres.set_preserve_tables(preserve_tables.clone());
+            postgres.set_rds_compatible(destination.rds_compatible.unwrap_or(false));
+
+            let mut task = FullRestoreTask::new(postgres, bridge, options);
+            task.set_jobs(destination.jobs.unwrap_or(1));
+            task.set_column_map(column_map(&args.column_map));
+            task.set_schema_mapping(args.target_schema.clone());
+            task.set_preserve_tables(preserve_tables);
+            task.set_tables(restore_tables(args)?);
+            if destination.transform_on_restore.unwrap_or(false) {
+                task.set_transformers(
+                    column_transformers(
+                        destination.transformers.as_deref().unwrap_or_default(),
+                        destination.pseudonym_secret()?.as_deref(),
+                    )?,
+                    destination.skip.clone().unwrap_or_default(),
+                );
+            }
+            task.run(progress_callback)?
+        }
+        ConnectionUri::Mysql(_host, _port, _username, _password, _database) => {
+            todo!() // FIXME
+        }
+        ConnectionUri::MongoDB(
+            host,
+            port,
+            username,
+            password,
+            database,
+            authentication_db,
+            connection_string,
+        ) => {
+            if !args.column_map.is_empty() {
+                return Err(anyhow::Error::from(Error::new(
+                    ErrorKind::Other,
+                    "--column-map is only supported by PostgreSQL destinations",
+                )));
+            }
+
+            if args.target_schema.is_some() {
+                return Err(anyhow::Error::from(Error::new(
+                    ErrorKind::Other,
+                    "--target-schema is only supported by PostgreSQL destinations",
+                )));
+            }
+
+            if args.tables_from_file.is_some() {
+                return Err(anyhow::Error::from(Error::new(
+                    ErrorKind::Other,
+                    "--tables-from-file is only supported by PostgreSQL destinations",
+                )));
+            }
+
+            if destination.transform_on_restore.unwrap_or(false) {
+                return Err(anyhow::Error::from(Error::new(
+                    ErrorKind::Other,
+                    "<transform_on_restore> is only supported by PostgreSQL destinations",
+                )));
+            }
+
+            let mut mongodb = DestinationMongoDB::new(
+                host.as_str(),
+                port,
+                database.as_str(),
+                username.as_str(),
+                password.as_str(),
+                authentication_db.as_str(),
+            );
+            mongodb.set_connection_string(connection_string.as_deref());
+
+            let task = FullRestoreTask::new(mongodb, bridge, options);
+            task.run(progress_callback)?
+        }
+    }
+
+    Ok(())
+}
+
+/// run `restore` - from the bridge, a backup file (`--from-file`) or a single object (`--object-key`)
+pub fn run(
+    cmd: &RestoreArgs,
+    bridge: S3,
+    config: Config,
+    destination_registry: &DestinationRegistry,
+    runtime: &Runtime,
+    summary_file: Option<&Path>,
+) -> anyhow::Result<()> {
+    let destination = match config.destination {
+        Some(destination) => destination,
+        None => {
+            return Err(anyhow::Error::from(Error::new(
+                ErrorKind::Other,
+                "missing <destination> object in the configuration file",
+            )));
+        }
+    };
+
+    with_summary(summary_file, "restore", |summary| {
+        let options = match cmd.value.as_deref() {
+            Some("latest") | None => ReadOptions::Latest,
+            Some(v) => ReadOptions::Backup {
+                name: v.to_string(),
+            },
+        };
+
+        if let (Some(max_age), None, None, ReadOptions::Latest) =
+            (cmd.max_age, &cmd.from_file, &cmd.object_key, &options)
+        {
+            check_backup_freshness(
+                &bridge.index_file()?.backups,
+                max_age,
+                cmd.check_fresh,
+                epoch_millis(),
+            )?;
+        }
+
+        let hooks = Hooks::restore(config.hooks.as_ref());
+        let backup_name = match (&cmd.from_file, &cmd.object_key) {
+            (Some(path), _) => path.display().to_string(),
+            (None, Some(object_key)) => object_key.clone(),
+            // resolve `latest` for the hooks only
+            (None, None) if !hooks.is_empty() => bridge
+                .index_file()?
+                .find_backup(&options)?
+                .directory_name
+                .clone(),
+            (None, None) => cmd.value.clone().unwrap_or_else(|| "latest".to_string()),
+        };
+        logger::set_backup_name(Some(backup_name.clone()));
+        summary.backup_name = Some(backup_name.clone());
+
+        // the size of the backup is only known from the progress
+        let restored_bytes = Cell::new(0);
+        let mut progress_callback = runtime.progress_callback();
+        let progress_callback = |transferred_bytes: TransferredBytes, max_bytes: MaxBytes| {
+            restored_bytes.set(max_bytes);
+            progress_callback(transferred_bytes, max_bytes)
+        };
+
+        let result = hooks.run(backup_name.as_str(), || -> anyhow::Result<()> {
+            match (&cmd.from_file, &cmd.object_key) {
+                (Some(path), _) => {
+                    // the backup file does not come with its index file
+                    let mut local_file = LocalFile::new(path);
+                    local_file.set_compression(destination.compression.unwrap_or(true));
+                    local_file.set_encryption_key(destination.encryption_key()?);
+
+                    restore(
+                        local_file,
+                        &destination,
+                        destination_registry,
+                        options,
+                        cmd,
+                        progress_callback,
+                    )
+                }
+                (None, Some(object_key)) => {
+                    // the index file is not read - E.g. it is lost
+                    let mut object_bridge = bridge.clone();
+                    object_bridge.set_compression(destination.compression.unwrap_or(true));
+                    object_bridge.set_encryption_key(destination.encryption_key()?);
+                    object_bridge.set_encryption_cipher(cmd.cipher.unwrap_or_default());
+
+                    let object = S3Object::new(object_bridge, object_key.as_str());
+                    restore(
+                        object,
+                        &destination,
+                        destination_registry,
+                        options,
+                        cmd,
+                        progress_callback,
+                    )
+                }
+                (None, None) => restore(
+                    bridge,
+                    &destination,
+                    destination_registry,
+                    options,
+                    cmd,
+                    progress_callback,
+                ),
+            }
+        });
+        summary.bytes = restored_bytes.get();
+        result?;
+
+        if !cmd.output {
+            println!("Restore successful!")
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::restore::confirm_database_wipe;
+
+    #[test]
+    fn confirm_database_wipe_aborts_when_not_interactive() {
+        let mut output = vec![];
+        assert!(confirm_database_wipe("root", false, b"root\n".as_slice(), &mut output).is_err());
+        // nothing is asked
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn confirm_database_wipe_with_database_name() {
+        let mut output = vec![];
+        assert!(confirm_database_wipe("root", true, b"root\n".as_slice(), &mut output).is_ok());
+        assert!(!output.is_empty());
+
+        assert!(confirm_database_wipe("root", true, b"toor\n".as_slice(), vec![]).is_err());
+        assert!(confirm_database_wipe("root", true, b"".as_slice(), vec![]).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::{Error, ErrorKind};
+
+use replibyte::cli::SourceCommand;
+use replibyte::config::{Config, ConnectionUri, SourceConfig};
+use replibyte::runtime::Runtime;
+use replibyte::source::mongodb::MongoDB as SourceMongoDB;
+use replibyte::source::postgres::Postgres as SourcePostgres;
+use replibyte::source::{SourceOptions, SourceRegistry};
+use replibyte::tasks::source_ping::{SourcePingTask, SourceThroughput};
+use replibyte::tasks::{MaxBytes, TransferredBytes};
+use replibyte::utils::to_human_readable_unit;
+
+use crate::commands::column_transformers;
+
+/// dump the `source` database through the transformers without storing it and print the throughput
+fn ping_source<F>(
+    source: &SourceConfig,
+    source_registry: &SourceRegistry,
+    progress_callback: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(TransferredBytes, MaxBytes),
+{
+    let transformers =
+        column_transformers(&source.transformers, source.pseudonym_secret()?.as_deref())?;
+
+    let empty_config = vec![];
+    let skip_config = match &source.skip {
+        Some(config) => config,
+        None => &empty_config,
+    };
+
+    let options = SourceOptions {
+        transformers: &transformers,
+        skip_config: &skip_config,
+    };
+
+    if let Some(source_type) = &source.source_type {
+        let registered_source = source_registry.source(source_type.as_str(), source)?;
+        let throughput = SourcePingTask::new(registered_source, options).run(progress_callback)?;
+        print_source_throughput(&throughput);
+
+        return Ok(());
+    }
+
+    let throughput = match source.connection_uri()? {
+        ConnectionUri::Postgres(host, port, username, password, database) => {
+            let mut postgres = SourcePostgres::new(
+                host.as_str(),
+                port,
+                database.as_str(),
+                username.as_str(),
+                password.as_str(),
+            );
+            postgres.set_dump_args(source.dump_args.clone().unwrap_or_default())?;
+            postgres.set_tls(source.tls.clone())?;
+            postgres.set_parallel_dump(source.parallel_dump.clone())?;
+
+            SourcePingTask::new(postgres, options).run(progress_callback)?
+        }
+        ConnectionUri::Mysql(..) => {
+            return Err(anyhow::Error::from(Error::new(
+                ErrorKind::Other,
+                "source ping is unsupported for MySQL",
+            )));
+        }
+        ConnectionUri::MongoDB(
+            host,
+            port,
+            username,
+            password,
+            database,
+            authentication_db,
+            connection_string,
+        ) => {
+            let mut mongodb = SourceMongoDB::new(
+                host.as_str(),
+                port,
+                database.as_str(),
+                username.as_str(),
+                password.as_str(),
+                authentication_db.as_str(),
+            );
+            mongodb.set_connection_string(connection_string.as_deref());
+            mongodb.set_dump_args(source.dump_args.clone().unwrap_or_default())?;
+
+            SourcePingTask::new(mongodb, options).run(progress_callback)?
+        }
+    };
+
+    print_source_throughput(&throughput);
+
+    Ok(())
+}
+
+fn print_source_throughput(throughput: &SourceThroughput) {
+    println!(
+        "{} rows, {} dumped in {:.2?}",
+        throughput.rows,
+        to_human_readable_unit(throughput.bytes),
+        throughput.elapsed
+    );
+    println!(
+        "{:.0} rows/sec, {}/sec",
+        throughput.rows_per_sec(),
+        to_human_readable_unit(throughput.bytes_per_sec() as usize)
+    );
+    println!(
+        "Estimated backup duration: at least {:.2?} - the compression, the encryption and the upload are not measured",
+        throughput.elapsed
+    );
+}
+
+/// run the `source` commands
+pub fn run(
+    cmd: &SourceCommand,
+    config: &Config,
+    source_registry: &SourceRegistry,
+    runtime: &Runtime,
+) -> anyhow::Result<()> {
+    match cmd {
+        SourceCommand::Ping => match &config.source {
+            Some(source) => ping_source(source, source_registry, runtime.progress_callback()),
+            None => Err(anyhow::Error::from(Error::new(
+                ErrorKind::Other,
+                "missing <source> object in the configuration file",
+            ))),
+        },
+    }
+}
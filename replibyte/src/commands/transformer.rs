@@ -0,0 +1,89 @@
+use std::io::Error;
+
+use replibyte::cli::{OutputFormat, TransformerCommand, TransformerListArgs};
+use replibyte::transformer::transformers;
+use replibyte::utils::table;
+
+/// display all transformers available
+fn list_transformers(args: &TransformerListArgs) -> Result<(), Error> {
+    if args.format == OutputFormat::Json {
+        println!("{}", transformers_json()?);
+        return Ok(());
+    }
+
+    let mut table = table();
+    table.set_titles(row!["name", "description", "options"]);
+
+    for transformer in transformers() {
+        let options = transformer
+            .option_schema()
+            .iter()
+            .map(|option| match option.required {
+                true => format!("{} ({}, required)", option.name, option.kind.name()),
+                false => format!("{} ({})", option.name, option.kind.name()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        table.add_row(row![transformer.id(), transformer.description(), options]);
+    }
+
+    let _ = table.printstd();
+
+    Ok(())
+}
+
+/// the id, the description and the options of the transformers as a JSON array
+fn transformers_json() -> Result<String, Error> {
+    let transformers = transformers()
+        .iter()
+        .map(|transformer| {
+            serde_json::json!({
+                "id": transformer.id(),
+                "description": transformer.description(),
+                "options": transformer.option_schema(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(serde_json::to_string_pretty(&transformers)?)
+}
+
+/// run the `transformer` commands
+pub fn run(cmd: &TransformerCommand) -> anyhow::Result<()> {
+    match cmd {
+        TransformerCommand::List(args) => {
+            let _ = list_transformers(args)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use replibyte::transformer::transformers;
+
+    use crate::commands::transformer::transformers_json;
+
+    #[test]
+    fn transformers_as_json() {
+        let json: serde_json::Value = serde_json::from_str(&transformers_json().unwrap()).unwrap();
+        let listed_transformers = json.as_array().unwrap();
+
+        assert_eq!(listed_transformers.len(), transformers().len());
+
+        for transformer in transformers() {
+            let listed_transformer = listed_transformers
+                .iter()
+                .find(|listed_transformer| listed_transformer["id"] == transformer.id())
+                .unwrap();
+
+            assert_eq!(listed_transformer["description"], transformer.description());
+            assert_eq!(
+                listed_transformer["options"].as_array().unwrap().len(),
+                transformer.option_schema().len()
+            );
+        }
+    }
+}
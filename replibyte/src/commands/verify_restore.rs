@@ -0,0 +1,171 @@
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+
+use replibyte::bridge::s3::S3;
+use replibyte::bridge::{Bridge, ReadOptions};
+use replibyte::cli::BackupVerifyRestoreArgs;
+use replibyte::config::{Config, ConnectionUri, DestinationConfig};
+use replibyte::destination::postgres::{Postgres as DestinationPostgres, TableChecksum};
+use replibyte::runtime::Runtime;
+use replibyte::tasks::verify_restore::{
+    table_differences, TableChecksums, TableDifference, VerifyRestoreTask,
+};
+use replibyte::tasks::{MaxBytes, Task, TransferredBytes};
+use replibyte::utils::table;
+
+/// restore a backup into the scratch destination, check that every row made it and wipe it -
+/// the rows and the checksum of its tables are recorded into `table_checksums`
+fn verify_restore<B, F>(
+    bridge: B,
+    scratch_destination: &DestinationConfig,
+    options: ReadOptions,
+    table_checksums: Option<Arc<TableChecksums>>,
+    progress_callback: F,
+) -> anyhow::Result<()>
+where
+    B: Bridge + 'static,
+    F: FnMut(TransferredBytes, MaxBytes),
+{
+    match scratch_destination.connection_uri()? {
+        ConnectionUri::Postgres(host, port, username, password, database) => {
+            let mut postgres = DestinationPostgres::new(
+                host.as_str(),
+                port,
+                database.as_str(),
+                username.as_str(),
+                password.as_str(),
+                true,
+            );
+            postgres.set_tls(scratch_destination.tls.clone())?;
+            postgres.set_rds_compatible(scratch_destination.rds_compatible.unwrap_or(false));
+
+            let mut task = VerifyRestoreTask::new(postgres, bridge, options);
+            if let Some(table_checksums) = table_checksums {
+                task.set_table_checksums(table_checksums);
+            }
+            task.run(progress_callback)?
+        }
+        _ => {
+            return Err(anyhow::Error::from(Error::new(
+                ErrorKind::Other,
+                "only PostgreSQL is supported as <scratch_destination>",
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn print_table_differences(
+    baseline_name: &str,
+    backup_name: &str,
+    differences: &[TableDifference],
+) {
+    let rows = |checksum: &Option<TableChecksum>| match checksum {
+        Some(checksum) => checksum.rows.to_string(),
+        None => "-".to_string(),
+    };
+
+    let mut table = table();
+    table.set_titles(row!["table", baseline_name, backup_name, "diff"]);
+
+    for difference in differences {
+        let diff = match (&difference.baseline, &difference.backup) {
+            (Some(_), None) => "removed",
+            (None, Some(_)) => "added",
+            (Some(baseline), Some(backup)) if baseline.rows != backup.rows => "rows",
+            _ => "checksum",
+        };
+
+        table.add_row(row![
+            difference.table_name,
+            rows(&difference.baseline),
+            rows(&difference.backup),
+            diff
+        ]);
+    }
+
+    let _ = table.printstd();
+}
+
+/// run `backup verify-restore` - with `--since-backup`, the tables of both backups are compared
+pub fn run(
+    args: &BackupVerifyRestoreArgs,
+    mut bridge: S3,
+    config: Config,
+    runtime: &Runtime,
+) -> anyhow::Result<()> {
+    let scratch_destination = match config.scratch_destination {
+        Some(scratch_destination) => scratch_destination,
+        None => {
+            return Err(anyhow::Error::from(Error::new(
+                ErrorKind::Other,
+                "missing <scratch_destination> object in the configuration file",
+            )));
+        }
+    };
+
+    bridge.set_compression(scratch_destination.compression.unwrap_or(true));
+    bridge.set_encryption_key(scratch_destination.encryption_key()?);
+
+    let read_options = |value: &str| match value {
+        "latest" => ReadOptions::Latest,
+        v => ReadOptions::Backup {
+            name: v.to_string(),
+        },
+    };
+
+    match &args.since_backup {
+        None => verify_restore(
+            bridge,
+            &scratch_destination,
+            read_options(args.value.as_str()),
+            None,
+            runtime.progress_callback(),
+        )?,
+        Some(since_backup) => {
+            // the baseline then the new backup - the scratch destination is wiped in between
+            let mut progress_callback = runtime.progress_callback();
+            let baseline_checksums = Arc::new(TableChecksums::default());
+            verify_restore(
+                bridge.clone(),
+                &scratch_destination,
+                read_options(since_backup.as_str()),
+                Some(baseline_checksums.clone()),
+                &mut progress_callback,
+            )?;
+
+            let table_checksums = Arc::new(TableChecksums::default());
+            verify_restore(
+                bridge,
+                &scratch_destination,
+                read_options(args.value.as_str()),
+                Some(table_checksums.clone()),
+                &mut progress_callback,
+            )?;
+
+            let differences = table_differences(
+                &baseline_checksums.checksums(),
+                &table_checksums.checksums(),
+            );
+
+            if !differences.is_empty() {
+                print_table_differences(since_backup.as_str(), args.value.as_str(), &differences);
+
+                return Err(anyhow::Error::from(Error::new(
+                    ErrorKind::Other,
+                    format!(
+                        "backup '{}' differs from '{}' on {} tables",
+                        args.value,
+                        since_backup,
+                        differences.len()
+                    ),
+                )));
+            }
+        }
+    }
+
+    println!("Backup verified successfully!");
+
+    Ok(())
+}
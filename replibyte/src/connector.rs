@@ -1,5 +1,174 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
 
 pub trait Connector {
     fn init(&mut self) -> Result<(), Error>;
 }
+
+/// Database engine of a source or a destination - recorded in the index file with each backup
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    PostgreSQL,
+    MongoDB,
+}
+
+impl Engine {
+    pub fn name(&self) -> &str {
+        match self {
+            Engine::PostgreSQL => "PostgreSQL",
+            Engine::MongoDB => "MongoDB",
+        }
+    }
+}
+
+/// `sslmode` of the PostgreSQL clients - see https://www.postgresql.org/docs/current/libpq-ssl.html
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn name(&self) -> &str {
+        match self {
+            SslMode::Disable => "disable",
+            SslMode::Allow => "allow",
+            SslMode::Prefer => "prefer",
+            SslMode::Require => "require",
+            SslMode::VerifyCa => "verify-ca",
+            SslMode::VerifyFull => "verify-full",
+        }
+    }
+}
+
+/// TLS options of a source or a destination - E.g. mutual TLS with a client certificate
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct Tls {
+    /// default to `verify-full` when `ca_cert` is set, `require` otherwise
+    pub mode: Option<SslMode>,
+    /// path of the client certificate
+    pub client_cert: Option<String>,
+    /// path of the private key of the client certificate
+    pub client_key: Option<String>,
+    /// path of the certificate authorities checking the server certificate
+    pub ca_cert: Option<String>,
+}
+
+impl Tls {
+    pub fn check(&self) -> Result<(), Error> {
+        match (&self.client_cert, &self.client_key) {
+            (Some(_), None) => Err(Error::new(
+                ErrorKind::Other,
+                "tls 'client_cert' requires a 'client_key'",
+            )),
+            (None, Some(_)) => Err(Error::new(
+                ErrorKind::Other,
+                "tls 'client_key' requires a 'client_cert'",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn mode(&self) -> SslMode {
+        match (self.mode, &self.ca_cert) {
+            (Some(mode), _) => mode,
+            (None, Some(_)) => SslMode::VerifyFull,
+            (None, None) => SslMode::Require,
+        }
+    }
+
+    /// environment variables read by `pg_dumpall` and `psql`
+    pub fn postgres_envs(&self) -> Vec<(&'static str, String)> {
+        let mut envs = vec![("PGSSLMODE", self.mode().name().to_string())];
+
+        let paths = [
+            ("PGSSLCERT", &self.client_cert),
+            ("PGSSLKEY", &self.client_key),
+            ("PGSSLROOTCERT", &self.ca_cert),
+        ];
+
+        for (env, path) in paths {
+            if let Some(path) = path {
+                envs.push((env, path.clone()));
+            }
+        }
+
+        envs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connector::{SslMode, Tls};
+
+    fn tls() -> Tls {
+        Tls {
+            mode: None,
+            client_cert: Some("/certs/client.crt".to_string()),
+            client_key: Some("/certs/client.key".to_string()),
+            ca_cert: None,
+        }
+    }
+
+    #[test]
+    fn postgres_envs() {
+        assert_eq!(
+            tls().postgres_envs(),
+            vec![
+                ("PGSSLMODE", "require".to_string()),
+                ("PGSSLCERT", "/certs/client.crt".to_string()),
+                ("PGSSLKEY", "/certs/client.key".to_string()),
+            ]
+        );
+
+        let tls = Tls {
+            ca_cert: Some("/certs/ca.crt".to_string()),
+            ..tls()
+        };
+
+        assert_eq!(
+            tls.postgres_envs(),
+            vec![
+                ("PGSSLMODE", "verify-full".to_string()),
+                ("PGSSLCERT", "/certs/client.crt".to_string()),
+                ("PGSSLKEY", "/certs/client.key".to_string()),
+                ("PGSSLROOTCERT", "/certs/ca.crt".to_string()),
+            ]
+        );
+
+        let tls = Tls {
+            mode: Some(SslMode::VerifyCa),
+            ..tls
+        };
+
+        assert_eq!(
+            tls.postgres_envs()[0],
+            ("PGSSLMODE", "verify-ca".to_string())
+        );
+    }
+
+    #[test]
+    fn client_cert_requires_a_key() {
+        assert!(tls().check().is_ok());
+        assert!(Tls::default().check().is_ok());
+
+        let without_key = Tls {
+            client_key: None,
+            ..tls()
+        };
+        assert!(without_key.check().is_err());
+
+        let without_cert = Tls {
+            client_cert: None,
+            ..tls()
+        };
+        assert!(without_cert.check().is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Error, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::connector::Connector;
+use crate::destination::{insert_into_rows, Destination};
+use crate::types::{Bytes, Column};
+
+/// Write the rows of a Postgres dump as one CSV file per table - `<directory>/<database>.<table>.csv`,
+/// with the column names as header. The other queries than `INSERT INTO` are skipped
+pub struct Csv {
+    directory: PathBuf,
+    pending_data: Mutex<Bytes>,
+    files: Mutex<HashMap<String, BufWriter<File>>>,
+}
+
+impl Csv {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Csv {
+            directory: directory.into(),
+            pending_data: Mutex::new(vec![]),
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Connector for Csv {
+    fn init(&mut self) -> Result<(), Error> {
+        create_dir_all(self.directory.as_path())
+    }
+}
+
+impl Destination for Csv {
+    fn write(&self, data: Bytes) -> Result<(), Error> {
+        let rows = insert_into_rows(&mut self.pending_data.lock().unwrap(), data);
+
+        let mut files = self.files.lock().unwrap();
+        for (database_name, row) in rows {
+            let file_name = format!("{}.{}.csv", database_name, row.table_name);
+
+            let file = match files.entry(file_name) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    // the header is the columns of the first row - pg_dump gives the same columns to every row
+                    let mut file = BufWriter::new(File::create(self.directory.join(entry.key()))?);
+                    let header = row
+                        .columns
+                        .iter()
+                        .map(|column| to_csv_field(column.name()))
+                        .collect::<Vec<_>>();
+                    write_record(&mut file, header)?;
+
+                    entry.insert(file)
+                }
+            };
+
+            write_record(file, row.columns.iter().map(to_csv_value).collect())?;
+        }
+
+        // the files are complete after each write
+        for file in files.values_mut() {
+            file.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_record<W: Write>(writer: &mut W, fields: Vec<String>) -> Result<(), Error> {
+    writer.write_all(fields.join(",").as_bytes())?;
+    writer.write_all(b"\r\n")
+}
+
+/// NULL is an empty field and an empty string a quoted one (`""`) to tell them apart
+fn to_csv_value(column: &Column) -> String {
+    match column {
+        Column::NumberValue(_, value) => value.to_string(),
+        Column::FloatNumberValue(_, value) => value.to_string(),
+        Column::StringValue(_, value) if value.is_empty() => "\"\"".to_string(),
+        Column::StringValue(_, value) => to_csv_field(value),
+        Column::CharValue(_, value) => to_csv_field(value.to_string().as_str()),
+        Column::BooleanValue(_, value) => value.to_string(),
+        Column::None(_) => String::new(),
+    }
+}
+
+/// quote the fields with a separator, a quote or a line break (RFC 4180)
+fn to_csv_field(value: &str) -> String {
+    if value.contains(|c: char| matches!(c, ',' | '"' | '\r' | '\n')) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::connector::Connector;
+    use crate::destination::csv::Csv;
+    use crate::destination::Destination;
+
+    fn write(chunks: Vec<&str>) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("replibyte-csv-{}", rand::random::<u64>()));
+
+        let mut csv = Csv::new(directory.as_path());
+        csv.init().unwrap();
+
+        for chunk in chunks {
+            csv.write(chunk.as_bytes().to_vec()).unwrap();
+        }
+
+        directory
+    }
+
+    #[test]
+    fn one_file_per_table() {
+        let directory = write(vec![
+            "CREATE TABLE public.users (id integer, name text);\n",
+            "INSERT INTO public.users (id, name, score, bio) VALUES (1, 'Doe, John', 4.5, NULL);\n",
+            "INSERT INTO public.orders (id, user_id) VALUES (10, 1);\n",
+            "INSERT INTO public.users (id, name, score, bio) VALUES (2, 'Jane \"JJ\"', 0.25, '');\n",
+            "INSERT INTO public.users (id, name, score, bio) VALUES (3, 'Jim', 1.0, 'line 1\nline 2');\n",
+        ]);
+
+        assert_eq!(
+            fs::read_to_string(directory.join("public.users.csv")).unwrap(),
+            "id,name,score,bio\r\n\
+             1,\"Doe, John\",4.5,\r\n\
+             2,\"Jane \"\"JJ\"\"\",0.25,\"\"\r\n\
+             3,Jim,1,\"line 1\nline 2\"\r\n"
+        );
+        assert_eq!(
+            fs::read_to_string(directory.join("public.orders.csv")).unwrap(),
+            "id,user_id\r\n10,1\r\n"
+        );
+
+        let _ = fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn negative_numbers_booleans_and_casts() {
+        let directory = write(vec![
+            "INSERT INTO public.users (id, active, created_at, score) VALUES (-1, true, '2022-01-01'::date, -0.5);\n",
+            "INSERT INTO public.users (id, active, created_at, score) VALUES (2, false, NULL, 1);\n",
+        ]);
+
+        assert_eq!(
+            fs::read_to_string(directory.join("public.users.csv")).unwrap(),
+            "id,active,created_at,score\r\n-1,true,2022-01-01,-0.5\r\n2,false,,1\r\n"
+        );
+
+        let _ = fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn query_cut_between_two_chunks() {
+        let directory = write(vec![
+            "INSERT INTO public.users (id, name) VALUES (1, 'Jo",
+            "hn');\nINSERT INTO public.users (id, name) VALUES (2, 'Jane');\n",
+        ]);
+
+        assert_eq!(
+            fs::read_to_string(directory.join("public.users.csv")).unwrap(),
+            "id,name\r\n1,John\r\n2,Jane\r\n"
+        );
+
+        let _ = fs::remove_dir_all(directory);
+    }
+}
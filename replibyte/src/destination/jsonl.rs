@@ -0,0 +1,172 @@
+use std::io::{stdout, Error, Stdout, Write};
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::connector::Connector;
+use crate::destination::{insert_into_rows, Destination};
+use crate::types::{Bytes, Column, InsertIntoQuery};
+
+/// Stream the rows of a Postgres dump as JSON lines - one `{"database": ..., "table": ..., "row": {...}}` object
+/// per `INSERT INTO` query. The other queries are skipped
+pub struct Jsonl<W: Write> {
+    writer: Mutex<W>,
+    pending_data: Mutex<Bytes>,
+}
+
+impl<W: Write> Jsonl<W> {
+    pub fn new(writer: W) -> Self {
+        Jsonl {
+            writer: Mutex::new(writer),
+            pending_data: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl Default for Jsonl<Stdout> {
+    fn default() -> Self {
+        Jsonl::new(stdout())
+    }
+}
+
+impl<W: Write> Connector for Jsonl<W> {
+    fn init(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<W: Write> Destination for Jsonl<W> {
+    fn write(&self, data: Bytes) -> Result<(), Error> {
+        let rows = insert_into_rows(&mut self.pending_data.lock().unwrap(), data);
+
+        let mut writer = self.writer.lock().unwrap();
+        for (database_name, row) in rows {
+            writer.write_all(to_json_line(database_name.as_str(), &row).as_bytes())?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// the JSON object of the row - the columns are kept in the order of the query
+fn to_json_line(database_name: &str, row: &InsertIntoQuery) -> String {
+    let columns = row
+        .columns
+        .iter()
+        .map(|column| format!("{}:{}", Value::from(column.name()), to_json_value(column)))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!(
+        "{{\"database\":{},\"table\":{},\"row\":{{{}}}}}\n",
+        Value::from(database_name),
+        Value::from(row.table_name.as_str()),
+        columns
+    )
+}
+
+fn to_json_value(column: &Column) -> Value {
+    match column {
+        // JSON parsers lose the precision of the numbers out of the i64 range - keep them as strings
+        Column::NumberValue(_, value) => match i64::try_from(*value) {
+            Ok(value) => Value::from(value),
+            Err(_) => Value::from(value.to_string()),
+        },
+        Column::FloatNumberValue(_, value) => Value::from(*value),
+        Column::StringValue(_, value) => Value::from(value.as_str()),
+        Column::CharValue(_, value) => Value::from(value.to_string()),
+        Column::BooleanValue(_, value) => Value::from(*value),
+        Column::None(_) => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::destination::jsonl::Jsonl;
+    use crate::destination::Destination;
+
+    fn write(chunks: Vec<&str>) -> Vec<Value> {
+        let jsonl = Jsonl::new(vec![]);
+
+        for chunk in chunks {
+            jsonl.write(chunk.as_bytes().to_vec()).unwrap();
+        }
+
+        let output = jsonl.writer.into_inner().unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn rows_as_json_lines() {
+        let rows = write(vec![
+            "CREATE TABLE public.users (id integer, name text);\n",
+            "INSERT INTO public.users (id, name, score, bio) VALUES (1, 'John ''Doe''', 4.5, NULL);\n",
+            "INSERT INTO public.users (id, name, score, bio) VALUES (170141183460469231731687303715884105727, '', 0.25, 'a;b');\n",
+        ]);
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({
+                    "database": "public",
+                    "table": "users",
+                    "row": {"id": 1, "name": "John 'Doe'", "score": 4.5, "bio": null},
+                }),
+                json!({
+                    "database": "public",
+                    "table": "users",
+                    "row": {"id": "170141183460469231731687303715884105727", "name": "", "score": 0.25, "bio": "a;b"},
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn negative_numbers_booleans_and_casts() {
+        let rows = write(vec![
+            "INSERT INTO public.users (id, balance, active, created_at, tags, score) VALUES (-1, -2.5, true, '2022-01-01'::date, '{a,b}'::character varying(10)[], 3);\n",
+            "INSERT INTO public.users (id, balance, active, created_at, tags, score) VALUES (2, 0, FALSE, NULL, NULL, -3);\n",
+        ]);
+
+        assert_eq!(
+            rows[0]["row"],
+            json!({"id": -1, "balance": -2.5, "active": true, "created_at": "2022-01-01", "tags": "{a,b}", "score": 3})
+        );
+        assert_eq!(
+            rows[1]["row"],
+            json!({"id": 2, "balance": 0, "active": false, "created_at": null, "tags": null, "score": -3})
+        );
+    }
+
+    #[test]
+    fn query_cut_between_two_chunks() {
+        let rows = write(vec![
+            "INSERT INTO public.users (id, name) VALUES (1, 'Jo",
+            "hn');\nINSERT INTO public.users (id, name) VALUES (2, 'Jane');\n",
+        ]);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["row"], json!({"id": 1, "name": "John"}));
+        assert_eq!(rows[1]["row"], json!({"id": 2, "name": "Jane"}));
+    }
+
+    #[test]
+    fn columns_in_query_order() {
+        let jsonl = Jsonl::new(vec![]);
+        jsonl
+            .write(b"INSERT INTO public.users (name, id) VALUES ('John', 1);\n".to_vec())
+            .unwrap();
+
+        let output = String::from_utf8(jsonl.writer.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            output,
+            "{\"database\":\"public\",\"table\":\"users\",\"row\":{\"name\":\"John\",\"id\":1}}\n"
+        );
+    }
+}
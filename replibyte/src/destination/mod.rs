@@ -1,8 +1,14 @@
-use std::io::Error;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
 
-use crate::connector::Connector;
-use crate::types::Bytes;
+use crate::config::DestinationConfig;
+use crate::connector::{Connector, Engine};
+use crate::destination::postgres::{insert_into_table_name, split_queries};
+use crate::source::postgres::parse_insert_into_query;
+use crate::types::{Bytes, InsertIntoQuery};
 
+pub mod csv;
+pub mod jsonl;
 pub mod mongodb;
 pub mod mongodb_stdout;
 pub mod postgres;
@@ -10,4 +16,87 @@ pub mod postgres_stdout;
 
 pub trait Destination: Connector {
     fn write(&self, data: Bytes) -> Result<(), Error>;
+    /// engine of the destination database - None when it accepts any backup (E.g. stdout)
+    fn engine(&self) -> Option<Engine> {
+        None
+    }
+    /// drop the foreign keys to fill the tables in any order - return the queries adding them back
+    fn drop_foreign_keys(&self) -> Result<Bytes, Error> {
+        Ok(vec![])
+    }
+}
+
+impl Connector for Box<dyn Destination + Sync> {
+    fn init(&mut self) -> Result<(), Error> {
+        (**self).init()
+    }
+}
+
+impl Destination for Box<dyn Destination + Sync> {
+    fn write(&self, data: Bytes) -> Result<(), Error> {
+        (**self).write(data)
+    }
+
+    fn engine(&self) -> Option<Engine> {
+        (**self).engine()
+    }
+
+    fn drop_foreign_keys(&self) -> Result<Bytes, Error> {
+        (**self).drop_foreign_keys()
+    }
+}
+
+/// build the destination of an engine from the `destination` configuration
+pub type DestinationFactory =
+    Box<dyn Fn(&DestinationConfig) -> Result<Box<dyn Destination + Sync>, Error>>;
+
+/// DestinationRegistry holds the destinations of the engines not built in RepliByte (E.g. ClickHouse) -
+/// selected by the `type` of the `destination` configuration
+#[derive(Default)]
+pub struct DestinationRegistry {
+    factories: HashMap<String, DestinationFactory>,
+}
+
+impl DestinationRegistry {
+    /// register the destination of `destination_type` - E.g. `clickhouse`. The type is case insensitive
+    pub fn register<F>(&mut self, destination_type: &str, factory: F)
+    where
+        F: Fn(&DestinationConfig) -> Result<Box<dyn Destination + Sync>, Error> + 'static,
+    {
+        let _ = self
+            .factories
+            .insert(destination_type.to_lowercase(), Box::new(factory));
+    }
+
+    pub fn destination(
+        &self,
+        destination_type: &str,
+        config: &DestinationConfig,
+    ) -> Result<Box<dyn Destination + Sync>, Error> {
+        match self.factories.get(destination_type.to_lowercase().as_str()) {
+            Some(factory) => factory(config),
+            None => Err(Error::new(
+                ErrorKind::Other,
+                format!("destination type '{}' is not registered", destination_type),
+            )),
+        }
+    }
+}
+
+/// parse the rows of the `INSERT INTO` queries of `data` - the trailing incomplete query is kept in `pending_data`
+/// until the next data. The other queries (E.g. `CREATE TABLE`, `COPY`) are skipped
+pub fn insert_into_rows(pending_data: &mut Bytes, data: Bytes) -> Vec<(String, InsertIntoQuery)> {
+    pending_data.extend(data);
+
+    let (queries, trailing_data) = split_queries(pending_data.as_slice());
+
+    let rows = queries
+        .into_iter()
+        .filter(|query| insert_into_table_name(query).is_some())
+        .filter_map(|query| parse_insert_into_query(String::from_utf8_lossy(query).as_ref()))
+        .collect::<Vec<_>>();
+
+    *pending_data = trailing_data.to_vec();
+
+    rows
 }
@@ -3,7 +3,7 @@ use std::process::{Command, Stdio};
 
 use bson::de;
 
-use crate::connector::Connector;
+use crate::connector::{Connector, Engine};
 use crate::destination::Destination;
 use crate::types::Bytes;
 use crate::utils::binary_exists;
@@ -15,6 +15,7 @@ pub struct MongoDB<'a> {
     username: &'a str,
     password: &'a str,
     authentication_database: &'a str,
+    connection_string: Option<&'a str>,
 }
 
 impl<'a> MongoDB<'a> {
@@ -33,8 +34,15 @@ impl<'a> MongoDB<'a> {
             username,
             password,
             authentication_database,
+            connection_string: None,
         }
     }
+
+    /// full connection string given to `mongorestore --uri` instead of the host, the port and the credentials -
+    /// E.g. `mongodb+srv://` or a replica set
+    pub fn set_connection_string(&mut self, connection_string: Option<&'a str>) {
+        self.connection_string = connection_string;
+    }
 }
 
 impl<'a> Connector for MongoDB<'a> {
@@ -50,9 +58,12 @@ impl<'a> Connector for MongoDB<'a> {
 impl<'a> Destination for MongoDB<'a> {
     fn write(&self, data: Bytes) -> Result<(), Error> {
         let s_port = self.port.to_string();
+        let ns_from = format!("--nsFrom='{}.*'", self.database);
+        let ns_to = format!("--nsTo='{}.*'", self.database);
 
-        let mut process = Command::new("mongorestore")
-            .args([
+        let mut args = match self.connection_string {
+            Some(connection_string) => vec!["--uri", connection_string],
+            None => vec![
                 "-h",
                 self.host,
                 "--port",
@@ -63,10 +74,12 @@ impl<'a> Destination for MongoDB<'a> {
                 self.username,
                 "-p",
                 self.password,
-                format!("--nsFrom='{}.*'", self.database).as_str(),
-                format!("--nsTo='{}.*'", self.database).as_str(),
-                "--archive",
-            ])
+            ],
+        };
+        args.extend([ns_from.as_str(), ns_to.as_str(), "--archive"]);
+
+        let mut process = Command::new("mongorestore")
+            .args(args)
             .stdin(Stdio::piped())
             .stdout(Stdio::null())
             .spawn()?;
@@ -87,6 +100,10 @@ impl<'a> Destination for MongoDB<'a> {
 
         Ok(())
     }
+
+    fn engine(&self) -> Option<Engine> {
+        Some(Engine::MongoDB)
+    }
 }
 
 fn check_connection_status(db: &MongoDB) -> Result<(), Error> {
@@ -97,8 +114,9 @@ fn check_connection_status(db: &MongoDB) -> Result<(), Error> {
         .stdout(Stdio::piped())
         .spawn()?;
 
-    let mut mongo_process = Command::new("mongo")
-        .args([
+    let args = match db.connection_string {
+        Some(connection_string) => vec![connection_string, "--quiet"],
+        None => vec![
             "--host",
             db.host,
             "--port",
@@ -110,7 +128,11 @@ fn check_connection_status(db: &MongoDB) -> Result<(), Error> {
             "-p",
             db.password,
             "--quiet",
-        ])
+        ],
+    };
+
+    let mut mongo_process = Command::new("mongo")
+        .args(args)
         .stdin(echo_process.stdout.take().unwrap())
         .stdout(Stdio::inherit())
         .spawn()?;
@@ -130,7 +152,7 @@ fn check_connection_status(db: &MongoDB) -> Result<(), Error> {
 mod tests {
     use dump_parser::utils::decode_hex;
 
-    use crate::connector::Connector;
+    use crate::connector::{Connector, Engine};
     use crate::destination::mongodb::MongoDB;
     use crate::destination::Destination;
 
@@ -1,11 +1,18 @@
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Error, ErrorKind, Write};
 use std::process::{Command, Stdio};
+use std::str::FromStr;
 
-use crate::connector::Connector;
+use log::warn;
+
+use crate::connector::{Connector, Engine, Tls};
 use crate::destination::Destination;
 use crate::types::Bytes;
 use crate::utils::binary_exists;
 
+/// schema holding the preserved tables while their schema is wiped
+const PRESERVED_SCHEMA: &str = "replibyte_preserved";
+
 pub struct Postgres<'a> {
     host: &'a str,
     port: u16,
@@ -13,6 +20,11 @@ pub struct Postgres<'a> {
     username: &'a str,
     password: &'a str,
     wipe_database: bool,
+    if_not_exists: bool,
+    tls: Option<Tls>,
+    target_schema: Option<String>,
+    preserve_tables: Vec<String>,
+    rds_compatible: bool,
 }
 
 impl<'a> Postgres<'a> {
@@ -31,7 +43,311 @@ impl<'a> Postgres<'a> {
             username,
             password,
             wipe_database,
+            if_not_exists: false,
+            tls: None,
+            target_schema: None,
+            preserve_tables: vec![],
+            rds_compatible: false,
+        }
+    }
+
+    /// restore without failing on the existing tables and rows - see `idempotent_query`
+    pub fn set_if_not_exists(&mut self, if_not_exists: bool) {
+        self.if_not_exists = if_not_exists;
+    }
+
+    /// restore into another schema than public - only this schema is wiped, the other ones and the large objects are kept.
+    /// The queries must be rewritten with `retarget_schema`
+    pub fn set_target_schema(&mut self, target_schema: Option<String>) {
+        self.target_schema = target_schema;
+    }
+
+    /// keep the tables and their rows while wiping - E.g. `public.countries`, or `tenant_x.countries` with a target schema.
+    /// Their queries must be rewritten with `PreservedTables`
+    pub fn set_preserve_tables(&mut self, preserve_tables: Vec<String>) {
+        self.preserve_tables = preserve_tables;
+    }
+
+    /// restore into a managed database without superuser (E.g. Amazon RDS or Aurora) - see `rds_compatible_query`
+    pub fn set_rds_compatible(&mut self, rds_compatible: bool) {
+        self.rds_compatible = rds_compatible;
+    }
+
+    pub fn set_tls(&mut self, tls: Option<Tls>) -> Result<(), Error> {
+        if let Some(tls) = &tls {
+            tls.check()?;
         }
+
+        self.tls = tls;
+        Ok(())
+    }
+
+    /// the queries sent to `psql` - rewritten when restoring with `if_not_exists` or `rds_compatible`
+    fn restore_queries(&self, data: Bytes) -> Bytes {
+        if !self.if_not_exists && !self.rds_compatible {
+            return data;
+        }
+
+        let (queries, remaining_data) = split_queries(data.as_slice());
+        queries
+            .into_iter()
+            .chain(std::iter::once(remaining_data))
+            .flat_map(|query| {
+                let query = match self.if_not_exists {
+                    true => idempotent_query(query),
+                    false => query.to_vec(),
+                };
+
+                match self.rds_compatible {
+                    true => rds_compatible_query(query.as_slice()),
+                    false => query,
+                }
+            })
+            .collect()
+    }
+
+    fn psql_command(&self) -> Command {
+        let mut command = Command::new("psql");
+        command.env("PGPASSWORD", self.password);
+
+        if let Some(tls) = &self.tls {
+            command.envs(tls.postgres_envs());
+        }
+
+        command
+    }
+}
+
+impl<'a> Postgres<'a> {
+    /// drop and re-create the public schema - the large objects live outside of the schemas and are unlinked.
+    /// Only the target schema is dropped and re-created when there is one
+    pub fn wipe(&self) -> Result<(), Error> {
+        let s_port = self.port.to_string();
+        let wipe_db_query = match &self.target_schema {
+            Some(target_schema) => wipe_schema_query(target_schema.as_str(), self.username),
+            None => wipe_database_query(self.username),
+        };
+        let wipe_db_query = preserve_tables_query(self.preserve_tables.as_slice(), wipe_db_query);
+
+        let exit_status = self
+            .psql_command()
+            .args([
+                "-h",
+                self.host,
+                "-p",
+                s_port.as_str(),
+                "-d",
+                self.database,
+                "-U",
+                self.username,
+                "-c",
+                wipe_db_query.as_str(),
+            ])
+            .stdout(Stdio::null())
+            .spawn()?
+            .wait()?;
+
+        if !exit_status.success() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("command error: {:?}", exit_status.to_string()),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// count the rows of all the user tables
+    pub fn count_rows(&self) -> Result<usize, Error> {
+        let s_port = self.port.to_string();
+
+        let output = self
+            .psql_command()
+            .args([
+                "-h",
+                self.host,
+                "-p",
+                s_port.as_str(),
+                "-d",
+                self.database,
+                "-U",
+                self.username,
+                "-t", // print rows only
+                "-A", // unaligned output
+                "-c",
+                COUNT_ROWS_QUERY,
+            ])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("command error: {:?}", output.status.to_string()),
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(output.stdout.as_slice());
+        stdout.trim().parse::<usize>().map_err(|err| {
+            Error::new(
+                ErrorKind::Other,
+                format!("can't parse rows count '{}': {}", stdout.trim(), err),
+            )
+        })
+    }
+}
+
+/// the rows of a table and their checksum - two tables with the same rows have the same checksum, whatever their order
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableChecksum {
+    pub rows: usize,
+    pub checksum: String,
+}
+
+impl<'a> Postgres<'a> {
+    /// the rows and the checksum of each user table by table name - E.g. `public.users`
+    pub fn table_checksums(&self) -> Result<BTreeMap<String, TableChecksum>, Error> {
+        psql_table_checksums(psql_connection_command(
+            self.host,
+            self.port,
+            self.database,
+            self.username,
+            self.password,
+            self.tls.as_ref(),
+        ))
+    }
+}
+
+/// `psql` connected to the database - the source and the destination query it the same way
+pub fn psql_connection_command(
+    host: &str,
+    port: u16,
+    database: &str,
+    username: &str,
+    password: &str,
+    tls: Option<&Tls>,
+) -> Command {
+    let mut command = Command::new("psql");
+    command.env("PGPASSWORD", password);
+
+    if let Some(tls) = tls {
+        command.envs(tls.postgres_envs());
+    }
+
+    command.args([
+        "-h",
+        host,
+        "-p",
+        port.to_string().as_str(),
+        "-d",
+        database,
+        "-U",
+        username,
+    ]);
+
+    command
+}
+
+/// the rows and the checksum of each user table with TABLE_CHECKSUMS_QUERY - `psql` comes from `psql_connection_command`
+pub fn psql_table_checksums(mut psql: Command) -> Result<BTreeMap<String, TableChecksum>, Error> {
+    let output = psql
+        .args([
+            "-t", // print rows only
+            "-A", // unaligned output
+            "--field-separator-zero",
+            "--record-separator-zero",
+            "-c",
+            TABLE_CHECKSUMS_QUERY,
+        ])
+        .stdin(Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "can't compute the table checksums: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+
+    parse_table_checksums(String::from_utf8_lossy(output.stdout.as_slice()).as_ref())
+}
+
+/// parse the `table`, `rows` and `checksum` fields of TABLE_CHECKSUMS_QUERY separated by zero bytes
+pub fn parse_table_checksums(output: &str) -> Result<BTreeMap<String, TableChecksum>, Error> {
+    let mut fields = output
+        .split('\0')
+        .map(|field| field.trim_end_matches('\n'))
+        .filter(|field| !field.is_empty());
+
+    let mut table_checksums = BTreeMap::new();
+    while let (Some(table_name), Some(rows), Some(checksum)) =
+        (fields.next(), fields.next(), fields.next())
+    {
+        let rows = rows.parse::<usize>().map_err(|err| {
+            Error::new(
+                ErrorKind::Other,
+                format!("can't parse rows count '{}': {}", rows, err),
+            )
+        })?;
+
+        let _ = table_checksums.insert(
+            table_name.to_string(),
+            TableChecksum {
+                rows,
+                checksum: checksum.to_string(),
+            },
+        );
+    }
+
+    Ok(table_checksums)
+}
+
+impl<'a> Postgres<'a> {
+    /// the queries dropping and adding back each foreign key of the database
+    pub fn foreign_keys(&self) -> Result<Vec<(String, String)>, Error> {
+        let s_port = self.port.to_string();
+
+        let output = self
+            .psql_command()
+            .args([
+                "-h",
+                self.host,
+                "-p",
+                s_port.as_str(),
+                "-d",
+                self.database,
+                "-U",
+                self.username,
+                "-t", // print rows only
+                "-A", // unaligned output
+                "--field-separator-zero",
+                "--record-separator-zero",
+                "-c",
+                FOREIGN_KEYS_QUERY,
+            ])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("command error: {:?}", output.status.to_string()),
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(output.stdout.as_slice());
+        let mut fields = stdout
+            .split('\0')
+            .map(|field| field.trim_end_matches('\n'))
+            .filter(|field| !field.is_empty());
+
+        let mut foreign_keys = vec![];
+        while let (Some(drop_query), Some(add_query)) = (fields.next(), fields.next()) {
+            foreign_keys.push((drop_query.to_string(), add_query.to_string()));
+        }
+
+        Ok(foreign_keys)
     }
 }
 
@@ -40,33 +356,13 @@ impl<'a> Connector for Postgres<'a> {
         let _ = binary_exists("psql")?;
 
         if self.wipe_database {
-            let s_port = self.port.to_string();
-            let wipe_db_query = wipe_database_query(self.username);
-
-            let exit_status = Command::new("psql")
-                .env("PGPASSWORD", self.password)
-                .args([
-                    "-h",
-                    self.host,
-                    "-p",
-                    s_port.as_str(),
-                    "-d",
-                    self.database,
-                    "-U",
-                    self.username,
-                    "-c",
-                    wipe_db_query.as_str(),
-                ])
-                .stdout(Stdio::null())
-                .spawn()?
-                .wait()?;
-
-            if !exit_status.success() {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    format!("command error: {:?}", exit_status.to_string()),
-                ));
-            }
+            self.wipe()?;
+        } else if let Some(target_schema) = &self.target_schema {
+            let query = format!(
+                "CREATE SCHEMA IF NOT EXISTS {};",
+                quote_identifier(target_schema.as_str())
+            );
+            self.write(query.into_bytes())?;
         }
 
         Ok(())
@@ -76,9 +372,10 @@ impl<'a> Connector for Postgres<'a> {
 impl<'a> Destination for Postgres<'a> {
     fn write(&self, data: Bytes) -> Result<(), Error> {
         let s_port = self.port.to_string();
+        let data = self.restore_queries(data);
 
-        let mut process = Command::new("psql")
-            .env("PGPASSWORD", self.password)
+        let mut process = self
+            .psql_command()
             .args([
                 "-h",
                 self.host,
@@ -105,6 +402,801 @@ impl<'a> Destination for Postgres<'a> {
 
         Ok(())
     }
+
+    fn engine(&self) -> Option<Engine> {
+        Some(Engine::PostgreSQL)
+    }
+
+    fn drop_foreign_keys(&self) -> Result<Bytes, Error> {
+        let foreign_keys = self.foreign_keys()?;
+
+        if foreign_keys.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let drop_queries = foreign_keys
+            .iter()
+            .map(|(drop_query, _)| format!("{}\n", drop_query))
+            .collect::<String>();
+
+        let add_queries = foreign_keys
+            .into_iter()
+            .map(|(_, add_query)| format!("{}\n", add_query))
+            .collect::<String>();
+
+        // recorded before the drop - they are lost if the restore is killed before adding them back
+        warn!(
+            "the foreign keys of the destination are dropped during the restore - add them back with:\n{}",
+            add_queries
+        );
+
+        let _ = self.write(drop_queries.into_bytes())?;
+
+        Ok(add_queries.into_bytes())
+    }
+}
+
+const COUNT_ROWS_QUERY: &str = "\
+    SELECT coalesce(sum((xpath('/row/c/text()', query_to_xml(\
+        format('SELECT count(*) AS c FROM %I.%I', table_schema, table_name), false, true, ''\
+    )))[1]::text::bigint), 0) \
+    FROM information_schema.tables \
+    WHERE table_schema NOT IN ('pg_catalog', 'information_schema') AND table_type = 'BASE TABLE';\
+    ";
+
+/// rows and order independent checksum of each user table - the MD5 of the sorted MD5s of its rows
+pub const TABLE_CHECKSUMS_QUERY: &str = "\
+    SELECT table_schema || '.' || table_name, \
+    (xpath('/row/c/text()', x))[1]::text, \
+    (xpath('/row/h/text()', x))[1]::text \
+    FROM information_schema.tables, LATERAL query_to_xml(format(\
+        'SELECT count(*) AS c, md5(coalesce(string_agg(md5(t::text), '''' ORDER BY md5(t::text)), '''')) AS h FROM %I.%I t', \
+        table_schema, table_name\
+    ), false, true, '') AS x \
+    WHERE table_schema NOT IN ('pg_catalog', 'information_schema') AND table_type = 'BASE TABLE' \
+    ORDER BY 1;\
+    ";
+
+const FOREIGN_KEYS_QUERY: &str = "\
+    SELECT format('ALTER TABLE %s DROP CONSTRAINT %I;', conrelid::regclass, conname), \
+    format('ALTER TABLE %s ADD CONSTRAINT %I %s;', conrelid::regclass, conname, pg_catalog.pg_get_constraintdef(oid)) \
+    FROM pg_catalog.pg_constraint \
+    WHERE contype = 'f' \
+    ORDER BY conrelid::regclass::text, conname;\
+    ";
+
+const INSERT_INTO_PREFIX: &[u8] = b"INSERT INTO ";
+const CREATE_TABLE_PREFIX: &[u8] = b"CREATE TABLE ";
+const COPY_PREFIX: &[u8] = b"COPY ";
+const SETVAL_PREFIX: &[u8] = b"SELECT pg_catalog.setval('";
+const IF_NOT_EXISTS: &[u8] = b"IF NOT EXISTS ";
+const ON_CONFLICT_DO_NOTHING: &[u8] = b" ON CONFLICT DO NOTHING";
+/// the statements failing when their object exists which accept `IF NOT EXISTS`
+const CREATE_PREFIXES: [&[u8]; 5] = [
+    CREATE_TABLE_PREFIX,
+    b"CREATE SCHEMA ",
+    b"CREATE SEQUENCE ",
+    b"CREATE INDEX ",
+    b"CREATE UNIQUE INDEX ",
+];
+/// the statements of the post-data section of a dump - they come after the rows of every table
+const ADD_CONSTRAINT: &[u8] = b" ADD CONSTRAINT ";
+const POST_DATA_PREFIXES: [&[u8]; 4] = [
+    b"CREATE INDEX ",
+    b"CREATE UNIQUE INDEX ",
+    b"CREATE TRIGGER ",
+    b"CREATE RULE ",
+];
+
+/// split `data` into complete queries - return the queries and the trailing bytes of the incomplete last query
+pub fn split_queries(data: &[u8]) -> (Vec<&[u8]>, &[u8]) {
+    let mut queries = vec![];
+    let mut query_start_idx = 0;
+    let mut idx = 0;
+
+    while idx < data.len() {
+        match data[idx] {
+            // skip the comments until the end of the line
+            b'-' if data.get(idx + 1) == Some(&b'-') => {
+                match data[idx..].iter().position(|byte| *byte == b'\n') {
+                    Some(position) => idx += position,
+                    None => break,
+                }
+            }
+            // skip quoted strings and identifiers - '' and "" escaping is handled by re-entering the quotes
+            quote @ (b'\'' | b'"') => {
+                match data[idx + 1..].iter().position(|byte| *byte == quote) {
+                    Some(position) => idx += position + 1,
+                    None => break,
+                }
+            }
+            // skip dollar quoted strings (E.g. function bodies)
+            b'$' => {
+                if let Some(tag) = dollar_quote_tag(&data[idx..]) {
+                    match data[idx + tag.len()..]
+                        .windows(tag.len())
+                        .position(|window| window == tag)
+                    {
+                        Some(position) => idx += tag.len() + position + tag.len() - 1,
+                        None => break,
+                    }
+                }
+            }
+            b';' => {
+                // keep the line break with the query it ends
+                let query_end_idx = match data.get(idx + 1) {
+                    Some(b'\n') => idx + 2,
+                    Some(_) => idx + 1,
+                    None => break, // wait for the next data to know if there is a line break
+                };
+
+                queries.push(&data[query_start_idx..query_end_idx]);
+                query_start_idx = query_end_idx;
+                idx = query_end_idx;
+                continue;
+            }
+            _ => {}
+        }
+
+        idx += 1;
+    }
+
+    (queries, &data[query_start_idx..])
+}
+
+/// return the dollar quote tag (E.g. `$$` or `$body$`) starting `data` if any
+fn dollar_quote_tag(data: &[u8]) -> Option<&[u8]> {
+    let tag_end_idx = data[1..]
+        .iter()
+        .position(|byte| !(byte.is_ascii_alphanumeric() || *byte == b'_'))?
+        + 1;
+
+    let is_valid_tag = data[tag_end_idx] == b'$' && !data.get(1)?.is_ascii_digit();
+    if is_valid_tag {
+        Some(&data[..=tag_end_idx])
+    } else {
+        None
+    }
+}
+
+/// index of the statement in `query` - skipping the leading blank lines and comments
+fn statement_start_idx(query: &[u8]) -> Option<usize> {
+    let mut idx = 0;
+
+    loop {
+        idx += query[idx..]
+            .iter()
+            .position(|byte| !byte.is_ascii_whitespace())?;
+
+        if !query[idx..].starts_with(b"--") {
+            return Some(idx);
+        }
+
+        idx += query[idx..].iter().position(|byte| *byte == b'\n')?;
+    }
+}
+
+/// return the table name targeted by an `INSERT INTO` query - None for any other query
+pub fn insert_into_table_name(query: &[u8]) -> Option<&[u8]> {
+    table_name_after(query, INSERT_INTO_PREFIX)
+}
+
+/// true for the constraints, indexes, triggers and rules added once the rows are inserted -
+/// E.g. `ALTER TABLE ONLY public.users ADD CONSTRAINT users_pkey PRIMARY KEY (id);`
+pub fn post_data_query(query: &[u8]) -> bool {
+    let query = match statement_start_idx(query) {
+        Some(idx) => &query[idx..],
+        None => return false,
+    };
+
+    // `ALTER TABLE ONLY ... ALTER COLUMN ... SET DEFAULT` comes with the table definitions
+    if query.starts_with(b"ALTER TABLE ") {
+        return query
+            .windows(ADD_CONSTRAINT.len())
+            .any(|window| window == ADD_CONSTRAINT);
+    }
+
+    POST_DATA_PREFIXES
+        .iter()
+        .any(|prefix| query.starts_with(prefix))
+}
+
+/// return the table name created by a `CREATE TABLE` query - None for any other query
+pub fn create_table_name(query: &[u8]) -> Option<&[u8]> {
+    table_name_after(query, CREATE_TABLE_PREFIX)
+}
+
+fn table_name_after<'a>(query: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    let query = &query[statement_start_idx(query)?..];
+
+    let table_name = query.strip_prefix(prefix)?;
+    let table_name_end_idx = table_name
+        .iter()
+        .position(|byte| byte.is_ascii_whitespace() || *byte == b'(')?;
+
+    Some(&table_name[..table_name_end_idx])
+}
+
+/// rewrite the query to not fail on the existing objects: `IF NOT EXISTS` is added to `CREATE TABLE`, `SCHEMA`,
+/// `SEQUENCE` and `INDEX`, and `ON CONFLICT DO NOTHING` to `INSERT INTO`. The other queries are kept as is
+pub fn idempotent_query(query: &[u8]) -> Vec<u8> {
+    let start_idx = match statement_start_idx(query) {
+        Some(idx) => idx,
+        None => return query.to_vec(),
+    };
+    let statement = &query[start_idx..];
+
+    if let Some(prefix) = CREATE_PREFIXES
+        .iter()
+        .find(|prefix| statement.starts_with(prefix))
+    {
+        if statement[prefix.len()..].starts_with(IF_NOT_EXISTS) {
+            return query.to_vec();
+        }
+
+        let insert_idx = start_idx + prefix.len();
+        return [&query[..insert_idx], IF_NOT_EXISTS, &query[insert_idx..]].concat();
+    }
+
+    if statement.starts_with(INSERT_INTO_PREFIX) {
+        if let Some(end_idx) = query.iter().rposition(|byte| *byte == b';') {
+            return [&query[..end_idx], ON_CONFLICT_DO_NOTHING, &query[end_idx..]].concat();
+        }
+    }
+
+    query.to_vec()
+}
+
+/// the statements requiring a superuser which are dropped by `rds_compatible_query` -
+/// the roles are managed by the cloud provider
+const RDS_DISALLOWED_PREFIXES: [&[u8]; 6] = [
+    b"SET session_replication_role",
+    b"ALTER SYSTEM ",
+    b"CREATE ROLE ",
+    b"ALTER ROLE ",
+    b"DROP ROLE ",
+    b"COMMENT ON EXTENSION ",
+];
+const DISABLE_TRIGGER_ALL: &[u8] = b" DISABLE TRIGGER ALL";
+const ENABLE_TRIGGER_ALL: &[u8] = b" ENABLE TRIGGER ALL";
+
+/// rewrite the query for a managed database without superuser (E.g. Amazon RDS or Aurora): the statements requiring a
+/// superuser are dropped and the system triggers (E.g. of the foreign keys) are kept enabled while the rows are loaded -
+/// `DISABLE TRIGGER ALL` becomes `DISABLE TRIGGER USER`. The other queries are kept as is
+pub fn rds_compatible_query(query: &[u8]) -> Vec<u8> {
+    let statement = match statement_start_idx(query) {
+        Some(idx) => &query[idx..],
+        None => return query.to_vec(),
+    };
+
+    let is_disallowed = RDS_DISALLOWED_PREFIXES.iter().any(|prefix| {
+        statement.len() >= prefix.len() && statement[..prefix.len()].eq_ignore_ascii_case(prefix)
+    });
+
+    if is_disallowed {
+        return vec![];
+    }
+
+    if statement.starts_with(b"ALTER TABLE ") {
+        for (trigger_all, trigger_user) in [
+            (DISABLE_TRIGGER_ALL, b" DISABLE TRIGGER USER".as_slice()),
+            (ENABLE_TRIGGER_ALL, b" ENABLE TRIGGER USER".as_slice()),
+        ] {
+            if let Some(idx) = query
+                .windows(trigger_all.len())
+                .position(|window| window == trigger_all)
+            {
+                return [
+                    &query[..idx],
+                    trigger_user,
+                    &query[idx + trigger_all.len()..],
+                ]
+                .concat();
+            }
+        }
+    }
+
+    query.to_vec()
+}
+
+/// the tables kept with their rows by the destination - see `set_preserve_tables`. The sequences they own are read
+/// from the `OWNED BY` and `SEQUENCE NAME` clauses of the dump
+#[derive(Debug, Clone, Default)]
+pub struct PreservedTables {
+    tables: Vec<String>,
+    sequences: Vec<String>,
+    /// the previous query is a `COPY` - its rows start the next query. True when they are skipped
+    copy_rows: Option<bool>,
+}
+
+impl PreservedTables {
+    pub fn new(tables: Vec<String>) -> Self {
+        PreservedTables {
+            tables,
+            ..PreservedTables::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    fn is_preserved(&self, table_name: &[u8]) -> bool {
+        self.tables
+            .iter()
+            .any(|preserved_table| preserved_table.as_bytes() == table_name)
+    }
+
+    /// drop the `INSERT INTO`, `COPY` (with its rows) and `setval` queries of the preserved tables and add
+    /// `IF NOT EXISTS` to their `CREATE TABLE` - the other queries are kept as is. The queries must be given in order
+    pub fn rewrite(&mut self, query: &[u8]) -> Vec<u8> {
+        let mut data = vec![];
+        let mut query = query;
+
+        // the rows of the previous COPY query end with a `\.` line
+        if let Some(skip_rows) = self.copy_rows {
+            let rows_end_idx = match copy_data_end_idx(query) {
+                Some(idx) => idx,
+                None if skip_rows => return vec![],
+                None => return query.to_vec(),
+            };
+
+            self.copy_rows = None;
+            if !skip_rows {
+                data.extend_from_slice(&query[..rows_end_idx]);
+            }
+            query = &query[rows_end_idx..];
+        }
+
+        data.extend(self.rewrite_statement(query));
+        data
+    }
+
+    fn rewrite_statement(&mut self, query: &[u8]) -> Vec<u8> {
+        if let Some((sequence_name, table_name)) = owned_sequence_name(query) {
+            if self.is_preserved(table_name) {
+                self.sequences
+                    .push(String::from_utf8_lossy(sequence_name).to_string());
+            }
+        }
+
+        if insert_into_table_name(query).map_or(false, |table_name| self.is_preserved(table_name)) {
+            return vec![];
+        }
+
+        if let Some(table_name) = copy_table_name(query) {
+            let is_preserved = self.is_preserved(table_name);
+            self.copy_rows = Some(is_preserved);
+
+            if is_preserved {
+                return vec![];
+            }
+        }
+
+        if setval_sequence_name(query).map_or(false, |sequence_name| {
+            self.sequences
+                .iter()
+                .any(|sequence| sequence.as_bytes() == sequence_name)
+        }) {
+            return vec![];
+        }
+
+        if create_table_name(query).map_or(false, |table_name| self.is_preserved(table_name)) {
+            return idempotent_query(query);
+        }
+
+        query.to_vec()
+    }
+}
+
+/// the table of a `COPY ... FROM stdin` query - None for any other query
+fn copy_table_name(query: &[u8]) -> Option<&[u8]> {
+    table_name_after(query, COPY_PREFIX)
+}
+
+/// the sequence set by a `SELECT pg_catalog.setval('public.users_id_seq', 42, true);` query
+fn setval_sequence_name(query: &[u8]) -> Option<&[u8]> {
+    let query = &query[statement_start_idx(query)?..];
+    let sequence_name = query.strip_prefix(SETVAL_PREFIX)?;
+    let sequence_name_end_idx = sequence_name.iter().position(|byte| *byte == b'\'')?;
+
+    Some(&sequence_name[..sequence_name_end_idx])
+}
+
+/// the sequence and the table owning it - E.g. `ALTER SEQUENCE public.users_id_seq OWNED BY public.users.id;`
+/// or `ALTER TABLE public.users ALTER COLUMN id ADD GENERATED ALWAYS AS IDENTITY (SEQUENCE NAME public.users_id_seq ...`
+fn owned_sequence_name(query: &[u8]) -> Option<(&[u8], &[u8])> {
+    let query = &query[statement_start_idx(query)?..];
+    let word_after = |data: &[u8], prefix: &[u8]| -> Option<usize> {
+        data.windows(prefix.len())
+            .position(|window| window == prefix)
+            .map(|position| position + prefix.len())
+    };
+    let word = |data: &[u8]| -> usize {
+        data.iter()
+            .position(|byte| byte.is_ascii_whitespace() || *byte == b';')
+            .unwrap_or(data.len())
+    };
+
+    if let Some(sequence_name) = query.strip_prefix(b"ALTER SEQUENCE ".as_slice()) {
+        let sequence_name = &sequence_name[..word(sequence_name)];
+        let owner = &query[word_after(query, b" OWNED BY ")?..];
+        // the owner is `<table>.<column>`
+        let owner = &owner[..word(owner)];
+        let table_name_end_idx = owner.iter().rposition(|byte| *byte == b'.')?;
+        return Some((sequence_name, &owner[..table_name_end_idx]));
+    }
+
+    let table_name = query.strip_prefix(b"ALTER TABLE ".as_slice())?;
+    let table_name = table_name
+        .strip_prefix(b"ONLY ".as_slice())
+        .unwrap_or(table_name);
+    let table_name = &table_name[..word(table_name)];
+    let sequence_name = &query[word_after(query, b"SEQUENCE NAME ")?..];
+    Some((&sequence_name[..word(sequence_name)], table_name))
+}
+
+/// drop the `INSERT INTO` queries of the tables which are not in `tables` - the other queries (E.g. the schema) are
+/// kept as is. All the queries are kept when `tables` is empty
+pub fn selected_query(query: &[u8], tables: &[String]) -> Vec<u8> {
+    let is_selected = |table_name: &[u8]| tables.iter().any(|table| table.as_bytes() == table_name);
+
+    match insert_into_table_name(query) {
+        Some(table_name) if !tables.is_empty() && !is_selected(table_name) => vec![],
+        _ => query.to_vec(),
+    }
+}
+
+/// a column renamed in the destination - E.g. `public.users.name=full_name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMapping {
+    /// the table as written in the dump - E.g. `public.users`
+    pub table_name: String,
+    pub column_name: String,
+    pub new_column_name: String,
+}
+
+impl FromStr for ColumnMapping {
+    type Err = String;
+
+    /// parse `<database>.<table>.<column>=<new column>`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid_mapping = || {
+            format!(
+                "invalid column mapping '{}' - expected <database>.<table>.<column>=<new column>",
+                value
+            )
+        };
+
+        let (column, new_column_name) = value.split_once('=').ok_or_else(invalid_mapping)?;
+        let (table_name, column_name) = column.rsplit_once('.').ok_or_else(invalid_mapping)?;
+
+        let is_valid = table_name.contains('.')
+            && table_name.split('.').all(|part| !part.is_empty())
+            && !column_name.is_empty()
+            && !new_column_name.is_empty();
+
+        if !is_valid {
+            return Err(invalid_mapping());
+        }
+
+        Ok(ColumnMapping {
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+            new_column_name: new_column_name.to_string(),
+        })
+    }
+}
+
+/// the new column names by old column name, by table
+pub type ColumnMap = HashMap<String, HashMap<String, String>>;
+
+pub fn column_map(column_mappings: &[ColumnMapping]) -> ColumnMap {
+    let mut column_map = ColumnMap::new();
+    for column_mapping in column_mappings {
+        column_map
+            .entry(column_mapping.table_name.clone())
+            .or_insert_with(HashMap::new)
+            .insert(
+                column_mapping.column_name.clone(),
+                column_mapping.new_column_name.clone(),
+            );
+    }
+
+    column_map
+}
+
+/// rename the columns of the column list of an `INSERT INTO` or a `COPY` query. The other queries are kept as is -
+/// E.g. `CREATE TABLE` is not rewritten, the table must already exist in the destination with the new columns
+pub fn remap_columns(query: &[u8], column_map: &ColumnMap) -> Vec<u8> {
+    let start_idx = match statement_start_idx(query) {
+        Some(idx) => idx,
+        None => return query.to_vec(),
+    };
+    let statement = &query[start_idx..];
+
+    let table_name_idx = match [INSERT_INTO_PREFIX, COPY_PREFIX]
+        .iter()
+        .find(|prefix| statement.starts_with(prefix))
+    {
+        Some(prefix) => start_idx + prefix.len(),
+        None => return query.to_vec(),
+    };
+
+    let table_name_end_idx = match query[table_name_idx..]
+        .iter()
+        .position(|byte| byte.is_ascii_whitespace() || *byte == b'(')
+    {
+        Some(position) => table_name_idx + position,
+        None => return query.to_vec(),
+    };
+
+    let table_name = String::from_utf8_lossy(&query[table_name_idx..table_name_end_idx]);
+    let columns = match column_map.get(table_name.as_ref()) {
+        Some(columns) => columns,
+        None => return query.to_vec(),
+    };
+
+    // the column list is right after the table name
+    let column_list_start_idx = match query[table_name_end_idx..]
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+    {
+        Some(position) if query[table_name_end_idx + position] == b'(' => {
+            table_name_end_idx + position + 1
+        }
+        _ => return query.to_vec(),
+    };
+
+    let column_list_end_idx = match query[column_list_start_idx..]
+        .iter()
+        .position(|byte| *byte == b')')
+    {
+        Some(position) => column_list_start_idx + position,
+        None => return query.to_vec(),
+    };
+
+    let column_list = String::from_utf8_lossy(&query[column_list_start_idx..column_list_end_idx])
+        .split(',')
+        .map(|column| {
+            let column = column.trim();
+            let is_quoted = column.len() >= 2 && column.starts_with('"') && column.ends_with('"');
+            let column_name = match is_quoted {
+                true => &column[1..column.len() - 1],
+                false => column,
+            };
+
+            match columns.get(column_name) {
+                Some(new_column_name) if is_quoted => format!("\"{}\"", new_column_name),
+                Some(new_column_name) => new_column_name.clone(),
+                None => column.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    [
+        &query[..column_list_start_idx],
+        column_list.as_bytes(),
+        &query[column_list_end_idx..],
+    ]
+    .concat()
+}
+
+/// the schema the objects of a backup schema are restored into - E.g. `tenant_x` (from `public`) or `tenant_a=tenant_x`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaMapping {
+    /// the schema as written in the dump
+    pub schema_name: String,
+    pub new_schema_name: String,
+}
+
+impl FromStr for SchemaMapping {
+    type Err = String;
+
+    /// parse `<new schema>` or `<schema>=<new schema>`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (schema_name, new_schema_name) = value.split_once('=').unwrap_or(("public", value));
+
+        let is_valid = [schema_name, new_schema_name]
+            .iter()
+            .all(|name| !name.is_empty() && !name.contains('.'));
+
+        if !is_valid {
+            return Err(format!(
+                "invalid schema '{}' - expected <new schema> or <schema>=<new schema>",
+                value
+            ));
+        }
+
+        Ok(SchemaMapping {
+            schema_name: schema_name.to_string(),
+            new_schema_name: new_schema_name.to_string(),
+        })
+    }
+}
+
+/// move the objects of a query from the `schema_mapping` schema to the new one - the qualified names (E.g. `public.users`),
+/// the `SCHEMA <schema>` clauses, the `search_path` and the qualified names quoted in strings (E.g. `nextval('public.users_id_seq')`)
+/// are rewritten. Only the table name of the `INSERT INTO` queries is rewritten - the values are kept as is,
+/// so is the data of the `COPY` queries
+pub fn retarget_schema(query: &[u8], schema_mapping: &SchemaMapping) -> Vec<u8> {
+    let start_idx = match statement_start_idx(query) {
+        Some(idx) => idx,
+        None => return query.to_vec(),
+    };
+
+    // the data of a COPY query comes before the next query - it ends with a `\.` line
+    if let Some(copy_data_end_idx) = copy_data_end_idx(&query[start_idx..]) {
+        let copy_data_end_idx = start_idx + copy_data_end_idx;
+        return [
+            &query[..copy_data_end_idx],
+            retarget_schema(&query[copy_data_end_idx..], schema_mapping).as_slice(),
+        ]
+        .concat();
+    }
+
+    let statement = &query[start_idx..];
+
+    if let Some(table_name) = insert_into_table_name(query) {
+        let table_name_idx = start_idx + INSERT_INTO_PREFIX.len();
+        return [
+            &query[..table_name_idx],
+            retarget_names(table_name, schema_mapping, false).as_slice(),
+            &query[table_name_idx + table_name.len()..],
+        ]
+        .concat();
+    }
+
+    let is_search_path = statement
+        .get(..15)
+        .map(|prefix| prefix.eq_ignore_ascii_case(b"SET search_path"))
+        .unwrap_or(false);
+
+    let query = [
+        &query[..start_idx],
+        retarget_names(statement, schema_mapping, is_search_path).as_slice(),
+    ]
+    .concat();
+
+    // the new schema may already exist - E.g. created by the wipe of the destination
+    match statement.starts_with(b"CREATE SCHEMA ") {
+        true => idempotent_query(query.as_slice()),
+        false => query,
+    }
+}
+
+/// index of the first byte after the `\.` line ending the data of a COPY query - None when there is none
+fn copy_data_end_idx(data: &[u8]) -> Option<usize> {
+    if data.starts_with(b"\\.\n") {
+        return Some(3);
+    }
+
+    data.windows(4)
+        .position(|window| window == b"\n\\.\n")
+        .map(|position| position + 4)
+}
+
+/// rewrite the names of the `schema_mapping` schema - `bare_names` rewrites the unqualified names too (E.g. a search_path)
+fn retarget_names(data: &[u8], schema_mapping: &SchemaMapping, bare_names: bool) -> Vec<u8> {
+    let schema_name = schema_mapping.schema_name.as_bytes();
+    let new_schema_name = quote_identifier(schema_mapping.new_schema_name.as_str());
+
+    let mut retargeted_data = Vec::with_capacity(data.len());
+    // the previous word is `SCHEMA` - E.g. `CREATE SCHEMA public` or `GRANT ALL ON SCHEMA public`
+    let mut is_schema_clause = false;
+    let mut idx = 0;
+
+    while idx < data.len() {
+        let byte = data[idx];
+
+        if byte == b'-' && data.get(idx + 1) == Some(&b'-') {
+            // keep the comments until the end of the line
+            let end_idx = data[idx..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .map(|position| idx + position)
+                .unwrap_or(data.len());
+            retargeted_data.extend_from_slice(&data[idx..end_idx]);
+            idx = end_idx;
+        } else if byte == b'\'' {
+            // a string quoting a qualified name - E.g. `'public.users_id_seq'::regclass`
+            let end_idx = quoted_end_idx(data, idx);
+            let string = &data[idx + 1..end_idx.saturating_sub(1).max(idx + 1)];
+            match qualified_by(string, schema_name) {
+                Some(name_idx) => {
+                    retargeted_data.push(b'\'');
+                    retargeted_data
+                        .extend_from_slice(new_schema_name.replace('\'', "''").as_bytes());
+                    retargeted_data.extend_from_slice(&data[idx + name_idx..end_idx]);
+                }
+                None => retargeted_data.extend_from_slice(&data[idx..end_idx]),
+            }
+            idx = end_idx;
+        } else if byte == b'"' || byte.is_ascii_alphabetic() || byte == b'_' {
+            let (name, end_idx) = match byte {
+                b'"' => {
+                    let end_idx = quoted_end_idx(data, idx);
+                    let name = data[idx + 1..end_idx.saturating_sub(1).max(idx + 1)].to_vec();
+                    (name, end_idx)
+                }
+                _ => {
+                    let end_idx = data[idx..]
+                        .iter()
+                        .position(|byte| {
+                            !(byte.is_ascii_alphanumeric() || *byte == b'_' || *byte == b'$')
+                        })
+                        .map(|position| idx + position)
+                        .unwrap_or(data.len());
+                    (data[idx..end_idx].to_ascii_lowercase(), end_idx)
+                }
+            };
+
+            let is_qualifier = data.get(end_idx) == Some(&b'.');
+            let is_name_part = retargeted_data.last() == Some(&b'.');
+
+            if name == schema_name
+                && !is_name_part
+                && (is_qualifier || is_schema_clause || bare_names)
+            {
+                retargeted_data.extend_from_slice(new_schema_name.as_bytes());
+            } else {
+                retargeted_data.extend_from_slice(&data[idx..end_idx]);
+            }
+
+            is_schema_clause = byte != b'"' && name == b"schema";
+            idx = end_idx;
+        } else {
+            retargeted_data.push(byte);
+            idx += 1;
+        }
+    }
+
+    retargeted_data
+}
+
+/// index of the first byte after the quoted string or identifier starting at `start_idx` - the doubled quotes are escaped ones
+fn quoted_end_idx(data: &[u8], start_idx: usize) -> usize {
+    let quote = data[start_idx];
+    let mut idx = start_idx + 1;
+
+    while idx < data.len() {
+        if data[idx] == quote {
+            if data.get(idx + 1) == Some(&quote) {
+                idx += 2;
+                continue;
+            }
+
+            return idx + 1;
+        }
+
+        idx += 1;
+    }
+
+    data.len()
+}
+
+/// index of the name qualified by `schema_name` in `string` - E.g. 7 for `public.users`
+fn qualified_by(string: &[u8], schema_name: &[u8]) -> Option<usize> {
+    let name = string.strip_prefix(schema_name)?.strip_prefix(b".")?;
+    Some(string.len() - name.len())
+}
+
+/// quote the identifiers which are not lowercase words - E.g. `Tenant X` is `"Tenant X"`
+fn quote_identifier(identifier: &str) -> String {
+    let is_lowercase_word = identifier
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_lowercase() || c == '_')
+        .unwrap_or(false)
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+
+    match is_lowercase_word {
+        true => identifier.to_string(),
+        false => format!("\"{}\"", identifier.replace('"', "\"\"")),
+    }
 }
 
 fn wipe_database_query(username: &str) -> String {
@@ -113,16 +1205,81 @@ fn wipe_database_query(username: &str) -> String {
     DROP SCHEMA public CASCADE; \
     CREATE SCHEMA public; \
     GRANT ALL ON SCHEMA public TO {}; \
-    GRANT ALL ON SCHEMA public TO public;\
+    GRANT ALL ON SCHEMA public TO public; \
+    SELECT pg_catalog.lo_unlink(oid) FROM pg_catalog.pg_largeobject_metadata;\
     ",
         username
     )
 }
 
+fn wipe_schema_query(schema_name: &str, username: &str) -> String {
+    let schema_name = quote_identifier(schema_name);
+
+    format!(
+        "\
+    DROP SCHEMA IF EXISTS {} CASCADE; \
+    CREATE SCHEMA {}; \
+    GRANT ALL ON SCHEMA {} TO {};\
+    ",
+        schema_name, schema_name, schema_name, username
+    )
+}
+
+/// move the preserved tables into a temporary schema while the wipe query drops theirs, then move them back.
+/// The sequences owned by the tables move with them
+fn preserve_tables_query(preserve_tables: &[String], wipe_query: String) -> String {
+    if preserve_tables.is_empty() {
+        return wipe_query;
+    }
+
+    let mut query = format!("CREATE SCHEMA IF NOT EXISTS {}; ", PRESERVED_SCHEMA);
+
+    let preserve_tables = preserve_tables
+        .iter()
+        .map(|table_name| {
+            let (schema_name, table_name) = table_name
+                .split_once('.')
+                .unwrap_or(("public", table_name.as_str()));
+
+            (quote_identifier(schema_name), quote_identifier(table_name))
+        })
+        .collect::<Vec<_>>();
+
+    for (schema_name, table_name) in &preserve_tables {
+        query.push_str(
+            format!(
+                "ALTER TABLE IF EXISTS {}.{} SET SCHEMA {}; ",
+                schema_name, table_name, PRESERVED_SCHEMA
+            )
+            .as_str(),
+        );
+    }
+
+    query.push_str(wipe_query.as_str());
+
+    for (schema_name, table_name) in &preserve_tables {
+        query.push_str(
+            format!(
+                " ALTER TABLE IF EXISTS {}.{} SET SCHEMA {};",
+                PRESERVED_SCHEMA, table_name, schema_name
+            )
+            .as_str(),
+        );
+    }
+
+    query.push_str(format!(" DROP SCHEMA {};", PRESERVED_SCHEMA).as_str());
+    query
+}
+
 #[cfg(test)]
 mod tests {
     use crate::connector::Connector;
-    use crate::destination::postgres::Postgres;
+    use crate::destination::postgres::{
+        column_map, create_table_name, idempotent_query, insert_into_table_name,
+        parse_table_checksums, post_data_query, preserve_tables_query, rds_compatible_query,
+        remap_columns, retarget_schema, split_queries, ColumnMapping, Postgres, PreservedTables,
+        SchemaMapping, TableChecksum,
+    };
     use crate::destination::Destination;
 
     fn get_postgres() -> Postgres<'static> {
@@ -144,6 +1301,669 @@ mod tests {
         assert!(p.write(b"SELECT 1".to_vec()).is_err());
     }
 
+    #[test]
+    fn count_rows() {
+        let mut p = get_postgres();
+        p.init().expect("can't init postgres");
+        assert_eq!(p.count_rows().unwrap(), 0);
+
+        assert!(p
+            .write(
+                b"CREATE TABLE public.users (name text); \
+                INSERT INTO public.users (name) VALUES ('romaric'); \
+                INSERT INTO public.users (name) VALUES ('lucas');"
+                    .to_vec()
+            )
+            .is_ok());
+
+        assert_eq!(p.count_rows().unwrap(), 2);
+        assert!(p.wipe().is_ok());
+        assert_eq!(p.count_rows().unwrap(), 0);
+    }
+
+    #[test]
+    fn restore_twice_if_not_exists() {
+        let mut p = get_postgres();
+        p.init().expect("can't init postgres");
+
+        let mut p = Postgres::new("localhost", 5453, "root", "root", "password", false);
+        p.set_if_not_exists(true);
+        p.init().expect("can't init postgres");
+
+        let dump = b"CREATE TABLE public.users (id integer PRIMARY KEY, name text);\n\
+        INSERT INTO public.users (id, name) VALUES (1, 'romaric');\n\
+        INSERT INTO public.users (id, name) VALUES (2, 'lucas');\n";
+
+        assert!(p.write(dump.to_vec()).is_ok());
+        assert!(p.write(dump.to_vec()).is_ok());
+        // the rows are not duplicated
+        assert_eq!(p.count_rows().unwrap(), 2);
+        assert!(p.wipe().is_ok());
+    }
+
+    #[test]
+    fn fill_tables_without_foreign_keys() {
+        let mut p = get_postgres();
+        p.init().expect("can't init postgres");
+
+        assert!(p
+            .write(
+                b"CREATE TABLE public.users (id integer PRIMARY KEY); \
+                CREATE TABLE public.orders (id integer, user_id integer REFERENCES public.users (id));"
+                    .to_vec()
+            )
+            .is_ok());
+
+        let foreign_keys_queries = p.drop_foreign_keys().unwrap();
+        assert_eq!(
+            String::from_utf8(foreign_keys_queries.clone()).unwrap(),
+            "ALTER TABLE orders ADD CONSTRAINT orders_user_id_fkey FOREIGN KEY (user_id) REFERENCES users(id);\n"
+        );
+        assert!(p.foreign_keys().unwrap().is_empty());
+
+        // the orders are restored before their users
+        assert!(p
+            .write(b"INSERT INTO public.orders (id, user_id) VALUES (1, 42);".to_vec())
+            .is_ok());
+        assert!(p
+            .write(b"INSERT INTO public.users (id) VALUES (42);".to_vec())
+            .is_ok());
+        assert_eq!(p.count_rows().unwrap(), 2);
+
+        assert!(p.write(foreign_keys_queries).is_ok());
+        assert_eq!(p.foreign_keys().unwrap().len(), 1);
+        assert!(p.wipe().is_ok());
+    }
+
+    #[test]
+    fn restore_into_a_renamed_column() {
+        let mut p = get_postgres();
+        p.init().expect("can't init postgres");
+
+        // the destination schema has drifted from the backup one
+        assert!(p
+            .write(b"CREATE TABLE public.users (id integer, full_name text);".to_vec())
+            .is_ok());
+
+        let dump = b"INSERT INTO public.users (id, name) VALUES (1, 'romaric');\n\
+        INSERT INTO public.users (id, name) VALUES (2, 'lucas');\n";
+        assert!(p.write(dump.to_vec()).is_err());
+
+        let column_map = column_map(&["public.users.name=full_name".parse().unwrap()]);
+        let (queries, _) = split_queries(dump);
+        let data = queries
+            .into_iter()
+            .flat_map(|query| remap_columns(query, &column_map))
+            .collect::<Vec<_>>();
+
+        assert!(p.write(data).is_ok());
+        assert_eq!(p.count_rows().unwrap(), 2);
+        assert!(p.wipe().is_ok());
+    }
+
+    #[test]
+    fn table_checksums_change_with_the_rows() {
+        let mut p = get_postgres();
+        p.init().expect("can't init postgres");
+
+        assert!(p
+            .write(
+                b"CREATE TABLE public.users (id integer, name text); \
+                INSERT INTO public.users (id, name) VALUES (1, 'romaric'); \
+                INSERT INTO public.users (id, name) VALUES (2, 'lucas');"
+                    .to_vec()
+            )
+            .is_ok());
+        let checksums = p.table_checksums().unwrap();
+        assert_eq!(checksums["public.users"].rows, 2);
+
+        // the order of the rows does not matter
+        let mut p = get_postgres();
+        p.init().expect("can't init postgres");
+        assert!(p
+            .write(
+                b"CREATE TABLE public.users (id integer, name text); \
+                INSERT INTO public.users (id, name) VALUES (2, 'lucas'); \
+                INSERT INTO public.users (id, name) VALUES (1, 'romaric');"
+                    .to_vec()
+            )
+            .is_ok());
+        assert_eq!(p.table_checksums().unwrap(), checksums);
+
+        assert!(p
+            .write(b"UPDATE public.users SET name = 'paul' WHERE id = 2;".to_vec())
+            .is_ok());
+        let new_checksums = p.table_checksums().unwrap();
+        assert_eq!(new_checksums["public.users"].rows, 2);
+        assert_ne!(
+            new_checksums["public.users"].checksum,
+            checksums["public.users"].checksum
+        );
+
+        assert!(p.wipe().is_ok());
+    }
+
+    #[test]
+    fn test_parse_table_checksums() {
+        let output = "public.orders\00\0d41d8cd98f00b204e9800998ecf8427e\0\
+        public.users\02\0a3c65c2974270fd093ee8a9bf8ae7d0b\0";
+
+        let checksums = parse_table_checksums(output).unwrap();
+        assert_eq!(checksums.len(), 2);
+        assert_eq!(
+            checksums["public.users"],
+            TableChecksum {
+                rows: 2,
+                checksum: "a3c65c2974270fd093ee8a9bf8ae7d0b".to_string(),
+            }
+        );
+        assert_eq!(checksums["public.orders"].rows, 0);
+
+        assert!(parse_table_checksums("public.users\0two\0a3c6\0").is_err());
+    }
+
+    #[test]
+    fn wipe_keeps_the_preserved_tables() {
+        let mut p = get_postgres();
+        p.init().expect("can't init postgres");
+
+        assert!(p
+            .write(
+                b"CREATE TABLE public.countries (id serial PRIMARY KEY, name text); \
+                INSERT INTO public.countries (name) VALUES ('France'); \
+                INSERT INTO public.countries (name) VALUES ('Italy'); \
+                CREATE TABLE public.users (name text); \
+                INSERT INTO public.users (name) VALUES ('romaric');"
+                    .to_vec()
+            )
+            .is_ok());
+
+        let mut p = get_postgres();
+        p.set_preserve_tables(vec!["public.countries".to_string()]);
+        p.init().expect("can't init postgres");
+
+        // only the rows of the preserved table are kept
+        assert_eq!(p.count_rows().unwrap(), 2);
+
+        let dump = b"CREATE TABLE public.countries (id integer PRIMARY KEY, name text);\n\
+        ALTER SEQUENCE public.countries_id_seq OWNED BY public.countries.id;\n\
+        INSERT INTO public.countries (id, name) VALUES (1, 'Spain');\n\
+        CREATE TABLE public.users (name text);\n\
+        INSERT INTO public.users (name) VALUES ('lucas');\n\
+        SELECT pg_catalog.setval('public.countries_id_seq', 1, true);\n";
+        let (queries, _) = split_queries(dump);
+        let mut preserved_tables = PreservedTables::new(vec!["public.countries".to_string()]);
+        let data = queries
+            .into_iter()
+            .flat_map(|query| preserved_tables.rewrite(query))
+            .collect::<Vec<_>>();
+
+        assert!(p.write(data).is_ok());
+        // the pre-restore countries and the restored users
+        assert_eq!(p.count_rows().unwrap(), 3);
+        // the sequence of the preserved table is kept too - its setval is dropped
+        assert!(p
+            .write(b"INSERT INTO public.countries (name) VALUES ('Spain');".to_vec())
+            .is_ok());
+        assert_eq!(p.count_rows().unwrap(), 4);
+
+        p.set_preserve_tables(vec![]);
+        assert!(p.wipe().is_ok());
+        assert_eq!(p.count_rows().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_preserved_tables_rewrite() {
+        let mut preserved_tables = PreservedTables::new(vec!["public.countries".to_string()]);
+
+        assert_eq!(
+            preserved_tables.rewrite(b"INSERT INTO public.countries (name) VALUES ('France');"),
+            b""
+        );
+        assert_eq!(
+            preserved_tables
+                .rewrite(b"\n-- Name: countries\nCREATE TABLE public.countries (name text);\n"),
+            b"\n-- Name: countries\nCREATE TABLE IF NOT EXISTS public.countries (name text);\n"
+        );
+        // the other tables are kept as is
+        assert_eq!(
+            preserved_tables.rewrite(b"INSERT INTO public.users (name) VALUES ('romaric');"),
+            b"INSERT INTO public.users (name) VALUES ('romaric');"
+        );
+        assert_eq!(
+            PreservedTables::default()
+                .rewrite(b"INSERT INTO public.countries (name) VALUES ('France');"),
+            b"INSERT INTO public.countries (name) VALUES ('France');"
+        );
+    }
+
+    #[test]
+    fn preserved_tables_skip_the_copy_data_and_the_sequences() {
+        let dump = b"ALTER SEQUENCE public.countries_id_seq OWNED BY public.countries.id;\n\
+        ALTER TABLE public.cities ALTER COLUMN id ADD GENERATED ALWAYS AS IDENTITY (\n\
+        SEQUENCE NAME public.cities_id_seq\nSTART WITH 1\n);\n\
+        ALTER SEQUENCE public.countries_codes_id_seq OWNED BY public.countries_codes.id;\n\
+        COPY public.countries (id, name) FROM stdin;\n1\tFrance\n2\tItaly\n\\.\n\n\
+        COPY public.countries_codes (id, code) FROM stdin;\n1\tFR\n\\.\n\n\
+        SELECT pg_catalog.setval('public.countries_id_seq', 2, true);\n\
+        SELECT pg_catalog.setval('public.cities_id_seq', 3, true);\n\
+        SELECT pg_catalog.setval('public.countries_codes_id_seq', 1, true);\n";
+
+        let mut preserved_tables = PreservedTables::new(vec![
+            "public.countries".to_string(),
+            "public.cities".to_string(),
+        ]);
+        let (queries, _) = split_queries(dump);
+        let data = queries
+            .into_iter()
+            .flat_map(|query| preserved_tables.rewrite(query))
+            .collect::<Vec<_>>();
+        let data = String::from_utf8(data).unwrap();
+
+        // the rows and the sequences of the preserved tables are kept by the destination
+        assert!(!data.contains("COPY public.countries (id, name)"));
+        assert!(!data.contains("France"));
+        assert!(!data.contains("setval('public.countries_id_seq'"));
+        assert!(!data.contains("setval('public.cities_id_seq'"));
+        // a table with the same prefix is restored
+        assert!(
+            data.contains("\n\nCOPY public.countries_codes (id, code) FROM stdin;\n1\tFR\n\\.\n")
+        );
+        assert!(data.contains("setval('public.countries_codes_id_seq', 1, true)"));
+    }
+
+    #[test]
+    fn test_preserve_tables_query() {
+        assert_eq!(
+            preserve_tables_query(&[], "DROP SCHEMA public CASCADE;".to_string()),
+            "DROP SCHEMA public CASCADE;"
+        );
+
+        assert_eq!(
+            preserve_tables_query(
+                &[
+                    "public.countries".to_string(),
+                    "Sales.Order Items".to_string()
+                ],
+                "DROP SCHEMA public CASCADE;".to_string()
+            ),
+            "CREATE SCHEMA IF NOT EXISTS replibyte_preserved; \
+            ALTER TABLE IF EXISTS public.countries SET SCHEMA replibyte_preserved; \
+            ALTER TABLE IF EXISTS \"Sales\".\"Order Items\" SET SCHEMA replibyte_preserved; \
+            DROP SCHEMA public CASCADE; \
+            ALTER TABLE IF EXISTS replibyte_preserved.countries SET SCHEMA public; \
+            ALTER TABLE IF EXISTS replibyte_preserved.\"Order Items\" SET SCHEMA \"Sales\"; \
+            DROP SCHEMA replibyte_preserved;"
+        );
+    }
+
+    #[test]
+    fn test_remap_columns() {
+        let column_map = column_map(&[
+            "public.users.name=full_name".parse().unwrap(),
+            "public.users.Email=email".parse().unwrap(),
+        ]);
+
+        assert_eq!(
+            remap_columns(
+                b"\n-- Data\nINSERT INTO public.users (id, name, \"Email\") VALUES (1, 'name', 'a@b.c');\n",
+                &column_map
+            ),
+            b"\n-- Data\nINSERT INTO public.users (id, full_name, \"email\") VALUES (1, 'name', 'a@b.c');\n"
+        );
+        assert_eq!(
+            remap_columns(b"COPY public.users (id, name) FROM stdin;\n", &column_map),
+            b"COPY public.users (id, full_name) FROM stdin;\n"
+        );
+        // other tables and queries are kept as is
+        assert_eq!(
+            remap_columns(
+                b"INSERT INTO public.orders (id, name) VALUES (1, 'name');",
+                &column_map
+            ),
+            b"INSERT INTO public.orders (id, name) VALUES (1, 'name');"
+        );
+        assert_eq!(
+            remap_columns(b"INSERT INTO public.users VALUES (1, 'name');", &column_map),
+            b"INSERT INTO public.users VALUES (1, 'name');"
+        );
+        assert_eq!(
+            remap_columns(b"CREATE TABLE public.users (name text);", &column_map),
+            b"CREATE TABLE public.users (name text);"
+        );
+        assert_eq!(remap_columns(b"", &column_map), b"");
+    }
+
+    #[test]
+    fn restore_into_another_schema() {
+        let mut p = get_postgres();
+        p.set_target_schema(Some("tenant_x".to_string()));
+        p.init().expect("can't init postgres");
+
+        let schema_mapping = "tenant_x".parse::<SchemaMapping>().unwrap();
+        let dump = b"CREATE TABLE public.users (id integer NOT NULL, name text);\n\
+        CREATE SEQUENCE public.users_id_seq AS integer START WITH 1;\n\
+        ALTER TABLE ONLY public.users ALTER COLUMN id SET DEFAULT nextval('public.users_id_seq'::regclass);\n\
+        INSERT INTO public.users (id, name) VALUES (1, 'romaric');\n\
+        SELECT pg_catalog.setval('public.users_id_seq', 1, true);\n";
+        let (queries, _) = split_queries(dump);
+        let data = queries
+            .into_iter()
+            .flat_map(|query| retarget_schema(query, &schema_mapping))
+            .collect::<Vec<_>>();
+
+        assert!(p.write(data).is_ok());
+        assert!(p
+            .write(b"INSERT INTO tenant_x.users (name) VALUES ('lucas');".to_vec())
+            .is_ok());
+
+        let query = |query: &str| {
+            let output = p
+                .psql_command()
+                .args([
+                    "-h",
+                    "localhost",
+                    "-p",
+                    "5453",
+                    "-d",
+                    "root",
+                    "-U",
+                    "root",
+                    "-t",
+                    "-A",
+                    "-c",
+                    query,
+                ])
+                .output()
+                .unwrap();
+
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+
+        assert_eq!(query("SELECT count(*) FROM tenant_x.users;"), "2");
+        assert_eq!(query("SELECT max(id) FROM tenant_x.users;"), "2");
+
+        // only the target schema is wiped
+        assert!(p.wipe().is_ok());
+        assert_eq!(
+            query(
+                "SELECT count(*) FROM information_schema.tables WHERE table_schema = 'tenant_x';"
+            ),
+            "0"
+        );
+    }
+
+    #[test]
+    fn test_retarget_schema() {
+        let schema_mapping = "tenant_x".parse::<SchemaMapping>().unwrap();
+        let retarget = |query: &str| {
+            String::from_utf8(retarget_schema(query.as_bytes(), &schema_mapping)).unwrap()
+        };
+
+        assert_eq!(
+            retarget(
+                "\n-- Name: users\nCREATE TABLE public.users (id integer, mood public.mood);\n"
+            ),
+            "\n-- Name: users\nCREATE TABLE tenant_x.users (id integer, mood tenant_x.mood);\n"
+        );
+        assert_eq!(
+            retarget("ALTER TABLE ONLY \"public\".orders ADD CONSTRAINT orders_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);"),
+            "ALTER TABLE ONLY tenant_x.orders ADD CONSTRAINT orders_user_id_fkey FOREIGN KEY (user_id) REFERENCES tenant_x.users(id);"
+        );
+        assert_eq!(
+            retarget("ALTER TABLE ONLY public.users ALTER COLUMN id SET DEFAULT nextval('public.users_id_seq'::regclass);"),
+            "ALTER TABLE ONLY tenant_x.users ALTER COLUMN id SET DEFAULT nextval('tenant_x.users_id_seq'::regclass);"
+        );
+        assert_eq!(
+            retarget("CREATE SCHEMA public;"),
+            "CREATE SCHEMA IF NOT EXISTS tenant_x;"
+        );
+        assert_eq!(
+            retarget("COMMENT ON SCHEMA public IS 'standard public schema';"),
+            "COMMENT ON SCHEMA tenant_x IS 'standard public schema';"
+        );
+        assert_eq!(
+            retarget("SET search_path = public, pg_catalog;"),
+            "SET search_path = tenant_x, pg_catalog;"
+        );
+        // only the table name of the INSERT INTO queries is rewritten
+        assert_eq!(
+            retarget("INSERT INTO public.users (id, name) VALUES (1, 'public.users');"),
+            "INSERT INTO tenant_x.users (id, name) VALUES (1, 'public.users');"
+        );
+        // the data of the COPY queries is kept as is
+        assert_eq!(
+            retarget("1\tpublic.users\n\\.\n\nALTER TABLE public.users OWNER TO root;"),
+            "1\tpublic.users\n\\.\n\nALTER TABLE tenant_x.users OWNER TO root;"
+        );
+        // the other schemas and the names containing the schema name are kept as is
+        assert_eq!(
+            retarget("SELECT pg_catalog.setval('other.public_id_seq', 1, true), users.public FROM mypublic.users;"),
+            "SELECT pg_catalog.setval('other.public_id_seq', 1, true), users.public FROM mypublic.users;"
+        );
+        assert_eq!(retarget(""), "");
+
+        let schema_mapping = "tenant_a=Tenant X".parse::<SchemaMapping>().unwrap();
+        assert_eq!(
+            String::from_utf8(retarget_schema(
+                b"SELECT pg_catalog.setval('tenant_a.users_id_seq', 1, true) FROM tenant_a.users;",
+                &schema_mapping
+            ))
+            .unwrap(),
+            "SELECT pg_catalog.setval('\"Tenant X\".users_id_seq', 1, true) FROM \"Tenant X\".users;"
+        );
+    }
+
+    #[test]
+    fn parse_schema_mapping() {
+        assert_eq!(
+            "tenant_x".parse::<SchemaMapping>(),
+            Ok(SchemaMapping {
+                schema_name: "public".to_string(),
+                new_schema_name: "tenant_x".to_string(),
+            })
+        );
+        assert_eq!(
+            "tenant_a=tenant_x".parse::<SchemaMapping>(),
+            Ok(SchemaMapping {
+                schema_name: "tenant_a".to_string(),
+                new_schema_name: "tenant_x".to_string(),
+            })
+        );
+        assert!("".parse::<SchemaMapping>().is_err());
+        assert!("tenant_a=".parse::<SchemaMapping>().is_err());
+        assert!("db.tenant_x".parse::<SchemaMapping>().is_err());
+    }
+
+    #[test]
+    fn parse_column_mapping() {
+        assert_eq!(
+            "public.users.name=full_name".parse::<ColumnMapping>(),
+            Ok(ColumnMapping {
+                table_name: "public.users".to_string(),
+                column_name: "name".to_string(),
+                new_column_name: "full_name".to_string(),
+            })
+        );
+        assert!("users.name=full_name".parse::<ColumnMapping>().is_err());
+        assert!("public.users.name".parse::<ColumnMapping>().is_err());
+        assert!("public.users.name=".parse::<ColumnMapping>().is_err());
+        assert!("public..name=full_name".parse::<ColumnMapping>().is_err());
+    }
+
+    #[test]
+    fn test_rds_compatible_query() {
+        assert!(rds_compatible_query(b"\nSET session_replication_role = replica;").is_empty());
+        assert!(rds_compatible_query(b"ALTER SYSTEM SET work_mem = '64MB';").is_empty());
+        assert!(rds_compatible_query(b"\n-- Roles\nCREATE ROLE root;").is_empty());
+        assert!(rds_compatible_query(
+            b"ALTER ROLE root WITH SUPERUSER INHERIT CREATEROLE CREATEDB LOGIN REPLICATION BYPASSRLS;"
+        )
+        .is_empty());
+        assert!(rds_compatible_query(
+            b"COMMENT ON EXTENSION plpgsql IS 'PL/pgSQL procedural language';"
+        )
+        .is_empty());
+
+        assert_eq!(
+            rds_compatible_query(b"\nALTER TABLE public.users DISABLE TRIGGER ALL;"),
+            b"\nALTER TABLE public.users DISABLE TRIGGER USER;"
+        );
+        assert_eq!(
+            rds_compatible_query(b"ALTER TABLE public.users ENABLE TRIGGER ALL;"),
+            b"ALTER TABLE public.users ENABLE TRIGGER USER;"
+        );
+
+        // the other queries are kept as is
+        for query in [
+            b"SET statement_timeout = 0;".as_slice(),
+            b"CREATE TABLE public.users (id integer, role text);",
+            b"INSERT INTO public.users (id, role) VALUES (1, 'ALTER ROLE');",
+            b"ALTER TABLE public.users OWNER TO root;",
+        ] {
+            assert_eq!(rds_compatible_query(query), query);
+        }
+    }
+
+    #[test]
+    fn rds_compatible_restore_does_not_emit_the_superuser_statements() {
+        let dump = b"\
+SET session_replication_role = replica;
+CREATE ROLE root;
+ALTER ROLE root WITH SUPERUSER INHERIT CREATEROLE CREATEDB LOGIN REPLICATION BYPASSRLS;
+ALTER SYSTEM SET max_connections = 200;
+COMMENT ON EXTENSION plpgsql IS 'PL/pgSQL procedural language';
+CREATE TABLE public.users (id integer, name text);
+ALTER TABLE public.users DISABLE TRIGGER ALL;
+INSERT INTO public.users (id, name) VALUES (1, 'John');
+ALTER TABLE public.users ENABLE TRIGGER ALL;
+SET session_replication_role = DEFAULT;
+";
+
+        let mut postgres = get_postgres();
+        postgres.set_rds_compatible(true);
+        let queries = postgres.restore_queries(dump.to_vec());
+        let queries = String::from_utf8_lossy(queries.as_slice());
+
+        for disallowed in [
+            "session_replication_role",
+            "CREATE ROLE",
+            "ALTER ROLE",
+            "ALTER SYSTEM",
+            "COMMENT ON EXTENSION",
+            "TRIGGER ALL",
+        ] {
+            assert!(!queries.contains(disallowed), "{} is emitted", disallowed);
+        }
+
+        assert!(queries.contains("CREATE TABLE public.users (id integer, name text);"));
+        assert!(queries.contains("ALTER TABLE public.users DISABLE TRIGGER USER;"));
+        assert!(queries.contains("INSERT INTO public.users (id, name) VALUES (1, 'John');"));
+
+        // nothing is rewritten by default
+        let queries = get_postgres().restore_queries(dump.to_vec());
+        assert_eq!(queries, dump.to_vec());
+    }
+
+    #[test]
+    fn test_idempotent_query() {
+        assert_eq!(
+            idempotent_query(b"\n-- Name: users\nCREATE TABLE public.users (name text);\n"),
+            b"\n-- Name: users\nCREATE TABLE IF NOT EXISTS public.users (name text);\n"
+        );
+        assert_eq!(
+            idempotent_query(b"CREATE UNIQUE INDEX users_name ON public.users (name);"),
+            b"CREATE UNIQUE INDEX IF NOT EXISTS users_name ON public.users (name);"
+        );
+        assert_eq!(
+            idempotent_query(b"CREATE TABLE IF NOT EXISTS public.users (name text);"),
+            b"CREATE TABLE IF NOT EXISTS public.users (name text);"
+        );
+        assert_eq!(
+            idempotent_query(b"INSERT INTO public.users (name) VALUES ('rom;aric');\n"),
+            b"INSERT INTO public.users (name) VALUES ('rom;aric') ON CONFLICT DO NOTHING;\n"
+        );
+        assert_eq!(
+            idempotent_query(
+                b"ALTER TABLE ONLY public.users ADD CONSTRAINT users_pkey PRIMARY KEY (id);"
+            ),
+            b"ALTER TABLE ONLY public.users ADD CONSTRAINT users_pkey PRIMARY KEY (id);"
+        );
+        assert_eq!(idempotent_query(b""), b"");
+    }
+
+    #[test]
+    fn test_split_queries() {
+        let data = b"-- Name: users; Type: TABLE\n\
+        CREATE TABLE public.users (name text);\n\
+        INSERT INTO public.users (name) VALUES ('rom;aric');\n\
+        CREATE FUNCTION public.f() RETURNS void AS $body$ BEGIN PERFORM 1; END; $body$ LANGUAGE plpgsql;\n\
+        INSERT INTO public.users (name) VALUES ('luc";
+
+        let (queries, remaining_data) = split_queries(data);
+        assert_eq!(queries.len(), 3);
+        assert!(queries[0].ends_with(b"(name text);\n"));
+        assert!(queries[1].ends_with(b"('rom;aric');\n"));
+        assert!(queries[2].ends_with(b"LANGUAGE plpgsql;\n"));
+        assert_eq!(
+            remaining_data,
+            b"INSERT INTO public.users (name) VALUES ('luc"
+        );
+        assert_eq!(queries.concat(), data[..data.len() - remaining_data.len()]);
+
+        let (queries, remaining_data) = split_queries(b"");
+        assert!(queries.is_empty());
+        assert!(remaining_data.is_empty());
+    }
+
+    #[test]
+    fn test_insert_into_table_name() {
+        assert_eq!(
+            insert_into_table_name(b"INSERT INTO public.users (name) VALUES ('romaric');"),
+            Some(b"public.users".as_slice())
+        );
+        assert_eq!(
+            insert_into_table_name(b"\n--\n-- Data for Name: users\n--\n\nINSERT INTO public.users (name) VALUES ('romaric');"),
+            Some(b"public.users".as_slice())
+        );
+        assert_eq!(
+            insert_into_table_name(b"CREATE TABLE public.users (name text);"),
+            None
+        );
+        assert_eq!(insert_into_table_name(b"-- comment only"), None);
+    }
+
+    #[test]
+    fn test_post_data_query() {
+        assert!(post_data_query(
+            b"\n--\n-- Name: users users_pkey\n--\n\nALTER TABLE ONLY public.users\n    ADD CONSTRAINT users_pkey PRIMARY KEY (id);"
+        ));
+        assert!(post_data_query(
+            b"CREATE INDEX users_email_idx ON public.users USING btree (email);"
+        ));
+        assert!(!post_data_query(
+            b"ALTER TABLE ONLY public.users ALTER COLUMN id SET DEFAULT nextval('public.users_id_seq'::regclass);"
+        ));
+        assert!(!post_data_query(
+            b"INSERT INTO public.users (name) VALUES ('romaric');"
+        ));
+        assert!(!post_data_query(b"-- comment only"));
+    }
+
+    #[test]
+    fn test_create_table_name() {
+        assert_eq!(
+            create_table_name(
+                b"\n--\n-- Name: users\n--\n\nCREATE TABLE public.users (\n    name text\n);"
+            ),
+            Some(b"public.users".as_slice())
+        );
+        assert_eq!(
+            create_table_name(b"INSERT INTO public.users (name) VALUES ('romaric');"),
+            None
+        );
+    }
+
     #[test]
     fn test_inserts() {}
 }
@@ -0,0 +1,60 @@
+use std::fmt;
+use std::io::Error;
+
+/// errors returned by the backup and restore pipelines - the variant tells which stage failed
+#[derive(Debug)]
+pub enum ReplibyteError {
+    /// invalid or unreadable configuration file
+    Config(Error),
+    /// the source can't be dumped
+    Source(Error),
+    /// the backup can't be written to or read from the bridge
+    Bridge(Error),
+    /// a transformer can't be built from its options
+    Transform(String),
+    /// the backup can't be restored into the destination
+    Destination(Error),
+}
+
+impl fmt::Display for ReplibyteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplibyteError::Config(err) => write!(f, "configuration error: {}", err),
+            ReplibyteError::Source(err) => write!(f, "source error: {}", err),
+            ReplibyteError::Bridge(err) => write!(f, "bridge error: {}", err),
+            ReplibyteError::Transform(message) => write!(f, "transformer error: {}", message),
+            ReplibyteError::Destination(err) => write!(f, "destination error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReplibyteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReplibyteError::Config(err)
+            | ReplibyteError::Source(err)
+            | ReplibyteError::Bridge(err)
+            | ReplibyteError::Destination(err) => Some(err),
+            ReplibyteError::Transform(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io::{Error, ErrorKind};
+
+    use crate::error::ReplibyteError;
+
+    #[test]
+    fn display_the_failing_stage() {
+        let err = ReplibyteError::Bridge(Error::new(ErrorKind::Other, "access denied"));
+        assert_eq!(err.to_string(), "bridge error: access denied");
+        assert_eq!(err.source().unwrap().to_string(), "access denied");
+
+        let err = ReplibyteError::Transform("invalid pattern".to_string());
+        assert_eq!(err.to_string(), "transformer error: invalid pattern");
+        assert!(err.source().is_none());
+    }
+}
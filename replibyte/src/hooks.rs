@@ -0,0 +1,268 @@
+use std::io::{self, Error, ErrorKind};
+use std::process::Command;
+
+use crate::config::HooksConfig;
+
+/// commands run before and after a backup or a restore - E.g. `pre_backup` and `post_backup`
+pub struct Hooks<'a> {
+    name: &'static str,
+    pre: Option<&'a str>,
+    post: Option<&'a str>,
+}
+
+impl<'a> Hooks<'a> {
+    pub fn backup(config: Option<&'a HooksConfig>) -> Self {
+        Hooks {
+            name: "backup",
+            pre: config.and_then(|config| config.pre_backup.as_deref()),
+            post: config.and_then(|config| config.post_backup.as_deref()),
+        }
+    }
+
+    pub fn restore(config: Option<&'a HooksConfig>) -> Self {
+        Hooks {
+            name: "restore",
+            pre: config.and_then(|config| config.pre_restore.as_deref()),
+            post: config.and_then(|config| config.post_restore.as_deref()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pre.is_none() && self.post.is_none()
+    }
+
+    /// run `task` between the pre and the post hooks - a failing pre hook aborts before running `task`,
+    /// a failing post hook only prints a warning. The post hook runs even when `task` panics
+    pub fn run<T, E, F>(&self, backup_name: &str, task: F) -> Result<T, E>
+    where
+        E: From<Error>,
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(command) = self.pre {
+            let hook_name = format!("pre_{}", self.name);
+            run_hook(hook_name.as_str(), command, backup_name, None)?;
+        }
+
+        let mut post_hook = PostHook {
+            hook_name: format!("post_{}", self.name),
+            command: self.post,
+            backup_name,
+            status: "failure",
+        };
+
+        let result = task();
+
+        if result.is_ok() {
+            post_hook.status = "success";
+        }
+
+        drop(post_hook);
+        result
+    }
+}
+
+/// run the post hook when dropped - once the task is done or while it panics
+struct PostHook<'a> {
+    hook_name: String,
+    command: Option<&'a str>,
+    backup_name: &'a str,
+    status: &'static str,
+}
+
+impl<'a> Drop for PostHook<'a> {
+    fn drop(&mut self) {
+        if let Some(command) = self.command {
+            if let Err(err) = run_hook(
+                self.hook_name.as_str(),
+                command,
+                self.backup_name,
+                Some(self.status),
+            ) {
+                eprintln!("warning: {}", err);
+            }
+        }
+    }
+}
+
+/// run `command` with `sh -c` and its output written to stderr - the hook name, the backup name and the status
+/// (post hooks only) are passed as `REPLIBYTE_HOOK`, `REPLIBYTE_BACKUP_NAME` and `REPLIBYTE_STATUS`
+fn run_hook(
+    hook_name: &str,
+    command: &str,
+    backup_name: &str,
+    status: Option<&str>,
+) -> Result<(), Error> {
+    let mut process = Command::new("sh");
+    process
+        .arg("-c")
+        .arg(command)
+        // stdout is kept for the data - E.g. `restore --output`
+        .stdout(io::stderr())
+        .env("REPLIBYTE_HOOK", hook_name)
+        .env("REPLIBYTE_BACKUP_NAME", backup_name);
+
+    if let Some(status) = status {
+        process.env("REPLIBYTE_STATUS", status);
+    }
+
+    let exit_status = process.status().map_err(|err| {
+        Error::new(
+            ErrorKind::Other,
+            format!("{} hook '{}' can't be run: {}", hook_name, command, err),
+        )
+    })?;
+
+    if !exit_status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("{} hook '{}' failed: {}", hook_name, command, exit_status),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{Error, ErrorKind};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::path::PathBuf;
+
+    use fake::{Fake, Faker};
+
+    use crate::config::HooksConfig;
+    use crate::hooks::Hooks;
+
+    fn hooks_log() -> PathBuf {
+        std::env::temp_dir().join(format!("replibyte-hooks-{}.log", Faker.fake::<u64>()))
+    }
+
+    fn log_command(log: &PathBuf) -> String {
+        format!(
+            "echo \"$REPLIBYTE_HOOK $REPLIBYTE_BACKUP_NAME $REPLIBYTE_STATUS\" >> {}",
+            log.display()
+        )
+    }
+
+    fn read_log(log: &PathBuf) -> Vec<String> {
+        fs::read_to_string(log)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn hooks_run_in_order() {
+        let log = hooks_log();
+        let config = HooksConfig {
+            pre_backup: Some(log_command(&log)),
+            post_backup: Some(log_command(&log)),
+            pre_restore: None,
+            post_restore: None,
+        };
+
+        let hooks = Hooks::backup(Some(&config));
+        let result: Result<(), Error> = hooks.run("backup-1647706359405", || {
+            assert_eq!(read_log(&log), vec!["pre_backup backup-1647706359405"]);
+            Ok(())
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(
+            read_log(&log),
+            vec![
+                "pre_backup backup-1647706359405",
+                "post_backup backup-1647706359405 success",
+            ]
+        );
+
+        let _ = fs::remove_file(log);
+    }
+
+    #[test]
+    fn post_hook_gets_the_failure() {
+        let log = hooks_log();
+        let config = HooksConfig {
+            pre_backup: None,
+            post_backup: None,
+            pre_restore: None,
+            post_restore: Some(log_command(&log)),
+        };
+
+        let result: Result<(), Error> = Hooks::restore(Some(&config)).run("latest", || {
+            Err(Error::new(ErrorKind::Other, "restore failed"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(read_log(&log), vec!["post_restore latest failure"]);
+
+        let _ = fs::remove_file(log);
+    }
+
+    #[test]
+    fn post_hook_runs_when_the_task_panics() {
+        let log = hooks_log();
+        let config = HooksConfig {
+            pre_backup: None,
+            post_backup: Some(log_command(&log)),
+            pre_restore: None,
+            post_restore: None,
+        };
+
+        let hooks = Hooks::backup(Some(&config));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _: Result<(), Error> = hooks.run("backup-1", || panic!("backup panicked"));
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(read_log(&log), vec!["post_backup backup-1 failure"]);
+
+        let _ = fs::remove_file(log);
+    }
+
+    #[test]
+    fn failing_pre_hook_blocks_the_task() {
+        let log = hooks_log();
+        let config = HooksConfig {
+            pre_backup: Some("exit 1".to_string()),
+            post_backup: Some(log_command(&log)),
+            pre_restore: None,
+            post_restore: None,
+        };
+
+        let mut task_ran = false;
+        let result: Result<(), Error> = Hooks::backup(Some(&config)).run("backup-1", || {
+            task_ran = true;
+            Ok(())
+        });
+
+        assert!(result.is_err());
+        assert!(!task_ran);
+        // the post hook does not run either
+        assert!(read_log(&log).is_empty());
+    }
+
+    #[test]
+    fn failing_post_hook_does_not_fail_the_task() {
+        let config = HooksConfig {
+            pre_backup: None,
+            post_backup: Some("exit 1".to_string()),
+            pre_restore: None,
+            post_restore: None,
+        };
+
+        let result: Result<u8, Error> = Hooks::backup(Some(&config)).run("backup-1", || Ok(42));
+        assert_eq!(result.unwrap(), 42);
+    }
+
+    #[test]
+    fn no_hooks() {
+        let hooks = Hooks::restore(None);
+        assert!(hooks.is_empty());
+
+        let result: Result<u8, Error> = hooks.run("backup-1", || Ok(42));
+        assert_eq!(result.unwrap(), 42);
+    }
+}
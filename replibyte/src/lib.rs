@@ -0,0 +1,21 @@
+//! RepliByte - the modules expose the `Source`, `Destination` and `Bridge` traits and the `SourceRegistry` and
+//! `DestinationRegistry` to add the engines not built in RepliByte (E.g. ClickHouse)
+
+pub mod bridge;
+pub mod cli;
+pub mod config;
+pub mod connector;
+pub mod destination;
+pub mod error;
+pub mod hooks;
+pub mod logger;
+pub mod metrics;
+pub mod preflight;
+pub mod runtime;
+pub mod schedule;
+pub mod source;
+pub mod tasks;
+pub mod timings;
+pub mod transformer;
+pub mod types;
+pub mod utils;
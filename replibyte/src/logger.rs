@@ -0,0 +1,102 @@
+use std::io::Write;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use log::Record;
+use serde_json::{json, Value};
+
+use crate::cli::LogFormat;
+
+lazy_static! {
+    /// backup being written or restored - added to the JSON log records
+    static ref BACKUP_NAME: RwLock<Option<String>> = RwLock::new(None);
+}
+
+/// initialize the logger - the level is set by `RUST_LOG` whatever the format
+pub fn init(format: LogFormat) {
+    let mut builder = env_logger::Builder::from_default_env();
+
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let timestamp = buf.timestamp_millis().to_string();
+            let backup_name = BACKUP_NAME.read().unwrap().clone();
+            writeln!(
+                buf,
+                "{}",
+                json_record(timestamp.as_str(), record, backup_name.as_deref())
+            )
+        });
+    }
+
+    builder.init();
+}
+
+/// the backup name added to the next JSON log records
+pub fn set_backup_name(backup_name: Option<String>) {
+    *BACKUP_NAME.write().unwrap() = backup_name;
+}
+
+fn json_record(timestamp: &str, record: &Record, backup_name: Option<&str>) -> Value {
+    json!({
+        "timestamp": timestamp,
+        "level": record.level().as_str(),
+        "module": record.module_path().unwrap_or_else(|| record.target()),
+        "message": record.args().to_string(),
+        "backup_name": backup_name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, Record};
+
+    use crate::logger::json_record;
+
+    #[test]
+    fn json_log_records() {
+        let lines = vec![
+            json_record(
+                "2022-03-19T16:12:39.405Z",
+                &Record::builder()
+                    .args(format_args!("upload object 'backup-1/1.dump' part 1"))
+                    .level(Level::Info)
+                    .module_path(Some("replibyte::bridge::s3"))
+                    .build(),
+                Some("backup-1"),
+            )
+            .to_string(),
+            json_record(
+                "2022-03-19T16:12:40.000Z",
+                &Record::builder()
+                    .args(format_args!("download interrupted \"retry\"\nnow"))
+                    .level(Level::Warn)
+                    .target("replibyte")
+                    .build(),
+                None,
+            )
+            .to_string(),
+        ]
+        .join("\n");
+
+        let records = lines
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["timestamp"], "2022-03-19T16:12:39.405Z");
+        assert_eq!(records[0]["level"], "INFO");
+        assert_eq!(records[0]["module"], "replibyte::bridge::s3");
+        assert_eq!(
+            records[0]["message"],
+            "upload object 'backup-1/1.dump' part 1"
+        );
+        assert_eq!(records[0]["backup_name"], "backup-1");
+
+        // the message is escaped and the module defaults to the target
+        assert_eq!(records[1]["level"], "WARN");
+        assert_eq!(records[1]["module"], "replibyte");
+        assert_eq!(records[1]["message"], "download interrupted \"retry\"\nnow");
+        assert!(records[1]["backup_name"].is_null());
+    }
+}
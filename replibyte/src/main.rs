@@ -1,151 +1,117 @@
 #[macro_use]
 extern crate prettytable;
 
-use std::fs::File;
-use std::io::{stdin, BufReader, Error, ErrorKind, Read};
-use std::sync::mpsc;
-use std::sync::mpsc::Receiver;
-use std::thread;
-use std::thread::sleep;
+mod commands;
+
+use std::env;
+use std::io::{stderr, stdin, stdout, Error, IsTerminal};
 use std::time::Duration;
 
 use clap::Parser;
-use indicatif::{ProgressBar, ProgressStyle};
-use timeago::Formatter;
-
-use utils::to_human_readable_unit;
-
-use crate::bridge::s3::S3;
-use crate::bridge::{Bridge, ReadOptions};
-use crate::cli::{BackupCommand, SubCommand, TransformerCommand, CLI};
-use crate::config::{Config, ConnectionUri};
-use crate::connector::Connector;
-use crate::destination::mongodb::MongoDB as DestinationMongoDB;
-use crate::destination::mongodb_stdout::MongoDBStdout;
-use crate::destination::postgres::Postgres as DestinationPostgres;
-use crate::destination::postgres_stdout::PostgresStdout;
-use crate::source::mongodb::MongoDB as SourceMongoDB;
-use crate::source::mongodb_stdin::MongoDBStdin;
-use crate::source::postgres::Postgres as SourcePostgres;
-use crate::source::postgres_stdin::PostgresStdin;
-use crate::source::{Source, SourceOptions};
-use crate::tasks::full_backup::FullBackupTask;
-use crate::tasks::full_restore::FullRestoreTask;
-use crate::tasks::{MaxBytes, Task, TransferredBytes};
-use crate::transformer::transformers;
-use crate::utils::{epoch_millis, table};
-
-mod bridge;
-mod cli;
-mod config;
-mod connector;
-mod destination;
-mod runtime;
-mod source;
-mod tasks;
-mod transformer;
-mod types;
-mod utils;
-
-fn list_backups(s3: &mut S3) -> Result<(), Error> {
-    let _ = s3.init()?;
-    let mut index_file = s3.index_file()?;
-
-    if index_file.backups.is_empty() {
-        println!("<empty> no backups available\n");
-        return Ok(());
-    }
-
-    index_file.backups.sort_by(|a, b| a.cmp(b).reverse());
 
-    let mut table = table();
-    table.set_titles(row!["name", "size", "when", "compressed", "encrypted"]);
-    let formatter = Formatter::new();
-    let now = epoch_millis();
-
-    for backup in index_file.backups {
-        table.add_row(row![
-            backup.directory_name.as_str(),
-            to_human_readable_unit(backup.size),
-            formatter.convert(Duration::from_millis((now - backup.created_at) as u64)),
-            backup.compressed,
-            backup.encrypted,
-        ]);
-    }
-
-    let _ = table.printstd();
+use replibyte::bridge::local_file::LocalFile;
+use replibyte::bridge::s3::{S3Object, S3};
+use replibyte::bridge::Bridge;
+use replibyte::cli::{BackupCommand, SubCommand, CLI};
+use replibyte::config::{resolve_profile, Config, ConnectionUri, PROFILE_ENV_VAR};
+use replibyte::destination::DestinationRegistry;
+use replibyte::logger;
+use replibyte::preflight::{preflight, PreflightTarget};
+use replibyte::runtime::Runtime;
+use replibyte::source::SourceRegistry;
+
+use crate::commands::restore::confirm_database_wipe;
+use crate::commands::{progress_bar, progress_json, progress_socket, PROGRESS_JSON_INTERVAL};
+
+/// the registries provide the engines not built in RepliByte, selected by the `type` of the `source` and `destination`
+/// configuration - E.g. `source_registry.register("clickhouse", |config| Ok(Box::new(ClickHouse::new(config.connection_uri.as_str()))))`
+fn main() -> anyhow::Result<()> {
+    let source_registry = SourceRegistry::default();
+    let destination_registry = DestinationRegistry::default();
 
-    Ok(())
-}
+    let args = CLI::parse();
+    logger::init(args.log_format);
+    let summary_file = args.summary_file.as_deref();
 
-fn show_progress_bar(rx_pb: Receiver<(TransferredBytes, MaxBytes)>) {
-    let pb = ProgressBar::new(0);
-    pb.set_style(ProgressStyle::default_spinner());
+    let profile = resolve_profile(args.profile.as_deref(), env::var(PROFILE_ENV_VAR).ok());
+    let config = Config::from_file_with_profile(args.config.as_path(), profile.as_deref())?;
 
-    let mut style_is_progress_bar = false;
-    let mut _max_bytes = 0usize;
-    let mut last_transferred_bytes = 0usize;
+    let mut runtime = Runtime::new(args.threads.or(config.workers));
+    let mut bridge = s3_bridge(&config)?;
 
-    loop {
-        let (transferred_bytes, max_bytes) = match rx_pb.try_recv() {
-            Ok(msg) => msg,
-            Err(_) => (last_transferred_bytes, _max_bytes),
-        };
+    let sub_commands: &SubCommand = &args.sub_commands;
 
-        if _max_bytes == 0 && style_is_progress_bar {
-            // show spinner if there is no max_bytes indicated
-            pb.set_style(ProgressStyle::default_spinner());
-            style_is_progress_bar = false;
-        } else if _max_bytes > 0 && !style_is_progress_bar {
-            pb.set_style(ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.green/blue}] {bytes}/{total_bytes} ({eta})")
-                .progress_chars("#>-"));
-            style_is_progress_bar = true;
-        }
+    // check everything is ready before starting a long backup or restore
+    check_ready(&mut bridge, &config, sub_commands)?;
 
-        if max_bytes != _max_bytes {
-            pb.set_length(max_bytes as u64);
-            _max_bytes = max_bytes;
+    match (sub_commands, args.progress_socket.as_deref()) {
+        // skip progress when output = true
+        (SubCommand::Restore(args), _) if args.output => {}
+        // the records are already throttled by the progress thread
+        (_, Some(progress_socket_path)) => runtime.start_progress(
+            args.progress_interval,
+            progress_json(progress_socket(progress_socket_path)?, Duration::ZERO),
+        ),
+        // the spinner is useless when stderr is not a terminal (E.g. CI)
+        _ if stderr().is_terminal() => {
+            runtime.start_progress(args.progress_interval, progress_bar())
         }
+        // only the transfers report their progress - stdout is kept for the output of the commands
+        (SubCommand::Backup(BackupCommand::Run(_)) | SubCommand::Restore(_), _) => runtime
+            .start_progress(
+                args.progress_interval,
+                progress_json(stderr(), PROGRESS_JSON_INTERVAL),
+            ),
+        _ => {}
+    };
 
-        last_transferred_bytes = transferred_bytes;
-        pb.set_position(transferred_bytes as u64);
-
-        sleep(Duration::from_micros(50));
-    }
-}
-
-/// display all transformers available
-fn list_transformers() {
-    let mut table = table();
-    table.set_titles(row!["name", "description"]);
-
-    for transformer in transformers() {
-        table.add_row(row![transformer.id(), transformer.description()]);
+    match sub_commands {
+        SubCommand::Backup(cmd) => commands::backup::run(
+            cmd,
+            bridge,
+            config,
+            &source_registry,
+            &runtime,
+            summary_file,
+        ),
+        SubCommand::Transformer(cmd) => commands::transformer::run(cmd),
+        SubCommand::Source(cmd) => commands::source::run(cmd, &config, &source_registry, &runtime),
+        SubCommand::Restore(cmd) => commands::restore::run(
+            cmd,
+            bridge,
+            config,
+            &destination_registry,
+            &runtime,
+            summary_file,
+        ),
     }
-
-    let _ = table.printstd();
 }
 
-fn main() -> anyhow::Result<()> {
-    env_logger::init();
-    let args = CLI::parse();
-
-    let file = File::open(args.config)?;
-    let config: Config = serde_yaml::from_reader(file)?;
-
+/// the bridge of the configuration - compressed and encrypted with the options of the source or the destination
+fn s3_bridge(config: &Config) -> anyhow::Result<S3> {
     let mut bridge = S3::new(
         config.bridge.bucket()?,
         config.bridge.region()?,
         config.bridge.access_key_id()?,
         config.bridge.secret_access_key()?,
         config.bridge.endpoint()?,
-    );
+    )?;
+
+    bridge.set_http_client_options(config.bridge.http_client_options()?)?;
+    bridge.set_prefix(config.bridge.prefix()?);
+    bridge.set_object_options(config.bridge.object_options()?);
+    if let Some(part_size) = config.bridge.multipart_part_size()? {
+        bridge.set_multipart_part_size(part_size);
+    }
+    bridge.set_deduplication(config.bridge.deduplication.unwrap_or(false));
+    bridge.set_key_template(config.bridge.key_template.clone())?;
 
     match &config.source {
         Some(source) => {
             bridge.set_compression(source.compression.unwrap_or(true));
-            bridge.set_encryption_key(source.encryption_key()?)
+            bridge.set_compression_format(source.compression_format.unwrap_or_default());
+            bridge.set_encryption_key(source.encryption_key()?);
+            bridge.set_encryption_cipher(source.encryption_cipher.unwrap_or_default());
         }
         None => {}
     }
@@ -158,194 +124,77 @@ fn main() -> anyhow::Result<()> {
         None => {}
     }
 
-    let (tx_pb, rx_pb) = mpsc::sync_channel::<(TransferredBytes, MaxBytes)>(1000);
-
-    let sub_commands: &SubCommand = &args.sub_commands;
-
-    match sub_commands {
-        // skip progress when output = true
-        SubCommand::Restore(args) if args.output => {}
-        _ => {
-            let _ = thread::spawn(move || show_progress_bar(rx_pb));
-        }
-    };
-
-    let progress_callback = |bytes: TransferredBytes, max_bytes: MaxBytes| {
-        let _ = tx_pb.send((bytes, max_bytes));
-    };
+    Ok(bridge)
+}
 
+/// check the bridge and the database before a backup or a restore - and confirm the wipe of the Postgres destination
+fn check_ready(bridge: &mut S3, config: &Config, sub_commands: &SubCommand) -> anyhow::Result<()> {
     match sub_commands {
-        SubCommand::Backup(cmd) => match cmd {
-            BackupCommand::List => {
-                let _ = list_backups(&mut bridge)?;
-            }
-            BackupCommand::Run(args) => match config.source {
-                Some(source) => {
-                    // Match the transformers from the config
-                    let transformers = source
-                        .transformers
-                        .iter()
-                        .flat_map(|transformer| {
-                            transformer.columns.iter().map(|column| {
-                                column.transformer.transformer(
-                                    transformer.database.as_str(),
-                                    transformer.table.as_str(),
-                                    column.name.as_str(),
-                                )
-                            })
-                        })
-                        .collect::<Vec<_>>();
-
-                    let empty_config = vec![];
-                    let skip_config = match &source.skip {
-                        Some(config) => config,
-                        None => &empty_config,
-                    };
-
-                    let options = SourceOptions {
-                        transformers: &transformers,
-                        skip_config: &skip_config,
-                    };
-
-                    match args.source_type.as_ref().map(|x| x.as_str()) {
-                        None => match source.connection_uri()? {
-                            ConnectionUri::Postgres(host, port, username, password, database) => {
-                                let postgres = SourcePostgres::new(
-                                    host.as_str(),
-                                    port,
-                                    database.as_str(),
-                                    username.as_str(),
-                                    password.as_str(),
-                                );
-
-                                let task = FullBackupTask::new(postgres, bridge, options);
-                                task.run(progress_callback)?
-                            }
-                            ConnectionUri::Mysql(host, port, username, password, database) => {
-                                todo!() // FIXME
-                            }
-                            ConnectionUri::MongoDB(
-                                host,
-                                port,
-                                username,
-                                password,
-                                database,
-                                authentication_db,
-                            ) => {
-                                let mongodb = SourceMongoDB::new(
-                                    host.as_str(),
-                                    port,
-                                    database.as_str(),
-                                    username.as_str(),
-                                    password.as_str(),
-                                    authentication_db.as_str(),
-                                );
-
-                                let task = FullBackupTask::new(mongodb, bridge, options);
-                                task.run(progress_callback)?
-                            }
-                        },
-                        // some user use "postgres" and "postgresql" both are valid
-                        Some(v) if v == "postgres" || v == "postgresql" => {
-                            if args.file.is_some() {
-                                let dump_file = File::open(args.file.as_ref().unwrap())?;
-                                let mut stdin = stdin(); // FIXME
-                                let reader = BufReader::new(dump_file);
-                                let _ = stdin.read_to_end(&mut reader.buffer().to_vec())?;
-                            }
-
-                            let postgres = PostgresStdin::default();
-                            let task = FullBackupTask::new(postgres, bridge, options);
-                            task.run(progress_callback)?
-                        }
-                        Some(v) => {
-                            return Err(anyhow::Error::from(Error::new(
-                                ErrorKind::Other,
-                                format!("source type '{}' not recognized", v),
-                            )));
-                        }
-                    }
+        SubCommand::Backup(BackupCommand::Run(args)) => {
+            if let Some(source) = &config.source {
+                let connection_uri = match (&args.source_type, &source.source_type) {
+                    (None, None) => Some(source.connection_uri()?),
+                    (Some(_), _) => None, // the dump is read from stdin or a file
+                    (None, Some(_)) => None, // the registered engines are not checked
+                };
 
-                    println!("Backup successful!")
-                }
-                None => {
-                    return Err(anyhow::Error::from(Error::new(
-                        ErrorKind::Other,
-                        "missing <source> object in the configuration file",
-                    )));
-                }
-            },
-        },
-        SubCommand::Transformer(cmd) => match cmd {
-            TransformerCommand::List => {
-                let _ = list_transformers();
+                preflight(bridge, PreflightTarget::Source, connection_uri.as_ref())
+                    .map_err(Error::from)?;
             }
-        },
-        SubCommand::Restore(cmd) => match config.destination {
-            Some(destination) => {
-                let options = match cmd.value.as_str() {
-                    "latest" => ReadOptions::Latest,
-                    v => ReadOptions::Backup {
-                        name: v.to_string(),
-                    },
+        }
+        SubCommand::Restore(args) => {
+            if let Some(destination) = &config.destination {
+                let connection_uri = match args.output {
+                    false if destination.destination_type.is_some() => None, // the registered engines are not checked
+                    false => Some(destination.connection_uri()?),
+                    true => None, // the dump is streamed on stdout
                 };
 
-                if cmd.output {
-                    let postgres = PostgresStdout::default();
-                    let task = FullRestoreTask::new(postgres, bridge, options);
-                    let _ = task.run(|_, _| {})?; // do not display the progress bar
-                    return Ok(());
+                match (&args.from_file, &args.object_key) {
+                    (Some(path), _) => preflight(
+                        &mut LocalFile::new(path),
+                        PreflightTarget::Destination,
+                        connection_uri.as_ref(),
+                    ),
+                    // the index file is not read
+                    (None, Some(object_key)) => preflight(
+                        &mut S3Object::new(bridge.clone(), object_key.as_str()),
+                        PreflightTarget::Destination,
+                        connection_uri.as_ref(),
+                    ),
+                    (None, None) => preflight(
+                        bridge,
+                        PreflightTarget::Destination,
+                        connection_uri.as_ref(),
+                    ),
                 }
+                .map_err(Error::from)?;
+            }
+        }
+        _ => {}
+    }
 
-                match destination.connection_uri()? {
-                    ConnectionUri::Postgres(host, port, username, password, database) => {
-                        let postgres = DestinationPostgres::new(
-                            host.as_str(),
-                            port,
-                            database.as_str(),
-                            username.as_str(),
-                            password.as_str(),
-                            true,
-                        );
-
-                        let task = FullRestoreTask::new(postgres, bridge, options);
-                        task.run(progress_callback)?
-                    }
-                    ConnectionUri::Mysql(host, port, username, password, database) => {
-                        todo!() // FIXME
-                    }
-                    ConnectionUri::MongoDB(
-                        host,
-                        port,
-                        username,
-                        password,
-                        database,
-                        authentication_db,
-                    ) => {
-                        let mongodb = DestinationMongoDB::new(
-                            host.as_str(),
-                            port,
-                            database.as_str(),
-                            username.as_str(),
-                            password.as_str(),
-                            authentication_db.as_str(),
-                        );
-
-                        let task = FullRestoreTask::new(mongodb, bridge, options);
-                        task.run(progress_callback)?
-                    }
+    // the Postgres destination is wiped before being restored - unless `--if-not-exists` is set
+    if let SubCommand::Restore(restore_args) = sub_commands {
+        if !restore_args.output && !restore_args.yes && !restore_args.if_not_exists {
+            if let Some(destination) = config
+                .destination
+                .as_ref()
+                .filter(|destination| destination.destination_type.is_none())
+            {
+                if let ConnectionUri::Postgres(_, _, _, _, database) =
+                    destination.connection_uri()?
+                {
+                    confirm_database_wipe(
+                        database.as_str(),
+                        stdin().is_terminal(),
+                        stdin().lock(),
+                        stdout(),
+                    )?;
                 }
-
-                println!("Restore successful!")
-            }
-            None => {
-                return Err(anyhow::Error::from(Error::new(
-                    ErrorKind::Other,
-                    "missing <destination> object in the configuration file",
-                )));
             }
-        },
-    };
+        }
+    }
 
     Ok(())
 }
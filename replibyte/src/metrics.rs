@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::transformer::{ColumnType, Transformer, TransformerOption};
+use crate::types::Column;
+
+/// values seen by a transformer
+#[derive(Debug, Default)]
+struct Counters {
+    transformed: AtomicUsize,
+    skipped: AtomicUsize,
+    /// time spent in the transformer - only measured when profiling
+    nanos: AtomicU64,
+}
+
+/// values seen by a transformer during a backup - a transformer without any value matches no column
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformerCount {
+    /// E.g. `public.users.email`
+    pub name: String,
+    pub id: String,
+    /// values replaced by the transformer
+    pub transformed: usize,
+    /// values kept as is - E.g. NULL or a number given to a string transformer
+    pub skipped: usize,
+    /// time spent in the transformer - zero when not profiling
+    pub duration: Duration,
+}
+
+impl TransformerCount {
+    /// values going through the transformer per second - None when not profiled
+    pub fn values_per_sec(&self) -> Option<f64> {
+        match self.duration.is_zero() {
+            true => None,
+            false => Some((self.transformed + self.skipped) as f64 / self.duration.as_secs_f64()),
+        }
+    }
+}
+
+/// TransformerMetrics counts the values going through each transformer - it is shared between the threads
+#[derive(Debug, Default)]
+pub struct TransformerMetrics {
+    transformers: Mutex<Vec<(String, String, Arc<Counters>)>>,
+    profiling: bool,
+}
+
+impl TransformerMetrics {
+    /// measure the time spent in each transformer - set before wrapping the transformers
+    pub fn set_profiling(&mut self, enable: bool) {
+        self.profiling = enable;
+    }
+
+    fn register(&self, transformer: &dyn Transformer) -> Arc<Counters> {
+        let counters = Arc::new(Counters::default());
+
+        self.transformers.lock().unwrap().push((
+            transformer.database_and_table_and_column_name(),
+            transformer.id().to_string(),
+            counters.clone(),
+        ));
+
+        counters
+    }
+
+    /// the counts in the order of the transformers
+    pub fn counts(&self) -> Vec<TransformerCount> {
+        self.transformers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, id, counters)| TransformerCount {
+                name: name.clone(),
+                id: id.clone(),
+                transformed: counters.transformed.load(Ordering::Relaxed),
+                skipped: counters.skipped.load(Ordering::Relaxed),
+                duration: Duration::from_nanos(counters.nanos.load(Ordering::Relaxed)),
+            })
+            .collect()
+    }
+
+    /// the counts from the slowest transformer to the fastest one
+    pub fn profile(&self) -> Vec<TransformerCount> {
+        let mut counts = self.counts();
+        counts.sort_by(|a, b| b.duration.cmp(&a.duration));
+        counts
+    }
+}
+
+/// TableRows records the rows dumped for each table during a backup - the tables are only found in the PostgreSQL dumps
+#[derive(Debug, Default)]
+pub struct TableRows {
+    rows: Mutex<BTreeMap<String, usize>>,
+}
+
+impl TableRows {
+    pub fn set(&self, rows: BTreeMap<String, usize>) {
+        *self.rows.lock().unwrap() = rows;
+    }
+
+    /// the rows of each table by table name - E.g. `public.users`
+    pub fn rows(&self) -> BTreeMap<String, usize> {
+        self.rows.lock().unwrap().clone()
+    }
+
+    /// the `table_names` without any row - an empty or a missing table, E.g. a transformer configured on the wrong table
+    pub fn empty_tables<'a, I>(&self, table_names: I) -> Vec<String>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let rows = self.rows.lock().unwrap();
+
+        let mut empty_tables = table_names
+            .into_iter()
+            .filter(|table_name| rows.get(*table_name).copied().unwrap_or_default() == 0)
+            .map(|table_name| table_name.to_string())
+            .collect::<Vec<_>>();
+
+        empty_tables.sort();
+        empty_tables.dedup();
+        empty_tables
+    }
+}
+
+/// Transformer wrapper counting the values replaced and kept by the wrapped *Transformer*
+pub struct CountedTransformer {
+    transformer: Box<dyn Transformer>,
+    counters: Arc<Counters>,
+    profiling: bool,
+}
+
+impl CountedTransformer {
+    pub fn new(transformer: Box<dyn Transformer>, metrics: &TransformerMetrics) -> Self {
+        let counters = metrics.register(transformer.as_ref());
+
+        CountedTransformer {
+            transformer,
+            counters,
+            profiling: metrics.profiling,
+        }
+    }
+
+    /// run `f` and record its time when profiling
+    fn measure<F: FnOnce() -> Column>(&self, f: F) -> Column {
+        if !self.profiling {
+            return f();
+        }
+
+        let start = Instant::now();
+        let column = f();
+        let _ = self
+            .counters
+            .nanos
+            .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        column
+    }
+
+    fn count(&self, original_column: &Column, column: &Column) {
+        let counter = if original_column.is_null() || same_value(original_column, column) {
+            &self.counters.skipped
+        } else {
+            &self.counters.transformed
+        };
+
+        let _ = counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn same_value(a: &Column, b: &Column) -> bool {
+    match (a, b) {
+        (Column::NumberValue(_, a), Column::NumberValue(_, b)) => a == b,
+        (Column::FloatNumberValue(_, a), Column::FloatNumberValue(_, b)) => a == b,
+        (Column::StringValue(_, a), Column::StringValue(_, b)) => a == b,
+        (Column::CharValue(_, a), Column::CharValue(_, b)) => a == b,
+        (Column::BooleanValue(_, a), Column::BooleanValue(_, b)) => a == b,
+        (Column::None(_), Column::None(_)) => true,
+        _ => false,
+    }
+}
+
+impl Transformer for CountedTransformer {
+    fn id(&self) -> &str {
+        self.transformer.id()
+    }
+
+    fn description(&self) -> &str {
+        self.transformer.description()
+    }
+
+    fn database_name(&self) -> &str {
+        self.transformer.database_name()
+    }
+
+    fn table_name(&self) -> &str {
+        self.transformer.table_name()
+    }
+
+    fn column_name(&self) -> &str {
+        self.transformer.column_name()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        self.transformer.expected_type()
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        self.transformer.option_schema()
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        let transformed_column = self.measure(|| self.transformer.transform(column.clone()));
+        self.count(&column, &transformed_column);
+        transformed_column
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        let transformed_column =
+            self.measure(|| self.transformer.transform_with_row(column.clone(), row));
+        self.count(&column, &transformed_column);
+        transformed_column
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use crate::metrics::{CountedTransformer, TableRows, TransformerCount, TransformerMetrics};
+    use crate::source::postgres::read_and_transform;
+    use crate::source::SourceOptions;
+    use crate::transformer::email::EmailTransformer;
+    use crate::transformer::random::RandomTransformer;
+    use crate::transformer::Transformer;
+
+    #[test]
+    fn values_are_counted_per_transformer() {
+        let metrics = TransformerMetrics::default();
+
+        let transformers: Vec<Box<dyn Transformer>> = vec![
+            Box::new(CountedTransformer::new(
+                Box::new(RandomTransformer::new("public", "users", "name")),
+                &metrics,
+            )),
+            // misconfigured - there is no `mail` column
+            Box::new(CountedTransformer::new(
+                Box::new(EmailTransformer::new("public", "users", "mail")),
+                &metrics,
+            )),
+        ];
+        let skip_config = vec![];
+
+        let dump = "INSERT INTO public.users (id, name) VALUES (1, 'John');
+INSERT INTO public.users (id, name) VALUES (2, NULL);
+INSERT INTO public.users (id, name) VALUES (3, 'Jane');
+INSERT INTO public.orders (id, name) VALUES (1, 'order');
+";
+
+        read_and_transform(
+            BufReader::new(dump.as_bytes()),
+            SourceOptions {
+                transformers: &transformers,
+                skip_config: &skip_config,
+            },
+            |_, _| {},
+        );
+
+        assert_eq!(
+            metrics.counts(),
+            vec![
+                TransformerCount {
+                    name: "public.users.name".to_string(),
+                    id: "random".to_string(),
+                    transformed: 2,
+                    skipped: 1,
+                    duration: Duration::ZERO,
+                },
+                TransformerCount {
+                    name: "public.users.mail".to_string(),
+                    id: "email".to_string(),
+                    transformed: 0,
+                    skipped: 0,
+                    duration: Duration::ZERO,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn transformers_are_timed_when_profiling() {
+        let mut metrics = TransformerMetrics::default();
+        metrics.set_profiling(true);
+
+        let transformers: Vec<Box<dyn Transformer>> = vec![
+            Box::new(CountedTransformer::new(
+                Box::new(RandomTransformer::new("public", "users", "name")),
+                &metrics,
+            )),
+            Box::new(CountedTransformer::new(
+                Box::new(EmailTransformer::new("public", "users", "mail")),
+                &metrics,
+            )),
+        ];
+        let skip_config = vec![];
+
+        let dump = (0..100)
+            .map(|i| {
+                format!(
+                    "INSERT INTO public.users (id, name) VALUES ({}, 'John');\n",
+                    i
+                )
+            })
+            .collect::<String>();
+
+        read_and_transform(
+            BufReader::new(dump.as_bytes()),
+            SourceOptions {
+                transformers: &transformers,
+                skip_config: &skip_config,
+            },
+            |_, _| {},
+        );
+
+        let profile = metrics.profile();
+        // the slowest first - the misconfigured transformer never runs
+        assert_eq!(profile[0].name, "public.users.name");
+        assert!(profile[0].duration > Duration::ZERO);
+        assert!(profile[0].values_per_sec().unwrap() > 0.0);
+        assert_eq!(profile[1].name, "public.users.mail");
+        assert_eq!(profile[1].duration, Duration::ZERO);
+        assert_eq!(profile[1].values_per_sec(), None);
+    }
+
+    #[test]
+    fn tables_without_rows_are_reported() {
+        let table_rows = TableRows::default();
+        table_rows.set(BTreeMap::from([
+            ("public.users".to_string(), 3),
+            ("public.orders".to_string(), 0),
+        ]));
+
+        assert_eq!(
+            table_rows.empty_tables([
+                "public.users",
+                "public.orders",
+                // missing from the dump
+                "public.user",
+                "public.orders",
+            ]),
+            vec!["public.orders".to_string(), "public.user".to_string()]
+        );
+        assert!(table_rows.empty_tables(["public.users"]).is_empty());
+    }
+}
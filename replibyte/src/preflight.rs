@@ -0,0 +1,260 @@
+use std::io::{Error, ErrorKind};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::bridge::Bridge;
+use crate::config::ConnectionUri;
+use crate::utils::binary_exists;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// side of the database checked by the preflight
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PreflightTarget {
+    Source,
+    Destination,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PreflightError {
+    MissingBinary { binary: String },
+    SourceUnreachable { address: String, reason: String },
+    DestinationUnreachable { address: String, reason: String },
+    BridgeUnavailable { reason: String },
+}
+
+impl From<PreflightError> for Error {
+    fn from(err: PreflightError) -> Self {
+        match err {
+            PreflightError::MissingBinary { binary } => Error::new(
+                ErrorKind::Other,
+                format!(
+                    "preflight check failed: cannot find '{}' binary in path",
+                    binary
+                ),
+            ),
+            PreflightError::SourceUnreachable { address, reason } => Error::new(
+                ErrorKind::Other,
+                format!(
+                    "preflight check failed: source '{}' is unreachable: {}",
+                    address, reason
+                ),
+            ),
+            PreflightError::DestinationUnreachable { address, reason } => Error::new(
+                ErrorKind::Other,
+                format!(
+                    "preflight check failed: destination '{}' is unreachable: {}",
+                    address, reason
+                ),
+            ),
+            PreflightError::BridgeUnavailable { reason } => Error::new(
+                ErrorKind::Other,
+                format!(
+                    "preflight check failed: bridge is not accessible: {}",
+                    reason
+                ),
+            ),
+        }
+    }
+}
+
+/// fast checks run before starting a long backup or restore:
+/// the bridge is readable (and writable for a backup), the required binaries are present and the database is reachable.
+/// `connection_uri` is None when the database is not used - E.g. the dump is read from stdin.
+pub fn preflight<B: Bridge>(
+    bridge: &mut B,
+    target: PreflightTarget,
+    connection_uri: Option<&ConnectionUri>,
+) -> Result<(), PreflightError> {
+    bridge
+        .init()
+        .and_then(|_| match target {
+            PreflightTarget::Source => bridge.check_write_access(),
+            // a restore only reads the bridge
+            PreflightTarget::Destination => bridge.index_file().map(|_| ()),
+        })
+        .map_err(|err| PreflightError::BridgeUnavailable {
+            reason: err.to_string(),
+        })?;
+
+    let connection_uri = match connection_uri {
+        Some(connection_uri) => connection_uri,
+        None => return Ok(()),
+    };
+
+    check_binaries(required_binaries(target, connection_uri).as_slice())?;
+
+    let (host, port) = match connection_uri {
+        ConnectionUri::Postgres(host, port, _, _, _) => (host, *port),
+        ConnectionUri::Mysql(host, port, _, _, _) => (host, *port),
+        // the hosts of a SRV connection string are only known from the DNS
+        ConnectionUri::MongoDB(_, _, _, _, _, _, Some(connection_string))
+            if connection_string.to_lowercase().starts_with("mongodb+srv") =>
+        {
+            return Ok(())
+        }
+        ConnectionUri::MongoDB(host, port, _, _, _, _, _) => (host, *port),
+    };
+
+    check_reachable(host.as_str(), port).map_err(|err| {
+        let address = format!("{}:{}", host, port);
+        let reason = err.to_string();
+
+        match target {
+            PreflightTarget::Source => PreflightError::SourceUnreachable { address, reason },
+            PreflightTarget::Destination => {
+                PreflightError::DestinationUnreachable { address, reason }
+            }
+        }
+    })
+}
+
+/// binaries used by the *Source* and *Destination* connectors
+fn required_binaries(target: PreflightTarget, connection_uri: &ConnectionUri) -> Vec<&'static str> {
+    match (target, connection_uri) {
+        (PreflightTarget::Source, ConnectionUri::Postgres(..)) => vec!["pg_dumpall"],
+        (PreflightTarget::Destination, ConnectionUri::Postgres(..)) => vec!["psql"],
+        (PreflightTarget::Source, ConnectionUri::MongoDB(..)) => vec!["mongodump"],
+        (PreflightTarget::Destination, ConnectionUri::MongoDB(..)) => vec!["mongorestore"],
+        (_, ConnectionUri::Mysql(..)) => vec![],
+    }
+}
+
+fn check_binaries(binaries: &[&str]) -> Result<(), PreflightError> {
+    for binary in binaries {
+        binary_exists(binary).map_err(|_| PreflightError::MissingBinary {
+            binary: binary.to_string(),
+        })?;
+    }
+
+    Ok(())
+}
+
+/// open (and close) a TCP connection to the database
+fn check_reachable(host: &str, port: u16) -> Result<(), Error> {
+    let addresses = (host, port).to_socket_addrs()?.collect::<Vec<_>>();
+
+    let mut last_err = Error::new(ErrorKind::Other, format!("can't resolve host '{}'", host));
+
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(_) => return Ok(()),
+            Err(err) => last_err = err,
+        }
+    }
+
+    Err(last_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use crate::bridge::in_memory::InMemoryBridge;
+    use crate::config::ConnectionUri;
+    use crate::preflight::{
+        check_binaries, check_reachable, preflight, required_binaries, PreflightError,
+        PreflightTarget,
+    };
+
+    fn connection_uri(port: u16) -> ConnectionUri {
+        ConnectionUri::Mysql(
+            "localhost".to_string(),
+            port,
+            "root".to_string(),
+            "password".to_string(),
+            "root".to_string(),
+        )
+    }
+
+    /// return a local port nobody listens on
+    fn closed_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[test]
+    fn preflight_succeeds() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut bridge = InMemoryBridge::default();
+        let connection_uri = connection_uri(port);
+
+        assert!(preflight(&mut bridge, PreflightTarget::Source, None).is_ok());
+        assert!(preflight(&mut bridge, PreflightTarget::Source, Some(&connection_uri)).is_ok());
+    }
+
+    #[test]
+    fn preflight_fails_when_bridge_is_not_writable() {
+        let mut bridge = InMemoryBridge {
+            read_only: true,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            preflight(&mut bridge, PreflightTarget::Source, None),
+            Err(PreflightError::BridgeUnavailable { .. })
+        ));
+    }
+
+    #[test]
+    fn restore_preflight_only_reads_the_bridge() {
+        let mut bridge = InMemoryBridge {
+            read_only: true,
+            ..Default::default()
+        };
+
+        assert!(preflight(&mut bridge, PreflightTarget::Destination, None).is_ok());
+    }
+
+    #[test]
+    fn preflight_fails_when_database_is_unreachable() {
+        let mut bridge = InMemoryBridge::default();
+        let connection_uri = connection_uri(closed_port());
+
+        assert!(check_reachable("localhost", closed_port()).is_err());
+
+        assert!(matches!(
+            preflight(&mut bridge, PreflightTarget::Source, Some(&connection_uri)),
+            Err(PreflightError::SourceUnreachable { .. })
+        ));
+
+        assert!(matches!(
+            preflight(
+                &mut bridge,
+                PreflightTarget::Destination,
+                Some(&connection_uri)
+            ),
+            Err(PreflightError::DestinationUnreachable { .. })
+        ));
+    }
+
+    #[test]
+    fn preflight_fails_when_binary_is_missing() {
+        assert!(check_binaries(&[]).is_ok());
+        assert_eq!(
+            check_binaries(&["replibyte-missing-binary"]),
+            Err(PreflightError::MissingBinary {
+                binary: "replibyte-missing-binary".to_string()
+            })
+        );
+
+        let postgres_uri = ConnectionUri::Postgres(
+            "localhost".to_string(),
+            5432,
+            "root".to_string(),
+            "password".to_string(),
+            "root".to_string(),
+        );
+
+        assert_eq!(
+            required_binaries(PreflightTarget::Source, &postgres_uri),
+            vec!["pg_dumpall"]
+        );
+        assert_eq!(
+            required_binaries(PreflightTarget::Destination, &postgres_uri),
+            vec!["psql"]
+        );
+    }
+}
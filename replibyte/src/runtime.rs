@@ -1,18 +1,361 @@
 use lazy_static::lazy_static;
 use std::future::Future;
-use std::sync::Mutex;
-use tokio::runtime::{Builder, Runtime};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::runtime::{Builder, Runtime as TokioRuntime};
+
+use crate::tasks::{MaxBytes, TransferredBytes};
 
 lazy_static! {
-    static ref TOKIO_RUNTIME: Mutex<Runtime> = Mutex::new({
+    static ref TOKIO_RUNTIME: Mutex<TokioRuntime> = Mutex::new({
         Builder::new_current_thread()
             .thread_name("tokio-blocking")
             .enable_all()
             .build()
             .unwrap()
     });
+    static ref WORKER_POOL: RwLock<Arc<WorkerPool>> =
+        RwLock::new(Arc::new(WorkerPool::new(default_max_workers())));
 }
 
 pub fn block_on<F: Future>(future: F) -> F::Output {
     TOKIO_RUNTIME.lock().unwrap().block_on(future)
 }
+
+/// Runtime owns the execution concerns of a command: the worker pool size, the progress thread
+/// and the shutdown signal. Dropping it shuts it down.
+pub struct Runtime {
+    progress_tx: Option<SyncSender<(TransferredBytes, MaxBytes)>>,
+    progress_thread: Option<JoinHandle<()>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Runtime {
+    /// `max_workers` caps the workers running at the same time - default to the number of CPUs
+    pub fn new(max_workers: Option<usize>) -> Self {
+        if let Some(max_workers) = max_workers {
+            set_max_workers(max_workers);
+        }
+
+        Runtime {
+            progress_tx: None,
+            progress_thread: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// start the progress thread - `display` is called with the last progress every `interval`
+    /// (E.g. to animate the spinner) until the runtime is shut down, whatever the rate of the updates
+    pub fn start_progress<D>(&mut self, interval: Duration, mut display: D)
+    where
+        D: FnMut(TransferredBytes, MaxBytes) + Send + 'static,
+    {
+        if self.progress_thread.is_some() {
+            return;
+        }
+
+        let (tx, rx) = mpsc::sync_channel::<(TransferredBytes, MaxBytes)>(1000);
+        let shutdown = self.shutdown.clone();
+        // a zero interval would spin the progress thread
+        let interval = interval.max(Duration::from_millis(1));
+
+        self.progress_tx = Some(tx);
+        self.progress_thread = Some(thread::spawn(move || {
+            let mut progress = (0, 0);
+            let mut displayed: Option<(Instant, (TransferredBytes, MaxBytes))> = None;
+
+            while !shutdown.load(Ordering::SeqCst) {
+                match rx.recv_timeout(interval) {
+                    Ok(new_progress) => progress = new_progress,
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                // the updates received in between are skipped - only the last one is displayed
+                if matches!(displayed, Some((displayed_at, _)) if displayed_at.elapsed() < interval)
+                {
+                    continue;
+                }
+
+                display(progress.0, progress.1);
+                displayed = Some((Instant::now(), progress));
+            }
+
+            // the last update must not be lost - E.g. the progress bar stops at 100%
+            while let Ok(new_progress) = rx.try_recv() {
+                progress = new_progress;
+            }
+
+            if displayed.map(|(_, displayed_progress)| displayed_progress) != Some(progress) {
+                display(progress.0, progress.1);
+            }
+        }));
+    }
+
+    /// callback to give to the tasks - it does nothing when the progress is not started
+    pub fn progress_callback(&self) -> impl FnMut(TransferredBytes, MaxBytes) {
+        let progress_tx = self.progress_tx.clone();
+
+        move |transferred_bytes: TransferredBytes, max_bytes: MaxBytes| {
+            if let Some(progress_tx) = &progress_tx {
+                // never block the pipeline - the next update will be displayed
+                let _ = progress_tx.try_send((transferred_bytes, max_bytes));
+            }
+        }
+    }
+
+    /// signal the shutdown on Ctrl-C instead of killing the process - the caller stops at the
+    /// next `is_shutdown` check, the tasks given the `shutdown_signal` abort what they are doing
+    pub fn shutdown_on_interrupt(&self) {
+        let shutdown = self.shutdown.clone();
+
+        // the shared tokio runtime only runs while blocked on - the signal gets its own
+        let _ = thread::spawn(move || {
+            let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+            if runtime.block_on(tokio::signal::ctrl_c()).is_ok() {
+                eprintln!("interrupted - aborting the current run");
+                shutdown.store(true, Ordering::SeqCst);
+            }
+        });
+    }
+
+    /// the flag set on shutdown - E.g. to abort a task on Ctrl-C
+    pub fn shutdown_signal(&self) -> Arc<AtomicBool> {
+        self.shutdown.clone()
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    /// signal the shutdown and wait for the progress thread to stop
+    pub fn shutdown(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.progress_tx = None;
+
+        if let Some(progress_thread) = self.progress_thread.take() {
+            let _ = progress_thread.join();
+        }
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// one worker per CPU by default
+fn default_max_workers() -> usize {
+    thread::available_parallelism()
+        .map(|workers| workers.get())
+        .unwrap_or(1)
+}
+
+/// WorkerPool caps the number of workers (E.g. upload, parallel restore) running at the same time
+/// across the whole pipeline - a worker waits for a free slot before running.
+pub struct WorkerPool {
+    max_workers: usize,
+    running_workers: Mutex<usize>,
+    worker_done: Condvar,
+}
+
+impl WorkerPool {
+    pub fn new(max_workers: usize) -> Self {
+        WorkerPool {
+            max_workers: max_workers.max(1),
+            running_workers: Mutex::new(0),
+            worker_done: Condvar::new(),
+        }
+    }
+
+    pub fn max_workers(&self) -> usize {
+        self.max_workers
+    }
+
+    /// run `work` as soon as less than `max_workers` workers are running
+    pub fn run<T, F: FnOnce() -> T>(&self, work: F) -> T {
+        {
+            let mut running_workers = self.running_workers.lock().unwrap();
+            while *running_workers >= self.max_workers {
+                running_workers = self.worker_done.wait(running_workers).unwrap();
+            }
+
+            *running_workers += 1;
+        }
+
+        // release the slot even if `work` panics
+        let _slot = WorkerSlot { pool: self };
+
+        work()
+    }
+}
+
+struct WorkerSlot<'a> {
+    pool: &'a WorkerPool,
+}
+
+impl<'a> Drop for WorkerSlot<'a> {
+    fn drop(&mut self) {
+        let mut running_workers = self.pool.running_workers.lock().unwrap();
+        *running_workers -= 1;
+        self.pool.worker_done.notify_one();
+    }
+}
+
+/// cap the number of workers running at the same time - `--threads` or `workers` in the configuration
+pub fn set_max_workers(max_workers: usize) {
+    *WORKER_POOL.write().unwrap() = Arc::new(WorkerPool::new(max_workers));
+}
+
+/// the worker pool shared by all the tasks
+pub fn worker_pool() -> Arc<WorkerPool> {
+    WORKER_POOL.read().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::runtime::{worker_pool, Runtime, WorkerPool};
+
+    #[test]
+    fn runtime_lifecycle() {
+        let mut runtime = Runtime::new(None);
+        assert!(!runtime.is_shutdown());
+
+        let progress = Arc::new(Mutex::new(vec![]));
+        let displayed_progress = progress.clone();
+        runtime.start_progress(
+            Duration::from_millis(100),
+            move |transferred_bytes, max_bytes| {
+                displayed_progress
+                    .lock()
+                    .unwrap()
+                    .push((transferred_bytes, max_bytes))
+            },
+        );
+
+        let mut progress_callback = runtime.progress_callback();
+        progress_callback(10, 100);
+        progress_callback(100, 100);
+
+        let start = Instant::now();
+        while !progress.lock().unwrap().contains(&(100, 100))
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        runtime.shutdown();
+        assert!(runtime.is_shutdown());
+        assert!(progress.lock().unwrap().contains(&(100, 100)));
+
+        // nothing is displayed after the shutdown
+        let displayed = progress.lock().unwrap().len();
+        progress_callback(200, 200);
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(progress.lock().unwrap().len(), displayed);
+    }
+
+    #[test]
+    fn progress_is_displayed_at_most_once_per_interval() {
+        let mut runtime = Runtime::new(None);
+        let interval = Duration::from_millis(50);
+
+        let displays = Arc::new(Mutex::new(vec![]));
+        let displayed_at = displays.clone();
+        runtime.start_progress(interval, move |transferred_bytes, _| {
+            displayed_at
+                .lock()
+                .unwrap()
+                .push((Instant::now(), transferred_bytes))
+        });
+
+        // flood the progress thread with updates for a while
+        let mut progress_callback = runtime.progress_callback();
+        let start = Instant::now();
+        let mut transferred_bytes = 0;
+        while start.elapsed() < Duration::from_millis(500) {
+            transferred_bytes += 1;
+            progress_callback(transferred_bytes, 0);
+            thread::sleep(Duration::from_micros(50));
+        }
+
+        runtime.shutdown();
+        let elapsed = start.elapsed();
+
+        let displays = displays.lock().unwrap();
+        assert!(displays.len() >= 2);
+        // one display per interval - plus the last update displayed on shutdown
+        assert!(displays.len() as u128 <= elapsed.as_millis() / 50 + 2);
+
+        for window in displays[..displays.len() - 1].windows(2) {
+            assert!(window[1].0.duration_since(window[0].0) >= interval);
+        }
+
+        // the last update is displayed
+        assert_eq!(displays.last().unwrap().1, transferred_bytes);
+    }
+
+    #[test]
+    fn runtime_without_progress() {
+        let mut runtime = Runtime::new(None);
+
+        // the progress callback does nothing
+        let mut progress_callback = runtime.progress_callback();
+        progress_callback(10, 100);
+
+        runtime.shutdown();
+        assert!(runtime.is_shutdown());
+    }
+
+    #[test]
+    fn runtime_sets_the_worker_pool_size() {
+        let _runtime = Runtime::new(Some(3));
+        assert_eq!(worker_pool().max_workers(), 3);
+    }
+
+    #[test]
+    fn worker_pool_caps_concurrent_workers() {
+        let pool = Arc::new(WorkerPool::new(2));
+        let running_workers = Arc::new(AtomicUsize::new(0));
+        let max_running_workers = Arc::new(AtomicUsize::new(0));
+
+        let join_handles = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                let running_workers = running_workers.clone();
+                let max_running_workers = max_running_workers.clone();
+
+                thread::spawn(move || {
+                    pool.run(|| {
+                        let running = running_workers.fetch_add(1, Ordering::SeqCst) + 1;
+                        let _ = max_running_workers.fetch_max(running, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        let _ = running_workers.fetch_sub(1, Ordering::SeqCst);
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for join_handle in join_handles {
+            join_handle.join().unwrap();
+        }
+
+        assert_eq!(max_running_workers.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn worker_pool_runs_at_least_one_worker() {
+        let pool = WorkerPool::new(0);
+        assert_eq!(pool.max_workers(), 1);
+        assert_eq!(pool.run(|| 42), 42);
+    }
+}
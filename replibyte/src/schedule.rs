@@ -0,0 +1,147 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// the shutdown is checked at least every SHUTDOWN_TICK while waiting for the next run
+const SHUTDOWN_TICK: Duration = Duration::from_secs(1);
+
+/// Clock abstracts the time - to test the scheduling without waiting
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// call `run` with the run number every `interval` until `is_shutdown` returns true.
+/// A failing run is logged and does not stop the next runs - `run` is expected to stop by itself on shutdown.
+pub fn run_every<C, S, F, E>(clock: &C, interval: Duration, is_shutdown: S, mut run: F)
+where
+    C: Clock,
+    S: Fn() -> bool,
+    F: FnMut(usize) -> Result<(), E>,
+    E: std::fmt::Display,
+{
+    let mut run_number = 1;
+
+    while !is_shutdown() {
+        let started_at = clock.now();
+
+        if let Err(err) = run(run_number) {
+            eprintln!("run #{} failed: {}", run_number, err);
+        }
+
+        run_number += 1;
+
+        let next_run_at = started_at + interval;
+        while !is_shutdown() {
+            let now = clock.now();
+            if now >= next_run_at {
+                break;
+            }
+
+            clock.sleep((next_run_at - now).min(SHUTDOWN_TICK));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::io::{Error, ErrorKind};
+    use std::time::{Duration, Instant};
+
+    use crate::schedule::{run_every, Clock};
+
+    struct FakeClock {
+        start: Instant,
+        elapsed: Cell<Duration>,
+    }
+
+    impl FakeClock {
+        fn new() -> Self {
+            FakeClock {
+                start: Instant::now(),
+                elapsed: Cell::new(Duration::ZERO),
+            }
+        }
+
+        fn elapsed(&self) -> Duration {
+            self.elapsed.get()
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.start + self.elapsed.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.elapsed.set(self.elapsed.get() + duration);
+        }
+    }
+
+    #[test]
+    fn run_on_interval_until_shutdown() {
+        let clock = FakeClock::new();
+        let interval = Duration::from_secs(5);
+        let runs = RefCell::new(vec![]);
+
+        run_every(
+            &clock,
+            interval,
+            || runs.borrow().len() == 2,
+            |run_number| -> Result<(), Error> {
+                runs.borrow_mut().push((run_number, clock.elapsed()));
+                Ok(())
+            },
+        );
+
+        assert_eq!(runs.into_inner(), vec![(1, Duration::ZERO), (2, interval)]);
+    }
+
+    #[test]
+    fn keep_running_after_a_failed_run() {
+        let clock = FakeClock::new();
+        let mut runs = 0;
+
+        run_every(
+            &clock,
+            Duration::from_millis(1500),
+            || clock.elapsed() >= Duration::from_secs(3),
+            |_| {
+                runs += 1;
+                Err(Error::new(ErrorKind::Other, "backup failed"))
+            },
+        );
+
+        assert_eq!(runs, 2);
+    }
+
+    #[test]
+    fn no_run_when_already_shutdown() {
+        let clock = FakeClock::new();
+        let mut runs = 0;
+
+        run_every(
+            &clock,
+            Duration::from_secs(1),
+            || true,
+            |_| -> Result<(), Error> {
+                runs += 1;
+                Ok(())
+            },
+        );
+
+        assert_eq!(runs, 0);
+    }
+}
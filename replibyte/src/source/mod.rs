@@ -1,6 +1,7 @@
-use std::io::Error;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
 
-use crate::config::SkipConfig;
+use crate::config::{SkipConfig, SourceConfig};
 use crate::connector::Connector;
 use crate::transformer::Transformer;
 use crate::types::{OriginalQuery, Query};
@@ -16,9 +17,117 @@ pub trait Source: Connector {
         options: SourceOptions,
         query_callback: F,
     ) -> Result<(), Error>;
+    /// the rows of these tables (E.g. `public.users`) are not dumped, their definition is - by default they are dumped
+    /// and dropped by the caller
+    fn set_excluded_table_data(&mut self, _tables: Vec<String>) {}
 }
 
+#[derive(Clone, Copy)]
 pub struct SourceOptions<'a> {
     pub transformers: &'a Vec<Box<dyn Transformer>>,
     pub skip_config: &'a Vec<SkipConfig>,
 }
+
+/// object safe *Source* - E.g. to build the source of a registered engine at runtime
+pub trait DynSource: Connector {
+    fn read_dyn(
+        &self,
+        options: SourceOptions,
+        query_callback: &mut dyn FnMut(OriginalQuery, Query),
+    ) -> Result<(), Error>;
+    fn set_excluded_table_data_dyn(&mut self, tables: Vec<String>);
+}
+
+impl<S: Source> DynSource for S {
+    fn read_dyn(
+        &self,
+        options: SourceOptions,
+        query_callback: &mut dyn FnMut(OriginalQuery, Query),
+    ) -> Result<(), Error> {
+        self.read(options, query_callback)
+    }
+
+    fn set_excluded_table_data_dyn(&mut self, tables: Vec<String>) {
+        self.set_excluded_table_data(tables)
+    }
+}
+
+impl Connector for Box<dyn DynSource> {
+    fn init(&mut self) -> Result<(), Error> {
+        (**self).init()
+    }
+}
+
+impl Source for Box<dyn DynSource> {
+    fn read<F: FnMut(OriginalQuery, Query)>(
+        &self,
+        options: SourceOptions,
+        mut query_callback: F,
+    ) -> Result<(), Error> {
+        (**self).read_dyn(options, &mut query_callback)
+    }
+
+    fn set_excluded_table_data(&mut self, tables: Vec<String>) {
+        (**self).set_excluded_table_data_dyn(tables)
+    }
+}
+
+/// build the source of an engine from the `source` configuration
+pub type SourceFactory = Box<dyn Fn(&SourceConfig) -> Result<Box<dyn DynSource>, Error>>;
+
+/// SourceRegistry holds the sources of the engines not built in RepliByte (E.g. ClickHouse) -
+/// selected by the `type` of the `source` configuration
+#[derive(Default)]
+pub struct SourceRegistry {
+    factories: HashMap<String, SourceFactory>,
+}
+
+impl SourceRegistry {
+    /// register the source of `source_type` - E.g. `clickhouse`. The type is case insensitive
+    pub fn register<F>(&mut self, source_type: &str, factory: F)
+    where
+        F: Fn(&SourceConfig) -> Result<Box<dyn DynSource>, Error> + 'static,
+    {
+        let _ = self
+            .factories
+            .insert(source_type.to_lowercase(), Box::new(factory));
+    }
+
+    pub fn source(
+        &self,
+        source_type: &str,
+        config: &SourceConfig,
+    ) -> Result<Box<dyn DynSource>, Error> {
+        match self.factories.get(source_type.to_lowercase().as_str()) {
+            Some(factory) => factory(config),
+            None => Err(Error::new(
+                ErrorKind::Other,
+                format!("source type '{}' is not registered", source_type),
+            )),
+        }
+    }
+}
+
+/// check the extra dump arguments do not change where or how the dump is written -
+/// RepliByte parses the dump from the standard output
+fn check_dump_args(dump_args: &[String], forbidden_args: &[&str]) -> Result<(), Error> {
+    for dump_arg in dump_args {
+        let name = dump_arg.split('=').next().unwrap_or_default();
+        // the value of a short option can be attached - E.g. `-fdump.sql`
+        let is_attached_value = |forbidden_arg: &&str| {
+            !forbidden_arg.starts_with("--") && dump_arg.starts_with(forbidden_arg)
+        };
+
+        if forbidden_args.contains(&name) || forbidden_args.iter().any(is_attached_value) {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "dump argument '{}' is not supported - it changes the dump output",
+                    dump_arg
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
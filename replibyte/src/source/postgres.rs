@@ -1,17 +1,22 @@
-use std::collections::HashMap;
-use std::io::{BufReader, Error, ErrorKind, Read};
-use std::process::{Command, Stdio};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
 
 use dump_parser::postgres::{
-    get_column_names_from_insert_into_query, get_column_values_from_insert_into_query,
-    get_tokens_from_query_str, get_word_value_at_position, match_keyword_at_position, Keyword,
-    Token,
+    get_column_names_from_insert_into_query, get_tokens_from_query_str, get_word_value_at_position,
+    match_keyword_at_position, Keyword, Token,
 };
 use dump_parser::utils::list_queries_from_dump_reader;
+use serde::{Deserialize, Serialize};
 
-use crate::connector::Connector;
-use crate::source::Source;
-use crate::transformer::Transformer;
+use crate::connector::{Connector, Tls};
+use crate::destination::postgres::{
+    psql_connection_command, psql_table_checksums, split_queries, TableChecksum,
+};
+use crate::source::{check_dump_args, Source};
+use crate::transformer::{ColumnTransformers, Transformer};
 use crate::types::{Column, InsertIntoQuery, OriginalQuery, Query};
 use crate::utils::binary_exists;
 
@@ -19,6 +24,73 @@ use super::SourceOptions;
 
 pub const COMMENT_CHARS: &str = "--";
 
+/// `pg_dumpall` arguments writing the dump somewhere else than the standard output
+const FORBIDDEN_DUMP_ARGS: [&str; 2] = ["-f", "--file"];
+
+/// format of the `pg_dump` output - only the directory format can be dumped in parallel
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DumpFormat {
+    Directory,
+}
+
+impl DumpFormat {
+    fn name(&self) -> &str {
+        match self {
+            DumpFormat::Directory => "directory",
+        }
+    }
+}
+
+/// dump the database with `pg_dump --jobs` instead of `pg_dumpall` - E.g. `{format: directory, jobs: 4}`.
+/// The dump is not parsed, it can't be used with transformers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParallelDump {
+    pub format: DumpFormat,
+    /// number of tables dumped at the same time
+    pub jobs: u16,
+}
+
+impl ParallelDump {
+    pub fn check(&self) -> Result<(), Error> {
+        if self.jobs == 0 {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "parallel_dump 'jobs' must be greater than 0",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// columns of the user tables with their type and their key constraint -
+/// E.g. `public|users|id|integer|PRIMARY KEY` or `public|users|email|character varying|`
+const COLUMN_TYPES_QUERY: &str = "\
+    SELECT c.table_schema, c.table_name, c.column_name, c.data_type, \
+    (SELECT min(tc.constraint_type) \
+    FROM information_schema.key_column_usage k \
+    JOIN information_schema.table_constraints tc \
+    ON tc.constraint_schema = k.constraint_schema AND tc.constraint_name = k.constraint_name \
+    WHERE k.table_schema = c.table_schema AND k.table_name = c.table_name AND k.column_name = c.column_name \
+    AND tc.constraint_type IN ('PRIMARY KEY', 'UNIQUE')) \
+    FROM information_schema.columns c \
+    WHERE c.table_schema NOT IN ('pg_catalog', 'information_schema') \
+    ORDER BY c.table_schema, c.table_name, c.ordinal_position;\
+    ";
+
+/// a column of the database and its type - E.g. `public.users.email` of type `character varying`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableColumn {
+    pub database: String,
+    pub table: String,
+    pub column: String,
+    /// `information_schema.columns.data_type`
+    pub data_type: String,
+    /// `PRIMARY KEY` or `UNIQUE` when the column is part of such a constraint - the primary key first
+    pub key: Option<String>,
+}
+
 enum RowType {
     InsertInto {
         database_name: String,
@@ -41,6 +113,10 @@ pub struct Postgres<'a> {
     database: &'a str,
     username: &'a str,
     password: &'a str,
+    dump_args: Vec<String>,
+    tls: Option<Tls>,
+    parallel_dump: Option<ParallelDump>,
+    excluded_table_data: Vec<String>,
 }
 
 impl<'a> Postgres<'a> {
@@ -57,12 +133,344 @@ impl<'a> Postgres<'a> {
             database,
             username,
             password,
+            dump_args: vec![],
+            tls: None,
+            parallel_dump: None,
+            excluded_table_data: vec![],
+        }
+    }
+
+    pub fn set_tls(&mut self, tls: Option<Tls>) -> Result<(), Error> {
+        if let Some(tls) = &tls {
+            tls.check()?;
+        }
+
+        self.tls = tls;
+        Ok(())
+    }
+
+    /// dump the database with `pg_dump --jobs` - only without transformers and skipped tables
+    pub fn set_parallel_dump(&mut self, parallel_dump: Option<ParallelDump>) -> Result<(), Error> {
+        if let Some(parallel_dump) = &parallel_dump {
+            parallel_dump.check()?;
+        }
+
+        self.parallel_dump = parallel_dump;
+        Ok(())
+    }
+
+    /// extra `pg_dumpall` arguments appended to the generated command (E.g. `--no-privileges`)
+    pub fn set_dump_args(&mut self, dump_args: Vec<String>) -> Result<(), Error> {
+        check_dump_args(&dump_args, &FORBIDDEN_DUMP_ARGS)?;
+        self.dump_args = dump_args;
+        Ok(())
+    }
+
+    /// the columns of the database with their type - E.g. to check the transformers against the column types
+    pub fn column_types(&self) -> Result<Vec<TableColumn>, Error> {
+        let output = self
+            .psql_command()
+            .args(["--no-align", "--tuples-only", "-c", COLUMN_TYPES_QUERY])
+            .stdin(Stdio::null())
+            .output()?;
+
+        if !output.status.success() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "can't list the column types: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            ));
+        }
+
+        Ok(parse_column_types(
+            String::from_utf8_lossy(&output.stdout).as_ref(),
+        ))
+    }
+
+    /// the rows and the checksum of each user table by table name (E.g. `public.users`) - the same checksum as
+    /// `verify-restore`, computed by the database without dumping the rows
+    pub fn table_checksums(&self) -> Result<BTreeMap<String, TableChecksum>, Error> {
+        psql_table_checksums(self.psql_command())
+    }
+
+    fn psql_command(&self) -> Command {
+        psql_connection_command(
+            self.host,
+            self.port,
+            self.database,
+            self.username,
+            self.password,
+            self.tls.as_ref(),
+        )
+    }
+
+    fn dump_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--column-inserts".to_string(), //dump data as INSERT commands with column names
+            "--no-owner".to_string(),       // skip restoration of object ownership
+            "-h".to_string(),
+            self.host.to_string(),
+            "-p".to_string(),
+            self.port.to_string(),
+            //"-d", pg_dumpall does not let picking the database as it is dump every dbs
+            //self.database,
+            "-U".to_string(),
+            self.username.to_string(),
+        ];
+
+        args.extend(self.dump_args.iter().cloned());
+
+        args
+    }
+
+    /// `pg_dumpall` - or `pg_dump` of the database when the rows of some tables are excluded, `pg_dumpall` can't
+    fn dump_command(&self) -> Command {
+        let mut command = match self.excluded_table_data.is_empty() {
+            true => Command::new("pg_dumpall"),
+            false => Command::new("pg_dump"),
+        };
+        command.env("PGPASSWORD", self.password);
+
+        if let Some(tls) = &self.tls {
+            command.envs(tls.postgres_envs());
+        }
+
+        command.args(self.dump_args());
+
+        if !self.excluded_table_data.is_empty() {
+            command
+                .args(["-d", self.database])
+                .args(exclude_table_data_args(&self.excluded_table_data));
+        }
+
+        command
+    }
+
+    /// `pg_dump` writing the dump of the database into `dump_dir`
+    fn parallel_dump_command(&self, parallel_dump: &ParallelDump, dump_dir: &Path) -> Command {
+        let mut command = Command::new("pg_dump");
+        command.env("PGPASSWORD", self.password);
+
+        if let Some(tls) = &self.tls {
+            command.envs(tls.postgres_envs());
+        }
+
+        command
+            .arg(format!("--format={}", parallel_dump.format.name()))
+            .arg(format!("--jobs={}", parallel_dump.jobs))
+            .arg("--file")
+            .arg(dump_dir)
+            .args([
+                "-h",
+                self.host,
+                "-p",
+                self.port.to_string().as_str(),
+                "-d",
+                self.database,
+                "-U",
+                self.username,
+            ])
+            .args(exclude_table_data_args(&self.excluded_table_data))
+            .args(self.dump_args.iter());
+
+        command
+    }
+
+    /// dump the database into a directory then read it back as a plain SQL dump -
+    /// the backup is restored like the `pg_dumpall` ones
+    fn read_parallel_dump<F: FnMut(OriginalQuery, Query)>(
+        &self,
+        parallel_dump: &ParallelDump,
+        options: SourceOptions,
+        query_callback: F,
+    ) -> Result<(), Error> {
+        if !options.transformers.is_empty() || !options.skip_config.is_empty() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "parallel_dump can't be used with transformers or skipped tables - the dump is not parsed",
+            ));
+        }
+
+        let dump_dir =
+            std::env::temp_dir().join(format!("replibyte-dump-{}", rand::random::<u64>()));
+
+        let result = wait_success(
+            self.parallel_dump_command(parallel_dump, dump_dir.as_path())
+                .stdout(Stdio::null())
+                .spawn()?,
+        )
+        .and_then(|_| read_directory_dump(dump_dir.as_path(), query_callback));
+
+        let _ = fs::remove_dir_all(dump_dir.as_path());
+
+        result
+    }
+}
+
+/// `pg_dump --exclude-table-data` of each table (E.g. `public.users`) - quoted to match the names as they are
+fn exclude_table_data_args(tables: &[String]) -> Vec<String> {
+    let quote = |name: &str| format!("\"{}\"", name.replace('"', "\"\""));
+
+    tables
+        .iter()
+        .map(|table| match table.split_once('.') {
+            Some((schema, table)) => {
+                format!("--exclude-table-data={}.{}", quote(schema), quote(table))
+            }
+            None => format!("--exclude-table-data={}", quote(table)),
+        })
+        .collect()
+}
+
+/// parse the `psql --no-align` output of COLUMN_TYPES_QUERY - one `schema|table|column|type|key` line per column,
+/// the key is empty (NULL) for the other columns
+fn parse_column_types(output: &str) -> Vec<TableColumn> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(5, '|');
+
+            Some(TableColumn {
+                database: fields.next()?.to_string(),
+                table: fields.next()?.to_string(),
+                column: fields.next()?.to_string(),
+                data_type: fields.next()?.to_string(),
+                key: fields
+                    .next()
+                    .filter(|key| !key.is_empty())
+                    .map(|key| key.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// the error tells why the process failed when its stderr is piped - E.g. when the server closes the connection mid-dump
+fn wait_success(mut process: Child) -> Result<(), Error> {
+    let mut stderr = String::new();
+    if let Some(mut process_stderr) = process.stderr.take() {
+        let _ = process_stderr.read_to_string(&mut stderr);
+    }
+
+    let exit_status = process.wait()?;
+
+    if !exit_status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            match stderr.trim() {
+                "" => format!("command error: {:?}", exit_status.to_string()),
+                stderr => format!("command error: {:?} - {}", exit_status.to_string(), stderr),
+            },
+        ));
+    }
+
+    Ok(())
+}
+
+/// convert the directory dump into a plain SQL dump with `pg_restore`
+fn read_directory_dump<F: FnMut(OriginalQuery, Query)>(
+    dump_dir: &Path,
+    query_callback: F,
+) -> Result<(), Error> {
+    let mut process = Command::new("pg_restore")
+        .args(["--no-owner", "--file", "-"])
+        .arg(dump_dir)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = process
+        .stdout
+        .take()
+        .ok_or_else(|| Error::new(ErrorKind::Other, "Could not capture standard output."))?;
+
+    read_plain_dump(BufReader::new(stdout), query_callback)?;
+
+    wait_success(process)
+}
+
+/// read the queries of a plain SQL dump without parsing them - the data lines of a `COPY ... FROM stdin;`
+/// query are kept with it, the backup parts are written to the destination separately
+fn read_plain_dump<R: Read, F: FnMut(OriginalQuery, Query)>(
+    reader: BufReader<R>,
+    mut query_callback: F,
+) -> Result<(), Error> {
+    let mut emit = |mut query: Vec<u8>| {
+        // the line break is added back when the queries are written
+        if query.last() == Some(&b'\n') {
+            let _ = query.pop();
+        }
+
+        query_callback(Query(query.clone()), Query(query))
+    };
+
+    let mut data = vec![];
+    let mut copy_query: Option<Vec<u8>> = None;
+
+    for line in reader.split(b'\n') {
+        let mut line = line?;
+        line.push(b'\n');
+
+        if let Some(mut query) = copy_query.take() {
+            let is_end_of_data = line.as_slice() == b"\\.\n";
+            query.extend(line);
+
+            match is_end_of_data {
+                true => emit(query),
+                false => copy_query = Some(query),
+            }
+
+            continue;
+        }
+
+        data.extend(line);
+        let (queries, trailing_data) = split_queries(data.as_slice());
+
+        for query in queries {
+            match is_copy_from_stdin(query) {
+                true => copy_query = Some(query.to_vec()),
+                false => emit(query.to_vec()),
+            }
         }
+
+        data = trailing_data.to_vec();
+    }
+
+    if let Some(query) = copy_query {
+        emit(query);
+    }
+
+    if !data.iter().all(u8::is_ascii_whitespace) {
+        emit(data);
+    }
+
+    Ok(())
+}
+
+/// E.g. `COPY public.users (id, name) FROM stdin;` - the rows follow the query until `\.`
+fn is_copy_from_stdin(query: &[u8]) -> bool {
+    let statement_end_idx = query
+        .iter()
+        .rposition(|byte| !byte.is_ascii_whitespace())
+        .map_or(0, |idx| idx + 1);
+
+    match query[..statement_end_idx]
+        .rsplit(|byte| *byte == b'\n')
+        .next()
+    {
+        Some(statement) => statement.starts_with(b"COPY ") && statement.ends_with(b" FROM stdin;"),
+        None => false,
     }
 }
 
 impl<'a> Connector for Postgres<'a> {
     fn init(&mut self) -> Result<(), Error> {
+        if self.parallel_dump.is_some() {
+            let _ = binary_exists("pg_dump")?;
+            let _ = binary_exists("pg_restore")?;
+            return Ok(());
+        }
+
         let _ = binary_exists("pg_dumpall")?;
 
         Ok(())
@@ -75,23 +483,13 @@ impl<'a> Source for Postgres<'a> {
         options: SourceOptions,
         query_callback: F,
     ) -> Result<(), Error> {
-        let s_port = self.port.to_string();
+        if let Some(parallel_dump) = &self.parallel_dump {
+            return self.read_parallel_dump(parallel_dump, options, query_callback);
+        }
 
         // use pg_dumpall instead of pg_dump to get all the users, roles and permissions
-        let mut process = Command::new("pg_dumpall")
-            .env("PGPASSWORD", self.password)
-            .args([
-                "--column-inserts", //dump data as INSERT commands with column names
-                "--no-owner",       // skip restoration of object ownership
-                "-h",
-                self.host,
-                "-p",
-                s_port.as_str(),
-                //"-d", pg_dumpall does not let picking the database as it is dump every dbs
-                //self.database,
-                "-U",
-                self.username,
-            ])
+        let mut process = self
+            .dump_command()
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?;
@@ -105,19 +503,11 @@ impl<'a> Source for Postgres<'a> {
 
         read_and_transform(reader, options, query_callback);
 
-        match process.wait() {
-            Ok(exit_status) => {
-                if !exit_status.success() {
-                    return Err(Error::new(
-                        ErrorKind::Other,
-                        format!("command error: {:?}", exit_status.to_string()),
-                    ));
-                }
-            }
-            Err(err) => return Err(err),
-        }
+        wait_success(process)
+    }
 
-        Ok(())
+    fn set_excluded_table_data(&mut self, tables: Vec<String>) {
+        self.excluded_table_data = tables;
     }
 }
 
@@ -127,16 +517,8 @@ pub fn read_and_transform<R: Read, F: FnMut(OriginalQuery, Query)>(
     options: SourceOptions,
     mut query_callback: F,
 ) {
-    // create a map variable with Transformer by column_name
-    let mut transformer_by_db_and_table_and_column_name: HashMap<String, &Box<dyn Transformer>> =
-        HashMap::with_capacity(options.transformers.len());
-
-    for transformer in options.transformers {
-        let _ = transformer_by_db_and_table_and_column_name.insert(
-            transformer.database_and_table_and_column_name(),
-            transformer,
-        );
-    }
+    // find the Transformer by column name - or by pattern (E.g. `*.*.email`)
+    let mut column_transformers = ColumnTransformers::new(options.transformers);
 
     let mut skip_tables_map: HashMap<String, bool> =
         HashMap::with_capacity(options.skip_config.len());
@@ -144,6 +526,12 @@ pub fn read_and_transform<R: Read, F: FnMut(OriginalQuery, Query)>(
         let _ = skip_tables_map.insert(format!("{}.{}", skip.database, skip.table), true);
     }
 
+    // array columns (E.g. `text[]`) by <database>.<table> - read from the CREATE TABLE queries
+    let mut array_columns_by_table: HashMap<String, HashSet<String>> = HashMap::new();
+
+    // large object being written by the dump - see `LargeObjectQuery`
+    let mut large_object: Option<LargeObject> = None;
+
     match list_queries_from_dump_reader(reader, COMMENT_CHARS, |query| {
         let tokens = get_tokens_from_query_str(query);
 
@@ -161,56 +549,42 @@ pub fn read_and_transform<R: Read, F: FnMut(OriginalQuery, Query)>(
                     // L Paren      -> position X?
                     // R Paren      -> position X?
                     let column_names = get_column_names_from_insert_into_query(&tokens);
-                    let column_values = get_column_values_from_insert_into_query(&tokens);
+                    let column_values = get_value_tokens_from_insert_into_query(&tokens);
 
                     let mut original_columns = vec![];
-                    let mut columns = vec![];
 
                     for (i, column_name) in column_names.iter().enumerate() {
-                        let value_token = column_values.get(i).unwrap();
-
-                        let column = match value_token {
-                            Token::Number(column_value, _) => {
-                                if column_value.contains(".") {
-                                    Column::FloatNumberValue(
-                                        column_name.to_string(),
-                                        column_value.parse::<f64>().unwrap(),
-                                    )
-                                } else {
-                                    Column::NumberValue(
-                                        column_name.to_string(),
-                                        column_value.parse::<i128>().unwrap(),
-                                    )
-                                }
-                            }
-                            Token::Char(column_value) => {
-                                Column::CharValue(column_name.to_string(), column_value.clone())
-                            }
-                            Token::SingleQuotedString(column_value) => {
-                                Column::StringValue(column_name.to_string(), column_value.clone())
-                            }
-                            Token::NationalStringLiteral(column_value) => {
-                                Column::StringValue(column_name.to_string(), column_value.clone())
-                            }
-                            Token::HexStringLiteral(column_value) => {
-                                Column::StringValue(column_name.to_string(), column_value.clone())
-                            }
-                            _ => Column::None(column_name.to_string()),
-                        };
+                        let value_tokens = column_values.get(i).unwrap();
+                        original_columns.push(to_column(column_name, value_tokens.as_slice()));
+                    }
 
+                    // the transformers can read the original columns of the row
+                    let mut columns = vec![];
+                    let array_columns =
+                        array_columns_by_table.get(&format!("{}.{}", database_name, table_name));
+
+                    for column in &original_columns {
                         // get the right transformer for the right column name
-                        let original_column = column.clone();
-
-                        let db_and_table_and_column_name =
-                            format!("{}.{}.{}", database_name, table_name, *column_name);
-                        let column = match transformer_by_db_and_table_and_column_name
-                            .get(db_and_table_and_column_name.as_str())
-                        {
-                            Some(transformer) => transformer.transform(column), // apply transformation on the column
-                            None => column,
+                        let column = match column_transformers.get(
+                            database_name.as_str(),
+                            table_name.as_str(),
+                            column.name(),
+                        ) {
+                            Some(transformer)
+                                if array_columns
+                                    .map(|columns| columns.contains(column.name()))
+                                    .unwrap_or(false) =>
+                            {
+                                // apply transformation on each element of the array
+                                transform_array_column(transformer, column, &original_columns)
+                            }
+                            Some(transformer) => {
+                                // apply transformation on the column
+                                transformer.transform_with_row(column.clone(), &original_columns)
+                            }
+                            None => column.clone(),
                         };
 
-                        original_columns.push(original_column);
                         columns.push(column);
                     }
 
@@ -236,6 +610,11 @@ pub fn read_and_transform<R: Read, F: FnMut(OriginalQuery, Query)>(
                 database_name,
                 table_name,
             } => {
+                let _ = array_columns_by_table.insert(
+                    format!("{}.{}", database_name, table_name),
+                    get_array_column_names_from_create_table_query(&tokens),
+                );
+
                 if !skip_tables_map.contains_key(&format!("{}.{}", database_name, table_name)) {
                     query_callback(
                         // there is no diff between the original and the modified one
@@ -256,14 +635,24 @@ pub fn read_and_transform<R: Read, F: FnMut(OriginalQuery, Query)>(
                     )
                 }
             }
-            RowType::Others => {
-                // other rows than `INSERT INTO ...` and `CREATE TABLE ...`
-                query_callback(
-                    // there is no diff between the original and the modified one
-                    Query(query.as_bytes().to_vec()),
-                    Query(query.as_bytes().to_vec()),
-                )
-            }
+            RowType::Others => match (LargeObjectQuery::parse(query), large_object.as_mut()) {
+                (Some(LargeObjectQuery::Open { oid }), _) => {
+                    large_object = Some(LargeObject { oid, offset: 0 });
+                }
+                (Some(LargeObjectQuery::Write { hex_data }), Some(large_object)) => {
+                    let query = large_object.put_query(hex_data);
+                    query_callback(query.clone(), query)
+                }
+                (Some(LargeObjectQuery::Close), Some(_)) => large_object = None,
+                _ => {
+                    // other rows than `INSERT INTO ...` and `CREATE TABLE ...`
+                    query_callback(
+                        // there is no diff between the original and the modified one
+                        Query(query.as_bytes().to_vec()),
+                        Query(query.as_bytes().to_vec()),
+                    )
+                }
+            },
         }
     }) {
         Ok(_) => {}
@@ -271,6 +660,73 @@ pub fn read_and_transform<R: Read, F: FnMut(OriginalQuery, Query)>(
     }
 }
 
+/// The dump writes the large objects through a descriptor which is only valid within its transaction -
+/// `lo_open`, one `lowrite` per chunk of data, then `lo_close`. The restore applies the backup chunks
+/// in different sessions, so the writes are rewritten into self-contained `lo_put` queries.
+#[derive(Debug, PartialEq)]
+enum LargeObjectQuery<'a> {
+    /// E.g. `SELECT pg_catalog.lo_open('16392', 131072);`
+    Open { oid: String },
+    /// E.g. `SELECT pg_catalog.lowrite(0, '\x89504e47');` - the hex data is `89504e47`
+    Write { hex_data: &'a str },
+    /// E.g. `SELECT pg_catalog.lo_close(0);`
+    Close,
+}
+
+impl<'a> LargeObjectQuery<'a> {
+    fn parse(query: &'a str) -> Option<Self> {
+        let query = query.trim().strip_prefix("SELECT pg_catalog.")?;
+        let (function, args) = query.strip_suffix(");")?.split_once('(')?;
+
+        match function {
+            "lo_open" => {
+                let (oid, _mode) = args.split_once(',')?;
+                let oid = oid.trim().trim_matches('\'');
+                match !oid.is_empty() && oid.chars().all(|c| c.is_ascii_digit()) {
+                    true => Some(LargeObjectQuery::Open {
+                        oid: oid.to_string(),
+                    }),
+                    false => None,
+                }
+            }
+            "lowrite" => {
+                let (_fd, data) = args.split_once(',')?;
+                // `E'\\x...'` when the dump does not use standard conforming strings
+                let hex_data = data
+                    .trim()
+                    .strip_prefix("'\\x")
+                    .or_else(|| data.trim().strip_prefix("E'\\\\x"))?
+                    .strip_suffix('\'')?;
+
+                match hex_data.len() % 2 == 0 && hex_data.chars().all(|c| c.is_ascii_hexdigit()) {
+                    true => Some(LargeObjectQuery::Write { hex_data }),
+                    false => None,
+                }
+            }
+            "lo_close" => Some(LargeObjectQuery::Close),
+            _ => None,
+        }
+    }
+}
+
+struct LargeObject {
+    oid: String,
+    /// bytes already written
+    offset: usize,
+}
+
+impl LargeObject {
+    fn put_query(&mut self, hex_data: &str) -> Query {
+        let query = format!(
+            "SELECT pg_catalog.lo_put('{}', {}, '\\x{}');",
+            self.oid, self.offset, hex_data
+        );
+
+        self.offset += hex_data.len() / 2;
+        Query(query.into_bytes())
+    }
+}
+
 fn is_insert_into_statement(tokens: &Vec<Token>) -> bool {
     match_keyword_at_position(Keyword::Insert, &tokens, 0)
         && match_keyword_at_position(Keyword::Into, &tokens, 2)
@@ -286,6 +742,128 @@ fn is_alter_table_statement(tokens: &Vec<Token>) -> bool {
         && match_keyword_at_position(Keyword::Table, &tokens, 2)
 }
 
+/// the column of an `INSERT INTO` query from the tokens of its value - NULL for the unsupported values.
+/// A cast is ignored - E.g. `'2022-01-01'::date` is the string `2022-01-01`
+fn to_column(column_name: &str, value_tokens: &[&Token]) -> Column {
+    let value_tokens = match value_tokens
+        .iter()
+        .position(|token| **token == Token::DoubleColon)
+    {
+        Some(idx) => &value_tokens[..idx],
+        None => value_tokens,
+    };
+
+    match value_tokens {
+        [Token::Minus, Token::Number(column_value, _)] => {
+            to_number_column(column_name, format!("-{}", column_value).as_str())
+        }
+        [Token::Number(column_value, _)] => to_number_column(column_name, column_value.as_str()),
+        [Token::Char(column_value)] => {
+            Column::CharValue(column_name.to_string(), column_value.clone())
+        }
+        [Token::SingleQuotedString(column_value)] => {
+            Column::StringValue(column_name.to_string(), column_value.clone())
+        }
+        [Token::NationalStringLiteral(column_value)] => {
+            Column::StringValue(column_name.to_string(), column_value.clone())
+        }
+        [Token::HexStringLiteral(column_value)] => {
+            Column::StringValue(column_name.to_string(), column_value.clone())
+        }
+        [Token::Word(word)] if word.quote_style.is_none() => {
+            match word.value.to_lowercase().as_str() {
+                "true" => Column::BooleanValue(column_name.to_string(), true),
+                "false" => Column::BooleanValue(column_name.to_string(), false),
+                _ => Column::None(column_name.to_string()),
+            }
+        }
+        _ => Column::None(column_name.to_string()),
+    }
+}
+
+fn to_number_column(column_name: &str, column_value: &str) -> Column {
+    if column_value.contains(".") {
+        Column::FloatNumberValue(
+            column_name.to_string(),
+            column_value.parse::<f64>().unwrap(),
+        )
+    } else {
+        Column::NumberValue(
+            column_name.to_string(),
+            column_value.parse::<i128>().unwrap(),
+        )
+    }
+}
+
+/// the tokens of each value of an `INSERT INTO ... VALUES (...)` query - a value can have several tokens,
+/// E.g. `-1` is `-` and `1`, and `'{1,2}'::integer[]` is the string, the cast and the type
+fn get_value_tokens_from_insert_into_query(tokens: &[Token]) -> Vec<Vec<&Token>> {
+    let mut values = vec![];
+    let mut value = vec![];
+    // parentheses opened inside a value - E.g. `numeric(10,2)`
+    let mut depth = 0;
+
+    // skip the column names
+    let tokens = tokens
+        .iter()
+        .skip_while(|token| **token != Token::RParen)
+        .skip_while(|token| **token != Token::LParen)
+        .skip(1);
+
+    for token in tokens {
+        match token {
+            Token::RParen if depth == 0 => break,
+            Token::Comma if depth == 0 => values.push(std::mem::take(&mut value)),
+            Token::Whitespace(_) => {}
+            token => {
+                match token {
+                    Token::LParen => depth += 1,
+                    Token::RParen => depth -= 1,
+                    _ => {}
+                }
+                value.push(token);
+            }
+        }
+    }
+
+    if !value.is_empty() || !values.is_empty() {
+        values.push(value);
+    }
+
+    values
+}
+
+/// the database name and the row of an `INSERT INTO <database>.<table> (...) VALUES (...)` query -
+/// None for the other queries
+pub fn parse_insert_into_query(query: &str) -> Option<(String, InsertIntoQuery)> {
+    let tokens = get_tokens_from_query_str(query);
+
+    match get_row_type(&tokens) {
+        RowType::InsertInto {
+            database_name,
+            table_name,
+        } => {
+            let column_names = get_column_names_from_insert_into_query(&tokens);
+            let column_values = get_value_tokens_from_insert_into_query(&tokens);
+
+            let columns = column_names
+                .iter()
+                .zip(column_values)
+                .map(|(column_name, value_tokens)| to_column(column_name, value_tokens.as_slice()))
+                .collect();
+
+            Some((
+                database_name,
+                InsertIntoQuery {
+                    table_name,
+                    columns,
+                },
+            ))
+        }
+        _ => None,
+    }
+}
+
 fn get_row_type(tokens: &Vec<Token>) -> RowType {
     let mut row_type = RowType::Others;
 
@@ -335,6 +913,221 @@ fn get_row_type(tokens: &Vec<Token>) -> RowType {
     row_type
 }
 
+/// list the columns declared as arrays (E.g. `tags text[]`) in a CREATE TABLE query
+fn get_array_column_names_from_create_table_query(tokens: &Vec<Token>) -> HashSet<String> {
+    let mut array_column_names = HashSet::new();
+    let mut column_name: Option<&str> = None;
+    let mut depth = 0usize;
+    let mut previous_token: Option<&Token> = None;
+
+    for token in tokens {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth = depth.saturating_sub(1),
+            Token::Comma if depth == 1 => column_name = None,
+            Token::Word(word) if depth == 1 && column_name.is_none() => {
+                column_name = Some(word.value.as_str())
+            }
+            Token::RBracket if depth == 1 => {
+                if let (Some(Token::LBracket), Some(column_name)) = (previous_token, column_name) {
+                    let _ = array_column_names.insert(column_name.to_string());
+                }
+            }
+            _ => {}
+        }
+
+        match token {
+            Token::Whitespace(_) => {}
+            token => previous_token = Some(token),
+        }
+    }
+
+    array_column_names
+}
+
+/// element of a PostgreSQL array literal - E.g. `{a,"b c",NULL}` or `{{1,2},{3,4}}`
+#[derive(Debug, PartialEq)]
+enum ArrayElement {
+    Value { value: String, quoted: bool },
+    Null,
+    Array(Vec<ArrayElement>),
+}
+
+/// parse a PostgreSQL array literal - `None` if the value is not an array
+fn parse_array(value: &str) -> Option<Vec<ArrayElement>> {
+    let mut chars = value.trim().chars().peekable();
+    let elements = parse_array_elements(&mut chars)?;
+
+    match chars.next() {
+        None => Some(elements),
+        Some(_) => None,
+    }
+}
+
+fn parse_array_elements(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+) -> Option<Vec<ArrayElement>> {
+    if chars.next()? != '{' {
+        return None;
+    }
+
+    let mut elements = vec![];
+
+    if chars.peek() == Some(&'}') {
+        let _ = chars.next();
+        return Some(elements);
+    }
+
+    loop {
+        let element = match chars.peek()? {
+            '{' => ArrayElement::Array(parse_array_elements(chars)?),
+            '"' => {
+                let _ = chars.next();
+                let mut value = String::new();
+
+                loop {
+                    match chars.next()? {
+                        '\\' => value.push(chars.next()?),
+                        '"' => break,
+                        c => value.push(c),
+                    }
+                }
+
+                ArrayElement::Value {
+                    value,
+                    quoted: true,
+                }
+            }
+            _ => {
+                let mut value = String::new();
+
+                while let Some(c) = chars.peek() {
+                    if *c == ',' || *c == '}' {
+                        break;
+                    }
+
+                    value.push(chars.next()?);
+                }
+
+                match value.trim() {
+                    "NULL" => ArrayElement::Null,
+                    value => ArrayElement::Value {
+                        value: value.to_string(),
+                        quoted: false,
+                    },
+                }
+            }
+        };
+
+        elements.push(element);
+
+        match chars.next()? {
+            ',' => continue,
+            '}' => return Some(elements),
+            _ => return None,
+        }
+    }
+}
+
+fn array_to_string(elements: &[ArrayElement]) -> String {
+    let elements = elements
+        .iter()
+        .map(|element| match element {
+            ArrayElement::Value {
+                value,
+                quoted: false,
+            } => value.to_string(),
+            ArrayElement::Value {
+                value,
+                quoted: true,
+            } => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+            ArrayElement::Null => "NULL".to_string(),
+            ArrayElement::Array(elements) => array_to_string(elements),
+        })
+        .collect::<Vec<_>>();
+
+    format!("{{{}}}", elements.join(","))
+}
+
+fn transform_array_elements(
+    transformer: &dyn Transformer,
+    column_name: &str,
+    elements: Vec<ArrayElement>,
+    row: &[Column],
+) -> Vec<ArrayElement> {
+    elements
+        .into_iter()
+        .map(|element| match element {
+            ArrayElement::Value { value, quoted } => {
+                let column = match value.parse::<i128>() {
+                    Ok(number) if !quoted => Column::NumberValue(column_name.to_string(), number),
+                    _ => match value.parse::<f64>() {
+                        Ok(number) if !quoted => {
+                            Column::FloatNumberValue(column_name.to_string(), number)
+                        }
+                        _ => Column::StringValue(column_name.to_string(), value),
+                    },
+                };
+
+                match transformer.transform_with_row(column, row) {
+                    Column::NumberValue(_, value) => ArrayElement::Value {
+                        value: value.to_string(),
+                        quoted: false,
+                    },
+                    Column::FloatNumberValue(_, value) => ArrayElement::Value {
+                        value: value.to_string(),
+                        quoted: false,
+                    },
+                    Column::StringValue(_, value) => ArrayElement::Value {
+                        value,
+                        quoted: true,
+                    },
+                    Column::CharValue(_, value) => ArrayElement::Value {
+                        value: value.to_string(),
+                        quoted: true,
+                    },
+                    Column::BooleanValue(_, value) => ArrayElement::Value {
+                        value: value.to_string(),
+                        quoted: false,
+                    },
+                    Column::None(_) => ArrayElement::Null,
+                }
+            }
+            ArrayElement::Null => ArrayElement::Null,
+            ArrayElement::Array(elements) => ArrayElement::Array(transform_array_elements(
+                transformer,
+                column_name,
+                elements,
+                row,
+            )),
+        })
+        .collect()
+}
+
+/// apply the transformer on each element of an array column and keep the array structure
+fn transform_array_column(
+    transformer: &dyn Transformer,
+    column: &Column,
+    row: &[Column],
+) -> Column {
+    match column {
+        Column::StringValue(column_name, value) => match parse_array(value) {
+            Some(elements) => Column::StringValue(
+                column_name.clone(),
+                array_to_string(&transform_array_elements(
+                    transformer,
+                    column_name,
+                    elements,
+                    row,
+                )),
+            ),
+            // not an array literal - E.g. an ARRAY[...] expression
+            None => column.clone(),
+        },
+        column => column.clone(),
+    }
+}
+
 fn to_query(database: Option<&str>, query: InsertIntoQuery) -> Query {
     let mut column_names = Vec::with_capacity(query.columns.len());
     let mut values = Vec::with_capacity(query.columns.len());
@@ -357,6 +1150,10 @@ fn to_query(database: Option<&str>, query: InsertIntoQuery) -> Query {
                 column_names.push(column_name);
                 values.push(format!("'{}'", value));
             }
+            Column::BooleanValue(column_name, value) => {
+                column_names.push(column_name);
+                values.push(value.to_string());
+            }
             Column::None(column_name) => {
                 column_names.push(column_name);
                 values.push("NULL".to_string());
@@ -383,12 +1180,25 @@ fn to_query(database: Option<&str>, query: InsertIntoQuery) -> Query {
 #[cfg(test)]
 mod tests {
     use crate::config::SkipConfig;
+    use crate::connector::{SslMode, Tls};
+    use crate::source::Source;
     use crate::source::SourceOptions;
-    use crate::Source;
+    use std::collections::HashMap;
+    use std::process::Command;
     use std::str;
     use std::vec;
 
-    use crate::source::postgres::{to_query, Postgres};
+    use std::io::BufReader;
+
+    use crate::connector::Connector;
+    use crate::destination::postgres::Postgres as DestinationPostgres;
+    use crate::destination::Destination;
+    use crate::source::postgres::{
+        array_to_string, parse_array, parse_column_types, read_and_transform, read_plain_dump,
+        to_query, ArrayElement, DumpFormat, LargeObjectQuery, ParallelDump, Postgres, TableColumn,
+    };
+    use crate::transformer::email::EmailTransformer;
+    use crate::transformer::keep_first_char::KeepFirstCharTransformer;
     use crate::transformer::random::RandomTransformer;
     use crate::transformer::transient::TransientTransformer;
     use crate::transformer::Transformer;
@@ -423,6 +1233,34 @@ mod tests {
         assert!(p.read(source_options, |original_query, query| {}).is_err());
     }
 
+    #[test]
+    fn test_parse_column_types() {
+        let output =
+            "public|users|id|integer|PRIMARY KEY\npublic|users|email|character varying|\n\n";
+
+        assert_eq!(
+            parse_column_types(output),
+            vec![
+                TableColumn {
+                    database: "public".to_string(),
+                    table: "users".to_string(),
+                    column: "id".to_string(),
+                    data_type: "integer".to_string(),
+                    key: Some("PRIMARY KEY".to_string()),
+                },
+                TableColumn {
+                    database: "public".to_string(),
+                    table: "users".to_string(),
+                    column: "email".to_string(),
+                    data_type: "character varying".to_string(),
+                    key: None,
+                },
+            ]
+        );
+
+        assert!(get_invalid_postgres().column_types().is_err());
+    }
+
     #[test]
     fn list_rows() {
         let p = get_postgres();
@@ -601,4 +1439,446 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn dump_args() {
+        let mut p = get_postgres();
+        assert!(p
+            .set_dump_args(vec![
+                "--no-privileges".to_string(),
+                "--exclude-database=template*".to_string()
+            ])
+            .is_ok());
+
+        let args = p.dump_args();
+        assert_eq!(args[0], "--column-inserts");
+        assert_eq!(
+            args[args.len() - 2..],
+            ["--no-privileges", "--exclude-database=template*"]
+        );
+
+        // the dump must be written on the standard output
+        assert!(p.set_dump_args(vec!["-f".to_string()]).is_err());
+        assert!(p.set_dump_args(vec!["-fdump.sql".to_string()]).is_err());
+        assert!(p
+            .set_dump_args(vec!["--file=dump.sql".to_string()])
+            .is_err());
+    }
+
+    #[test]
+    fn large_objects_are_written_with_lo_put() {
+        let dump = r"CREATE TABLE public.files (id integer, data bytea);
+INSERT INTO public.files (id, data) VALUES (1, '\x00ff275c3b0a');
+SELECT pg_catalog.lo_create('16392');
+BEGIN;
+SELECT pg_catalog.lo_open('16392', 131072);
+SELECT pg_catalog.lowrite(0, '\x0001');
+SELECT pg_catalog.lowrite(0, '\xfeff');
+SELECT pg_catalog.lo_close(0);
+COMMIT;
+";
+
+        let mut queries = vec![];
+        read_and_transform(
+            BufReader::new(dump.as_bytes()),
+            SourceOptions {
+                transformers: &vec![],
+                skip_config: &vec![],
+            },
+            |_, query| queries.push(str::from_utf8(query.data()).unwrap().trim().to_string()),
+        );
+
+        assert_eq!(
+            queries[1..],
+            [
+                r"INSERT INTO public.files (id, data) VALUES (1, '\x00ff275c3b0a');",
+                "SELECT pg_catalog.lo_create('16392');",
+                "BEGIN;",
+                r"SELECT pg_catalog.lo_put('16392', 0, '\x0001');",
+                r"SELECT pg_catalog.lo_put('16392', 2, '\xfeff');",
+                "COMMIT;",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_large_object_queries() {
+        assert_eq!(
+            LargeObjectQuery::parse("SELECT pg_catalog.lo_open('16392', 131072);\n"),
+            Some(LargeObjectQuery::Open {
+                oid: "16392".to_string()
+            })
+        );
+        assert_eq!(
+            LargeObjectQuery::parse(r"SELECT pg_catalog.lowrite(0, E'\\x0aff');"),
+            Some(LargeObjectQuery::Write { hex_data: "0aff" })
+        );
+        assert_eq!(
+            LargeObjectQuery::parse("SELECT pg_catalog.lo_close(0);"),
+            Some(LargeObjectQuery::Close)
+        );
+
+        // kept as is
+        assert_eq!(
+            LargeObjectQuery::parse(r"SELECT pg_catalog.lowrite(0, '\x0af');"),
+            None
+        );
+        assert_eq!(
+            LargeObjectQuery::parse("SELECT pg_catalog.lo_create('16392');"),
+            None
+        );
+        assert_eq!(LargeObjectQuery::parse("SELECT 1;"), None);
+    }
+
+    /// run a query with psql and return its unaligned output
+    fn psql(port: u16, query: &str) -> String {
+        let output = Command::new("psql")
+            .env("PGPASSWORD", "password")
+            .args(["-h", "localhost", "-p", port.to_string().as_str()])
+            .args(["-d", "root", "-U", "root", "-t", "-A", "-c", query])
+            .output()
+            .expect("can't run psql");
+
+        assert!(output.status.success(), "query failed: {}", query);
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    #[test]
+    fn backup_and_restore_binary_data() {
+        // the large object is bigger than the chunks written by pg_dump
+        let large_object_hex = "00ff275c3b0a".repeat(10_000);
+
+        let _ = psql(
+            5432,
+            format!(
+                "DROP TABLE IF EXISTS public.binary_files; \
+                SELECT pg_catalog.lo_unlink(oid) FROM pg_catalog.pg_largeobject_metadata WHERE oid = 424242; \
+                CREATE TABLE public.binary_files (id integer, data bytea); \
+                INSERT INTO public.binary_files (id, data) VALUES (1, decode('00ff275c3b0a', 'hex')); \
+                SELECT pg_catalog.lo_from_bytea(424242, decode('{}', 'hex'));",
+                large_object_hex
+            )
+            .as_str(),
+        );
+
+        let mut queries = vec![];
+        let source_options = SourceOptions {
+            transformers: &vec![],
+            skip_config: &vec![],
+        };
+        assert!(get_postgres()
+            .read(source_options, |_, query| queries.push(query))
+            .is_ok());
+
+        let mut destination =
+            DestinationPostgres::new("localhost", 5453, "root", "root", "password", true);
+        destination.init().expect("can't init postgres");
+
+        // worst case - every query is applied in its own session
+        for query in queries {
+            let _ = destination.write(query.data().to_vec());
+        }
+
+        assert_eq!(
+            psql(
+                5453,
+                "SELECT encode(data, 'hex') FROM public.binary_files WHERE id = 1"
+            ),
+            "00ff275c3b0a"
+        );
+        assert_eq!(
+            psql(5453, "SELECT encode(pg_catalog.lo_get(424242), 'hex')"),
+            large_object_hex
+        );
+    }
+
+    #[test]
+    fn read_plain_dump_keeps_copy_data_with_its_query() {
+        let dump = r"SET statement_timeout = 0;
+
+--
+-- Name: users; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.users (
+    id integer,
+    name text
+);
+
+--
+-- Data for Name: users; Type: TABLE DATA; Schema: public; Owner: -
+--
+
+COPY public.users (id, name) FROM stdin;
+1	Alice; Bob
+2	O'Neil
+\.
+
+
+--
+-- PostgreSQL database dump complete
+--
+
+";
+
+        let mut queries = vec![];
+        assert!(
+            read_plain_dump(BufReader::new(dump.as_bytes()), |_, query| {
+                queries.push(str::from_utf8(query.data()).unwrap().to_string())
+            })
+            .is_ok()
+        );
+
+        assert_eq!(queries.len(), 4);
+        assert_eq!(queries[0], "SET statement_timeout = 0;");
+        assert!(
+            queries[1].ends_with("CREATE TABLE public.users (\n    id integer,\n    name text\n);")
+        );
+        assert!(queries[2]
+            .ends_with("COPY public.users (id, name) FROM stdin;\n1\tAlice; Bob\n2\tO'Neil\n\\."));
+        assert!(queries[3].contains("PostgreSQL database dump complete"));
+
+        // the dump is written back as it was read
+        let data = queries
+            .iter()
+            .map(|query| format!("{}\n", query))
+            .collect::<String>();
+        assert_eq!(data, dump);
+    }
+
+    #[test]
+    fn parallel_dump() {
+        let mut p = get_postgres();
+
+        let parallel_dump = ParallelDump {
+            format: DumpFormat::Directory,
+            jobs: 0,
+        };
+        assert!(p.set_parallel_dump(Some(parallel_dump)).is_err());
+
+        let parallel_dump = ParallelDump {
+            format: DumpFormat::Directory,
+            jobs: 4,
+        };
+        assert!(p.set_parallel_dump(Some(parallel_dump)).is_ok());
+
+        // the dump must be parsed to apply the transformers
+        let transformers: Vec<Box<dyn Transformer>> =
+            vec![Box::new(RandomTransformer::new("public", "users", "name"))];
+        let source_options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &vec![],
+        };
+        assert!(p.read(source_options, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn backup_and_restore_parallel_dump() {
+        let _ = psql(
+            5432,
+            "DROP TABLE IF EXISTS public.parallel_users; \
+            CREATE TABLE public.parallel_users (id integer, name text); \
+            INSERT INTO public.parallel_users (id, name) \
+            SELECT i, 'user; ''' || i FROM generate_series(1, 1000) AS i;",
+        );
+        let _ = psql(5453, "DROP TABLE IF EXISTS public.parallel_users;");
+
+        let mut p = get_postgres();
+        let parallel_dump = ParallelDump {
+            format: DumpFormat::Directory,
+            jobs: 2,
+        };
+        assert!(p.set_parallel_dump(Some(parallel_dump)).is_ok());
+        assert!(p.init().is_ok());
+
+        let mut queries = vec![];
+        let source_options = SourceOptions {
+            transformers: &vec![],
+            skip_config: &vec![],
+        };
+        assert!(p
+            .read(source_options, |_, query| queries.push(query))
+            .is_ok());
+
+        let mut destination =
+            DestinationPostgres::new("localhost", 5453, "root", "root", "password", false);
+        destination.init().expect("can't init postgres");
+
+        // worst case - every query is applied in its own session
+        for query in queries {
+            let _ = destination.write(query.data().to_vec());
+        }
+
+        assert_eq!(
+            psql(5453, "SELECT count(*) FROM public.parallel_users"),
+            "1000"
+        );
+        assert_eq!(
+            psql(5453, "SELECT name FROM public.parallel_users WHERE id = 42"),
+            "user; '42"
+        );
+    }
+
+    #[test]
+    fn dump_command_with_tls() {
+        let mut p = get_postgres();
+
+        let envs = |p: &Postgres| {
+            p.dump_command()
+                .get_envs()
+                .filter_map(|(key, value)| {
+                    Some((key.to_str()?.to_string(), value?.to_str()?.to_string()))
+                })
+                .collect::<HashMap<_, _>>()
+        };
+
+        assert!(!envs(&p).contains_key("PGSSLMODE"));
+
+        let tls = Tls {
+            mode: Some(SslMode::VerifyCa),
+            client_cert: Some("/certs/client.crt".to_string()),
+            client_key: Some("/certs/client.key".to_string()),
+            ca_cert: Some("/certs/ca.crt".to_string()),
+        };
+        assert!(p.set_tls(Some(tls)).is_ok());
+
+        let envs = envs(&p);
+        assert_eq!(envs["PGPASSWORD"], "password");
+        assert_eq!(envs["PGSSLMODE"], "verify-ca");
+        assert_eq!(envs["PGSSLCERT"], "/certs/client.crt");
+        assert_eq!(envs["PGSSLKEY"], "/certs/client.key");
+        assert_eq!(envs["PGSSLROOTCERT"], "/certs/ca.crt");
+
+        // a client certificate without its key
+        let tls = Tls {
+            client_cert: Some("/certs/client.crt".to_string()),
+            ..Tls::default()
+        };
+        assert!(p.set_tls(Some(tls)).is_err());
+    }
+
+    #[test]
+    fn dump_command_without_the_rows_of_the_excluded_tables() {
+        let mut p = get_postgres();
+
+        let command = |p: &Postgres| {
+            let command = p.dump_command();
+            let args = command
+                .get_args()
+                .map(|arg| arg.to_str().unwrap().to_string())
+                .collect::<Vec<_>>();
+            (command.get_program().to_str().unwrap().to_string(), args)
+        };
+
+        let (program, args) = command(&p);
+        assert_eq!(program, "pg_dumpall");
+        assert!(!args
+            .iter()
+            .any(|arg| arg.starts_with("--exclude-table-data")));
+
+        p.set_excluded_table_data(vec![
+            "public.users".to_string(),
+            "public.\"odd\"name".to_string(),
+        ]);
+
+        let (program, args) = command(&p);
+        assert_eq!(program, "pg_dump");
+        assert!(args.contains(&"--exclude-table-data=\"public\".\"users\"".to_string()));
+        assert!(args.contains(&"--exclude-table-data=\"public\".\"\"\"odd\"\"name\"".to_string()));
+        assert!(args.windows(2).any(|args| args == ["-d", "root"]));
+    }
+
+    #[test]
+    fn transform_columns_matching_pattern() {
+        let dump = b"\
+INSERT INTO public.users (id, email) VALUES (1, 'john@doe.com');
+INSERT INTO public.customers (id, email, name) VALUES (2, 'jane@doe.com', 'jane');
+INSERT INTO public.admins (id, email) VALUES (3, 'root@doe.com');
+";
+
+        // any column named `email`, anywhere - except in public.admins
+        let t1: Box<dyn Transformer> = Box::new(EmailTransformer::new("*", "*", "email"));
+        let t2: Box<dyn Transformer> =
+            Box::new(TransientTransformer::new("public", "admins", "email"));
+
+        let transformers = vec![t1, t2];
+        let source_options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &vec![],
+        };
+
+        let mut queries = vec![];
+        read_and_transform(
+            BufReader::new(dump.as_slice()),
+            source_options,
+            |_, query| queries.push(str::from_utf8(query.data()).unwrap().to_string()),
+        );
+
+        assert_eq!(queries.len(), 3);
+        assert!(!queries[0].contains("john@doe.com"));
+        assert!(!queries[1].contains("jane@doe.com"));
+        assert!(queries[1].contains("'jane'"));
+        assert!(queries[2].contains("root@doe.com"));
+    }
+
+    #[test]
+    fn parse_array_literal() {
+        assert_eq!(
+            parse_array(r#"{alice,"bob smith",NULL,"say \"hi\""}"#).unwrap(),
+            vec![
+                ArrayElement::Value {
+                    value: "alice".to_string(),
+                    quoted: false
+                },
+                ArrayElement::Value {
+                    value: "bob smith".to_string(),
+                    quoted: true
+                },
+                ArrayElement::Null,
+                ArrayElement::Value {
+                    value: "say \"hi\"".to_string(),
+                    quoted: true
+                },
+            ]
+        );
+
+        let nested = parse_array("{{1,2},{3,4}}").unwrap();
+        assert_eq!(nested.len(), 2);
+        assert_eq!(array_to_string(&nested), "{{1,2},{3,4}}");
+
+        assert_eq!(parse_array("{}").unwrap(), vec![]);
+        assert!(parse_array(r#"{"a": 1}"#).is_none());
+        assert!(parse_array("not an array").is_none());
+    }
+
+    #[test]
+    fn transform_each_element_of_array_column() {
+        let dump = b"\
+CREATE TABLE public.users (id integer NOT NULL, nickname text, tags text[], scores integer[]);
+INSERT INTO public.users (id, nickname, tags, scores) VALUES (1, 'johnny', '{alice,\"bob smith\",NULL}', '{10,20}');
+";
+
+        let transformers: Vec<Box<dyn Transformer>> = vec![
+            Box::new(KeepFirstCharTransformer::new("public", "users", "nickname")),
+            Box::new(KeepFirstCharTransformer::new("public", "users", "tags")),
+            Box::new(KeepFirstCharTransformer::new("public", "users", "scores")),
+        ];
+        let source_options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &vec![],
+        };
+
+        let mut queries = vec![];
+        read_and_transform(
+            BufReader::new(dump.as_slice()),
+            source_options,
+            |_, query| queries.push(str::from_utf8(query.data()).unwrap().to_string()),
+        );
+
+        assert_eq!(
+            queries[1],
+            "INSERT INTO public.users (id, nickname, tags, scores) \
+            VALUES (1, 'j', '{\"a\",\"b\",NULL}', '{1,2}');"
+        );
+    }
 }
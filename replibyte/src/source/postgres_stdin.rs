@@ -2,9 +2,9 @@ use std::io::{stdin, BufReader, Error};
 
 use crate::connector::Connector;
 use crate::source::postgres::read_and_transform;
+use crate::source::Source;
+use crate::source::SourceOptions;
 use crate::types::{OriginalQuery, Query};
-use crate::Source;
-use crate::SourceOptions;
 
 /// Source Postgres dump from STDIN
 pub struct PostgresStdin {}
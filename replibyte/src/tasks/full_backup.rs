@@ -1,14 +1,40 @@
-use std::io::Error;
-use std::sync::mpsc;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Error, ErrorKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{mpsc, Arc};
 use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::bridge::Bridge;
+use log::warn;
+
+use crate::bridge::{BackupChunk, BackupContent, Bridge, RUN_LOCK_TTL};
+use crate::destination::postgres::{
+    create_table_name, insert_into_table_name, post_data_query, TableChecksum,
+};
+use crate::error::ReplibyteError;
+use crate::metrics::TableRows;
+use crate::runtime::worker_pool;
+use crate::source::Source;
 use crate::source::SourceOptions;
 use crate::tasks::{MaxBytes, Message, Task, TransferredBytes};
+use crate::timings::{Stage, Timings};
 use crate::types::{to_bytes, Queries};
-use crate::Source;
+use crate::utils::to_human_readable_unit;
+
+type DataMessage = (u16, Part);
 
-type DataMessage = (u16, Queries);
+/// what is written as a part - the queries of the dump, or the chunks of a previous backup shared by this one
+enum Part {
+    Queries(Queries),
+    Chunks(Vec<BackupChunk>),
+}
+
+/// buffer of 100MB in memory - a part is uploaded as soon as its buffer is full
+const DEFAULT_CHUNK_SIZE: usize = 100 * 1024 * 1024;
+/// parts waiting for their upload - at most `DEFAULT_BUFFERED_PARTS + 2` parts are in memory (the waiting parts, the part
+/// being uploaded and the part being read)
+const DEFAULT_BUFFERED_PARTS: usize = 1;
 
 /// FullBackupTask is a wrapping struct to execute the synchronization between a *Source* and a *Bridge*
 pub struct FullBackupTask<'a, S, B>
@@ -19,6 +45,17 @@ where
     source: S,
     bridge: B,
     options: SourceOptions<'a>,
+    max_size: Option<usize>,
+    chunk_size: usize,
+    buffered_parts: usize,
+    part_per_table: bool,
+    timings: Arc<Timings>,
+    table_rows: Arc<TableRows>,
+    source_retries: usize,
+    table_checksums: Option<BTreeMap<String, TableChecksum>>,
+    interrupted: Arc<AtomicBool>,
+    /// the lock is renewed well before it expires
+    run_lock_renewal_interval: Duration,
 }
 
 impl<'a, S, B> FullBackupTask<'a, S, B>
@@ -31,7 +68,503 @@ where
             source,
             bridge,
             options,
+            max_size: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buffered_parts: DEFAULT_BUFFERED_PARTS,
+            part_per_table: false,
+            timings: Arc::new(Timings::default()),
+            table_rows: Arc::new(TableRows::default()),
+            source_retries: 0,
+            table_checksums: None,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            run_lock_renewal_interval: RUN_LOCK_TTL / 3,
+        }
+    }
+
+    /// size of the parts uploaded while the source is still being read
+    pub fn set_chunk_size(&mut self, chunk_size: usize) {
+        self.chunk_size = chunk_size;
+    }
+
+    /// parts waiting for their upload - the source is paused while they are all waiting, a slow upload does not buffer
+    /// the dump in memory. Default to 1
+    pub fn set_buffered_parts(&mut self, buffered_parts: Option<usize>) {
+        self.buffered_parts = buffered_parts.unwrap_or(DEFAULT_BUFFERED_PARTS);
+    }
+
+    /// start a new part with the rows of each table - the parts of the unchanged tables are the same from one backup
+    /// to the next, E.g. to be deduplicated by the bridge
+    pub fn set_part_per_table(&mut self, part_per_table: bool) {
+        self.part_per_table = part_per_table;
+    }
+
+    /// record the time spent reading the source into `timings`
+    pub fn set_timings(&mut self, timings: Arc<Timings>) {
+        self.timings = timings;
+    }
+
+    /// record the rows dumped for each table into `table_rows`
+    pub fn set_table_rows(&mut self, table_rows: Arc<TableRows>) {
+        self.table_rows = table_rows;
+    }
+
+    /// restart the dump from scratch up to `source_retries` times when the source fails - E.g. when the connection drops.
+    /// What has been uploaded is deleted before each retry. Default to 0
+    pub fn set_source_retries(&mut self, source_retries: Option<usize>) {
+        self.source_retries = source_retries.unwrap_or_default();
+    }
+
+    /// the checksums of the source tables - the rows of the tables with the same checksum as in the last backup are not
+    /// uploaded again, their parts are shared with it. A part only holds the rows of a single table
+    pub fn set_table_checksums(
+        &mut self,
+        table_checksums: Option<BTreeMap<String, TableChecksum>>,
+    ) {
+        self.table_checksums = table_checksums;
+    }
+
+    /// abort the backup and delete what has been uploaded when the dump exceeds `max_size` bytes
+    pub fn set_max_size(&mut self, max_size: Option<usize>) {
+        self.max_size = max_size;
+    }
+
+    /// abort the backup and delete what has been uploaded once `interrupted` is set - E.g. on Ctrl-C
+    pub fn set_interrupted(&mut self, interrupted: Arc<AtomicBool>) {
+        self.interrupted = interrupted;
+    }
+}
+
+/// a run of the source - see `FullBackupTask::dump`
+struct Dump {
+    read_result: Result<(), Error>,
+    max_size_exceeded: bool,
+    interrupted: bool,
+    table_rows: BTreeMap<String, usize>,
+    /// parts holding the rows of each table - only recorded with the table checksums
+    table_parts: BTreeMap<String, Vec<u16>>,
+}
+
+/// number the chunks of the unchanged tables from `last_part + 1` and record their parts
+fn shared_chunks(
+    table_chunks: BTreeMap<String, Vec<BackupChunk>>,
+    last_part: &mut u16,
+    table_parts: &mut BTreeMap<String, Vec<u16>>,
+) -> Vec<BackupChunk> {
+    let mut chunks = vec![];
+
+    for (table_name, mut table_chunks) in table_chunks {
+        let parts = table_parts.entry(table_name).or_default();
+
+        for _ in &table_chunks {
+            *last_part += 1;
+            parts.push(*last_part);
         }
+
+        chunks.append(&mut table_chunks);
+    }
+
+    chunks
+}
+
+impl<'a, S, B> FullBackupTask<'a, S, B>
+where
+    S: Source,
+    B: Bridge + 'static,
+{
+    /// read the source through the transformers and upload it - the `unchanged_table_chunks` are shared instead
+    fn dump<F: FnMut(TransferredBytes, MaxBytes)>(
+        &self,
+        progress_callback: &mut F,
+        unchanged_table_chunks: &BTreeMap<String, Vec<BackupChunk>>,
+    ) -> Dump {
+        // the source and the transformers run in the same thread - the channel is the only buffer of the dump
+        let (tx, rx) = mpsc::sync_channel::<Message<DataMessage>>(self.buffered_parts);
+        // bytes uploaded by the bridge for the part being uploaded - and the size of the part
+        let (upload_tx, upload_rx) = mpsc::channel::<(usize, usize)>();
+        let bridge = &self.bridge;
+        let worker_pool = worker_pool();
+
+        // the definition of the unchanged tables is dumped, not their rows
+        let unchanged_tables = unchanged_table_chunks
+            .keys()
+            .map(|table_name| table_name.as_bytes())
+            .collect::<HashSet<_>>();
+
+        thread::scope(|scope| {
+            let join_handle = scope.spawn(move || {
+                // managing Bridge (S3) upload here
+                loop {
+                    let (chunk_part, part) = match rx.recv() {
+                        Ok(Message::Data((chunk_part, part))) => (chunk_part, part),
+                        Ok(Message::EOF) => break,
+                        Err(err) => panic!("{:?}", err), // FIXME what should I do here?
+                    };
+
+                    let _ = match worker_pool.run(|| match part {
+                        Part::Queries(queries) => bridge.write_with_progress(
+                            chunk_part,
+                            to_bytes(queries),
+                            &mut |uploaded_bytes, part_size| {
+                                let _ = upload_tx.send((uploaded_bytes, part_size));
+                            },
+                        ),
+                        Part::Chunks(chunks) => bridge.write_chunks(chunk_part, chunks),
+                    }) {
+                        Ok(_) => {}
+                        Err(err) => {
+                            panic!("{:?}", err);
+                        } // FIXME what should we do?
+                    };
+                }
+            });
+
+            // the source, the transformers and the upload of the previous parts run concurrently -
+            // at most `buffered_parts` parts are waiting to be uploaded
+            let buffer_size = self.chunk_size;
+            let mut queries = vec![];
+            let mut consumed_buffer_size = 0usize;
+            let mut total_transferred_bytes = 0usize;
+            let mut chunk_part = 0u16;
+
+            // init progress
+            progress_callback(
+                total_transferred_bytes,
+                buffer_size * (chunk_part as usize + 1),
+            );
+
+            let max_size = self.max_size;
+            let mut max_size_exceeded = false;
+            let mut interrupted = false;
+
+            // a part only holds the rows of a single table - its chunk can be shared by the next backups
+            let table_per_part = self.table_checksums.is_some();
+            let part_per_table = self.part_per_table || table_per_part;
+            // table of the last `INSERT INTO` query - of the last query when `table_per_part`
+            let mut part_table = None;
+
+            // the tables are recorded into the index file once the backup is written
+            let mut table_rows = BTreeMap::<String, usize>::new();
+            let mut table_parts = BTreeMap::<String, Vec<u16>>::new();
+
+            // shared before the first rows - or before the constraints when no table has changed
+            let mut unchanged_table_chunks = match unchanged_table_chunks.is_empty() {
+                true => None,
+                false => Some(unchanged_table_chunks.clone()),
+            };
+
+            let read_start = Instant::now();
+            let transform_duration = self.timings.duration(Stage::Transform);
+            // time waiting for the bridge - it is not spent by the source
+            let mut send_wait = Duration::ZERO;
+            let mut send = |message: DataMessage| {
+                let send_start = Instant::now();
+                let _ = tx.send(Message::Data(message)); // FIXME catch SendError?
+                send_wait += send_start.elapsed();
+            };
+
+            let read_result = self.source.read(self.options, |original_query, query| {
+                if max_size_exceeded || interrupted {
+                    // skip the remaining queries - nothing else is uploaded
+                    return;
+                }
+
+                if self.interrupted.load(Ordering::SeqCst) {
+                    interrupted = true;
+                    return;
+                }
+
+                let query_table_name = insert_into_table_name(query.data());
+
+                if unchanged_table_chunks.is_some()
+                    && (query_table_name.is_some() || post_data_query(query.data()))
+                {
+                    if !queries.is_empty() {
+                        chunk_part += 1;
+                        consumed_buffer_size = 0;
+                        send((chunk_part, Part::Queries(std::mem::take(&mut queries))));
+                    }
+
+                    let first_part = chunk_part + 1;
+                    let chunks = shared_chunks(
+                        unchanged_table_chunks.take().unwrap_or_default(),
+                        &mut chunk_part,
+                        &mut table_parts,
+                    );
+                    send((first_part, Part::Chunks(chunks)));
+                    part_table = None;
+                }
+
+                if query_table_name
+                    .map_or(false, |table_name| unchanged_tables.contains(table_name))
+                {
+                    return;
+                }
+
+                if let Some(max_size) = max_size {
+                    if total_transferred_bytes + query.data().len() > max_size {
+                        max_size_exceeded = true;
+                        return;
+                    }
+                }
+
+                let table_changed = match query_table_name {
+                    Some(table_name) => part_table.as_deref() != Some(table_name),
+                    None => table_per_part && part_table.is_some(),
+                };
+
+                if query_table_name.is_some() || table_per_part {
+                    part_table = query_table_name.map(|table_name| table_name.to_vec());
+                }
+
+                if consumed_buffer_size + query.data().len() > buffer_size
+                    || (table_changed && part_per_table && !queries.is_empty())
+                {
+                    chunk_part += 1;
+                    consumed_buffer_size = 0;
+                    send((chunk_part, Part::Queries(std::mem::take(&mut queries))));
+                }
+
+                // the rows go into the part being filled
+                match query_table_name.map(std::str::from_utf8) {
+                    Some(Ok(table_name)) if table_per_part => {
+                        let parts = table_parts.entry(table_name.to_string()).or_default();
+                        if parts.last() != Some(&(chunk_part + 1)) {
+                            parts.push(chunk_part + 1);
+                        }
+                    }
+                    _ => {}
+                }
+
+                // the tables of the PostgreSQL dumps and their rows - nothing is found in the MongoDB archives
+                if let Some(Ok(table_name)) =
+                    insert_into_table_name(query.data()).map(std::str::from_utf8)
+                {
+                    match table_rows.get_mut(table_name) {
+                        Some(rows) => *rows += 1,
+                        None => {
+                            let _ = table_rows.insert(table_name.to_string(), 1);
+                        }
+                    }
+                } else if let Some(Ok(table_name)) =
+                    create_table_name(query.data()).map(std::str::from_utf8)
+                {
+                    if !table_rows.contains_key(table_name) {
+                        let _ = table_rows.insert(table_name.to_string(), 0);
+                    }
+                }
+
+                consumed_buffer_size += query.data().len();
+                total_transferred_bytes += query.data().len();
+                progress_callback(
+                    total_transferred_bytes,
+                    buffer_size * (chunk_part as usize + 1),
+                );
+                queries.push(query);
+            });
+
+            // the transformers are timed by themselves
+            let source_duration = read_start
+                .elapsed()
+                .saturating_sub(send_wait)
+                .saturating_sub(
+                    self.timings
+                        .duration(Stage::Transform)
+                        .saturating_sub(transform_duration),
+                );
+
+            self.timings.add(Stage::Source, source_duration);
+
+            // E.g. the source has been stopped by the same Ctrl-C
+            let interrupted = interrupted || self.interrupted.load(Ordering::SeqCst);
+
+            if !max_size_exceeded && !interrupted {
+                progress_callback(total_transferred_bytes, total_transferred_bytes);
+
+                // the dump has neither rows nor constraints
+                if let Some(unchanged_table_chunks) = unchanged_table_chunks {
+                    if !queries.is_empty() {
+                        chunk_part += 1;
+                        let queries = std::mem::take(&mut queries);
+                        let _ = tx.send(Message::Data((chunk_part, Part::Queries(queries))));
+                    }
+
+                    let first_part = chunk_part + 1;
+                    let chunks =
+                        shared_chunks(unchanged_table_chunks, &mut chunk_part, &mut table_parts);
+                    let _ = tx.send(Message::Data((first_part, Part::Chunks(chunks))));
+                }
+
+                chunk_part += 1;
+                let _ = tx.send(Message::Data((chunk_part, Part::Queries(queries))));
+            }
+
+            let _ = tx.send(Message::EOF);
+
+            // the last parts are still uploading - report their progress until the upload thread is done
+            for (uploaded_bytes, part_size) in upload_rx {
+                progress_callback(uploaded_bytes, part_size);
+            }
+
+            // wait for end of upload execution
+            if let Err(err) = join_handle.join() {
+                panic!("{:?}", err);
+            }
+
+            Dump {
+                read_result,
+                max_size_exceeded,
+                interrupted,
+                table_rows,
+                table_parts,
+            }
+        })
+    }
+
+    /// the transformed columns and their transformer - E.g. `public.users.email: email`
+    fn transformers(&self) -> Vec<String> {
+        let mut transformers = self
+            .options
+            .transformers
+            .iter()
+            .map(|transformer| {
+                format!(
+                    "{}: {}",
+                    transformer.database_and_table_and_column_name(),
+                    transformer.id()
+                )
+            })
+            .collect::<Vec<_>>();
+        transformers.sort();
+        transformers
+    }
+
+    /// the checksum of each table - only with `set_table_checksums`
+    fn checksums(&self) -> Option<BTreeMap<String, String>> {
+        self.table_checksums.as_ref().map(|table_checksums| {
+            table_checksums
+                .iter()
+                .map(|(table_name, table_checksum)| {
+                    (table_name.clone(), table_checksum.checksum.clone())
+                })
+                .collect::<BTreeMap<_, _>>()
+        })
+    }
+
+    /// the chunks of the last backup holding the rows of the tables with the same checksum
+    fn unchanged_table_chunks(&self) -> Result<BTreeMap<String, Vec<BackupChunk>>, Error> {
+        match self.checksums() {
+            Some(table_checksums) => self
+                .bridge
+                .unchanged_table_chunks(&table_checksums, &self.transformers()),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    /// `backup` renewing the lock until the backup is written - however long the backup takes
+    fn backup_with_renewed_lock<F: FnMut(TransferredBytes, MaxBytes)>(
+        &self,
+        progress_callback: &mut F,
+        unchanged_table_chunks: &BTreeMap<String, Vec<BackupChunk>>,
+    ) -> Result<(), ReplibyteError> {
+        thread::scope(|scope| {
+            let (done_tx, done_rx) = mpsc::channel::<()>();
+            let bridge = &self.bridge;
+            let renewal_interval = self.run_lock_renewal_interval;
+
+            let _ = scope.spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = done_rx.recv_timeout(renewal_interval) {
+                    if let Err(err) = bridge.renew_run_lock() {
+                        warn!("can't renew the bridge lock: {}", err);
+                    }
+                }
+            });
+
+            let result = self.backup(progress_callback, unchanged_table_chunks);
+            drop(done_tx);
+            result
+        })
+    }
+
+    /// dump the source into the bridge and record the content of the backup
+    fn backup<F: FnMut(TransferredBytes, MaxBytes)>(
+        &self,
+        progress_callback: &mut F,
+        unchanged_table_chunks: &BTreeMap<String, Vec<BackupChunk>>,
+    ) -> Result<(), ReplibyteError> {
+        let transformers = self.transformers();
+        let table_checksums = self.checksums();
+
+        // the partial backup is discarded and the dump restarted when the source fails - E.g. a server-side timeout
+        let mut attempt = 0usize;
+        let dump = loop {
+            let dump = self.dump(progress_callback, unchanged_table_chunks);
+
+            match &dump.read_result {
+                Err(err)
+                    if !dump.max_size_exceeded
+                        && !dump.interrupted
+                        && attempt < self.source_retries =>
+                {
+                    attempt += 1;
+                    warn!(
+                        "the source failed: {} - restarting the dump from scratch ({}/{})",
+                        err, attempt, self.source_retries
+                    );
+                    self.bridge.abort_write().map_err(ReplibyteError::Bridge)?;
+                }
+                _ => break dump,
+            }
+        };
+
+        if dump.interrupted {
+            // clean up the parts already uploaded
+            self.bridge.abort_write().map_err(ReplibyteError::Bridge)?;
+
+            return Err(ReplibyteError::Source(Error::new(
+                ErrorKind::Interrupted,
+                "backup interrupted: the uploaded parts are deleted",
+            )));
+        }
+
+        if dump.max_size_exceeded {
+            // clean up the parts already uploaded
+            self.bridge.abort_write().map_err(ReplibyteError::Bridge)?;
+
+            return Err(ReplibyteError::Source(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "backup aborted: the dump exceeds the max size of {}",
+                    to_human_readable_unit(self.max_size.unwrap_or_default())
+                ),
+            )));
+        }
+
+        let _ = dump.read_result.map_err(ReplibyteError::Source)?;
+
+        // the rows of the unchanged tables are not counted by the dump
+        let mut table_rows = dump.table_rows;
+        if let Some(checksums) = &self.table_checksums {
+            for table_name in unchanged_table_chunks.keys() {
+                if let Some(table_checksum) = checksums.get(table_name) {
+                    let _ = table_rows.insert(table_name.clone(), table_checksum.rows);
+                }
+            }
+        }
+
+        let tables = table_rows.keys().cloned().collect::<Vec<_>>();
+        self.table_rows.set(table_rows);
+
+        self.bridge
+            .write_backup_content(BackupContent {
+                tables: match tables.is_empty() {
+                    true => None,
+                    false => Some(tables),
+                },
+                transformers,
+                table_parts: table_checksums.as_ref().map(|_| dump.table_parts),
+                table_checksums,
+            })
+            .map_err(ReplibyteError::Bridge)
     }
 }
 
@@ -43,78 +576,829 @@ where
     fn run<F: FnMut(TransferredBytes, MaxBytes)>(
         mut self,
         mut progress_callback: F,
-    ) -> Result<(), Error> {
+    ) -> Result<(), ReplibyteError> {
         // initialize the source
-        let _ = self.source.init()?;
+        let _ = self.source.init().map_err(ReplibyteError::Source)?;
 
         // initialize the bridge
-        let _ = self.bridge.init()?;
+        let _ = self.bridge.init().map_err(ReplibyteError::Bridge)?;
 
-        let (tx, rx) = mpsc::sync_channel::<Message<DataMessage>>(1);
-        let bridge = self.bridge;
+        // a second backup into the same bridge fails fast
+        let _ = self
+            .bridge
+            .acquire_run_lock()
+            .map_err(ReplibyteError::Bridge)?;
 
-        let join_handle = thread::spawn(move || {
-            // managing Bridge (S3) upload here
-            let bridge = bridge;
+        let result = match self.unchanged_table_chunks() {
+            Ok(unchanged_table_chunks) => {
+                // the rows of the unchanged tables are not dumped - their parts are shared with the last backup
+                self.source
+                    .set_excluded_table_data(unchanged_table_chunks.keys().cloned().collect());
 
-            loop {
-                let (chunk_part, queries) = match rx.recv() {
-                    Ok(Message::Data((chunk_part, queries))) => (chunk_part, queries),
-                    Ok(Message::EOF) => break,
-                    Err(err) => panic!("{:?}", err), // FIXME what should I do here?
-                };
+                self.backup_with_renewed_lock(&mut progress_callback, &unchanged_table_chunks)
+            }
+            Err(err) => Err(ReplibyteError::Bridge(err)),
+        };
 
-                let _ = match bridge.write(chunk_part, to_bytes(queries)) {
-                    Ok(_) => {}
-                    Err(err) => {
-                        panic!("{:?}", err);
-                    } // FIXME what should we do?
-                };
+        // the lock expires by itself when it can't be released
+        if let Err(err) = self.bridge.release_run_lock() {
+            warn!("can't release the bridge lock: {}", err);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::{Error, ErrorKind};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::bridge::in_memory::InMemoryBridge;
+    use crate::bridge::{BackupChunk, BackupContent};
+    use crate::config::SourceConfig;
+    use crate::connector::Connector;
+    use crate::destination::postgres::TableChecksum;
+    use crate::error::ReplibyteError;
+    use crate::metrics::TableRows;
+    use crate::source::{Source, SourceOptions, SourceRegistry};
+    use crate::tasks::full_backup::FullBackupTask;
+    use crate::tasks::Task;
+    use crate::timings::{Stage, Timings};
+    use crate::transformer::random::RandomTransformer;
+    use crate::transformer::Transformer;
+    use crate::types::{Bytes, OriginalQuery, Query};
+
+    /// source returning the same INSERT INTO queries
+    struct InMemorySource {
+        queries: usize,
+    }
+
+    impl Connector for InMemorySource {
+        fn init(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl Source for InMemorySource {
+        fn read<F: FnMut(OriginalQuery, Query)>(
+            &self,
+            _: SourceOptions,
+            mut query_callback: F,
+        ) -> Result<(), Error> {
+            for i in 0..self.queries {
+                let query = format!("INSERT INTO public.users (id) VALUES ({});", i);
+                query_callback(
+                    Query(query.as_bytes().to_vec()),
+                    Query(query.as_bytes().to_vec()),
+                );
+            }
+
+            Ok(())
+        }
+    }
+
+    /// source waiting for the first part to be uploaded before returning its last query
+    struct SlowSource {
+        parts: Arc<Mutex<Vec<Bytes>>>,
+        finished: Arc<AtomicBool>,
+    }
+
+    impl Connector for SlowSource {
+        fn init(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl Source for SlowSource {
+        fn read<F: FnMut(OriginalQuery, Query)>(
+            &self,
+            _: SourceOptions,
+            mut query_callback: F,
+        ) -> Result<(), Error> {
+            for i in 0..10 {
+                let query = format!("INSERT INTO public.users (id) VALUES ({});", i);
+                query_callback(
+                    Query(query.as_bytes().to_vec()),
+                    Query(query.as_bytes().to_vec()),
+                );
+            }
+
+            let start = Instant::now();
+            while self.parts.lock().unwrap().is_empty() && start.elapsed() < Duration::from_secs(5)
+            {
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            self.finished.store(true, Ordering::SeqCst);
+
+            Ok(())
+        }
+    }
+
+    /// source returning the queries of a PostgreSQL dump with two tables - without the rows of the excluded tables
+    struct DumpSource {
+        excluded_table_data: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Connector for DumpSource {
+        fn init(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl Source for DumpSource {
+        fn read<F: FnMut(OriginalQuery, Query)>(
+            &self,
+            _: SourceOptions,
+            mut query_callback: F,
+        ) -> Result<(), Error> {
+            let dump = "CREATE TABLE public.orders (id integer);
+CREATE TABLE public.users (id integer, name text);
+INSERT INTO public.orders (id) VALUES (1);
+INSERT INTO public.users (id, name) VALUES (1, 'John');
+INSERT INTO public.users (id, name) VALUES (2, 'Jane');
+ALTER TABLE ONLY public.users ADD CONSTRAINT users_pkey PRIMARY KEY (id);
+";
+            let excluded_table_data = self.excluded_table_data.lock().unwrap();
+
+            for query in dump.lines() {
+                if excluded_table_data
+                    .iter()
+                    .any(|table| query.starts_with(&format!("INSERT INTO {} ", table)))
+                {
+                    continue;
+                }
+
+                query_callback(
+                    Query(query.as_bytes().to_vec()),
+                    Query(query.as_bytes().to_vec()),
+                );
             }
+
+            Ok(())
+        }
+
+        fn set_excluded_table_data(&mut self, tables: Vec<String>) {
+            *self.excluded_table_data.lock().unwrap() = tables;
+        }
+    }
+
+    fn backup(max_size: Option<usize>) -> (Result<(), ReplibyteError>, InMemoryBridge) {
+        let bridge = InMemoryBridge::default();
+        let recorder = InMemoryBridge {
+            parts: bridge.parts.clone(),
+            aborted: bridge.aborted.clone(),
+            content: bridge.content.clone(),
+            ..Default::default()
+        };
+
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let mut task = FullBackupTask::new(InMemorySource { queries: 100 }, bridge, options);
+        task.set_max_size(max_size);
+
+        (task.run(|_, _| {}), recorder)
+    }
+
+    #[test]
+    fn backup_without_max_size() {
+        let (result, bridge) = backup(None);
+
+        assert!(result.is_ok());
+        assert!(!*bridge.aborted.lock().unwrap());
+        assert_eq!(bridge.parts.lock().unwrap().len(), 1);
+
+        let (result, bridge) = backup(Some(1_000_000));
+        assert!(result.is_ok());
+        assert!(!*bridge.aborted.lock().unwrap());
+    }
+
+    #[test]
+    fn backup_records_source_timings() {
+        let timings = Arc::new(Timings::default());
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let mut task = FullBackupTask::new(
+            InMemorySource { queries: 100 },
+            InMemoryBridge::default(),
+            options,
+        );
+        task.set_timings(timings.clone());
+
+        assert!(task.run(|_, _| {}).is_ok());
+        assert!(timings.duration(Stage::Source) > Duration::ZERO);
+        // no transformers
+        assert_eq!(timings.duration(Stage::Transform), Duration::ZERO);
+    }
+
+    #[test]
+    fn backup_reports_the_upload_progress_of_the_parts() {
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let bridge = InMemoryBridge {
+            upload_requests: 2,
+            ..Default::default()
+        };
+        let parts = bridge.parts.clone();
+
+        let task = FullBackupTask::new(InMemorySource { queries: 100 }, bridge, options);
+
+        let mut progress = vec![];
+        assert!(task
+            .run(|transferred_bytes, max_bytes| progress.push((transferred_bytes, max_bytes)))
+            .is_ok());
+
+        let part_size = parts.lock().unwrap()[0].len();
+        assert!(progress.ends_with(&[(part_size / 2, part_size), (part_size, part_size)]));
+    }
+
+    #[test]
+    fn backup_aborts_when_max_size_is_exceeded() {
+        let (result, bridge) = backup(Some(100));
+
+        assert!(matches!(result, Err(ReplibyteError::Source(_))));
+        assert!(*bridge.aborted.lock().unwrap());
+        assert!(bridge.parts.lock().unwrap().is_empty());
+        assert!(bridge.content.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn backup_aborts_when_interrupted() {
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let bridge = InMemoryBridge::default();
+        let parts = bridge.parts.clone();
+        let aborted = bridge.aborted.clone();
+        let content = bridge.content.clone();
+
+        let mut task = FullBackupTask::new(InMemorySource { queries: 100 }, bridge, options);
+        task.set_interrupted(Arc::new(AtomicBool::new(true)));
+
+        match task.run(|_, _| {}) {
+            Err(ReplibyteError::Source(err)) => assert_eq!(err.kind(), ErrorKind::Interrupted),
+            _ => panic!("the backup must be interrupted"),
+        }
+
+        assert!(*aborted.lock().unwrap());
+        assert!(parts.lock().unwrap().is_empty());
+        assert!(content.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn backup_records_its_content() {
+        let bridge = InMemoryBridge::default();
+        let content = bridge.content.clone();
+
+        let transformers: Vec<Box<dyn Transformer>> =
+            vec![Box::new(RandomTransformer::new("public", "users", "name"))];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let table_rows = Arc::new(TableRows::default());
+
+        let mut task = FullBackupTask::new(InMemorySource { queries: 100 }, bridge, options);
+        task.set_table_rows(table_rows.clone());
+        assert!(task.run(|_, _| {}).is_ok());
+
+        assert_eq!(
+            table_rows.rows(),
+            BTreeMap::from([("public.users".to_string(), 100)])
+        );
+        assert_eq!(
+            *content.lock().unwrap(),
+            Some(BackupContent {
+                tables: Some(vec!["public.users".to_string()]),
+                transformers: vec!["public.users.name: random".to_string()],
+                table_checksums: None,
+                table_parts: None,
+            })
+        );
+    }
+
+    #[test]
+    fn backup_uploads_before_the_source_finishes() {
+        let bridge = InMemoryBridge::default();
+        let parts = bridge.parts.clone();
+        let parts_written_before_source_finished =
+            bridge.parts_written_before_source_finished.clone();
+
+        let source = SlowSource {
+            parts: bridge.parts.clone(),
+            finished: bridge.source_finished.clone(),
+        };
+
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let mut task = FullBackupTask::new(source, bridge, options);
+        // ~3 queries per part
+        task.set_chunk_size(128);
+
+        assert!(task.run(|_, _| {}).is_ok());
+        assert!(*parts_written_before_source_finished.lock().unwrap() > 0);
+
+        // nothing is lost
+        let data = parts.lock().unwrap().concat();
+        assert_eq!(String::from_utf8(data).unwrap().lines().count(), 10);
+    }
+
+    #[test]
+    fn backup_with_a_registered_source() {
+        let mut registry = SourceRegistry::default();
+        registry.register("in-memory", |config| {
+            assert_eq!(config.connection_uri, "in-memory://localhost:1234");
+            Ok(Box::new(InMemorySource { queries: 10 }))
         });
 
-        // buffer of 100MB in memory to use and re-use to upload data into bridge
-        let buffer_size = 100 * 1024 * 1024;
-        let mut queries = vec![];
-        let mut consumed_buffer_size = 0usize;
-        let mut total_transferred_bytes = 0usize;
-        let mut chunk_part = 0u16;
-
-        // init progress
-        progress_callback(
-            total_transferred_bytes,
-            buffer_size * (chunk_part as usize + 1),
+        let config: SourceConfig = serde_yaml::from_str(
+            "type: in-memory\nconnection_uri: in-memory://localhost:1234\ntransformers: []\n",
+        )
+        .unwrap();
+        assert!(registry.source("clickhouse", &config).is_err());
+
+        let source = registry
+            .source(config.source_type.as_deref().unwrap(), &config)
+            .unwrap();
+
+        let bridge = InMemoryBridge::default();
+        let parts = bridge.parts.clone();
+
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let task = FullBackupTask::new(source, bridge, options);
+        assert!(task.run(|_, _| {}).is_ok());
+
+        let data = parts.lock().unwrap().concat();
+        assert_eq!(String::from_utf8(data).unwrap().lines().count(), 10);
+    }
+
+    #[test]
+    fn backup_with_a_part_per_table() {
+        struct TwoTablesSource;
+
+        impl Connector for TwoTablesSource {
+            fn init(&mut self) -> Result<(), Error> {
+                Ok(())
+            }
+        }
+
+        impl Source for TwoTablesSource {
+            fn read<F: FnMut(OriginalQuery, Query)>(
+                &self,
+                _: SourceOptions,
+                mut query_callback: F,
+            ) -> Result<(), Error> {
+                let queries = [
+                    "CREATE TABLE public.users (id integer);",
+                    "CREATE TABLE public.orders (id integer);",
+                    "INSERT INTO public.users (id) VALUES (1);",
+                    "INSERT INTO public.users (id) VALUES (2);",
+                    "INSERT INTO public.orders (id) VALUES (1);",
+                    "ALTER TABLE public.orders ADD PRIMARY KEY (id);",
+                ];
+
+                for query in queries {
+                    query_callback(
+                        Query(query.as_bytes().to_vec()),
+                        Query(query.as_bytes().to_vec()),
+                    );
+                }
+
+                Ok(())
+            }
+        }
+
+        let bridge = InMemoryBridge::default();
+        let parts = bridge.parts.clone();
+
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let mut task = FullBackupTask::new(TwoTablesSource, bridge, options);
+        task.set_part_per_table(true);
+        assert!(task.run(|_, _| {}).is_ok());
+
+        let parts = parts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|part| String::from_utf8(part.clone()).unwrap().lines().count())
+            .collect::<Vec<_>>();
+
+        // the schema, the users and the orders with the constraints
+        assert_eq!(parts, vec![2, 2, 2]);
+    }
+
+    #[test]
+    fn backup_buffers_a_bounded_number_of_parts() {
+        /// source recording the max number of queries read but not uploaded yet
+        struct CountingSource {
+            parts: Arc<Mutex<Vec<Bytes>>>,
+            max_in_memory: Arc<Mutex<usize>>,
+        }
+
+        impl Connector for CountingSource {
+            fn init(&mut self) -> Result<(), Error> {
+                Ok(())
+            }
+        }
+
+        impl Source for CountingSource {
+            fn read<F: FnMut(OriginalQuery, Query)>(
+                &self,
+                _: SourceOptions,
+                mut query_callback: F,
+            ) -> Result<(), Error> {
+                for i in 0..300 {
+                    let query = format!("INSERT INTO public.users (id) VALUES ({});", i);
+                    query_callback(
+                        Query(query.as_bytes().to_vec()),
+                        Query(query.as_bytes().to_vec()),
+                    );
+
+                    // one query per line
+                    let uploaded_queries = String::from_utf8(self.parts.lock().unwrap().concat())
+                        .unwrap()
+                        .lines()
+                        .count();
+                    let mut max_in_memory = self.max_in_memory.lock().unwrap();
+                    *max_in_memory = (*max_in_memory).max(i + 1 - uploaded_queries);
+                }
+
+                Ok(())
+            }
+        }
+
+        let bridge = InMemoryBridge {
+            upload_delay: Duration::from_millis(2),
+            ..Default::default()
+        };
+        let parts = bridge.parts.clone();
+
+        let max_in_memory = Arc::new(Mutex::new(0));
+        let source = CountingSource {
+            parts: bridge.parts.clone(),
+            max_in_memory: max_in_memory.clone(),
+        };
+
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let mut task = FullBackupTask::new(source, bridge, options);
+        // ~3 queries per part
+        task.set_chunk_size(128);
+        task.set_buffered_parts(Some(2));
+        assert!(task.run(|_, _| {}).is_ok());
+
+        // the buffered parts, the part being uploaded and the part being read - at most 3 queries each
+        let max_in_memory = *max_in_memory.lock().unwrap();
+        assert!(max_in_memory > 0);
+        assert!(max_in_memory <= (2 + 2) * 3);
+
+        // nothing is lost
+        let data = parts.lock().unwrap().concat();
+        assert_eq!(String::from_utf8(data).unwrap().lines().count(), 300);
+    }
+
+    #[test]
+    fn backup_restarts_the_dump_when_the_source_fails() {
+        /// source dropping its connection mid-dump on its first `failures` reads
+        struct FlakySource {
+            failures: usize,
+            reads: Mutex<usize>,
+        }
+
+        impl Connector for FlakySource {
+            fn init(&mut self) -> Result<(), Error> {
+                Ok(())
+            }
+        }
+
+        impl Source for FlakySource {
+            fn read<F: FnMut(OriginalQuery, Query)>(
+                &self,
+                _: SourceOptions,
+                mut query_callback: F,
+            ) -> Result<(), Error> {
+                let mut reads = self.reads.lock().unwrap();
+                *reads += 1;
+
+                for i in 0..100 {
+                    if *reads <= self.failures && i == 50 {
+                        return Err(Error::new(
+                            ErrorKind::Other,
+                            "server closed the connection unexpectedly",
+                        ));
+                    }
+
+                    let query = format!("INSERT INTO public.users (id) VALUES ({});", i);
+                    query_callback(
+                        Query(query.as_bytes().to_vec()),
+                        Query(query.as_bytes().to_vec()),
+                    );
+                }
+
+                Ok(())
+            }
+        }
+
+        let backup = |failures: usize, source_retries: Option<usize>| {
+            let bridge = InMemoryBridge::default();
+            let recorder = InMemoryBridge {
+                parts: bridge.parts.clone(),
+                aborted: bridge.aborted.clone(),
+                ..Default::default()
+            };
+
+            let transformers = vec![];
+            let skip_config = vec![];
+            let options = SourceOptions {
+                transformers: &transformers,
+                skip_config: &skip_config,
+            };
+
+            let source = FlakySource {
+                failures,
+                reads: Mutex::new(0),
+            };
+
+            let table_rows = Arc::new(TableRows::default());
+            let mut task = FullBackupTask::new(source, bridge, options);
+            task.set_source_retries(source_retries);
+            task.set_table_rows(table_rows.clone());
+
+            (task.run(|_, _| {}), recorder, table_rows)
+        };
+
+        // the partial dump is discarded - only the rows of the last dump are uploaded
+        let (result, bridge, table_rows) = backup(2, Some(2));
+        assert!(result.is_ok());
+        assert!(*bridge.aborted.lock().unwrap());
+        let data = bridge.parts.lock().unwrap().concat();
+        assert_eq!(String::from_utf8(data).unwrap().lines().count(), 100);
+        assert_eq!(
+            table_rows.rows(),
+            BTreeMap::from([("public.users".to_string(), 100)])
         );
 
-        let _ = self.source.read(self.options, |original_query, query| {
-            if consumed_buffer_size + query.data().len() > buffer_size {
-                chunk_part += 1;
-                consumed_buffer_size = 0;
-                // TODO .clone() - look if we do not consume more mem
+        // out of retries
+        let (result, _, _) = backup(2, Some(1));
+        assert!(matches!(result, Err(ReplibyteError::Source(_))));
+
+        // no retry by default
+        let (result, bridge, _) = backup(1, None);
+        assert!(matches!(result, Err(ReplibyteError::Source(_))));
+        assert!(!*bridge.aborted.lock().unwrap());
+    }
 
-                let message = Message::Data((chunk_part, queries.clone()));
+    #[test]
+    fn backup_is_rejected_while_another_backup_holds_the_lock() {
+        /// source starting a second backup into the same bridge while it is read
+        struct ConcurrentSource {
+            run_lock: Arc<Mutex<Option<String>>>,
+            second_result: Arc<Mutex<Option<Result<(), ReplibyteError>>>>,
+        }
 
-                let _ = tx.send(message); // FIXME catch SendError?
-                let _ = queries.clear();
+        impl Connector for ConcurrentSource {
+            fn init(&mut self) -> Result<(), Error> {
+                Ok(())
             }
+        }
 
-            consumed_buffer_size += query.data().len();
-            total_transferred_bytes += query.data().len();
-            progress_callback(
-                total_transferred_bytes,
-                buffer_size * (chunk_part as usize + 1),
-            );
-            queries.push(query);
-        });
+        impl Source for ConcurrentSource {
+            fn read<F: FnMut(OriginalQuery, Query)>(
+                &self,
+                options: SourceOptions,
+                query_callback: F,
+            ) -> Result<(), Error> {
+                let bridge = InMemoryBridge {
+                    run_lock: self.run_lock.clone(),
+                    name: "backup-2".to_string(),
+                    ..Default::default()
+                };
 
-        progress_callback(total_transferred_bytes, total_transferred_bytes);
+                let task = FullBackupTask::new(InMemorySource { queries: 10 }, bridge, options);
+                *self.second_result.lock().unwrap() = Some(task.run(|_, _| {}));
 
-        chunk_part += 1;
-        let _ = tx.send(Message::Data((chunk_part, queries)));
-        let _ = tx.send(Message::EOF);
-        // wait for end of upload execution
-        let _ = join_handle.join(); // FIXME catch result here
+                InMemorySource { queries: 10 }.read(options, query_callback)
+            }
+        }
+
+        let run_lock = Arc::new(Mutex::new(None));
+        let second_result = Arc::new(Mutex::new(None));
+        let bridge = InMemoryBridge {
+            run_lock: run_lock.clone(),
+            name: "backup-1".to_string(),
+            ..Default::default()
+        };
+        let source = ConcurrentSource {
+            run_lock: run_lock.clone(),
+            second_result: second_result.clone(),
+        };
+
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let task = FullBackupTask::new(source, bridge, options);
+        assert!(task.run(|_, _| {}).is_ok());
+
+        match second_result.lock().unwrap().take() {
+            Some(Err(ReplibyteError::Bridge(err))) => {
+                assert!(err.to_string().contains("another backup is in progress"))
+            }
+            _ => panic!("the second backup must be rejected"),
+        }
+
+        // released at the end of the first backup
+        assert!(run_lock.lock().unwrap().is_none());
+    }
 
-        Ok(())
+    #[test]
+    fn backup_renews_the_lock_while_it_runs() {
+        let bridge = InMemoryBridge {
+            upload_delay: Duration::from_millis(20),
+            name: "backup-1".to_string(),
+            ..Default::default()
+        };
+        let run_lock = bridge.run_lock.clone();
+        let run_lock_acquisitions = bridge.run_lock_acquisitions.clone();
+
+        let transformers = vec![];
+        let skip_config = vec![];
+        let options = SourceOptions {
+            transformers: &transformers,
+            skip_config: &skip_config,
+        };
+
+        let mut task = FullBackupTask::new(InMemorySource { queries: 10 }, bridge, options);
+        // a part per query - the upload of the parts outlasts the renewal interval
+        task.set_chunk_size(1);
+        task.run_lock_renewal_interval = Duration::from_millis(5);
+        assert!(task.run(|_, _| {}).is_ok());
+
+        assert!(*run_lock_acquisitions.lock().unwrap() > 1);
+        assert!(run_lock.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn backup_shares_the_parts_of_the_unchanged_tables() {
+        let table_checksums = BTreeMap::from([
+            (
+                "public.orders".to_string(),
+                TableChecksum {
+                    rows: 1,
+                    checksum: "orders-2".to_string(),
+                },
+            ),
+            (
+                "public.users".to_string(),
+                TableChecksum {
+                    rows: 2,
+                    checksum: "users-1".to_string(),
+                },
+            ),
+        ]);
+
+        let excluded_table_data = Arc::new(Mutex::new(vec![]));
+        let backup = |bridge: InMemoryBridge, table_rows: Arc<TableRows>| {
+            let transformers = vec![];
+            let skip_config = vec![];
+            let options = SourceOptions {
+                transformers: &transformers,
+                skip_config: &skip_config,
+            };
+
+            let source = DumpSource {
+                excluded_table_data: excluded_table_data.clone(),
+            };
+            let mut task = FullBackupTask::new(source, bridge, options);
+            task.set_table_checksums(Some(table_checksums.clone()));
+            task.set_table_rows(table_rows);
+            task.run(|_, _| {})
+        };
+
+        // first backup - every table is dumped, one part per table
+        let bridge = InMemoryBridge::default();
+        let parts = bridge.parts.clone();
+        let content = bridge.content.clone();
+        assert!(backup(bridge, Arc::new(TableRows::default())).is_ok());
+
+        assert!(excluded_table_data.lock().unwrap().is_empty());
+        let parts = parts.lock().unwrap();
+        assert_eq!(parts.len(), 4);
+        assert!(String::from_utf8_lossy(&parts[2]).contains("'John'"));
+        assert!(!String::from_utf8_lossy(&parts[2]).contains("ADD CONSTRAINT"));
+
+        let content = content.lock().unwrap().take().unwrap();
+        assert_eq!(
+            content.table_checksums,
+            Some(BTreeMap::from([
+                ("public.orders".to_string(), "orders-2".to_string()),
+                ("public.users".to_string(), "users-1".to_string()),
+            ]))
+        );
+        assert_eq!(
+            content.table_parts,
+            Some(BTreeMap::from([
+                ("public.orders".to_string(), vec![2]),
+                ("public.users".to_string(), vec![3]),
+            ]))
+        );
+
+        // second backup - the users have not changed since the first one
+        let users_chunk = BackupChunk {
+            part: 3,
+            hash: "users".to_string(),
+            size: 100,
+            uncompressed_size: Some(1000),
+        };
+        let bridge = InMemoryBridge {
+            unchanged_table_chunks: BTreeMap::from([(
+                "public.users".to_string(),
+                vec![users_chunk.clone()],
+            )]),
+            ..Default::default()
+        };
+        let parts = bridge.parts.clone();
+        let shared_chunks = bridge.shared_chunks.clone();
+        let content = bridge.content.clone();
+        let table_rows = Arc::new(TableRows::default());
+        assert!(backup(bridge, table_rows.clone()).is_ok());
+
+        // the users are defined but their rows are neither dumped nor uploaded again
+        assert_eq!(
+            *excluded_table_data.lock().unwrap(),
+            vec!["public.users".to_string()]
+        );
+        let data = String::from_utf8(parts.lock().unwrap().concat()).unwrap();
+        assert!(data.contains("INSERT INTO public.orders"));
+        assert!(!data.contains("INSERT INTO public.users"));
+        assert!(data.contains("ADD CONSTRAINT"));
+
+        // shared before the rows of the other tables
+        assert_eq!(*shared_chunks.lock().unwrap(), vec![(2, vec![users_chunk])]);
+        assert_eq!(
+            content.lock().unwrap().as_ref().unwrap().table_parts,
+            Some(BTreeMap::from([
+                ("public.orders".to_string(), vec![3]),
+                ("public.users".to_string(), vec![2]),
+            ]))
+        );
+        assert_eq!(
+            table_rows.rows(),
+            BTreeMap::from([
+                ("public.orders".to_string(), 1),
+                ("public.users".to_string(), 2),
+            ])
+        );
     }
 }
@@ -1,26 +1,50 @@
-use std::io::Error;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, Error, ErrorKind};
+use std::path::Path;
 use std::sync::mpsc;
 use std::thread;
 
-use crate::bridge::{Bridge, ReadOptions};
+use log::error;
+
+use crate::bridge::{Backup, Bridge, ReadOptions};
+use crate::config::SkipConfig;
+use crate::connector::Engine;
+use crate::destination::postgres::{
+    insert_into_table_name, remap_columns, retarget_schema, selected_query, split_queries,
+    ColumnMap, PreservedTables, SchemaMapping,
+};
 use crate::destination::Destination;
+use crate::error::ReplibyteError;
+use crate::runtime::worker_pool;
+use crate::source::postgres::read_and_transform;
+use crate::source::SourceOptions;
 use crate::tasks::{MaxBytes, Message, Task, TransferredBytes};
+use crate::transformer::Transformer;
 use crate::types::Bytes;
 
 /// FullRestoreTask is a wrapping struct to execute the synchronization between a *Bridge* and a *Source*.
 pub struct FullRestoreTask<D, B>
 where
-    D: Destination,
+    D: Destination + Sync,
     B: Bridge + 'static,
 {
     destination: D,
     bridge: B,
     read_options: ReadOptions,
+    jobs: usize,
+    column_map: ColumnMap,
+    schema_mapping: Option<SchemaMapping>,
+    transformers: Vec<Box<dyn Transformer>>,
+    skip_config: Vec<SkipConfig>,
+    preserve_tables: Vec<String>,
+    tables: Vec<String>,
 }
 
 impl<D, B> FullRestoreTask<D, B>
 where
-    D: Destination,
+    D: Destination + Sync,
     B: Bridge + 'static,
 {
     pub fn new(destination: D, bridge: B, read_options: ReadOptions) -> Self {
@@ -28,31 +52,115 @@ where
             destination,
             bridge,
             read_options,
+            jobs: 1,
+            column_map: ColumnMap::new(),
+            schema_mapping: None,
+            transformers: vec![],
+            skip_config: vec![],
+            preserve_tables: vec![],
+            tables: vec![],
         }
     }
+
+    /// set the number of workers applying the INSERT INTO queries of independent tables concurrently.
+    /// Only valid for SQL dumps - the data is written as it comes when `jobs` is 1.
+    /// The workers running at the same time are capped by the worker pool (`--threads`).
+    pub fn set_jobs(&mut self, jobs: usize) {
+        self.jobs = jobs.max(1);
+    }
+
+    /// rename the columns of the INSERT INTO and COPY queries - see `remap_columns`.
+    /// Only valid for SQL dumps.
+    pub fn set_column_map(&mut self, column_map: ColumnMap) {
+        self.column_map = column_map;
+    }
+
+    /// move the objects of a schema into another one - see `retarget_schema`.
+    /// Only valid for SQL dumps, the schema is rewritten after the columns are remapped
+    pub fn set_schema_mapping(&mut self, schema_mapping: Option<SchemaMapping>) {
+        self.schema_mapping = schema_mapping;
+    }
+
+    /// transform the INSERT INTO queries and drop the ones of the skipped tables while restoring -
+    /// E.g. to anonymize a raw backup. Only valid for SQL dumps, the columns are remapped after being transformed
+    pub fn set_transformers(
+        &mut self,
+        transformers: Vec<Box<dyn Transformer>>,
+        skip_config: Vec<SkipConfig>,
+    ) {
+        self.transformers = transformers;
+        self.skip_config = skip_config;
+    }
+
+    /// drop the rows and the sequence values of the tables kept by the destination - see `PreservedTables`.
+    /// Only valid for SQL dumps, the tables are matched after being moved into the new schema
+    pub fn set_preserve_tables(&mut self, preserve_tables: Vec<String>) {
+        self.preserve_tables = preserve_tables;
+    }
+
+    /// restore the rows of these tables only - E.g. `public.users`. The other INSERT INTO queries are dropped, the schema
+    /// is restored as is. Only valid for SQL dumps, the tables are matched as named in the backup
+    pub fn set_tables(&mut self, tables: Vec<String>) {
+        self.tables = tables;
+    }
+}
+
+/// read the tables to restore from a file - one `<database>.<table>` per line (E.g. `public.users`),
+/// the empty lines and the `#` comments are ignored
+pub fn read_tables_file(path: &Path) -> Result<Vec<String>, Error> {
+    let content = fs::read_to_string(path).map_err(|err| {
+        Error::new(
+            err.kind(),
+            format!("can't read the tables file '{}': {}", path.display(), err),
+        )
+    })?;
+
+    let tables = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+
+    if tables.is_empty() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("the tables file '{}' lists no table", path.display()),
+        ));
+    }
+
+    Ok(tables)
 }
 
 impl<D, B> Task for FullRestoreTask<D, B>
 where
-    D: Destination,
+    D: Destination + Sync,
     B: Bridge + 'static,
 {
     fn run<F: FnMut(TransferredBytes, MaxBytes)>(
         mut self,
         mut progress_callback: F,
-    ) -> Result<(), Error> {
+    ) -> Result<(), ReplibyteError> {
         // initialize the destination
-        let _ = self.destination.init()?;
+        let _ = self
+            .destination
+            .init()
+            .map_err(ReplibyteError::Destination)?;
 
         // initialize the bridge
-        let _ = self.bridge.init()?;
+        let _ = self.bridge.init().map_err(ReplibyteError::Bridge)?;
 
         // bound to 1 to avoid eating too much memory if we download the dump faster than we ingest it
-        let (tx, rx) = mpsc::sync_channel::<Message<Bytes>>(1);
+        let (tx, rx) = mpsc::sync_channel::<Result<Message<Bytes>, Error>>(1);
         let bridge = self.bridge;
 
-        let mut index_file = bridge.index_file()?;
-        let backup = index_file.find_backup(&self.read_options)?;
+        let mut index_file = bridge.index_file().map_err(ReplibyteError::Bridge)?;
+        let backup = index_file
+            .find_backup(&self.read_options)
+            .map_err(ReplibyteError::Bridge)?;
+
+        // fail fast instead of feeding the destination with a dump it can't read
+        check_engine(backup, self.destination.engine())?;
 
         // init progress
         progress_callback(0, backup.size);
@@ -64,27 +172,123 @@ where
             let bridge = bridge;
             let read_options = read_options;
 
-            let _ = match bridge.read(&read_options, |data| {
-                let _ = tx.send(Message::Data(data));
-            }) {
-                Ok(_) => {}
-                Err(err) => panic!("{:?}", err),
-            };
+            let read_result = bridge.read(&read_options, |data| {
+                let _ = tx.send(Ok(Message::Data(data)));
+            });
 
-            let _ = tx.send(Message::EOF);
+            // the restore stops on the download error
+            let _ = tx.send(read_result.map(|_| Message::EOF));
         });
 
-        loop {
-            let data = match rx.recv() {
-                Ok(Message::Data(data)) => data,
-                Ok(Message::EOF) => break,
-                Err(err) => panic!("{:?}", err), // FIXME what should I do here?
-            };
+        // the workers fill the tables in any order - the foreign keys of the destination (E.g. an existing schema)
+        // are dropped while the data is loaded then added back
+        let foreign_keys = ForeignKeys {
+            destination: &self.destination,
+            queries: match self.jobs > 1 {
+                true => self
+                    .destination
+                    .drop_foreign_keys()
+                    .map_err(ReplibyteError::Destination)?,
+                false => vec![],
+            },
+        };
 
-            progress_callback(data.len(), backup.size);
+        let mut parallel_writer = ParallelWriter::new(&self.destination, self.jobs);
+        let destination = &self.destination;
+        let jobs = self.jobs;
+        let column_map = &self.column_map;
+        let schema_mapping = self.schema_mapping.as_ref();
+        let mut preserved_tables = PreservedTables::new(self.preserve_tables.clone());
+        let tables = self.tables.as_slice();
+        let options = match self.transformers.is_empty() && self.skip_config.is_empty() {
+            true => None,
+            false => Some(SourceOptions {
+                transformers: &self.transformers,
+                skip_config: &self.skip_config,
+            }),
+        };
 
-            let _ = self.destination.write(data)?;
-        }
+        let mut write = |data: Bytes| -> Result<(), ReplibyteError> {
+            if jobs > 1 {
+                parallel_writer
+                    .write(data)
+                    .map_err(ReplibyteError::Destination)
+            } else {
+                destination.write(data).map_err(ReplibyteError::Destination)
+            }
+        };
+
+        // bytes of the incomplete last query - only kept when the queries are rewritten
+        let mut remaining_data = vec![];
+
+        let restore_result = (|| -> Result<(), ReplibyteError> {
+            loop {
+                let data = match rx.recv() {
+                    Ok(Ok(Message::Data(data))) => data,
+                    Ok(Ok(Message::EOF)) => break,
+                    Ok(Err(err)) => return Err(ReplibyteError::Bridge(err)),
+                    Err(_) => {
+                        return Err(ReplibyteError::Bridge(Error::new(
+                            ErrorKind::Other,
+                            "the download of the backup stopped before its end",
+                        )))
+                    }
+                };
+
+                progress_callback(data.len(), backup.size);
+
+                if column_map.is_empty()
+                    && schema_mapping.is_none()
+                    && options.is_none()
+                    && preserved_tables.is_empty()
+                    && tables.is_empty()
+                {
+                    write(data)?;
+                    continue;
+                }
+
+                remaining_data.extend(data);
+                let (queries, trailing_data) = split_queries(remaining_data.as_slice());
+                let queries_len = queries.iter().map(|query| query.len()).sum::<usize>();
+                let data = rewrite_queries(
+                    &remaining_data[..queries_len],
+                    options.as_ref(),
+                    column_map,
+                    schema_mapping,
+                    &mut preserved_tables,
+                    tables,
+                );
+                remaining_data = trailing_data.to_vec();
+
+                if !data.is_empty() {
+                    write(data)?;
+                }
+            }
+
+            if !remaining_data.is_empty() {
+                write(rewrite_queries(
+                    remaining_data.as_slice(),
+                    options.as_ref(),
+                    column_map,
+                    schema_mapping,
+                    &mut preserved_tables,
+                    tables,
+                ))?;
+            }
+
+            Ok(())
+        })()
+        .and_then(|_| {
+            parallel_writer
+                .finish()
+                .map_err(ReplibyteError::Destination)
+        });
+
+        // the foreign keys are added back by the guard when the restore failed - its error comes first
+        restore_result?;
+        foreign_keys
+            .add_back()
+            .map_err(ReplibyteError::Destination)?;
 
         // wait for end of download execution
         let _ = join_handle.join(); // FIXME catch result here
@@ -94,3 +298,735 @@ where
         Ok(())
     }
 }
+
+/// guard of the foreign keys dropped during the parallel restore - they are added back when it is dropped,
+/// E.g. on the error of the restore
+struct ForeignKeys<'a, D>
+where
+    D: Destination,
+{
+    destination: &'a D,
+    // queries adding back the foreign keys - empty once added back
+    queries: Bytes,
+}
+
+impl<'a, D> ForeignKeys<'a, D>
+where
+    D: Destination,
+{
+    fn add_back(mut self) -> Result<(), Error> {
+        match self.queries.is_empty() {
+            true => Ok(()),
+            false => self.destination.write(std::mem::take(&mut self.queries)),
+        }
+    }
+}
+
+impl<'a, D> Drop for ForeignKeys<'a, D>
+where
+    D: Destination,
+{
+    fn drop(&mut self) {
+        if self.queries.is_empty() {
+            return;
+        }
+
+        if let Err(err) = self.destination.write(std::mem::take(&mut self.queries)) {
+            error!(
+                "the foreign keys of the destination can't be added back: {}",
+                err
+            );
+        }
+    }
+}
+
+/// transform the complete queries of `data`, drop the ones of the tables which are not restored, rename their columns,
+/// move them into the new schema then drop the ones of the preserved tables
+fn rewrite_queries(
+    data: &[u8],
+    options: Option<&SourceOptions>,
+    column_map: &ColumnMap,
+    schema_mapping: Option<&SchemaMapping>,
+    preserved_tables: &mut PreservedTables,
+    tables: &[String],
+) -> Bytes {
+    let data = match options {
+        Some(options) => {
+            let mut transformed_data = vec![];
+            let options = SourceOptions {
+                transformers: options.transformers,
+                skip_config: options.skip_config,
+            };
+
+            read_and_transform(BufReader::new(data), options, |_, query| {
+                transformed_data.extend(query.0);
+                transformed_data.push(b'\n');
+            });
+
+            transformed_data
+        }
+        None => data.to_vec(),
+    };
+
+    if column_map.is_empty()
+        && schema_mapping.is_none()
+        && preserved_tables.is_empty()
+        && tables.is_empty()
+    {
+        return data;
+    }
+
+    let (queries, remaining_data) = split_queries(data.as_slice());
+    queries
+        .into_iter()
+        .chain(std::iter::once(remaining_data))
+        .flat_map(|query| {
+            let query = selected_query(query, tables);
+            let query = remap_columns(query.as_slice(), column_map);
+            let query = match schema_mapping {
+                Some(schema_mapping) => retarget_schema(query.as_slice(), schema_mapping),
+                None => query,
+            };
+            preserved_tables.rewrite(query.as_slice())
+        })
+        .collect()
+}
+
+/// the backup can be restored when its source engine is the destination one - or when one of both is unknown
+fn check_engine(backup: &Backup, destination_engine: Option<Engine>) -> Result<(), ReplibyteError> {
+    match (backup.engine, destination_engine) {
+        (Some(backup_engine), Some(destination_engine)) if backup_engine != destination_engine => {
+            Err(ReplibyteError::Destination(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "backup '{}' is a {} dump - it can't be restored into a {} destination",
+                    backup.directory_name,
+                    backup_engine.name(),
+                    destination_engine.name()
+                ),
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// ParallelWriter dispatches the INSERT INTO queries to one worker per table (modulo `jobs`),
+/// any other query is a barrier - it is applied once all the previous queries have been applied.
+/// Since the foreign keys are added after the data in the dumps, the tables can be filled concurrently.
+struct ParallelWriter<'a, D>
+where
+    D: Destination + Sync,
+{
+    destination: &'a D,
+    // bytes of the incomplete last query
+    remaining_data: Bytes,
+    // consecutive queries to apply serially
+    serial_queries: Bytes,
+    // INSERT INTO queries to apply concurrently - one buffer per worker
+    parallel_queries: Vec<Bytes>,
+}
+
+impl<'a, D> ParallelWriter<'a, D>
+where
+    D: Destination + Sync,
+{
+    fn new(destination: &'a D, jobs: usize) -> Self {
+        ParallelWriter {
+            destination,
+            remaining_data: vec![],
+            serial_queries: vec![],
+            parallel_queries: vec![vec![]; jobs],
+        }
+    }
+
+    fn write(&mut self, data: Bytes) -> Result<(), Error> {
+        let mut data_to_split = std::mem::take(&mut self.remaining_data);
+        data_to_split.extend(data);
+
+        let (queries, remaining_data) = split_queries(data_to_split.as_slice());
+
+        for query in queries {
+            match insert_into_table_name(query) {
+                Some(table_name) => {
+                    self.write_serial_queries()?;
+
+                    let worker_idx = worker_idx(table_name, self.parallel_queries.len());
+                    self.parallel_queries[worker_idx].extend_from_slice(query);
+                }
+                None => {
+                    self.write_parallel_queries()?;
+                    self.serial_queries.extend_from_slice(query);
+                }
+            }
+        }
+
+        self.remaining_data = remaining_data.to_vec();
+
+        // only one of both is not empty
+        self.write_serial_queries()?;
+        self.write_parallel_queries()
+    }
+
+    /// write the trailing bytes - E.g. the last query does not end with ';'
+    fn finish(mut self) -> Result<(), Error> {
+        self.serial_queries = std::mem::take(&mut self.remaining_data);
+        self.write_serial_queries()
+    }
+
+    fn write_serial_queries(&mut self) -> Result<(), Error> {
+        if self.serial_queries.is_empty() {
+            return Ok(());
+        }
+
+        self.destination
+            .write(std::mem::take(&mut self.serial_queries))
+    }
+
+    fn write_parallel_queries(&mut self) -> Result<(), Error> {
+        if self
+            .parallel_queries
+            .iter()
+            .all(|queries| queries.is_empty())
+        {
+            return Ok(());
+        }
+
+        let destination = self.destination;
+        let parallel_queries = &mut self.parallel_queries;
+        let worker_pool = worker_pool();
+
+        thread::scope(|scope| {
+            let join_handles = parallel_queries
+                .iter_mut()
+                .filter(|queries| !queries.is_empty())
+                .map(|queries| {
+                    let queries = std::mem::take(queries);
+                    let worker_pool = &worker_pool;
+                    scope.spawn(move || worker_pool.run(|| destination.write(queries)))
+                })
+                .collect::<Vec<_>>();
+
+            join_handles
+                .into_iter()
+                .map(|join_handle| match join_handle.join() {
+                    Ok(result) => result,
+                    Err(err) => panic!("{:?}", err),
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        })?;
+
+        Ok(())
+    }
+}
+
+/// pick the worker of a table - the queries of a table are always applied by the same worker, in order
+fn worker_idx(table_name: &[u8], jobs: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    table_name.hash(&mut hasher);
+    (hasher.finish() % jobs as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::{Error, ErrorKind};
+    use std::sync::{Arc, Mutex};
+
+    use fake::{Fake, Faker};
+
+    use crate::bridge::in_memory::InMemoryBridge;
+    use crate::bridge::local_file::LocalFile;
+    use crate::bridge::{Bridge, ReadOptions};
+    use crate::config::SkipConfig;
+    use crate::connector::{Connector, Engine};
+    use crate::destination::postgres::{column_map, insert_into_table_name};
+    use crate::destination::Destination;
+    use crate::error::ReplibyteError;
+    use crate::tasks::full_restore::{read_tables_file, FullRestoreTask};
+    use crate::tasks::Task;
+    use crate::transformer::random::RandomTransformer;
+    use crate::types::Bytes;
+
+    /// in memory destination recording the applied queries
+    #[derive(Default)]
+    struct InMemoryDestination {
+        writes: Arc<Mutex<Vec<Bytes>>>,
+        engine: Option<Engine>,
+        /// queries adding back the foreign keys of the destination
+        foreign_keys_queries: Bytes,
+        /// the data is refused - the queries adding back the foreign keys are still applied
+        refuse_data: bool,
+    }
+
+    impl Connector for InMemoryDestination {
+        fn init(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl Destination for InMemoryDestination {
+        fn write(&self, data: Bytes) -> Result<(), Error> {
+            if self.refuse_data && data != self.foreign_keys_queries {
+                return Err(Error::new(ErrorKind::Other, "permission denied"));
+            }
+
+            self.writes.lock().unwrap().push(data);
+            Ok(())
+        }
+
+        fn engine(&self) -> Option<Engine> {
+            self.engine
+        }
+
+        fn drop_foreign_keys(&self) -> Result<Bytes, Error> {
+            if !self.foreign_keys_queries.is_empty() {
+                self.writes.lock().unwrap().push(DROP_FOREIGN_KEYS.to_vec());
+            }
+
+            Ok(self.foreign_keys_queries.clone())
+        }
+    }
+
+    const DROP_FOREIGN_KEYS: &[u8] = b"-- drop foreign keys";
+
+    /// destination refusing every query
+    struct FailingDestination;
+
+    impl Connector for FailingDestination {
+        fn init(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl Destination for FailingDestination {
+        fn write(&self, _: Bytes) -> Result<(), Error> {
+            Err(Error::new(ErrorKind::Other, "permission denied"))
+        }
+    }
+
+    /// the applied queries by table - None for the other queries
+    fn queries_by_table(writes: &[Bytes]) -> HashMap<Option<String>, Vec<String>> {
+        let data = String::from_utf8(writes.concat()).unwrap();
+
+        let mut queries_by_table = HashMap::new();
+        for query in data.lines().filter(|line| !line.is_empty()) {
+            let table_name = insert_into_table_name(query.as_bytes())
+                .map(|table_name| String::from_utf8(table_name.to_vec()).unwrap());
+
+            queries_by_table
+                .entry(table_name)
+                .or_insert_with(Vec::new)
+                .push(query.to_string());
+        }
+
+        queries_by_table
+    }
+
+    fn chunks() -> Vec<Bytes> {
+        let mut dump = String::new();
+        dump.push_str("CREATE TABLE public.users (id integer, name text);\n");
+        dump.push_str("CREATE TABLE public.orders (id integer, user_id integer);\n");
+        dump.push_str("CREATE TABLE public.items (id integer, order_id integer);\n");
+
+        for i in 0..100 {
+            dump.push_str(&format!(
+                "INSERT INTO public.users (id, name) VALUES ({}, 'user;{}');\n",
+                i, i
+            ));
+            dump.push_str(&format!(
+                "INSERT INTO public.orders (id, user_id) VALUES ({}, {});\n",
+                i, i
+            ));
+            dump.push_str(&format!(
+                "INSERT INTO public.items (id, order_id) VALUES ({}, {});\n",
+                i, i
+            ));
+        }
+
+        dump.push_str(
+            "ALTER TABLE ONLY public.orders ADD CONSTRAINT orders_pkey PRIMARY KEY (id);\n",
+        );
+
+        // split the dump in the middle of the queries like the backup chunks
+        dump.into_bytes()
+            .chunks(1000)
+            .map(|chunk| chunk.to_vec())
+            .collect()
+    }
+
+    /// restore the dump and return the writes made into the destination
+    fn restore(jobs: usize) -> Vec<Bytes> {
+        let destination = InMemoryDestination::default();
+        let writes = destination.writes.clone();
+        let bridge = InMemoryBridge::with_objects(chunks(), None);
+
+        let mut task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+        task.set_jobs(jobs);
+        task.run(|_, _| {}).unwrap();
+
+        let writes = writes.lock().unwrap().clone();
+        writes
+    }
+
+    #[test]
+    fn parallel_restore_applies_the_same_queries_as_serial_restore() {
+        let serial_writes = restore(1);
+        let parallel_writes = restore(4);
+
+        // the chunks are written as they come in serial restore
+        assert_eq!(serial_writes, chunks());
+
+        // the queries of each table are applied in the same order
+        let serial_queries = queries_by_table(&serial_writes);
+        assert_eq!(serial_queries.len(), 4);
+        assert_eq!(serial_queries, queries_by_table(&parallel_writes));
+
+        // the schema is created before the data and the constraints are added after
+        assert!(parallel_writes
+            .first()
+            .unwrap()
+            .starts_with(b"CREATE TABLE"));
+        assert!(parallel_writes.last().unwrap().starts_with(b"ALTER TABLE"));
+    }
+
+    #[test]
+    fn parallel_restore_drops_the_foreign_keys_while_loading_the_data() {
+        let foreign_keys_queries = b"ALTER TABLE public.orders ADD CONSTRAINT orders_user_id_fkey \
+        FOREIGN KEY (user_id) REFERENCES public.users(id);\n"
+            .to_vec();
+
+        // the orders are restored before their users
+        let dump = b"INSERT INTO public.orders (id, user_id) VALUES (1, 42);\n\
+        INSERT INTO public.users (id, name) VALUES (42, 'romaric');\n"
+            .to_vec();
+
+        for jobs in [1, 4] {
+            let destination = InMemoryDestination {
+                foreign_keys_queries: foreign_keys_queries.clone(),
+                ..InMemoryDestination::default()
+            };
+            let writes = destination.writes.clone();
+            let bridge = InMemoryBridge::with_objects(vec![dump.clone()], None);
+
+            let mut task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+            task.set_jobs(jobs);
+            task.run(|_, _| {}).unwrap();
+
+            let writes = writes.lock().unwrap().clone();
+
+            if jobs == 1 {
+                // the data is written as it comes
+                assert_eq!(writes, vec![dump.clone()]);
+                continue;
+            }
+
+            let queries = queries_by_table(&writes[1..writes.len() - 1]);
+            assert_eq!(writes.first().unwrap(), DROP_FOREIGN_KEYS);
+            assert_eq!(writes.last().unwrap(), &foreign_keys_queries);
+            assert_eq!(queries[&Some("public.orders".to_string())].len(), 1);
+            assert_eq!(queries[&Some("public.users".to_string())].len(), 1);
+        }
+    }
+
+    /// destination refusing the data and the foreign keys added back
+    struct ForeignKeysDestination;
+
+    impl Connector for ForeignKeysDestination {
+        fn init(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl Destination for ForeignKeysDestination {
+        fn write(&self, data: Bytes) -> Result<(), Error> {
+            match data.starts_with(b"ALTER TABLE") {
+                true => Err(Error::new(ErrorKind::Other, "foreign key violation")),
+                false => Err(Error::new(ErrorKind::Other, "permission denied")),
+            }
+        }
+
+        fn drop_foreign_keys(&self) -> Result<Bytes, Error> {
+            Ok(
+                b"ALTER TABLE public.orders ADD CONSTRAINT orders_user_id_fkey \
+            FOREIGN KEY (user_id) REFERENCES public.users(id);\n"
+                    .to_vec(),
+            )
+        }
+    }
+
+    #[test]
+    fn parallel_restore_returns_the_restore_error_before_the_foreign_keys_one() {
+        let bridge = InMemoryBridge::with_objects(chunks(), None);
+
+        let mut task = FullRestoreTask::new(ForeignKeysDestination, bridge, ReadOptions::Latest);
+        task.set_jobs(4);
+
+        let err = task.run(|_, _| {}).err().unwrap();
+        assert!(matches!(err, ReplibyteError::Destination(_)));
+        assert!(err.to_string().contains("permission denied"));
+    }
+
+    #[test]
+    fn failed_parallel_restore_adds_the_foreign_keys_back() {
+        let foreign_keys_queries = b"ALTER TABLE public.orders ADD CONSTRAINT orders_user_id_fkey \
+        FOREIGN KEY (user_id) REFERENCES public.users(id);\n"
+            .to_vec();
+
+        let destination = InMemoryDestination {
+            foreign_keys_queries: foreign_keys_queries.clone(),
+            refuse_data: true,
+            ..InMemoryDestination::default()
+        };
+        let writes = destination.writes.clone();
+        let bridge = InMemoryBridge::with_objects(chunks(), None);
+
+        let mut task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+        task.set_jobs(4);
+
+        let err = task.run(|_, _| {}).err().unwrap();
+        assert!(matches!(err, ReplibyteError::Destination(_)));
+
+        // dropped then added back - no data made it
+        assert_eq!(
+            *writes.lock().unwrap(),
+            vec![DROP_FOREIGN_KEYS.to_vec(), foreign_keys_queries]
+        );
+    }
+
+    #[test]
+    fn restore_with_renamed_columns() {
+        for jobs in [1, 4] {
+            let destination = InMemoryDestination::default();
+            let writes = destination.writes.clone();
+            let bridge = InMemoryBridge::with_objects(chunks(), None);
+
+            let mut task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+            task.set_jobs(jobs);
+            let column_mapping = "public.users.name=full_name".parse().unwrap();
+            task.set_column_map(column_map(&[column_mapping]));
+            task.run(|_, _| {}).unwrap();
+
+            let queries = queries_by_table(&writes.lock().unwrap());
+            let users_queries = &queries[&Some("public.users".to_string())];
+            assert_eq!(users_queries.len(), 100);
+            // the queries split across the chunks are rewritten too
+            assert!(users_queries
+                .iter()
+                .all(|query| query.starts_with("INSERT INTO public.users (id, full_name) VALUES")));
+
+            // the other tables are kept as is
+            assert_eq!(queries, {
+                let mut expected_queries = queries_by_table(&chunks());
+                expected_queries.insert(Some("public.users".to_string()), users_queries.clone());
+                expected_queries
+            });
+        }
+    }
+
+    #[test]
+    fn restore_into_another_schema() {
+        for jobs in [1, 4] {
+            let destination = InMemoryDestination::default();
+            let writes = destination.writes.clone();
+            let bridge = InMemoryBridge::with_objects(chunks(), None);
+
+            let mut task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+            task.set_jobs(jobs);
+            task.set_schema_mapping(Some("tenant_x".parse().unwrap()));
+            task.run(|_, _| {}).unwrap();
+
+            let queries = queries_by_table(&writes.lock().unwrap());
+            for table_name in ["users", "orders", "items"] {
+                let table_queries = &queries[&Some(format!("tenant_x.{}", table_name))];
+                assert_eq!(table_queries.len(), 100);
+            }
+            assert!(!queries
+                .keys()
+                .flatten()
+                .any(|table_name| table_name.starts_with("public.")));
+
+            let data = writes.lock().unwrap().concat();
+            let data = String::from_utf8_lossy(data.as_slice());
+            assert!(data.contains("CREATE TABLE tenant_x.users (id integer, name text);"));
+            assert!(data.contains("ALTER TABLE ONLY tenant_x.orders ADD CONSTRAINT"));
+            // the values are kept as is
+            assert!(data.contains("VALUES (42, 'user;42');"));
+        }
+    }
+
+    #[test]
+    fn restore_without_the_preserved_tables_rows() {
+        for jobs in [1, 4] {
+            let destination = InMemoryDestination::default();
+            let writes = destination.writes.clone();
+            let bridge = InMemoryBridge::with_objects(chunks(), None);
+
+            let mut task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+            task.set_jobs(jobs);
+            task.set_preserve_tables(vec!["public.orders".to_string()]);
+            task.run(|_, _| {}).unwrap();
+
+            let queries = queries_by_table(&writes.lock().unwrap());
+            // the pre-restore orders are kept by the destination
+            assert!(!queries.contains_key(&Some("public.orders".to_string())));
+            assert_eq!(queries[&Some("public.users".to_string())].len(), 100);
+            assert_eq!(queries[&Some("public.items".to_string())].len(), 100);
+
+            let data = writes.lock().unwrap().concat();
+            let data = String::from_utf8_lossy(data.as_slice());
+            assert!(data.contains("CREATE TABLE IF NOT EXISTS public.orders"));
+            assert!(data.contains("CREATE TABLE public.users"));
+        }
+    }
+
+    #[test]
+    fn restore_the_tables_of_a_tables_file() {
+        let path =
+            std::env::temp_dir().join(format!("replibyte-tables-{}.txt", Faker.fake::<u64>()));
+        fs::write(
+            path.as_path(),
+            "# tables needed by the billing service\npublic.users\n\npublic.items # no order\n",
+        )
+        .unwrap();
+
+        let tables = read_tables_file(path.as_path()).unwrap();
+        let _ = fs::remove_file(path.as_path());
+        assert_eq!(tables, vec!["public.users", "public.items"]);
+
+        for jobs in [1, 4] {
+            let destination = InMemoryDestination::default();
+            let writes = destination.writes.clone();
+            let bridge = InMemoryBridge::with_objects(chunks(), None);
+
+            let mut task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+            task.set_jobs(jobs);
+            task.set_tables(tables.clone());
+            task.run(|_, _| {}).unwrap();
+
+            let queries = queries_by_table(&writes.lock().unwrap());
+            assert!(!queries.contains_key(&Some("public.orders".to_string())));
+            assert_eq!(queries[&Some("public.users".to_string())].len(), 100);
+            assert_eq!(queries[&Some("public.items".to_string())].len(), 100);
+
+            // the schema is restored as is
+            let data = writes.lock().unwrap().concat();
+            let data = String::from_utf8_lossy(data.as_slice());
+            assert!(data.contains("CREATE TABLE public.orders"));
+        }
+
+        assert!(
+            read_tables_file(std::env::temp_dir().join("replibyte-missing.txt").as_path()).is_err()
+        );
+    }
+
+    #[test]
+    fn restore_with_transformers() {
+        let destination = InMemoryDestination::default();
+        let writes = destination.writes.clone();
+        // the dump parser of the transformers splits the queries on every `;` - even the quoted ones
+        let dump = chunks().concat();
+        let dump = String::from_utf8_lossy(dump.as_slice()).replace("'user;", "'user:");
+        let bridge = InMemoryBridge::with_objects(
+            dump.into_bytes()
+                .chunks(1000)
+                .map(|chunk| chunk.to_vec())
+                .collect(),
+            None,
+        );
+
+        let mut task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+        task.set_transformers(
+            vec![Box::new(RandomTransformer::new("public", "users", "name"))],
+            vec![SkipConfig {
+                database: "public".to_string(),
+                table: "items".to_string(),
+            }],
+        );
+        task.run(|_, _| {}).unwrap();
+
+        let queries = queries_by_table(&writes.lock().unwrap());
+
+        // the names are anonymized - including the queries split across the chunks
+        let users_queries = &queries[&Some("public.users".to_string())];
+        assert_eq!(users_queries.len(), 100);
+        assert!(users_queries.iter().all(|query| !query.contains("'user:")));
+
+        assert_eq!(queries[&Some("public.orders".to_string())].len(), 100);
+        assert!(!queries.contains_key(&Some("public.items".to_string())));
+    }
+
+    #[test]
+    fn restore_from_local_file() {
+        let dir = std::env::temp_dir().join(format!("replibyte-restore-{}", Faker.fake::<u64>()));
+        fs::create_dir_all(dir.as_path()).unwrap();
+
+        // backup parts downloaded from the bridge
+        for (idx, chunk) in chunks().into_iter().enumerate() {
+            fs::write(dir.join(format!("{}.dump", idx + 1)), chunk).unwrap();
+        }
+
+        let mut local_file = LocalFile::new(dir.as_path());
+        local_file.set_compression(false);
+
+        let destination = InMemoryDestination::default();
+        let writes = destination.writes.clone();
+
+        let task = FullRestoreTask::new(destination, local_file, ReadOptions::Latest);
+        task.run(|_, _| {}).unwrap();
+
+        assert_eq!(*writes.lock().unwrap(), chunks());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn restore_errors_tell_the_failing_stage() {
+        let task = FullRestoreTask::new(
+            InMemoryDestination::default(),
+            InMemoryBridge::with_objects(chunks(), None),
+            ReadOptions::Backup {
+                name: "backup-2".to_string(),
+            },
+        );
+        let result = task.run(|_, _| {});
+        assert!(matches!(result, Err(ReplibyteError::Bridge(_))));
+
+        let task = FullRestoreTask::new(
+            FailingDestination,
+            InMemoryBridge::with_objects(chunks(), None),
+            ReadOptions::Latest,
+        );
+        let result = task.run(|_, _| {});
+        assert!(matches!(result, Err(ReplibyteError::Destination(_))));
+    }
+
+    #[test]
+    fn restore_rejects_a_backup_from_another_engine() {
+        let destination = InMemoryDestination {
+            engine: Some(Engine::PostgreSQL),
+            ..Default::default()
+        };
+        let writes = destination.writes.clone();
+
+        let bridge = InMemoryBridge::with_objects(chunks(), Some(Engine::MongoDB));
+
+        let task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+        let err = task.run(|_, _| {}).unwrap_err();
+
+        assert!(matches!(err, ReplibyteError::Destination(_)));
+        assert!(err.to_string().contains("MongoDB dump"));
+        // nothing is written
+        assert!(writes.lock().unwrap().is_empty());
+
+        // same engine
+        let destination = InMemoryDestination {
+            engine: Some(Engine::PostgreSQL),
+            ..Default::default()
+        };
+        let bridge = InMemoryBridge::with_objects(chunks(), Some(Engine::PostgreSQL));
+
+        let task = FullRestoreTask::new(destination, bridge, ReadOptions::Latest);
+        assert!(task.run(|_, _| {}).is_ok());
+    }
+}
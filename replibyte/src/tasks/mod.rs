@@ -1,13 +1,18 @@
-use std::io::Error;
+use crate::error::ReplibyteError;
 
 pub mod full_backup;
 pub mod full_restore;
+pub mod source_ping;
+pub mod verify_restore;
 
 pub type TransferredBytes = usize;
 pub type MaxBytes = usize;
 
 pub trait Task {
-    fn run<F: FnMut(TransferredBytes, MaxBytes)>(self, progress_callback: F) -> Result<(), Error>;
+    fn run<F: FnMut(TransferredBytes, MaxBytes)>(
+        self,
+        progress_callback: F,
+    ) -> Result<(), ReplibyteError>;
 }
 
 /// inter-thread message for Source/Destination and Bridge
@@ -0,0 +1,164 @@
+use std::time::{Duration, Instant};
+
+use crate::error::ReplibyteError;
+use crate::source::{Source, SourceOptions};
+use crate::tasks::{MaxBytes, TransferredBytes};
+
+/// rows and bytes read from the source and the time it took
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceThroughput {
+    /// queries read from the dump - one per row for the INSERT INTO dumps, one per document for MongoDB
+    pub rows: usize,
+    pub bytes: usize,
+    pub elapsed: Duration,
+}
+
+impl SourceThroughput {
+    pub fn rows_per_sec(&self) -> f64 {
+        self.rows as f64 / self.elapsed_secs()
+    }
+
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.bytes as f64 / self.elapsed_secs()
+    }
+
+    // a tiny dump can be read in less than the clock resolution
+    fn elapsed_secs(&self) -> f64 {
+        self.elapsed.max(Duration::from_nanos(1)).as_secs_f64()
+    }
+}
+
+/// SourcePingTask dumps the *Source* through the transformers without storing anything -
+/// to estimate how long a backup takes before scheduling it
+pub struct SourcePingTask<'a, S>
+where
+    S: Source,
+{
+    source: S,
+    options: SourceOptions<'a>,
+}
+
+impl<'a, S> SourcePingTask<'a, S>
+where
+    S: Source,
+{
+    pub fn new(source: S, options: SourceOptions<'a>) -> Self {
+        SourcePingTask { source, options }
+    }
+
+    pub fn run<F: FnMut(TransferredBytes, MaxBytes)>(
+        mut self,
+        mut progress_callback: F,
+    ) -> Result<SourceThroughput, ReplibyteError> {
+        // initialize the source
+        let _ = self.source.init().map_err(ReplibyteError::Source)?;
+
+        let mut rows = 0usize;
+        let mut bytes = 0usize;
+
+        // the size of the dump is unknown until it is read
+        progress_callback(bytes, 0);
+
+        let read_start = Instant::now();
+
+        let _ = self
+            .source
+            .read(self.options, |_, query| {
+                rows += 1;
+                bytes += query.data().len();
+                progress_callback(bytes, 0);
+            })
+            .map_err(ReplibyteError::Source)?;
+
+        let elapsed = read_start.elapsed();
+        progress_callback(bytes, bytes);
+
+        Ok(SourceThroughput {
+            rows,
+            bytes,
+            elapsed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Error};
+    use std::time::Duration;
+
+    use crate::connector::Connector;
+    use crate::source::postgres::read_and_transform;
+    use crate::source::{Source, SourceOptions};
+    use crate::tasks::source_ping::{SourcePingTask, SourceThroughput};
+    use crate::transformer::random::RandomTransformer;
+    use crate::transformer::Transformer;
+    use crate::types::{OriginalQuery, Query};
+
+    /// source reading a small PostgreSQL dump from memory
+    struct FixtureSource {
+        dump: String,
+    }
+
+    impl Connector for FixtureSource {
+        fn init(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl Source for FixtureSource {
+        fn read<F: FnMut(OriginalQuery, Query)>(
+            &self,
+            options: SourceOptions,
+            query_callback: F,
+        ) -> Result<(), Error> {
+            read_and_transform(
+                BufReader::new(self.dump.as_bytes()),
+                options,
+                query_callback,
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn ping_measures_the_source_throughput() {
+        let mut dump = String::new();
+        for i in 0..100 {
+            dump.push_str(&format!(
+                "INSERT INTO public.users (id, name) VALUES ({}, 'user {}');\n",
+                i, i
+            ));
+        }
+
+        let transformers: Vec<Box<dyn Transformer>> =
+            vec![Box::new(RandomTransformer::new("public", "users", "name"))];
+        let skip_config = vec![];
+
+        let task = SourcePingTask::new(
+            FixtureSource { dump },
+            SourceOptions {
+                transformers: &transformers,
+                skip_config: &skip_config,
+            },
+        );
+
+        let throughput = task.run(|_, _| {}).unwrap();
+
+        assert_eq!(throughput.rows, 100);
+        assert!(throughput.bytes > 0);
+        assert!(throughput.rows_per_sec() > 0.0);
+        assert!(throughput.bytes_per_sec() > 0.0);
+    }
+
+    #[test]
+    fn throughput_of_an_instant_read() {
+        let throughput = SourceThroughput {
+            rows: 10,
+            bytes: 1_000,
+            elapsed: Duration::ZERO,
+        };
+
+        assert!(throughput.rows_per_sec().is_finite());
+        assert!(throughput.bytes_per_sec() > 0.0);
+    }
+}
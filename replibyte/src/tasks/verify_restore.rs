@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::bridge::{Bridge, ReadOptions};
+use crate::connector::{Connector, Engine};
+use crate::destination::postgres::{Postgres, TableChecksum};
+use crate::destination::Destination;
+use crate::error::ReplibyteError;
+use crate::tasks::full_restore::FullRestoreTask;
+use crate::tasks::{MaxBytes, Task, TransferredBytes};
+use crate::types::Bytes;
+
+const INSERT_INTO_PREFIX: &[u8] = b"INSERT INTO ";
+
+/// VerifyRestoreTask restores a backup into a scratch *Destination*, checks that every row made it and wipes it.
+pub struct VerifyRestoreTask<'a, B>
+where
+    B: Bridge + 'static,
+{
+    scratch_destination: Postgres<'a>,
+    bridge: B,
+    read_options: ReadOptions,
+    table_checksums: Option<Arc<TableChecksums>>,
+}
+
+impl<'a, B> VerifyRestoreTask<'a, B>
+where
+    B: Bridge + 'static,
+{
+    pub fn new(scratch_destination: Postgres<'a>, bridge: B, read_options: ReadOptions) -> Self {
+        VerifyRestoreTask {
+            scratch_destination,
+            bridge,
+            read_options,
+            table_checksums: None,
+        }
+    }
+
+    /// record the rows and the checksum of each restored table before the scratch destination is wiped
+    pub fn set_table_checksums(&mut self, table_checksums: Arc<TableChecksums>) {
+        self.table_checksums = Some(table_checksums);
+    }
+}
+
+/// TableChecksums records the rows and the checksum of each table restored by a *VerifyRestoreTask*
+#[derive(Debug, Default)]
+pub struct TableChecksums {
+    checksums: Mutex<BTreeMap<String, TableChecksum>>,
+}
+
+impl TableChecksums {
+    pub fn set(&self, checksums: BTreeMap<String, TableChecksum>) {
+        *self.checksums.lock().unwrap() = checksums;
+    }
+
+    /// the checksums by table name - E.g. `public.users`
+    pub fn checksums(&self) -> BTreeMap<String, TableChecksum> {
+        self.checksums.lock().unwrap().clone()
+    }
+}
+
+/// a table whose rows differ between two backups - None when the table is missing from the backup
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDifference {
+    pub table_name: String,
+    pub baseline: Option<TableChecksum>,
+    pub backup: Option<TableChecksum>,
+}
+
+/// the tables added, removed or with other rows in `backup` than in `baseline`
+pub fn table_differences(
+    baseline: &BTreeMap<String, TableChecksum>,
+    backup: &BTreeMap<String, TableChecksum>,
+) -> Vec<TableDifference> {
+    let mut table_names = baseline.keys().chain(backup.keys()).collect::<Vec<_>>();
+    table_names.sort();
+    table_names.dedup();
+
+    table_names
+        .into_iter()
+        .filter(|table_name| baseline.get(*table_name) != backup.get(*table_name))
+        .map(|table_name| TableDifference {
+            table_name: table_name.clone(),
+            baseline: baseline.get(table_name).cloned(),
+            backup: backup.get(table_name).cloned(),
+        })
+        .collect()
+}
+
+impl<'a, B> Task for VerifyRestoreTask<'a, B>
+where
+    B: Bridge + 'static,
+{
+    fn run<F: FnMut(TransferredBytes, MaxBytes)>(
+        mut self,
+        progress_callback: F,
+    ) -> Result<(), ReplibyteError> {
+        let inserted_rows = AtomicUsize::new(0);
+
+        let destination = RowCounterDestination {
+            destination: &mut self.scratch_destination,
+            inserted_rows: &inserted_rows,
+        };
+
+        let task = FullRestoreTask::new(destination, self.bridge, self.read_options);
+        let restore_result = task.run(progress_callback);
+
+        let result = restore_result.and_then(|_| {
+            let restored_rows = self
+                .scratch_destination
+                .count_rows()
+                .map_err(ReplibyteError::Destination)?;
+            let expected_rows = inserted_rows.load(Ordering::SeqCst);
+
+            if restored_rows != expected_rows {
+                return Err(ReplibyteError::Destination(Error::new(
+                    ErrorKind::Other,
+                    format!(
+                        "backup contains {} rows but {} rows have been restored",
+                        expected_rows, restored_rows
+                    ),
+                )));
+            }
+
+            if let Some(table_checksums) = &self.table_checksums {
+                table_checksums.set(
+                    self.scratch_destination
+                        .table_checksums()
+                        .map_err(ReplibyteError::Destination)?,
+                );
+            }
+
+            Ok(())
+        });
+
+        // tear down the scratch destination whatever the result is
+        let wipe_result = self
+            .scratch_destination
+            .wipe()
+            .map_err(ReplibyteError::Destination);
+
+        result.and(wipe_result)
+    }
+}
+
+/// Destination wrapper counting the rows inserted into the wrapped *Destination*
+struct RowCounterDestination<'a, D>
+where
+    D: Destination + Sync,
+{
+    destination: &'a mut D,
+    inserted_rows: &'a AtomicUsize,
+}
+
+impl<'a, D> Connector for RowCounterDestination<'a, D>
+where
+    D: Destination + Sync,
+{
+    fn init(&mut self) -> Result<(), Error> {
+        self.destination.init()
+    }
+}
+
+impl<'a, D> Destination for RowCounterDestination<'a, D>
+where
+    D: Destination + Sync,
+{
+    fn write(&self, data: Bytes) -> Result<(), Error> {
+        self.inserted_rows
+            .fetch_add(count_insert_queries(data.as_slice()), Ordering::SeqCst);
+
+        self.destination.write(data)
+    }
+
+    fn engine(&self) -> Option<Engine> {
+        self.destination.engine()
+    }
+}
+
+/// count the `INSERT INTO` queries - one per row since dumps are made with `--column-inserts`
+fn count_insert_queries(data: &[u8]) -> usize {
+    data.split(|byte| *byte == b'\n')
+        .filter(|line| line.starts_with(INSERT_INTO_PREFIX))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bridge::s3::S3;
+    use crate::bridge::ReadOptions;
+    use crate::config::Endpoint;
+    use std::collections::BTreeMap;
+
+    use crate::destination::postgres::{Postgres, TableChecksum};
+    use crate::tasks::verify_restore::{
+        count_insert_queries, table_differences, TableDifference, VerifyRestoreTask,
+    };
+    use crate::tasks::Task;
+
+    #[test]
+    fn test_count_insert_queries() {
+        let data = b"CREATE TABLE public.users (name text);\n\
+        INSERT INTO public.users (name) VALUES ('romaric');\n\
+        INSERT INTO public.users (name) VALUES ('lucas');\n\
+        ALTER TABLE ONLY public.users OWNER TO root;\n";
+
+        assert_eq!(count_insert_queries(data), 2);
+        assert_eq!(count_insert_queries(b""), 0);
+    }
+
+    #[test]
+    fn differences_between_two_backups() {
+        let checksum = |rows: usize, checksum: &str| TableChecksum {
+            rows,
+            checksum: checksum.to_string(),
+        };
+
+        let baseline = BTreeMap::from([
+            ("public.users".to_string(), checksum(2, "a3c65c29")),
+            ("public.orders".to_string(), checksum(10, "d41d8cd9")),
+            ("public.audit_logs".to_string(), checksum(5, "0cc175b9")),
+        ]);
+        let backup = BTreeMap::from([
+            // the same rows
+            ("public.users".to_string(), checksum(2, "a3c65c29")),
+            // a row changed
+            ("public.orders".to_string(), checksum(10, "92eb5ffe")),
+            ("public.items".to_string(), checksum(3, "4a8a08f0")),
+        ]);
+
+        assert_eq!(
+            table_differences(&baseline, &backup),
+            vec![
+                TableDifference {
+                    table_name: "public.audit_logs".to_string(),
+                    baseline: Some(checksum(5, "0cc175b9")),
+                    backup: None,
+                },
+                TableDifference {
+                    table_name: "public.items".to_string(),
+                    baseline: None,
+                    backup: Some(checksum(3, "4a8a08f0")),
+                },
+                TableDifference {
+                    table_name: "public.orders".to_string(),
+                    baseline: Some(checksum(10, "d41d8cd9")),
+                    backup: Some(checksum(10, "92eb5ffe")),
+                },
+            ]
+        );
+
+        assert!(table_differences(&baseline, &baseline).is_empty());
+    }
+
+    #[test]
+    fn verify_restore_into_scratch_destination() {
+        // scratch destination is the destination database from docker-compose
+        let scratch_destination =
+            Postgres::new("localhost", 5453, "root", "root", "password", true);
+
+        let bridge = S3::new(
+            "replibyte-test".to_string(),
+            "us-east-2".to_string(),
+            Some("minioadmin".to_string()),
+            Some("minioadmin".to_string()),
+            Endpoint::Custom("http://localhost:9000".to_string()),
+        )
+        .unwrap();
+
+        let task = VerifyRestoreTask::new(scratch_destination, bridge, ReadOptions::Latest);
+        assert!(task.run(|_, _| {}).is_ok());
+    }
+}
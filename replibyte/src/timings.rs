@@ -0,0 +1,171 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::transformer::{ColumnType, Transformer, TransformerOption};
+use crate::types::Column;
+
+/// backup stages - they run concurrently, the source and the transformers feed the bridge
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Stage {
+    Source,
+    Transform,
+    Compress,
+    Encrypt,
+    Upload,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Source,
+        Stage::Transform,
+        Stage::Compress,
+        Stage::Encrypt,
+        Stage::Upload,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Stage::Source => "source dump",
+            Stage::Transform => "transform",
+            Stage::Compress => "compress",
+            Stage::Encrypt => "encrypt",
+            Stage::Upload => "upload",
+        }
+    }
+
+    fn idx(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Timings accumulates the time spent in each stage - it is shared between the threads
+#[derive(Debug, Default)]
+pub struct Timings {
+    nanos: [AtomicU64; 5],
+}
+
+impl Timings {
+    pub fn add(&self, stage: Stage, duration: Duration) {
+        let _ = self.nanos[stage.idx()].fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// time the execution of `f`
+    pub fn measure<T, F: FnOnce() -> T>(&self, stage: Stage, f: F) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.add(stage, start.elapsed());
+        result
+    }
+
+    pub fn duration(&self, stage: Stage) -> Duration {
+        Duration::from_nanos(self.nanos[stage.idx()].load(Ordering::Relaxed))
+    }
+}
+
+/// Transformer wrapper accumulating the time spent in the wrapped *Transformer*
+pub struct TimedTransformer {
+    transformer: Box<dyn Transformer>,
+    timings: Arc<Timings>,
+}
+
+impl TimedTransformer {
+    pub fn new(transformer: Box<dyn Transformer>, timings: Arc<Timings>) -> Self {
+        TimedTransformer {
+            transformer,
+            timings,
+        }
+    }
+}
+
+impl Transformer for TimedTransformer {
+    fn id(&self) -> &str {
+        self.transformer.id()
+    }
+
+    fn description(&self) -> &str {
+        self.transformer.description()
+    }
+
+    fn database_name(&self) -> &str {
+        self.transformer.database_name()
+    }
+
+    fn table_name(&self) -> &str {
+        self.transformer.table_name()
+    }
+
+    fn column_name(&self) -> &str {
+        self.transformer.column_name()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        self.transformer.expected_type()
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        self.transformer.option_schema()
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        self.timings
+            .measure(Stage::Transform, || self.transformer.transform(column))
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        self.timings.measure(Stage::Transform, || {
+            self.transformer.transform_with_row(column, row)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::timings::{Stage, TimedTransformer, Timings};
+    use crate::transformer::random::RandomTransformer;
+    use crate::transformer::Transformer;
+    use crate::types::Column;
+
+    #[test]
+    fn timings_are_accumulated() {
+        let timings = Timings::default();
+
+        for stage in Stage::ALL {
+            assert_eq!(timings.duration(stage), Duration::ZERO);
+        }
+
+        timings.add(Stage::Upload, Duration::from_millis(10));
+        timings.add(Stage::Upload, Duration::from_millis(5));
+        assert_eq!(timings.duration(Stage::Upload), Duration::from_millis(15));
+
+        let value = timings.measure(Stage::Compress, || {
+            std::thread::sleep(Duration::from_millis(1));
+            42
+        });
+
+        assert_eq!(value, 42);
+        assert!(timings.duration(Stage::Compress) >= Duration::from_millis(1));
+        assert_eq!(timings.duration(Stage::Encrypt), Duration::ZERO);
+    }
+
+    #[test]
+    fn timed_transformer() {
+        let timings = Arc::new(Timings::default());
+        let transformer = TimedTransformer::new(
+            Box::new(RandomTransformer::new("github", "users", "name")),
+            timings.clone(),
+        );
+
+        assert_eq!(transformer.id(), "random");
+        assert_eq!(transformer.column_name(), "name");
+
+        let column = Column::StringValue("name".to_string(), "romaric".to_string());
+        let transformed_column = transformer.transform(column);
+
+        assert_ne!(transformed_column.string_value().unwrap(), "romaric");
+        assert!(timings.duration(Stage::Transform) > Duration::ZERO);
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::{
+    name_matches, ColumnType, Transformer, TransformerOption, TransformerOptionKind,
+};
+use crate::types::Column;
+
+/// This struct is dedicated to replacing a string by the value of the first rule it matches - like a SQL `CASE`.
+/// The values matching no rule are kept.
+#[derive(Default)]
+pub struct CaseTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    options: CaseTransformerOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct CaseTransformerOptions {
+    /// rules tried in order - the first matching rule wins
+    pub rules: Vec<CaseRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct CaseRule {
+    /// values matched by the rule - `*` matches any characters, E.g. `gold*`
+    pub when: Vec<String>,
+    /// value replacing the matched values
+    pub then: String,
+}
+
+impl CaseRule {
+    fn matches(&self, value: &str) -> bool {
+        self.when
+            .iter()
+            .any(|pattern| name_matches(pattern.as_str(), value))
+    }
+}
+
+impl CaseTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: CaseTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        CaseTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            options,
+        }
+    }
+}
+
+impl Transformer for CaseTransformer {
+    fn id(&self) -> &str {
+        "case"
+    }
+
+    fn description(&self) -> &str {
+        "Replace the value by the one of the first matching rule, `*` matching any characters (string only). [gold_plus]->[tier_a]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![TransformerOption::new(
+            "rules",
+            TransformerOptionKind::MapList,
+            true,
+            "rules tried in order - `when` lists the matched values (`*` matches any characters) and `then` is the new value",
+        )]
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                let new_value = match self
+                    .options
+                    .rules
+                    .iter()
+                    .find(|rule| rule.matches(value.as_str()))
+                {
+                    Some(rule) => rule.then.clone(),
+                    None => value,
+                };
+
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{CaseRule, CaseTransformer, CaseTransformerOptions};
+
+    #[test]
+    fn transform_with_rules() {
+        let transformer = get_transformer();
+
+        for (value, expected_value) in [
+            ("premium", "tier_a"),
+            ("gold", "tier_a"),
+            ("gold_plus", "tier_a"),
+            ("basic", "tier_b"),
+            ("trial_2022", "tier_b"),
+            // the first matching rule wins
+            ("gold_trial", "tier_a"),
+        ] {
+            let column = Column::StringValue("plan".to_string(), value.to_string());
+            let transformed_column = transformer.transform(column);
+
+            assert_eq!(transformed_column.string_value().unwrap(), expected_value);
+        }
+    }
+
+    #[test]
+    fn unmatched_values_are_kept() {
+        let transformer = get_transformer();
+
+        for value in ["enterprise", "Premium", "old_gold", ""] {
+            let column = Column::StringValue("plan".to_string(), value.to_string());
+            let transformed_column = transformer.transform(column);
+
+            assert_eq!(transformed_column.string_value().unwrap(), value);
+        }
+
+        let column = Column::None("plan".to_string());
+        assert!(matches!(transformer.transform(column), Column::None(_)));
+    }
+
+    fn get_transformer() -> CaseTransformer {
+        let rules = vec![
+            CaseRule {
+                when: vec!["premium".to_string(), "gold*".to_string()],
+                then: "tier_a".to_string(),
+            },
+            CaseRule {
+                when: vec!["basic".to_string(), "*trial*".to_string()],
+                then: "tier_b".to_string(),
+            },
+        ];
+
+        CaseTransformer::new("github", "users", "plan", CaseTransformerOptions { rules })
+    }
+}
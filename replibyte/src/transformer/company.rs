@@ -0,0 +1,211 @@
+use fake::faker::company::raw::CompanyName;
+use fake::locales::EN;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::keyed_hash::keyed_seed;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+/// This struct is dedicated to replacing a string by a company name -
+/// the same key (the original value or the `key_column` value, E.g. the organization id) always gives the same company name.
+#[derive(Default)]
+pub struct CompanyTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    options: CompanyTransformerOptions,
+    secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct CompanyTransformerOptions {
+    /// column of the same row identifying the company - E.g. `organization_id`
+    pub key_column: Option<String>,
+}
+
+impl CompanyTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: CompanyTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        CompanyTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            options,
+            secret: String::new(),
+        }
+    }
+
+    /// the value of the key column - None when the row has no key column or the key is NULL
+    fn key(&self, row: &[Column]) -> Option<String> {
+        let key_column = self.options.key_column.as_ref()?;
+
+        row.iter()
+            .find(|column| column.name() == key_column.as_str())
+            .and_then(|column| match column {
+                Column::NumberValue(_, value) => Some(value.to_string()),
+                Column::FloatNumberValue(_, value) => Some(value.to_string()),
+                Column::StringValue(_, value) => Some(value.clone()),
+                Column::CharValue(_, value) => Some(value.to_string()),
+                Column::BooleanValue(_, value) => Some(value.to_string()),
+                Column::None(_) => None,
+            })
+    }
+
+    fn fake_company_name(&self, key: &str) -> String {
+        // the company name only depends on the secret and the key - it is the same in every table
+        let mut rng = StdRng::seed_from_u64(keyed_seed(self.secret.as_str(), &[key.as_bytes()]));
+        CompanyName(EN).fake_with_rng(&mut rng)
+    }
+}
+
+impl Transformer for CompanyTransformer {
+    fn id(&self) -> &str {
+        "company"
+    }
+
+    fn description(&self) -> &str {
+        "Generate the same company name for the same value or key column (string only). [Qovery]->[Hegmann LLC]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![TransformerOption::new(
+            "key_column",
+            TransformerOptionKind::String,
+            false,
+            "column of the same row identifying the company - E.g. `organization_id`",
+        )]
+    }
+
+    fn set_secret(&mut self, secret: &str) {
+        self.secret = secret.to_string();
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        self.transform_with_row(column, &[])
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                let new_value = if value.is_empty() {
+                    value
+                } else {
+                    let key = self.key(row).unwrap_or(value);
+                    self.fake_company_name(key.as_str())
+                };
+
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{CompanyTransformer, CompanyTransformerOptions};
+
+    fn row(organization_id: i128, name: &str) -> Vec<Column> {
+        vec![
+            Column::NumberValue("organization_id".to_string(), organization_id),
+            Column::StringValue("name".to_string(), name.to_string()),
+        ]
+    }
+
+    fn transform(transformer: &CompanyTransformer, row: &[Column]) -> String {
+        let transformed_column = transformer.transform_with_row(row[1].clone(), row);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn same_key_gives_same_company() {
+        let options = CompanyTransformerOptions {
+            key_column: Some("organization_id".to_string()),
+        };
+
+        let organizations =
+            CompanyTransformer::new("public", "organizations", "name", options.clone());
+        let invoices = CompanyTransformer::new("public", "invoices", "company_name", options);
+
+        let company_name = transform(&organizations, &row(42, "Qovery"));
+        assert_ne!(company_name, "Qovery");
+        assert!(!company_name.is_empty());
+
+        // same organization in another table - even if the original name is spelled differently
+        assert_eq!(transform(&invoices, &row(42, "Qovery SAS")), company_name);
+        assert_eq!(transform(&organizations, &row(42, "Qovery")), company_name);
+    }
+
+    #[test]
+    fn same_value_gives_same_company_without_key_column() {
+        let transformer = CompanyTransformer::default();
+
+        let column = Column::StringValue("name".to_string(), "Qovery".to_string());
+        let company_name = transformer.transform(column.clone());
+        assert_eq!(
+            company_name.string_value().unwrap(),
+            transformer.transform(column).string_value().unwrap()
+        );
+    }
+
+    #[test]
+    fn company_depends_on_the_secret() {
+        let transform = |secret: &str| {
+            let mut transformer = CompanyTransformer::default();
+            transformer.set_secret(secret);
+            let column = Column::StringValue("name".to_string(), "Qovery".to_string());
+            transformer
+                .transform(column)
+                .string_value()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(transform("secret"), transform("secret"));
+        assert_ne!(transform("secret"), transform("another secret"));
+    }
+
+    #[test]
+    fn null_value() {
+        let transformer = CompanyTransformer::new(
+            "public",
+            "organizations",
+            "name",
+            CompanyTransformerOptions {
+                key_column: Some("organization_id".to_string()),
+            },
+        );
+
+        let column = Column::None("name".to_string());
+        let transformed_column = transformer.transform_with_row(column, &row(42, "Qovery"));
+        assert!(matches!(transformed_column, Column::None(_)));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::transformer::Transformer;
+use crate::transformer::{ColumnType, Transformer};
 use crate::types::Column;
 use fake::faker::internet::raw::SafeEmail;
 use fake::locales::EN;
@@ -55,6 +55,10 @@ impl Transformer for EmailTransformer {
         self.column_name.as_str()
     }
 
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
     fn transform(&self, column: Column) -> Column {
         match column {
             Column::StringValue(column_name, value) => {
@@ -1,4 +1,4 @@
-use crate::transformer::Transformer;
+use crate::transformer::{ColumnType, Transformer};
 use crate::types::Column;
 use fake::faker::name::raw::FirstName;
 use fake::locales::EN;
@@ -55,6 +55,10 @@ impl Transformer for FirstNameTransformer {
         self.column_name.as_str()
     }
 
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
     fn transform(&self, column: Column) -> Column {
         match column {
             Column::NumberValue(column_name, value) => Column::NumberValue(column_name, value),
@@ -71,6 +75,7 @@ impl Transformer for FirstNameTransformer {
                 Column::StringValue(column_name, new_value)
             }
             Column::CharValue(column_name, value) => Column::CharValue(column_name, value),
+            Column::BooleanValue(column_name, value) => Column::BooleanValue(column_name, value),
             Column::None(column_name) => Column::None(column_name),
         }
     }
@@ -0,0 +1,299 @@
+use fake::faker::name::raw::{FirstName, LastName};
+use fake::locales::EN;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::keyed_hash::keyed_seed;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+const DEFAULT_FIRST_NAME_COLUMN: &str = "first_name";
+const DEFAULT_LAST_NAME_COLUMN: &str = "last_name";
+const DEFAULT_FULL_NAME_COLUMN: &str = "full_name";
+
+/// This struct is dedicated to replacing the first, last and full name columns of a row by the same fake person -
+/// the full name is always `<first name> <last name>`. Set the transformer on each column of the row.
+pub struct FullNameTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    first_name_column: String,
+    last_name_column: String,
+    full_name_column: String,
+    secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct FullNameTransformerOptions {
+    /// column of the same row holding the first name - default to `first_name`
+    pub first_name_column: Option<String>,
+    /// column of the same row holding the last name - default to `last_name`
+    pub last_name_column: Option<String>,
+    /// column of the same row holding the full name - default to `full_name`
+    pub full_name_column: Option<String>,
+}
+
+impl FullNameTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: FullNameTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        FullNameTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            first_name_column: options
+                .first_name_column
+                .unwrap_or_else(|| DEFAULT_FIRST_NAME_COLUMN.to_string()),
+            last_name_column: options
+                .last_name_column
+                .unwrap_or_else(|| DEFAULT_LAST_NAME_COLUMN.to_string()),
+            full_name_column: options
+                .full_name_column
+                .unwrap_or_else(|| DEFAULT_FULL_NAME_COLUMN.to_string()),
+            secret: String::new(),
+        }
+    }
+
+    /// the fake first and last names of the row - they only depend on the original names of the row,
+    /// or on `value` when the row has none of the name columns (E.g. `transform` without the row)
+    fn fake_names(&self, value: &str, row: &[Column]) -> (String, String) {
+        let mut names: Vec<&[u8]> = vec![];
+
+        for name_column in [
+            &self.first_name_column,
+            &self.last_name_column,
+            &self.full_name_column,
+        ] {
+            if let Some(Column::StringValue(_, name)) = row
+                .iter()
+                .find(|column| column.name() == name_column.as_str())
+            {
+                names.push(name.as_bytes());
+            }
+        }
+
+        if names.is_empty() {
+            names.push(value.as_bytes());
+        }
+
+        let mut rng = StdRng::seed_from_u64(keyed_seed(self.secret.as_str(), names.as_slice()));
+        let first_name: String = FirstName(EN).fake_with_rng(&mut rng);
+        let last_name: String = LastName(EN).fake_with_rng(&mut rng);
+
+        (first_name, last_name)
+    }
+}
+
+impl Default for FullNameTransformer {
+    fn default() -> Self {
+        FullNameTransformer::new("", "", "", FullNameTransformerOptions::default())
+    }
+}
+
+impl Transformer for FullNameTransformer {
+    fn id(&self) -> &str {
+        "full-name"
+    }
+
+    fn description(&self) -> &str {
+        "Replace the first, last and full name columns of the row by the same fake person (string only). [John|Doe|John Doe]->[Paul|Smith|Paul Smith]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "first_name_column",
+                TransformerOptionKind::String,
+                false,
+                "column of the same row holding the first name - default to `first_name`",
+            ),
+            TransformerOption::new(
+                "last_name_column",
+                TransformerOptionKind::String,
+                false,
+                "column of the same row holding the last name - default to `last_name`",
+            ),
+            TransformerOption::new(
+                "full_name_column",
+                TransformerOptionKind::String,
+                false,
+                "column of the same row holding the full name - default to `full_name`",
+            ),
+        ]
+    }
+
+    fn set_secret(&mut self, secret: &str) {
+        self.secret = secret.to_string();
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        self.transform_with_row(column, &[])
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        match column {
+            Column::StringValue(column_name, value) if !value.is_empty() => {
+                let (first_name, last_name) = self.fake_names(value.as_str(), row);
+
+                // any other column gets the full name
+                let new_value = if column_name == self.first_name_column {
+                    first_name
+                } else if column_name == self.last_name_column {
+                    last_name
+                } else {
+                    format!("{} {}", first_name, last_name)
+                };
+
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{FullNameTransformer, FullNameTransformerOptions};
+
+    fn row(first_name: &str, last_name: &str, full_name: &str) -> Vec<Column> {
+        vec![
+            Column::NumberValue("id".to_string(), 1),
+            Column::StringValue("first_name".to_string(), first_name.to_string()),
+            Column::StringValue("last_name".to_string(), last_name.to_string()),
+            Column::StringValue("full_name".to_string(), full_name.to_string()),
+        ]
+    }
+
+    fn transform_row(row: &[Column]) -> (String, String, String) {
+        let transform = |column_name: &str| {
+            let transformer = FullNameTransformer::new(
+                "public",
+                "users",
+                column_name,
+                FullNameTransformerOptions::default(),
+            );
+            let column = row
+                .iter()
+                .find(|column| column.name() == column_name)
+                .unwrap();
+
+            transformer
+                .transform_with_row(column.clone(), row)
+                .string_value()
+                .unwrap()
+                .to_string()
+        };
+
+        (
+            transform("first_name"),
+            transform("last_name"),
+            transform("full_name"),
+        )
+    }
+
+    #[test]
+    fn names_of_a_row_are_consistent() {
+        for (first_name, last_name) in [("John", "Doe"), ("Jane", "Roe"), ("Alice", "Martin")] {
+            let row = row(
+                first_name,
+                last_name,
+                format!("{} {}", first_name, last_name).as_str(),
+            );
+            let (new_first_name, new_last_name, new_full_name) = transform_row(&row);
+
+            assert_eq!(
+                new_full_name,
+                format!("{} {}", new_first_name, new_last_name)
+            );
+            assert!(!new_first_name.is_empty());
+            assert!(!new_last_name.is_empty());
+        }
+
+        // the same person always gets the same fake
+        assert_eq!(
+            transform_row(&row("John", "Doe", "John Doe")),
+            transform_row(&row("John", "Doe", "John Doe"))
+        );
+    }
+
+    #[test]
+    fn custom_name_columns() {
+        let options = FullNameTransformerOptions {
+            first_name_column: Some("given_name".to_string()),
+            last_name_column: Some("family_name".to_string()),
+            full_name_column: Some("display_name".to_string()),
+        };
+        let row = vec![
+            Column::StringValue("given_name".to_string(), "John".to_string()),
+            Column::StringValue("family_name".to_string(), "Doe".to_string()),
+            Column::StringValue("display_name".to_string(), "John Doe".to_string()),
+        ];
+
+        let names = row
+            .iter()
+            .map(|column| {
+                FullNameTransformer::new("public", "users", column.name(), options.clone())
+                    .transform_with_row(column.clone(), &row)
+                    .string_value()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names[2], format!("{} {}", names[0], names[1]));
+    }
+
+    #[test]
+    fn names_depend_on_the_secret() {
+        let transform = |secret: &str| {
+            let mut transformer = FullNameTransformer::default();
+            transformer.set_secret(secret);
+            let column = Column::StringValue("full_name".to_string(), "John Doe".to_string());
+            transformer
+                .transform(column)
+                .string_value()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(transform("secret"), transform("secret"));
+        assert_ne!(transform("secret"), transform("another secret"));
+    }
+
+    #[test]
+    fn null_and_empty_values_are_kept() {
+        let transformer = FullNameTransformer::default();
+
+        let column = Column::None("full_name".to_string());
+        assert!(transformer.transform(column).is_null());
+
+        let column = Column::StringValue("full_name".to_string(), "".to_string());
+        assert_eq!(transformer.transform(column).string_value(), Some(""));
+    }
+}
@@ -0,0 +1,435 @@
+use std::f64::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReplibyteError;
+use crate::transformer::keyed_hash::keyed_seed;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+const DEFAULT_RADIUS: u32 = 1_000;
+/// length of a degree of latitude - and of longitude at the equator
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// This struct is dedicated to moving a location to a random point within a radius.
+/// The latitude and the longitude columns of a row are moved by the same offset.
+pub struct GeoJitterTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    radius: f64,
+    grid: Option<f64>,
+    coordinate_columns: Option<(String, String)>,
+    secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct GeoJitterTransformerOptions {
+    /// max distance in meters between the original and the new location - default to 1000
+    pub radius: Option<u32>,
+    /// snap the new location to a grid of this size in meters
+    pub grid: Option<u32>,
+    /// column of the same row holding the latitude - with `longitude_column`
+    pub latitude_column: Option<String>,
+    /// column of the same row holding the longitude - with `latitude_column`
+    pub longitude_column: Option<String>,
+}
+
+impl GeoJitterTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: GeoJitterTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        match Self::try_new(database_name, table_name, column_name, options) {
+            Ok(transformer) => transformer,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// fail when only one of the latitude and longitude columns is set
+    pub fn try_new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: GeoJitterTransformerOptions,
+    ) -> Result<Self, ReplibyteError>
+    where
+        S: Into<String>,
+    {
+        let coordinate_columns = match (options.latitude_column, options.longitude_column) {
+            (Some(latitude_column), Some(longitude_column)) => {
+                Some((latitude_column, longitude_column))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(ReplibyteError::Transform(
+                    "geo-jitter latitude_column and longitude_column must be set together"
+                        .to_string(),
+                ))
+            }
+        };
+
+        Ok(GeoJitterTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            radius: options.radius.unwrap_or(DEFAULT_RADIUS) as f64,
+            grid: options
+                .grid
+                .filter(|grid| *grid > 0)
+                .map(|grid| grid as f64),
+            coordinate_columns,
+            secret: String::new(),
+        })
+    }
+
+    /// move the location to a random point within the radius - the same location always gets the same point
+    fn jitter(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        let seed = keyed_seed(
+            self.secret.as_str(),
+            &[
+                &latitude.to_bits().to_be_bytes(),
+                &longitude.to_bits().to_be_bytes(),
+            ],
+        );
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        // uniform within the disk
+        let distance = self.radius * rng.gen::<f64>().sqrt();
+        let angle = rng.gen::<f64>() * 2.0 * PI;
+
+        let meters_per_longitude_degree =
+            (METERS_PER_DEGREE * latitude.to_radians().cos()).max(f64::EPSILON);
+
+        let mut new_latitude = latitude + distance * angle.sin() / METERS_PER_DEGREE;
+        let mut new_longitude = longitude + distance * angle.cos() / meters_per_longitude_degree;
+
+        if let Some(grid) = self.grid {
+            let latitude_step = grid / METERS_PER_DEGREE;
+            let longitude_step = grid / meters_per_longitude_degree;
+            new_latitude = (new_latitude / latitude_step).round() * latitude_step;
+            new_longitude = (new_longitude / longitude_step).round() * longitude_step;
+        }
+
+        (
+            new_latitude.clamp(-90.0, 90.0),
+            (new_longitude + 540.0).rem_euclid(360.0) - 180.0,
+        )
+    }
+
+    /// the original latitude and longitude of the row - None when a coordinate is missing or invalid
+    fn row_location(&self, row: &[Column]) -> Option<(f64, f64)> {
+        let (latitude_column, longitude_column) = self.coordinate_columns.as_ref()?;
+        let coordinate = |name: &str| {
+            row.iter()
+                .find(|column| column.name() == name)
+                .and_then(coordinate_value)
+        };
+
+        Some((coordinate(latitude_column)?, coordinate(longitude_column)?))
+    }
+
+    /// jitter a `latitude,longitude` point - E.g. `48.8566,2.3522` or `(48.8566,2.3522)`
+    fn jitter_point(&self, value: &str) -> Option<String> {
+        let trimmed_value = value.trim();
+        let (prefix, suffix, point) = match trimmed_value
+            .strip_prefix('(')
+            .and_then(|point| point.strip_suffix(')'))
+        {
+            Some(point) => ("(", ")", point),
+            None => ("", "", trimmed_value),
+        };
+
+        let (latitude, longitude) = point.split_once(',')?;
+        let latitude = latitude.trim().parse::<f64>().ok()?;
+        let longitude = longitude.trim().parse::<f64>().ok()?;
+
+        let (latitude, longitude) = self.jitter(latitude, longitude);
+        Some(format!(
+            "{}{:.6},{:.6}{}",
+            prefix, latitude, longitude, suffix
+        ))
+    }
+}
+
+fn coordinate_value(column: &Column) -> Option<f64> {
+    match column {
+        Column::FloatNumberValue(_, value) => Some(*value),
+        Column::NumberValue(_, value) => Some(*value as f64),
+        Column::StringValue(_, value) => value.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+impl Default for GeoJitterTransformer {
+    fn default() -> Self {
+        GeoJitterTransformer::new("", "", "", GeoJitterTransformerOptions::default())
+    }
+}
+
+impl Transformer for GeoJitterTransformer {
+    fn id(&self) -> &str {
+        "geo-jitter"
+    }
+
+    fn description(&self) -> &str {
+        "Move a `latitude,longitude` point (or the `latitude_column` and `longitude_column` of the row) to a random point within `radius` meters. [48.8566,2.3522]->[48.8601,2.3479]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "radius",
+                TransformerOptionKind::Integer,
+                false,
+                "max distance in meters between the original and the new location - default to 1000",
+            ),
+            TransformerOption::new(
+                "grid",
+                TransformerOptionKind::Integer,
+                false,
+                "snap the new location to a grid of this size in meters",
+            ),
+            TransformerOption::new(
+                "latitude_column",
+                TransformerOptionKind::String,
+                false,
+                "column of the same row holding the latitude - with `longitude_column`",
+            ),
+            TransformerOption::new(
+                "longitude_column",
+                TransformerOptionKind::String,
+                false,
+                "column of the same row holding the longitude - with `latitude_column`",
+            ),
+        ]
+    }
+
+    fn set_secret(&mut self, secret: &str) {
+        self.secret = secret.to_string();
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        self.transform_with_row(column, &[])
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        let (latitude_column, longitude_column) = match &self.coordinate_columns {
+            Some(coordinate_columns) => coordinate_columns,
+            None => {
+                // the column holds the whole point
+                return match column {
+                    Column::StringValue(column_name, value) => {
+                        match self.jitter_point(value.as_str()) {
+                            Some(point) => Column::StringValue(column_name, point),
+                            None => Column::StringValue(column_name, value),
+                        }
+                    }
+                    column => column,
+                };
+            }
+        };
+
+        let (latitude, longitude) = match self.row_location(row) {
+            Some(location) => self.jitter(location.0, location.1),
+            None => return column,
+        };
+
+        let new_coordinate = if column.name() == latitude_column.as_str() {
+            latitude
+        } else if column.name() == longitude_column.as_str() {
+            longitude
+        } else {
+            return column;
+        };
+
+        match column {
+            Column::FloatNumberValue(column_name, _) => {
+                Column::FloatNumberValue(column_name, new_coordinate)
+            }
+            Column::StringValue(column_name, _) => {
+                Column::StringValue(column_name, format!("{:.6}", new_coordinate))
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ReplibyteError;
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{GeoJitterTransformer, GeoJitterTransformerOptions};
+
+    /// great-circle distance in meters
+    fn distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+        let (from_latitude, to_latitude) = (from.0.to_radians(), to.0.to_radians());
+        let latitude_delta = to_latitude - from_latitude;
+        let longitude_delta = (to.1 - from.1).to_radians();
+
+        let a = (latitude_delta / 2.0).sin().powi(2)
+            + from_latitude.cos() * to_latitude.cos() * (longitude_delta / 2.0).sin().powi(2);
+
+        2.0 * 6_371_000.0 * a.sqrt().asin()
+    }
+
+    fn parse_point(point: &str) -> (f64, f64) {
+        let (latitude, longitude) = point.split_once(',').unwrap();
+        (latitude.parse().unwrap(), longitude.parse().unwrap())
+    }
+
+    fn transform_point(transformer: &GeoJitterTransformer, point: &str) -> String {
+        let column = Column::StringValue("location".to_string(), point.to_string());
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn point_stays_within_radius() {
+        let transformer = GeoJitterTransformer::new(
+            "public",
+            "stores",
+            "location",
+            GeoJitterTransformerOptions {
+                radius: Some(500),
+                ..Default::default()
+            },
+        );
+
+        for i in 0..1_000 {
+            let original = (48.8566 + i as f64 * 0.001, 2.3522 - i as f64 * 0.001);
+            let point = format!("{},{}", original.0, original.1);
+            let new_point = parse_point(transform_point(&transformer, point.as_str()).as_str());
+
+            // the output is rounded to 6 decimals (~0.1 meter)
+            assert!(distance(original, new_point) <= 501.0);
+        }
+
+        // the same location always gets the same point
+        assert_eq!(
+            transform_point(&transformer, "48.8566,2.3522"),
+            transform_point(&transformer, "48.8566,2.3522")
+        );
+    }
+
+    #[test]
+    fn point_depends_on_the_secret() {
+        let transform = |secret: &str| {
+            let mut transformer = GeoJitterTransformer::default();
+            transformer.set_secret(secret);
+            transform_point(&transformer, "48.8566,2.3522")
+        };
+
+        assert_eq!(transform("secret"), transform("secret"));
+        assert_ne!(transform("secret"), transform("another secret"));
+    }
+
+    #[test]
+    fn point_with_parentheses() {
+        let point = transform_point(&GeoJitterTransformer::default(), "(48.8566, 2.3522)");
+        assert!(point.starts_with('('));
+        assert!(point.ends_with(')'));
+        let new_point = parse_point(point.trim_matches(|c| c == '(' || c == ')'));
+        assert!(distance((48.8566, 2.3522), new_point) <= 1_001.0);
+    }
+
+    #[test]
+    fn latitude_and_longitude_columns_move_together() {
+        let options = GeoJitterTransformerOptions {
+            radius: Some(200),
+            latitude_column: Some("lat".to_string()),
+            longitude_column: Some("lng".to_string()),
+            ..Default::default()
+        };
+        let latitude_transformer =
+            GeoJitterTransformer::new("public", "stores", "lat", options.clone());
+        let longitude_transformer = GeoJitterTransformer::new("public", "stores", "lng", options);
+
+        for i in 0..1_000 {
+            let original = (-33.8688 + i as f64 * 0.01, 151.2093 + i as f64 * 0.01);
+            let row = vec![
+                Column::StringValue("name".to_string(), "store".to_string()),
+                Column::FloatNumberValue("lat".to_string(), original.0),
+                Column::FloatNumberValue("lng".to_string(), original.1),
+            ];
+
+            let latitude = latitude_transformer.transform_with_row(row[1].clone(), &row);
+            let longitude = longitude_transformer.transform_with_row(row[2].clone(), &row);
+            let new_location = (
+                *latitude.float_number_value().unwrap(),
+                *longitude.float_number_value().unwrap(),
+            );
+
+            assert!(distance(original, new_location) <= 200.1);
+        }
+    }
+
+    #[test]
+    fn snap_to_grid() {
+        let transformer = GeoJitterTransformer::new(
+            "public",
+            "stores",
+            "location",
+            GeoJitterTransformerOptions {
+                radius: Some(1_000),
+                grid: Some(5_000),
+                ..Default::default()
+            },
+        );
+
+        // all the locations of the cell are snapped to a few grid points
+        let points = (0..100)
+            .map(|i| transform_point(&transformer, format!("0.0{:02},0.0{:02}", i, i).as_str()))
+            .collect::<std::collections::HashSet<_>>();
+        assert!(points.len() < 10);
+    }
+
+    #[test]
+    fn invalid_values_are_kept() {
+        let transformer = GeoJitterTransformer::default();
+        assert_eq!(transform_point(&transformer, "somewhere"), "somewhere");
+        assert_eq!(transform_point(&transformer, "48.8566"), "48.8566");
+
+        let column = Column::None("location".to_string());
+        assert!(transformer.transform(column).is_null());
+    }
+
+    #[test]
+    fn latitude_column_without_longitude_column() {
+        let result = GeoJitterTransformer::try_new(
+            "public",
+            "stores",
+            "lat",
+            GeoJitterTransformerOptions {
+                latitude_column: Some("lat".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(result, Err(ReplibyteError::Transform(_))));
+    }
+}
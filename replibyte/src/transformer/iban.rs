@@ -0,0 +1,279 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReplibyteError;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+const DEFAULT_COUNTRY_CODE: &str = "DE";
+
+/// BBAN (the IBAN without the country code and the check digits) format by country -
+/// `n` is a digit, `a` an uppercase letter and `c` a digit or an uppercase letter
+const BBAN_FORMATS: [(&str, &str); 17] = [
+    ("AT", "nnnnnnnnnnnnnnnn"),
+    ("BE", "nnnnnnnnnnnn"),
+    ("CH", "nnnnncccccccccccc"),
+    ("DE", "nnnnnnnnnnnnnnnnnn"),
+    ("DK", "nnnnnnnnnnnnnn"),
+    ("ES", "nnnnnnnnnnnnnnnnnnnn"),
+    ("FI", "nnnnnnnnnnnnnn"),
+    ("FR", "nnnnnnnnnncccccccccccnn"),
+    ("GB", "aaaannnnnnnnnnnnnn"),
+    ("IE", "aaaannnnnnnnnnnnnn"),
+    ("IT", "annnnnnnnnncccccccccccc"),
+    ("LU", "nnnccccccccccccc"),
+    ("NL", "aaaannnnnnnnnn"),
+    ("NO", "nnnnnnnnnnn"),
+    ("PL", "nnnnnnnnnnnnnnnnnnnnnnnn"),
+    ("PT", "nnnnnnnnnnnnnnnnnnnnn"),
+    ("SE", "nnnnnnnnnnnnnnnnnnnn"),
+];
+
+const DIGITS: &[u8] = b"0123456789";
+const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS_AND_LETTERS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// This struct is dedicated to replacing a string by a fake IBAN with valid check digits.
+pub struct IbanTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    country_code: String,
+    bban_format: &'static str,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct IbanTransformerOptions {
+    /// ISO 3166-1 country code of the generated IBANs - default to `DE`
+    pub country_code: Option<String>,
+}
+
+impl IbanTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: IbanTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        match Self::try_new(database_name, table_name, column_name, options) {
+            Ok(transformer) => transformer,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// fail when the country code is not supported
+    pub fn try_new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: IbanTransformerOptions,
+    ) -> Result<Self, ReplibyteError>
+    where
+        S: Into<String>,
+    {
+        let country_code = options
+            .country_code
+            .unwrap_or_else(|| DEFAULT_COUNTRY_CODE.to_string())
+            .to_uppercase();
+
+        let bban_format = match BBAN_FORMATS
+            .iter()
+            .find(|(code, _)| *code == country_code.as_str())
+        {
+            Some((_, bban_format)) => *bban_format,
+            None => {
+                return Err(ReplibyteError::Transform(format!(
+                    "iban country code '{}' is not supported - use one of {}",
+                    country_code,
+                    BBAN_FORMATS
+                        .iter()
+                        .map(|(code, _)| *code)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )))
+            }
+        };
+
+        Ok(IbanTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            country_code,
+            bban_format,
+        })
+    }
+
+    fn fake_iban(&self) -> String {
+        let mut rng = rand::thread_rng();
+
+        let bban = self
+            .bban_format
+            .chars()
+            .map(|kind| {
+                let charset = match kind {
+                    'n' => DIGITS,
+                    'a' => LETTERS,
+                    _ => DIGITS_AND_LETTERS,
+                };
+
+                charset[rng.gen_range(0..charset.len())] as char
+            })
+            .collect::<String>();
+
+        // the check digits make the mod-97 of the IBAN equal to 1
+        let check_digits = 98 - mod97(format!("{}{}00", bban, self.country_code).as_str());
+
+        format!("{}{:02}{}", self.country_code, check_digits, bban)
+    }
+}
+
+impl Default for IbanTransformer {
+    fn default() -> Self {
+        IbanTransformer::new("", "", "", IbanTransformerOptions::default())
+    }
+}
+
+/// mod-97 (ISO 7064) of the value where each letter stands for two digits (A = 10... Z = 35)
+fn mod97(value: &str) -> u32 {
+    value.chars().fold(0, |remainder, c| match c.to_digit(36) {
+        Some(n) if n >= 10 => (remainder * 100 + n) % 97,
+        Some(n) => (remainder * 10 + n) % 97,
+        None => remainder,
+    })
+}
+
+impl Transformer for IbanTransformer {
+    fn id(&self) -> &str {
+        "iban"
+    }
+
+    fn description(&self) -> &str {
+        "Generate an IBAN with valid check digits for the `country_code` country (string only). [FR7630006000011234567890189]->[DE89370400440532013000]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![TransformerOption::new(
+            "country_code",
+            TransformerOptionKind::String,
+            false,
+            "ISO 3166-1 country code of the generated IBANs - default to `DE`",
+        )]
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, _value) => {
+                Column::StringValue(column_name, self.fake_iban())
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ReplibyteError;
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{mod97, IbanTransformer, IbanTransformerOptions, BBAN_FORMATS};
+
+    /// an IBAN is valid when the mod-97 of its 4 first characters moved to the end is 1
+    fn is_valid_iban(iban: &str) -> bool {
+        iban.len() > 4 && mod97(format!("{}{}", &iban[4..], &iban[..4]).as_str()) == 1
+    }
+
+    fn transform(transformer: &IbanTransformer) -> String {
+        let column = Column::StringValue(
+            "iban".to_string(),
+            "FR7630006000011234567890189".to_string(),
+        );
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn known_ibans_are_valid() {
+        assert!(is_valid_iban("DE89370400440532013000"));
+        assert!(is_valid_iban("GB29NWBK60161331926819"));
+        assert!(is_valid_iban("FR7630006000011234567890189"));
+        assert!(!is_valid_iban("DE89370400440532013001"));
+    }
+
+    #[test]
+    fn generated_ibans_are_valid() {
+        for (country_code, bban_format) in BBAN_FORMATS {
+            let transformer = IbanTransformer::new(
+                "public",
+                "accounts",
+                "iban",
+                IbanTransformerOptions {
+                    country_code: Some(country_code.to_lowercase()),
+                },
+            );
+
+            for _ in 0..100 {
+                let iban = transform(&transformer);
+                assert!(iban.starts_with(country_code));
+                assert_eq!(iban.len(), 4 + bban_format.len());
+                assert!(is_valid_iban(iban.as_str()), "invalid IBAN {}", iban);
+            }
+        }
+    }
+
+    #[test]
+    fn default_country_code() {
+        let iban = transform(&IbanTransformer::default());
+        assert!(iban.starts_with("DE"));
+        assert_eq!(iban.len(), 22);
+        assert!(is_valid_iban(iban.as_str()));
+    }
+
+    #[test]
+    fn null_value() {
+        let transformer = IbanTransformer::default();
+        let column = Column::None("iban".to_string());
+        let transformed_column = transformer.transform(column);
+        assert!(transformed_column.is_null());
+    }
+
+    #[test]
+    fn number_value() {
+        let transformer = IbanTransformer::default();
+        let column = Column::NumberValue("iban".to_string(), 42);
+        let transformed_column = transformer.transform(column);
+        assert_eq!(transformed_column.number_value(), Some(&42));
+    }
+
+    #[test]
+    fn unsupported_country_code() {
+        let result = IbanTransformer::try_new(
+            "public",
+            "accounts",
+            "iban",
+            IbanTransformerOptions {
+                country_code: Some("XX".to_string()),
+            },
+        );
+
+        assert!(matches!(result, Err(ReplibyteError::Transform(_))));
+    }
+}
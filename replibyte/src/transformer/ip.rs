@@ -0,0 +1,368 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReplibyteError;
+use crate::transformer::keyed_hash::keyed_digest;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+const DEFAULT_IPV4_PREFIX_LENGTH: u8 = 24;
+const DEFAULT_IPV6_PREFIX_LENGTH: u8 = 48;
+
+/// how the host part of the address is anonymized
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum IpMode {
+    /// set the host bits to zero - E.g. 192.168.1.42 -> 192.168.1.0
+    Truncate,
+    /// replace the host bits by a hash of the address - the same address always gets the same host
+    Pseudonymize,
+}
+
+/// This struct is dedicated to anonymizing an IPv4 or IPv6 address while keeping its network.
+pub struct IpTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    ipv4_prefix_length: u8,
+    ipv6_prefix_length: u8,
+    mode: IpMode,
+    secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct IpTransformerOptions {
+    /// number of bits of the IPv4 network kept - default to 24
+    pub ipv4_prefix_length: Option<u8>,
+    /// number of bits of the IPv6 network kept - default to 48
+    pub ipv6_prefix_length: Option<u8>,
+    /// `truncate` (default) or `pseudonymize`
+    pub mode: Option<String>,
+}
+
+impl IpTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: IpTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        match Self::try_new(database_name, table_name, column_name, options) {
+            Ok(transformer) => transformer,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// fail when a prefix length is longer than the address or the mode is unknown
+    pub fn try_new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: IpTransformerOptions,
+    ) -> Result<Self, ReplibyteError>
+    where
+        S: Into<String>,
+    {
+        let ipv4_prefix_length = options
+            .ipv4_prefix_length
+            .unwrap_or(DEFAULT_IPV4_PREFIX_LENGTH);
+
+        if ipv4_prefix_length > 32 {
+            return Err(ReplibyteError::Transform(format!(
+                "ip ipv4_prefix_length '{}' must be between 0 and 32",
+                ipv4_prefix_length
+            )));
+        }
+
+        let ipv6_prefix_length = options
+            .ipv6_prefix_length
+            .unwrap_or(DEFAULT_IPV6_PREFIX_LENGTH);
+
+        if ipv6_prefix_length > 128 {
+            return Err(ReplibyteError::Transform(format!(
+                "ip ipv6_prefix_length '{}' must be between 0 and 128",
+                ipv6_prefix_length
+            )));
+        }
+
+        let mode = match options.mode.as_deref() {
+            None | Some("truncate") => IpMode::Truncate,
+            Some("pseudonymize") => IpMode::Pseudonymize,
+            Some(mode) => {
+                return Err(ReplibyteError::Transform(format!(
+                    "ip mode '{}' is not supported - use truncate or pseudonymize",
+                    mode
+                )))
+            }
+        };
+
+        Ok(IpTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            ipv4_prefix_length,
+            ipv6_prefix_length,
+            mode,
+            secret: String::new(),
+        })
+    }
+
+    fn anonymize(&self, ip: IpAddr) -> IpAddr {
+        match ip {
+            IpAddr::V4(ip) => {
+                let bits = self.anonymize_bits(u32::from(ip) as u128, 32, self.ipv4_prefix_length);
+                IpAddr::V4(Ipv4Addr::from(bits as u32))
+            }
+            IpAddr::V6(ip) => {
+                let bits = self.anonymize_bits(u128::from(ip), 128, self.ipv6_prefix_length);
+                IpAddr::V6(Ipv6Addr::from(bits))
+            }
+        }
+    }
+
+    /// keep the `prefix_length` first bits of the `width` bits address and anonymize the others
+    fn anonymize_bits(&self, bits: u128, width: u8, prefix_length: u8) -> u128 {
+        let host_bits = width - prefix_length;
+        if host_bits == 0 {
+            return bits;
+        }
+
+        let host_mask = u128::MAX >> (128 - host_bits as u32);
+        let network = bits & !host_mask;
+
+        match self.mode {
+            IpMode::Truncate => network,
+            IpMode::Pseudonymize => network | (pseudonym(self.secret.as_str(), bits) & host_mask),
+        }
+    }
+}
+
+/// 128 bits derived from the address - the same address always gets the same pseudonym
+fn pseudonym(secret: &str, bits: u128) -> u128 {
+    let digest = keyed_digest(secret, &[&bits.to_be_bytes()]);
+    u128::from_be_bytes(digest[..16].try_into().unwrap())
+}
+
+impl Default for IpTransformer {
+    fn default() -> Self {
+        IpTransformer::new("", "", "", IpTransformerOptions::default())
+    }
+}
+
+impl Transformer for IpTransformer {
+    fn id(&self) -> &str {
+        "ip"
+    }
+
+    fn description(&self) -> &str {
+        "Anonymize an IPv4 or IPv6 address but keep its network (string only). [192.168.1.42]->[192.168.1.0]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "ipv4_prefix_length",
+                TransformerOptionKind::Integer,
+                false,
+                "number of bits of the IPv4 network kept - default to 24",
+            ),
+            TransformerOption::new(
+                "ipv6_prefix_length",
+                TransformerOptionKind::Integer,
+                false,
+                "number of bits of the IPv6 network kept - default to 48",
+            ),
+            TransformerOption::new(
+                "mode",
+                TransformerOptionKind::String,
+                false,
+                "`truncate` zeroes the host bits (default), `pseudonymize` replaces them by the same value for the same address",
+            ),
+        ]
+    }
+
+    fn set_secret(&mut self, secret: &str) {
+        self.secret = secret.to_string();
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => match value.parse::<IpAddr>() {
+                Ok(ip) => Column::StringValue(column_name, self.anonymize(ip).to_string()),
+                // not an IP address - kept as is
+                Err(_) => Column::StringValue(column_name, value),
+            },
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ReplibyteError;
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{IpTransformer, IpTransformerOptions};
+
+    fn ip_transformer(
+        ipv4_prefix_length: Option<u8>,
+        ipv6_prefix_length: Option<u8>,
+        mode: Option<&str>,
+    ) -> IpTransformer {
+        IpTransformer::new(
+            "public",
+            "visits",
+            "ip",
+            IpTransformerOptions {
+                ipv4_prefix_length,
+                ipv6_prefix_length,
+                mode: mode.map(|mode| mode.to_string()),
+            },
+        )
+    }
+
+    fn transform(transformer: &IpTransformer, ip: &str) -> String {
+        let column = Column::StringValue("ip".to_string(), ip.to_string());
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn truncate_ipv4() {
+        let transformer = IpTransformer::default();
+        assert_eq!(transform(&transformer, "192.168.1.42"), "192.168.1.0");
+
+        let transformer = ip_transformer(Some(16), None, None);
+        assert_eq!(transform(&transformer, "192.168.1.42"), "192.168.0.0");
+
+        let transformer = ip_transformer(Some(20), None, None);
+        assert_eq!(transform(&transformer, "10.1.255.42"), "10.1.240.0");
+
+        let transformer = ip_transformer(Some(32), None, None);
+        assert_eq!(transform(&transformer, "10.1.255.42"), "10.1.255.42");
+
+        let transformer = ip_transformer(Some(0), None, None);
+        assert_eq!(transform(&transformer, "10.1.255.42"), "0.0.0.0");
+    }
+
+    #[test]
+    fn truncate_ipv6() {
+        let transformer = IpTransformer::default();
+        assert_eq!(
+            transform(&transformer, "2001:db8:85a3:8d3:1319:8a2e:370:7348"),
+            "2001:db8:85a3::"
+        );
+
+        let transformer = ip_transformer(None, Some(64), None);
+        assert_eq!(
+            transform(&transformer, "2001:db8:85a3:8d3:1319:8a2e:370:7348"),
+            "2001:db8:85a3:8d3::"
+        );
+
+        let transformer = ip_transformer(None, Some(0), None);
+        assert_eq!(transform(&transformer, "2001:db8::1"), "::");
+    }
+
+    #[test]
+    fn pseudonymize_keeps_the_network() {
+        let transformer = ip_transformer(Some(16), Some(64), Some("pseudonymize"));
+
+        let ipv4 = transform(&transformer, "192.168.1.42");
+        assert!(ipv4.starts_with("192.168."));
+        // the same address always gets the same pseudonym
+        assert_eq!(transform(&transformer, "192.168.1.42"), ipv4);
+        assert_ne!(transform(&transformer, "192.168.1.43"), ipv4);
+
+        let ipv6 = transform(&transformer, "2001:db8:85a3:8d3:1319:8a2e:370:7348");
+        assert!(ipv6.starts_with("2001:db8:85a3:8d3:"));
+        assert_eq!(
+            transform(&transformer, "2001:db8:85a3:8d3:1319:8a2e:370:7348"),
+            ipv6
+        );
+    }
+
+    #[test]
+    fn pseudonymize_depends_on_the_secret() {
+        let mut transformer = ip_transformer(Some(16), None, Some("pseudonymize"));
+        transformer.set_secret("secret");
+        let ipv4 = transform(&transformer, "192.168.1.42");
+        assert_eq!(transform(&transformer, "192.168.1.42"), ipv4);
+
+        // another secret gives other pseudonyms
+        let mut another_transformer = ip_transformer(Some(16), None, Some("pseudonymize"));
+        another_transformer.set_secret("another secret");
+        let another_ipv4 = transform(&another_transformer, "192.168.1.42");
+        assert!(another_ipv4.starts_with("192.168."));
+        assert_ne!(another_ipv4, ipv4);
+    }
+
+    #[test]
+    fn invalid_ip_is_kept() {
+        let transformer = IpTransformer::default();
+        assert_eq!(transform(&transformer, "not an ip"), "not an ip");
+        assert_eq!(transform(&transformer, "256.1.1.1"), "256.1.1.1");
+    }
+
+    #[test]
+    fn null_value() {
+        let transformer = IpTransformer::default();
+        let column = Column::None("ip".to_string());
+        let transformed_column = transformer.transform(column);
+        assert!(transformed_column.is_null());
+    }
+
+    #[test]
+    fn invalid_options() {
+        let result = IpTransformer::try_new(
+            "public",
+            "visits",
+            "ip",
+            IpTransformerOptions {
+                ipv4_prefix_length: Some(33),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(ReplibyteError::Transform(_))));
+
+        let result = IpTransformer::try_new(
+            "public",
+            "visits",
+            "ip",
+            IpTransformerOptions {
+                ipv6_prefix_length: Some(129),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(ReplibyteError::Transform(_))));
+
+        let result = IpTransformer::try_new(
+            "public",
+            "visits",
+            "ip",
+            IpTransformerOptions {
+                mode: Some("shuffle".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(ReplibyteError::Transform(_))));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::transformer::{ColumnType, Transformer, TransformerOption};
+use crate::types::Column;
+
+/// Transformer wrapper keeping NULL values NULL and empty strings empty -
+/// E.g. a `credit-card` transformer would otherwise turn an empty string into a card number.
+pub struct KeepNullAndEmptyTransformer {
+    transformer: Box<dyn Transformer>,
+}
+
+impl KeepNullAndEmptyTransformer {
+    pub fn new(transformer: Box<dyn Transformer>) -> Self {
+        KeepNullAndEmptyTransformer { transformer }
+    }
+}
+
+impl Transformer for KeepNullAndEmptyTransformer {
+    fn id(&self) -> &str {
+        self.transformer.id()
+    }
+
+    fn description(&self) -> &str {
+        self.transformer.description()
+    }
+
+    fn database_name(&self) -> &str {
+        self.transformer.database_name()
+    }
+
+    fn table_name(&self) -> &str {
+        self.transformer.table_name()
+    }
+
+    fn column_name(&self) -> &str {
+        self.transformer.column_name()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        self.transformer.expected_type()
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        self.transformer.option_schema()
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        if column.is_null() || column.is_empty_string() {
+            return column;
+        }
+
+        self.transformer.transform(column)
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        if column.is_null() || column.is_empty_string() {
+            return column;
+        }
+
+        self.transformer.transform_with_row(column, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transformer::keep_null_and_empty::KeepNullAndEmptyTransformer;
+    use crate::transformer::{transformers, Transformer};
+    use crate::types::Column;
+
+    fn transformer(id: &str) -> Box<dyn Transformer> {
+        transformers()
+            .into_iter()
+            .find(|transformer| transformer.id() == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn every_transformer_keeps_null_and_empty() {
+        let cases: [(&str, fn() -> Column); 2] = [
+            ("NULL", || Column::None("a_column".to_string())),
+            ("an empty string", || {
+                Column::StringValue("a_column".to_string(), "".to_string())
+            }),
+        ];
+
+        for transformer in transformers() {
+            let id = transformer.id().to_string();
+            let transformer = KeepNullAndEmptyTransformer::new(transformer);
+
+            for (name, column) in cases.iter() {
+                for transformed_column in [
+                    transformer.transform(column()),
+                    transformer.transform_with_row(column(), &[column()]),
+                ] {
+                    match (column(), transformed_column) {
+                        (Column::None(_), Column::None(_)) => {}
+                        (Column::StringValue(_, _), Column::StringValue(_, value))
+                            if value.is_empty() => {}
+                        _ => panic!("{} does not keep {}", id, name),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_strings_are_transformed_without_the_wrapper() {
+        for id in ["credit-card", "iban", "national-id", "phone-number"] {
+            let column = Column::StringValue("a_column".to_string(), "".to_string());
+            let transformed_column = transformer(id).transform(column);
+            assert_ne!(transformed_column.string_value(), Some(""), "{}", id);
+        }
+    }
+
+    #[test]
+    fn other_values_are_transformed() {
+        let transformer = KeepNullAndEmptyTransformer::new(transformer("credit-card"));
+
+        let column = Column::StringValue("a_column".to_string(), "4242424242424242".to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert!(!transformed_value.is_empty());
+        assert_ne!(transformed_value, "4242424242424242");
+    }
+}
@@ -0,0 +1,88 @@
+use sha2::{Digest, Sha256};
+
+const BLOCK_SIZE: usize = 64;
+
+/// HMAC-SHA256 of `data` keyed with `secret` (RFC 2104) - stable across builds and platforms
+pub fn hmac_sha256(secret: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; BLOCK_SIZE];
+    if secret.len() > BLOCK_SIZE {
+        key[..32].copy_from_slice(&Sha256::digest(secret));
+    } else {
+        key[..secret.len()].copy_from_slice(secret);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(key.map(|b| b ^ 0x36));
+    inner.update(data);
+
+    let mut outer = Sha256::new();
+    outer.update(key.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+/// keyed hash of several values - each value is length prefixed so `("ab", "c")` and `("a", "bc")` differ
+pub fn keyed_digest(secret: &str, values: &[&[u8]]) -> [u8; 32] {
+    let mut data = Vec::new();
+    for value in values {
+        data.extend_from_slice(&(value.len() as u64).to_be_bytes());
+        data.extend_from_slice(value);
+    }
+
+    hmac_sha256(secret.as_bytes(), data.as_slice())
+}
+
+/// 64 bits seed derived from the values - E.g. to seed a `StdRng`
+pub fn keyed_seed(secret: &str, values: &[&[u8]]) -> u64 {
+    let digest = keyed_digest(secret, values);
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hmac_sha256, keyed_digest, keyed_seed};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn rfc_4231_test_vectors() {
+        assert_eq!(
+            hex(&hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // key longer than the block size
+        assert_eq!(
+            hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn values_are_length_prefixed() {
+        assert_ne!(
+            keyed_digest("secret", &[b"ab", b"c"]),
+            keyed_digest("secret", &[b"a", b"bc"])
+        );
+    }
+
+    #[test]
+    fn seed_depends_on_the_secret() {
+        assert_eq!(
+            keyed_seed("secret", &[b"john"]),
+            keyed_seed("secret", &[b"john"])
+        );
+        assert_ne!(
+            keyed_seed("secret", &[b"john"]),
+            keyed_seed("another secret", &[b"john"])
+        );
+    }
+}
@@ -0,0 +1,399 @@
+use fake::faker::lorem::raw::{Word, Words};
+use fake::locales::EN;
+use fake::Fake;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+/// This struct is dedicated to replacing a string by lorem ipsum text.
+#[derive(Default)]
+pub struct LoremTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    options: LoremTransformerOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+pub struct LoremTransformerOptions {
+    /// generate a fixed number of words instead of matching the original length
+    pub words: Option<usize>,
+    /// generate words of the language of the original text (E.g. French) instead of lorem ipsum -
+    /// requires the `language-detection` feature (the configuration is rejected without it),
+    /// lorem ipsum is generated when the language is not detected
+    pub detect_language: Option<bool>,
+}
+
+/// languages of the generated text when `detect_language` is set
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(not(feature = "language-detection"), allow(dead_code))]
+enum Language {
+    English,
+    French,
+    German,
+    Spanish,
+    Italian,
+    Portuguese,
+}
+
+impl Language {
+    #[cfg(feature = "language-detection")]
+    fn detect(text: &str) -> Option<Self> {
+        use whatlang::Lang;
+
+        match whatlang::detect_lang(text)? {
+            Lang::Eng => Some(Language::English),
+            Lang::Fra => Some(Language::French),
+            Lang::Deu => Some(Language::German),
+            Lang::Spa => Some(Language::Spanish),
+            Lang::Ita => Some(Language::Italian),
+            Lang::Por => Some(Language::Portuguese),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(feature = "language-detection"))]
+    fn detect(_text: &str) -> Option<Self> {
+        None
+    }
+
+    fn words(&self) -> &'static [&'static str] {
+        match self {
+            Language::English => &ENGLISH_WORDS,
+            Language::French => &FRENCH_WORDS,
+            Language::German => &GERMAN_WORDS,
+            Language::Spanish => &SPANISH_WORDS,
+            Language::Italian => &ITALIAN_WORDS,
+            Language::Portuguese => &PORTUGUESE_WORDS,
+        }
+    }
+
+    fn word(&self) -> &'static str {
+        self.words()
+            .choose(&mut rand::thread_rng())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+const ENGLISH_WORDS: [&str; 24] = [
+    "the", "house", "water", "small", "people", "always", "morning", "city", "green", "window",
+    "friend", "between", "travel", "letter", "garden", "quickly", "table", "because", "evening",
+    "river", "bring", "often", "paper", "light",
+];
+
+const FRENCH_WORDS: [&str; 24] = [
+    "le",
+    "maison",
+    "eau",
+    "petit",
+    "toujours",
+    "matin",
+    "ville",
+    "vert",
+    "fenêtre",
+    "ami",
+    "entre",
+    "voyage",
+    "lettre",
+    "jardin",
+    "rapidement",
+    "table",
+    "parce",
+    "soir",
+    "rivière",
+    "apporter",
+    "souvent",
+    "papier",
+    "lumière",
+    "été",
+];
+
+const GERMAN_WORDS: [&str; 24] = [
+    "das", "Haus", "Wasser", "klein", "Leute", "immer", "Morgen", "Stadt", "grün", "Fenster",
+    "Freund", "zwischen", "Reise", "Brief", "Garten", "schnell", "Tisch", "weil", "Abend", "Fluss",
+    "bringen", "oft", "Papier", "Licht",
+];
+
+const SPANISH_WORDS: [&str; 24] = [
+    "la", "casa", "agua", "pequeño", "gente", "siempre", "mañana", "ciudad", "verde", "ventana",
+    "amigo", "entre", "viaje", "carta", "jardín", "rápido", "mesa", "porque", "tarde", "río",
+    "traer", "menudo", "papel", "luz",
+];
+
+const ITALIAN_WORDS: [&str; 24] = [
+    "la", "casa", "acqua", "piccolo", "gente", "sempre", "mattina", "città", "verde", "finestra",
+    "amico", "tra", "viaggio", "lettera", "giardino", "veloce", "tavolo", "perché", "sera",
+    "fiume", "portare", "spesso", "carta", "luce",
+];
+
+const PORTUGUESE_WORDS: [&str; 24] = [
+    "a", "casa", "água", "pequeno", "gente", "sempre", "manhã", "cidade", "verde", "janela",
+    "amigo", "entre", "viagem", "carta", "jardim", "rápido", "mesa", "porque", "noite", "rio",
+    "trazer", "muitas", "papel", "luz",
+];
+
+impl LoremTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: LoremTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        LoremTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            options,
+        }
+    }
+}
+
+impl Transformer for LoremTransformer {
+    fn id(&self) -> &str {
+        "lorem"
+    }
+
+    fn description(&self) -> &str {
+        "Generate lorem ipsum text with the same length (string only). [I like it]->[sit amet]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "words",
+                TransformerOptionKind::Integer,
+                false,
+                "generate a fixed number of words instead of matching the original length",
+            ),
+            TransformerOption::new(
+                "detect_language",
+                TransformerOptionKind::Boolean,
+                false,
+                "generate words of the language of the original text (English, French, German, Spanish, Italian or Portuguese)",
+            ),
+        ]
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                let language = match self.options.detect_language {
+                    Some(true) => Language::detect(value.as_str()),
+                    _ => None,
+                };
+
+                let word = || match language {
+                    Some(language) => language.word(),
+                    None => Word(EN).fake(),
+                };
+
+                let new_value = match self.options.words {
+                    Some(words) if language.is_some() => {
+                        (0..words).map(|_| word()).collect::<Vec<_>>().join(" ")
+                    }
+                    Some(words) => {
+                        let words: Vec<String> = Words(EN, words..words + 1).fake();
+                        words.join(" ")
+                    }
+                    None => words_with_length(value.chars().count(), word),
+                };
+
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+/// generate words until `length` characters are reached
+fn words_with_length<F: FnMut() -> &'static str>(length: usize, mut word: F) -> String {
+    let mut value = String::with_capacity(length + 16);
+    let mut value_length = 0;
+
+    while value_length < length {
+        if !value.is_empty() {
+            value.push(' ');
+            value_length += 1;
+        }
+
+        let word = word();
+        value.push_str(word);
+        value_length += word.chars().count();
+    }
+
+    // the words of the other languages than lorem ipsum are not ascii only
+    let value = value.chars().take(length).collect::<String>();
+    value.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{
+        words_with_length, Language, LoremTransformer, LoremTransformerOptions, GERMAN_WORDS,
+    };
+
+    #[test]
+    fn transform_lorem_with_string_value() {
+        let transformer = get_transformer(LoremTransformerOptions::default());
+        let original_value = "I'd like to say... I don't know what to say.";
+        let column = Column::StringValue("description".to_string(), original_value.to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_ne!(transformed_value, original_value);
+        // the length can differ by one char when the last word ends with a trimmed space
+        let diff = (original_value.len() as i64 - transformed_value.len() as i64).abs();
+        assert!(diff <= 1);
+    }
+
+    #[test]
+    fn transform_lorem_with_fixed_words() {
+        let transformer = get_transformer(LoremTransformerOptions {
+            words: Some(5),
+            ..Default::default()
+        });
+        let column = Column::StringValue("description".to_string(), "hello".to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_eq!(transformed_value.split(' ').count(), 5);
+    }
+
+    #[test]
+    fn transform_lorem_with_empty_string_value() {
+        let transformer = get_transformer(LoremTransformerOptions::default());
+        let column = Column::StringValue("description".to_string(), "".to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_eq!(transformed_value, "");
+    }
+
+    #[test]
+    fn transform_lorem_with_null_value() {
+        let transformer = get_transformer(LoremTransformerOptions::default());
+        let column = Column::None("description".to_string());
+        let transformed_column = transformer.transform(column);
+
+        assert!(matches!(transformed_column, Column::None(_)));
+    }
+
+    /// the transformed words all belong to the words of `language`
+    #[cfg(feature = "language-detection")]
+    fn assert_words_of_language(
+        transformer: &LoremTransformer,
+        original_value: &str,
+        language: Language,
+    ) {
+        let column = Column::StringValue("description".to_string(), original_value.to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_ne!(transformed_value, original_value);
+
+        let mut words = transformed_value.split(' ').collect::<Vec<_>>();
+        // the last word is cut to match the original length
+        let last_word = words.pop().unwrap();
+        assert!(words.iter().all(|word| language.words().contains(word)));
+        assert!(language
+            .words()
+            .iter()
+            .any(|word| word.starts_with(last_word)));
+    }
+
+    #[test]
+    fn words_of_language_with_length() {
+        for length in [1, 12, 55] {
+            let value = words_with_length(length, || Language::German.word());
+            // the trailing space of a cut value is trimmed
+            assert!(value.chars().count() >= length - 1 && value.chars().count() <= length);
+
+            let mut words = value.split(' ').collect::<Vec<_>>();
+            // the last word is cut to match the original length
+            let last_word = words.pop().unwrap();
+            assert!(words.iter().all(|word| GERMAN_WORDS.contains(word)));
+            assert!(GERMAN_WORDS.iter().any(|word| word.starts_with(last_word)));
+        }
+    }
+
+    #[cfg(feature = "language-detection")]
+    #[test]
+    fn transform_lorem_with_detected_language() {
+        let transformer = get_transformer(LoremTransformerOptions {
+            detect_language: Some(true),
+            ..Default::default()
+        });
+
+        assert_words_of_language(
+            &transformer,
+            "Je voudrais réserver une table pour deux personnes ce soir, s'il vous plaît.",
+            Language::French,
+        );
+        assert_words_of_language(
+            &transformer,
+            "Ich möchte heute Abend einen Tisch für zwei Personen reservieren, bitte.",
+            Language::German,
+        );
+
+        let transformer = get_transformer(LoremTransformerOptions {
+            words: Some(5),
+            detect_language: Some(true),
+        });
+        let column = Column::StringValue(
+            "description".to_string(),
+            "Me gustaría reservar una mesa para dos personas esta noche, por favor.".to_string(),
+        );
+        let transformed_column = transformer.transform(column);
+        let words = transformed_column
+            .string_value()
+            .unwrap()
+            .split(' ')
+            .map(|word| word.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(words.len(), 5);
+        assert!(words
+            .iter()
+            .all(|word| Language::Spanish.words().contains(&word.as_str())));
+    }
+
+    #[cfg(feature = "language-detection")]
+    #[test]
+    fn transform_lorem_with_detected_language_null_value() {
+        let transformer = get_transformer(LoremTransformerOptions {
+            detect_language: Some(true),
+            ..Default::default()
+        });
+        let column = Column::None("description".to_string());
+
+        assert!(matches!(transformer.transform(column), Column::None(_)));
+    }
+
+    fn get_transformer(options: LoremTransformerOptions) -> LoremTransformer {
+        LoremTransformer::new("github", "users", "description", options)
+    }
+}
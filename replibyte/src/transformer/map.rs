@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+/// This struct is dedicated to replacing a string by its value from a mapping.
+#[derive(Default)]
+pub struct MapTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    options: MapTransformerOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct MapTransformerOptions {
+    pub mapping: HashMap<String, String>,
+    /// value used when the original value is not in the mapping - keep the original value otherwise
+    pub default: Option<String>,
+}
+
+impl MapTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: MapTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        MapTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            options,
+        }
+    }
+}
+
+impl Transformer for MapTransformer {
+    fn id(&self) -> &str {
+        "map"
+    }
+
+    fn description(&self) -> &str {
+        "Replace the value by the one from the mapping (string only). [premium]->[tier_a]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "mapping",
+                TransformerOptionKind::StringMap,
+                true,
+                "original value -> new value",
+            ),
+            TransformerOption::new(
+                "default",
+                TransformerOptionKind::String,
+                false,
+                "value used when the original value is not in the mapping - keep the original value otherwise",
+            ),
+        ]
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                let new_value = match self.options.mapping.get(value.as_str()) {
+                    Some(mapped_value) => mapped_value.clone(),
+                    None => match &self.options.default {
+                        Some(default) => default.clone(),
+                        None => value,
+                    },
+                };
+
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{MapTransformer, MapTransformerOptions};
+
+    #[test]
+    fn transform_mapped_value() {
+        let transformer = get_transformer(None);
+        let column = Column::StringValue("plan".to_string(), "premium".to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_eq!(transformed_value, "tier_a");
+    }
+
+    #[test]
+    fn transform_unmapped_value() {
+        let transformer = get_transformer(None);
+        let column = Column::StringValue("plan".to_string(), "enterprise".to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_eq!(transformed_value, "enterprise");
+
+        let transformer = get_transformer(Some("tier_z".to_string()));
+        let column = Column::StringValue("plan".to_string(), "enterprise".to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_eq!(transformed_value, "tier_z");
+    }
+
+    #[test]
+    fn transform_null_value() {
+        let transformer = get_transformer(Some("tier_z".to_string()));
+        let column = Column::None("plan".to_string());
+        let transformed_column = transformer.transform(column);
+
+        assert!(matches!(transformed_column, Column::None(_)));
+    }
+
+    fn get_transformer(default: Option<String>) -> MapTransformer {
+        let mut mapping = HashMap::new();
+        mapping.insert("premium".to_string(), "tier_a".to_string());
+        mapping.insert("basic".to_string(), "tier_b".to_string());
+
+        MapTransformer::new(
+            "github",
+            "users",
+            "plan",
+            MapTransformerOptions { mapping, default },
+        )
+    }
+}
@@ -1,21 +1,58 @@
+use crate::transformer::case::CaseTransformer;
+use crate::transformer::company::CompanyTransformer;
 use crate::transformer::credit_card::CreditCardTransformer;
 use crate::transformer::email::EmailTransformer;
 use crate::transformer::first_name::FirstNameTransformer;
+use crate::transformer::full_name::FullNameTransformer;
+use crate::transformer::geo_jitter::GeoJitterTransformer;
+use crate::transformer::iban::IbanTransformer;
+use crate::transformer::ip::IpTransformer;
 use crate::transformer::keep_first_char::KeepFirstCharTransformer;
+use crate::transformer::lorem::LoremTransformer;
+use crate::transformer::map::MapTransformer;
+use crate::transformer::national_id::NationalIdTransformer;
+use crate::transformer::person_name::PersonNameTransformer;
 use crate::transformer::phone_number::PhoneNumberTransformer;
 use crate::transformer::random::RandomTransformer;
 use crate::transformer::redacted::RedactedTransformer;
+use crate::transformer::regex_redact::RegexRedactTransformer;
+use crate::transformer::template::TemplateTransformer;
 use crate::transformer::transient::TransientTransformer;
+use crate::transformer::truncate_time::TruncateTimeTransformer;
+use crate::transformer::url::UrlTransformer;
+use crate::transformer::username::UsernameTransformer;
+use crate::transformer::xml::XmlTransformer;
 use crate::types::Column;
+use serde::Serialize;
+use std::collections::HashMap;
 
+pub mod case;
+pub mod company;
 pub mod credit_card;
 pub mod email;
 pub mod first_name;
+pub mod full_name;
+pub mod geo_jitter;
+pub mod iban;
+pub mod ip;
 pub mod keep_first_char;
+pub mod keep_null_and_empty;
+pub mod keyed_hash;
+pub mod lorem;
+pub mod map;
+pub mod national_id;
+pub mod person_name;
 pub mod phone_number;
+pub mod preserve_case;
 pub mod random;
 pub mod redacted;
+pub mod regex_redact;
+pub mod template;
 pub mod transient;
+pub mod truncate_time;
+pub mod url;
+pub mod username;
+pub mod xml;
 
 pub fn transformers() -> Vec<Box<dyn Transformer>> {
     vec![
@@ -27,6 +64,22 @@ pub fn transformers() -> Vec<Box<dyn Transformer>> {
         Box::new(TransientTransformer::default()),
         Box::new(CreditCardTransformer::default()),
         Box::new(RedactedTransformer::default()),
+        Box::new(LoremTransformer::default()),
+        Box::new(MapTransformer::default()),
+        Box::new(RegexRedactTransformer::default()),
+        Box::new(PersonNameTransformer::default()),
+        Box::new(CompanyTransformer::default()),
+        Box::new(IbanTransformer::default()),
+        Box::new(IpTransformer::default()),
+        Box::new(UrlTransformer::default()),
+        Box::new(GeoJitterTransformer::default()),
+        Box::new(NationalIdTransformer::default()),
+        Box::new(UsernameTransformer::default()),
+        Box::new(TemplateTransformer::default()),
+        Box::new(XmlTransformer::default()),
+        Box::new(FullNameTransformer::default()),
+        Box::new(TruncateTimeTransformer::default()),
+        Box::new(CaseTransformer::default()),
     ]
 }
 
@@ -48,5 +101,254 @@ pub trait Transformer: Sync {
             self.column_name()
         )
     }
+    /// type of the columns the transformer is meant for - checked against the source columns
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Any
+    }
+    /// options accepted in `transformer_options` - checked when the configuration is loaded
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![]
+    }
+    /// secret keying the pseudonyms - set from `pseudonym_secret`, ignored by default
+    fn set_secret(&mut self, _secret: &str) {}
     fn transform(&self, column: Column) -> Column;
+    /// transform the column knowing the original columns of its row - default to `transform`
+    fn transform_with_row(&self, column: Column, _row: &[Column]) -> Column {
+        self.transform(column)
+    }
+}
+
+/// an option of a transformer (E.g. `words` for `lorem`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TransformerOption {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub kind: TransformerOptionKind,
+    pub required: bool,
+    pub description: &'static str,
+}
+
+impl TransformerOption {
+    pub fn new(
+        name: &'static str,
+        kind: TransformerOptionKind,
+        required: bool,
+        description: &'static str,
+    ) -> Self {
+        TransformerOption {
+            name,
+            kind,
+            required,
+            description,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformerOptionKind {
+    String,
+    Integer,
+    Boolean,
+    Char,
+    StringList,
+    StringMap,
+    MapList,
+}
+
+impl TransformerOptionKind {
+    pub fn name(&self) -> &str {
+        match self {
+            TransformerOptionKind::String => "string",
+            TransformerOptionKind::Integer => "integer",
+            TransformerOptionKind::Boolean => "boolean",
+            TransformerOptionKind::Char => "char",
+            TransformerOptionKind::StringList => "list of strings",
+            TransformerOptionKind::StringMap => "map of strings",
+            TransformerOptionKind::MapList => "list of maps",
+        }
+    }
+}
+
+/// type of a column - E.g. `credit-card` expects a text column and a text transformer on an integer column is likely a mistake
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    /// any column - E.g. `random` or `transient`
+    Any,
+    Text,
+    Number,
+    Boolean,
+    Date,
+}
+
+impl ColumnType {
+    pub fn name(&self) -> &str {
+        match self {
+            ColumnType::Any => "any",
+            ColumnType::Text => "text",
+            ColumnType::Number => "number",
+            ColumnType::Boolean => "boolean",
+            ColumnType::Date => "date",
+        }
+    }
+
+    /// type of a PostgreSQL column from its `information_schema.columns.data_type` -
+    /// None for the types which are not checked (E.g. arrays, json or user-defined types)
+    pub fn of_postgres_type(data_type: &str) -> Option<ColumnType> {
+        match data_type {
+            "text" | "character varying" | "character" | "name" | "xml" => Some(ColumnType::Text),
+            "smallint" | "integer" | "bigint" | "numeric" | "real" | "double precision" => {
+                Some(ColumnType::Number)
+            }
+            "boolean" => Some(ColumnType::Boolean),
+            "date" | "interval" => Some(ColumnType::Date),
+            data_type if data_type.starts_with("timestamp") || data_type.starts_with("time ") => {
+                Some(ColumnType::Date)
+            }
+            _ => None,
+        }
+    }
+
+    /// a transformer expecting `self` can be applied on a `column_type` column
+    pub fn accepts(&self, column_type: ColumnType) -> bool {
+        *self == ColumnType::Any || *self == column_type
+    }
+}
+
+/// `*` in the database, table or column name of a transformer matches any name (E.g. `*.*.email`)
+const WILDCARD: &str = "*";
+
+/// `*` matches any characters, the other characters match themselves - E.g. `gold*` matches `gold` and `gold_plus`
+pub(crate) fn name_matches(pattern: &str, name: &str) -> bool {
+    match pattern.split_once(WILDCARD) {
+        None => pattern == name,
+        Some((prefix, suffix)) if !suffix.contains(WILDCARD) => {
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        }
+        Some((prefix, rest)) => match name.strip_prefix(prefix) {
+            Some(name) => (0..=name.len())
+                .filter(|idx| name.is_char_boundary(*idx))
+                .any(|idx| name_matches(rest, &name[idx..])),
+            None => false,
+        },
+    }
+}
+
+fn is_pattern(transformer: &dyn Transformer) -> bool {
+    transformer
+        .database_and_table_and_column_name()
+        .contains(WILDCARD)
+}
+
+/// Find the transformer of a column - the transformers with the exact `database.table.column` name
+/// take precedence over the patterns, which are expanded the first time a column is seen
+pub struct ColumnTransformers<'a> {
+    transformers: HashMap<String, &'a dyn Transformer>,
+    patterns: Vec<&'a dyn Transformer>,
+}
+
+impl<'a> ColumnTransformers<'a> {
+    pub fn new(transformers: &'a Vec<Box<dyn Transformer>>) -> Self {
+        let mut column_transformers = ColumnTransformers {
+            transformers: HashMap::with_capacity(transformers.len()),
+            patterns: vec![],
+        };
+
+        for transformer in transformers {
+            let transformer = transformer.as_ref();
+
+            if is_pattern(transformer) {
+                column_transformers.patterns.push(transformer);
+            } else {
+                let _ = column_transformers.transformers.insert(
+                    transformer.database_and_table_and_column_name(),
+                    transformer,
+                );
+            }
+        }
+
+        column_transformers
+    }
+
+    pub fn get(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        column_name: &str,
+    ) -> Option<&'a dyn Transformer> {
+        let key = format!("{}.{}.{}", database_name, table_name, column_name);
+
+        if let Some(transformer) = self.transformers.get(key.as_str()) {
+            return Some(*transformer);
+        }
+
+        let transformer = self.patterns.iter().copied().find(|transformer| {
+            name_matches(transformer.database_name(), database_name)
+                && name_matches(transformer.table_name(), table_name)
+                && name_matches(transformer.column_name(), column_name)
+        })?;
+
+        // the next rows of the table don't go through the patterns again
+        let _ = self.transformers.insert(key, transformer);
+
+        Some(transformer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transformer::email::EmailTransformer;
+    use crate::transformer::random::RandomTransformer;
+    use crate::transformer::{name_matches, ColumnTransformers, Transformer};
+
+    #[test]
+    fn test_name_matches() {
+        assert!(name_matches("email", "email"));
+        assert!(!name_matches("email", "emails"));
+        assert!(name_matches("*", "users"));
+        assert!(name_matches("*_email", "contact_email"));
+        assert!(name_matches("user_*", "user_"));
+        assert!(name_matches("*email*", "work_email_address"));
+        assert!(!name_matches("*_email", "email"));
+        assert!(!name_matches("user_*_at", "user_at"));
+    }
+
+    #[test]
+    fn test_column_transformers() {
+        let transformers: Vec<Box<dyn Transformer>> = vec![
+            Box::new(EmailTransformer::new("*", "*", "email")),
+            Box::new(RandomTransformer::new("public", "admins", "email")),
+        ];
+
+        let mut column_transformers = ColumnTransformers::new(&transformers);
+
+        assert_eq!(
+            column_transformers
+                .get("public", "users", "email")
+                .unwrap()
+                .id(),
+            "email"
+        );
+        assert_eq!(
+            column_transformers
+                .get("sales", "customers", "email")
+                .unwrap()
+                .id(),
+            "email"
+        );
+        // the exact name takes precedence over the pattern
+        assert_eq!(
+            column_transformers
+                .get("public", "admins", "email")
+                .unwrap()
+                .id(),
+            "random"
+        );
+        assert!(column_transformers
+            .get("public", "users", "first_name")
+            .is_none());
+    }
 }
@@ -0,0 +1,439 @@
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReplibyteError;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+const DEFAULT_COUNTRY_CODE: &str = "US";
+
+/// supported country codes and the national identifier generated for them
+const COUNTRY_CODES: [(&str, NationalId); 5] = [
+    ("US", NationalId::Ssn),
+    ("GB", NationalId::Nino),
+    ("FR", NationalId::Nir),
+    ("ES", NationalId::Dni),
+    ("NL", NationalId::Bsn),
+];
+
+/// NINO prefix letters - the second letter can't be `O` either
+const NINO_PREFIX_LETTERS: &[u8] = b"ABCEGHJKLMNOPRSTWXYZ";
+/// NINO prefixes which are never allocated
+const NINO_FORBIDDEN_PREFIXES: [&str; 7] = ["BG", "GB", "KN", "NK", "NT", "TN", "ZZ"];
+const NINO_SUFFIX_LETTERS: &[u8] = b"ABCD";
+
+/// DNI check letter by the remainder of the number divided by 23
+const DNI_CHECK_LETTERS: &[u8] = b"TRWAGMYFPDXBNJZSQVHLCKE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NationalId {
+    /// US Social Security Number - E.g. `512-38-4410`
+    Ssn,
+    /// UK National Insurance Number - E.g. `AB123456C`
+    Nino,
+    /// French social security number (NIR) with its 2 key digits - E.g. `185057800608491`
+    Nir,
+    /// Spanish national identity document number with its check letter - E.g. `12345678Z`
+    Dni,
+    /// Dutch citizen service number passing the 11-test - E.g. `111222333`
+    Bsn,
+}
+
+impl NationalId {
+    fn fake(&self, rng: &mut ThreadRng) -> String {
+        match self {
+            NationalId::Ssn => fake_ssn(rng),
+            NationalId::Nino => fake_nino(rng),
+            NationalId::Nir => fake_nir(rng),
+            NationalId::Dni => fake_dni(rng),
+            NationalId::Bsn => fake_bsn(rng),
+        }
+    }
+}
+
+/// the area is never 000, 666 or 9xx, the group never 00 and the serial never 0000
+fn fake_ssn(rng: &mut ThreadRng) -> String {
+    let area = loop {
+        let area = rng.gen_range(1..900);
+        if area != 666 {
+            break area;
+        }
+    };
+
+    format!(
+        "{:03}-{:02}-{:04}",
+        area,
+        rng.gen_range(1..100),
+        rng.gen_range(1..10_000)
+    )
+}
+
+fn fake_nino(rng: &mut ThreadRng) -> String {
+    let prefix = loop {
+        let first = NINO_PREFIX_LETTERS[rng.gen_range(0..NINO_PREFIX_LETTERS.len())] as char;
+        let second = NINO_PREFIX_LETTERS[rng.gen_range(0..NINO_PREFIX_LETTERS.len())] as char;
+        let prefix = format!("{}{}", first, second);
+
+        if second != 'O' && !NINO_FORBIDDEN_PREFIXES.contains(&prefix.as_str()) {
+            break prefix;
+        }
+    };
+
+    format!(
+        "{}{:06}{}",
+        prefix,
+        rng.gen_range(0..1_000_000),
+        NINO_SUFFIX_LETTERS[rng.gen_range(0..NINO_SUFFIX_LETTERS.len())] as char
+    )
+}
+
+/// sex, year, month, department of birth (mainland, without Corsica), commune and order number,
+/// followed by the key: 97 minus the number modulo 97
+fn fake_nir(rng: &mut ThreadRng) -> String {
+    let department = loop {
+        let department = rng.gen_range(1..96);
+        if department != 20 {
+            break department;
+        }
+    };
+
+    let number = format!(
+        "{}{:02}{:02}{:02}{:03}{:03}",
+        rng.gen_range(1..=2),
+        rng.gen_range(0..100),
+        rng.gen_range(1..=12),
+        department,
+        rng.gen_range(1..991),
+        rng.gen_range(1..1000)
+    );
+
+    let key = 97 - number.parse::<u64>().unwrap() % 97;
+
+    format!("{}{:02}", number, key)
+}
+
+fn fake_dni(rng: &mut ThreadRng) -> String {
+    let number: u32 = rng.gen_range(0..100_000_000);
+    let check_letter = DNI_CHECK_LETTERS[(number % 23) as usize] as char;
+
+    format!("{:08}{}", number, check_letter)
+}
+
+/// the weighted sum of the digits (9..2 and -1 for the last one) is a multiple of 11
+fn fake_bsn(rng: &mut ThreadRng) -> String {
+    loop {
+        let digits = (0..8).map(|_| rng.gen_range(0..10)).collect::<Vec<u32>>();
+        let sum = bsn_weighted_sum(&digits);
+
+        // the check digit is the remainder - retry when it does not fit in a digit
+        let check_digit = sum % 11;
+        if check_digit <= 9 && digits[0] != 0 {
+            return digits
+                .iter()
+                .chain(std::iter::once(&check_digit))
+                .map(|digit| digit.to_string())
+                .collect();
+        }
+    }
+}
+
+fn bsn_weighted_sum(digits: &[u32]) -> u32 {
+    digits
+        .iter()
+        .zip((2..=9).rev())
+        .map(|(digit, weight)| digit * weight)
+        .sum()
+}
+
+/// This struct is dedicated to replacing a string by a fake national identifier which passes the
+/// format and check digit validations of the `country_code` country.
+pub struct NationalIdTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    national_id: NationalId,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct NationalIdTransformerOptions {
+    /// ISO 3166-1 country code of the generated identifiers - default to `US`
+    pub country_code: Option<String>,
+}
+
+impl NationalIdTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: NationalIdTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        match Self::try_new(database_name, table_name, column_name, options) {
+            Ok(transformer) => transformer,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// fail when the country code is not supported
+    pub fn try_new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: NationalIdTransformerOptions,
+    ) -> Result<Self, ReplibyteError>
+    where
+        S: Into<String>,
+    {
+        let country_code = options
+            .country_code
+            .unwrap_or_else(|| DEFAULT_COUNTRY_CODE.to_string())
+            .to_uppercase();
+
+        let national_id = match COUNTRY_CODES
+            .iter()
+            .find(|(code, _)| *code == country_code.as_str())
+        {
+            Some((_, national_id)) => *national_id,
+            None => {
+                return Err(ReplibyteError::Transform(format!(
+                    "national-id country code '{}' is not supported - use one of {}",
+                    country_code,
+                    COUNTRY_CODES
+                        .iter()
+                        .map(|(code, _)| *code)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )))
+            }
+        };
+
+        Ok(NationalIdTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            national_id,
+        })
+    }
+}
+
+impl Default for NationalIdTransformer {
+    fn default() -> Self {
+        NationalIdTransformer::new("", "", "", NationalIdTransformerOptions::default())
+    }
+}
+
+impl Transformer for NationalIdTransformer {
+    fn id(&self) -> &str {
+        "national-id"
+    }
+
+    fn description(&self) -> &str {
+        "Generate a national identifier with a valid format for the `country_code` country - US SSN, GB NINO, FR NIR, ES DNI or NL BSN (string only). [078-05-1120]->[512-38-4410]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![TransformerOption::new(
+            "country_code",
+            TransformerOptionKind::String,
+            false,
+            "ISO 3166-1 country code of the generated identifiers (US, GB, FR, ES or NL) - default to `US`",
+        )]
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, _value) => {
+                Column::StringValue(column_name, self.national_id.fake(&mut rand::thread_rng()))
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ReplibyteError;
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{
+        bsn_weighted_sum, NationalIdTransformer, NationalIdTransformerOptions, DNI_CHECK_LETTERS,
+        NINO_FORBIDDEN_PREFIXES,
+    };
+
+    fn transformer(country_code: &str) -> NationalIdTransformer {
+        NationalIdTransformer::new(
+            "public",
+            "users",
+            "national_id",
+            NationalIdTransformerOptions {
+                country_code: Some(country_code.to_string()),
+            },
+        )
+    }
+
+    fn transform(transformer: &NationalIdTransformer) -> String {
+        let column = Column::StringValue("national_id".to_string(), "078-05-1120".to_string());
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    fn is_digits(value: &str) -> bool {
+        !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+    }
+
+    fn is_valid_ssn(ssn: &str) -> bool {
+        let parts = ssn.split('-').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [area, group, serial] => {
+                area.len() == 3
+                    && group.len() == 2
+                    && serial.len() == 4
+                    && [area, group, serial].iter().all(|part| is_digits(part))
+                    && !["000", "666"].contains(area)
+                    && !area.starts_with('9')
+                    && *group != "00"
+                    && *serial != "0000"
+            }
+            _ => false,
+        }
+    }
+
+    fn is_valid_nino(nino: &str) -> bool {
+        nino.len() == 9
+            && nino[..2]
+                .chars()
+                .all(|c| "ABCEGHJKLMNOPRSTWXYZ".contains(c))
+            && !nino[1..2].contains('O')
+            && !NINO_FORBIDDEN_PREFIXES.contains(&&nino[..2])
+            && is_digits(&nino[2..8])
+            && "ABCD".contains(&nino[8..])
+    }
+
+    fn is_valid_nir(nir: &str) -> bool {
+        if nir.len() != 15 || !is_digits(nir) {
+            return false;
+        }
+
+        let number = nir[..13].parse::<u64>().unwrap();
+        let key = nir[13..].parse::<u64>().unwrap();
+        let month = nir[3..5].parse::<u32>().unwrap();
+
+        ["1", "2"].contains(&&nir[..1]) && (1..=12).contains(&month) && key == 97 - number % 97
+    }
+
+    fn is_valid_dni(dni: &str) -> bool {
+        dni.len() == 9
+            && is_digits(&dni[..8])
+            && dni[8..].as_bytes()[0]
+                == DNI_CHECK_LETTERS[(dni[..8].parse::<usize>().unwrap()) % 23]
+    }
+
+    fn is_valid_bsn(bsn: &str) -> bool {
+        if bsn.len() != 9 || !is_digits(bsn) {
+            return false;
+        }
+
+        let digits = bsn
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .collect::<Vec<_>>();
+        let sum = bsn_weighted_sum(&digits[..8]) as i64 - digits[8] as i64;
+        sum % 11 == 0
+    }
+
+    #[test]
+    fn known_identifiers_are_valid() {
+        assert!(is_valid_ssn("078-05-1120"));
+        assert!(!is_valid_ssn("666-05-1120"));
+        assert!(!is_valid_ssn("078-00-1120"));
+        assert!(is_valid_nino("AB123456C"));
+        assert!(!is_valid_nino("GB123456C"));
+        assert!(is_valid_nir("185057800608491"));
+        assert!(!is_valid_nir("185057800608436"));
+        assert!(is_valid_dni("12345678Z"));
+        assert!(!is_valid_dni("12345678A"));
+        assert!(is_valid_bsn("111222333"));
+        assert!(!is_valid_bsn("111222334"));
+    }
+
+    #[test]
+    fn generated_identifiers_are_valid() {
+        let is_valid_by_country: [(&str, fn(&str) -> bool); 5] = [
+            ("US", is_valid_ssn),
+            ("GB", is_valid_nino),
+            ("FR", is_valid_nir),
+            ("ES", is_valid_dni),
+            ("NL", is_valid_bsn),
+        ];
+
+        for (country_code, is_valid) in is_valid_by_country {
+            let transformer = transformer(country_code.to_lowercase().as_str());
+
+            for _ in 0..1000 {
+                let national_id = transform(&transformer);
+                assert!(
+                    is_valid(national_id.as_str()),
+                    "invalid {} national id {}",
+                    country_code,
+                    national_id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn default_country_code() {
+        let ssn = transform(&NationalIdTransformer::default());
+        assert!(is_valid_ssn(ssn.as_str()));
+        assert_ne!(ssn, "078-05-1120");
+    }
+
+    #[test]
+    fn null_value() {
+        let transformer = NationalIdTransformer::default();
+        let column = Column::None("national_id".to_string());
+        let transformed_column = transformer.transform(column);
+        assert!(transformed_column.is_null());
+    }
+
+    #[test]
+    fn number_value() {
+        let transformer = transformer("NL");
+        let column = Column::NumberValue("national_id".to_string(), 111222333);
+        let transformed_column = transformer.transform(column);
+        assert_eq!(transformed_column.number_value(), Some(&111222333));
+    }
+
+    #[test]
+    fn unsupported_country_code() {
+        let result = NationalIdTransformer::try_new(
+            "public",
+            "users",
+            "national_id",
+            NationalIdTransformerOptions {
+                country_code: Some("XX".to_string()),
+            },
+        );
+
+        assert!(matches!(result, Err(ReplibyteError::Transform(_))));
+    }
+}
@@ -0,0 +1,363 @@
+use fake::faker::name::raw::{FirstName, LastName};
+use fake::locales::EN;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::keyed_hash::keyed_seed;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+const FEMALE_FIRST_NAMES: [&str; 30] = [
+    "Alice",
+    "Amelia",
+    "Anna",
+    "Ava",
+    "Charlotte",
+    "Chloe",
+    "Clara",
+    "Elena",
+    "Ella",
+    "Emily",
+    "Emma",
+    "Eva",
+    "Grace",
+    "Hannah",
+    "Isabella",
+    "Julia",
+    "Laura",
+    "Lily",
+    "Lucy",
+    "Maria",
+    "Mia",
+    "Nora",
+    "Olivia",
+    "Rose",
+    "Sarah",
+    "Sofia",
+    "Sophie",
+    "Victoria",
+    "Zoe",
+    "Louise",
+];
+
+const MALE_FIRST_NAMES: [&str; 30] = [
+    "Adam",
+    "Alexander",
+    "Arthur",
+    "Benjamin",
+    "Charles",
+    "Daniel",
+    "David",
+    "Ethan",
+    "Felix",
+    "Gabriel",
+    "George",
+    "Henry",
+    "Hugo",
+    "Jack",
+    "James",
+    "John",
+    "Leo",
+    "Liam",
+    "Louis",
+    "Lucas",
+    "Mark",
+    "Martin",
+    "Noah",
+    "Oliver",
+    "Oscar",
+    "Paul",
+    "Peter",
+    "Samuel",
+    "Thomas",
+    "William",
+];
+
+/// This struct is dedicated to replacing a first or last name by a fake one -
+/// the same original value always gives the same fake and the first names follow the gender column.
+#[derive(Default)]
+pub struct PersonNameTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    options: PersonNameTransformerOptions,
+    secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PersonNameKind {
+    #[default]
+    FirstName,
+    LastName,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct PersonNameTransformerOptions {
+    /// `first_name` (default) or `last_name`
+    pub kind: Option<PersonNameKind>,
+    /// column of the same row holding the gender - E.g. `F`, `female`, `M` or `male`
+    pub gender_column: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Gender {
+    Female,
+    Male,
+    Unknown,
+}
+
+impl Gender {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Gender::Female => "female",
+            Gender::Male => "male",
+            Gender::Unknown => "unknown",
+        }
+    }
+}
+
+impl PersonNameTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: PersonNameTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        PersonNameTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            options,
+            secret: String::new(),
+        }
+    }
+
+    fn gender(&self, row: &[Column]) -> Gender {
+        let gender_column = match &self.options.gender_column {
+            Some(gender_column) => gender_column.as_str(),
+            None => return Gender::Unknown,
+        };
+
+        let gender_value = row
+            .iter()
+            .find(|column| column.name() == gender_column)
+            .and_then(|column| match column {
+                Column::StringValue(_, value) => value.trim().chars().next(),
+                Column::CharValue(_, value) => Some(*value),
+                _ => None,
+            });
+
+        match gender_value.map(|c| c.to_ascii_lowercase()) {
+            Some('f') | Some('w') => Gender::Female,
+            Some('m') => Gender::Male,
+            _ => Gender::Unknown,
+        }
+    }
+
+    fn fake_name(&self, value: &str, gender: Gender) -> String {
+        // the fake name only depends on the secret, the original value and the gender
+        let hash = keyed_seed(
+            self.secret.as_str(),
+            &[value.as_bytes(), gender.as_str().as_bytes()],
+        );
+
+        let mut rng = StdRng::seed_from_u64(hash);
+
+        match (self.options.kind.unwrap_or_default(), gender) {
+            (PersonNameKind::FirstName, Gender::Female) => {
+                FEMALE_FIRST_NAMES[hash as usize % FEMALE_FIRST_NAMES.len()].to_string()
+            }
+            (PersonNameKind::FirstName, Gender::Male) => {
+                MALE_FIRST_NAMES[hash as usize % MALE_FIRST_NAMES.len()].to_string()
+            }
+            (PersonNameKind::FirstName, Gender::Unknown) => FirstName(EN).fake_with_rng(&mut rng),
+            (PersonNameKind::LastName, _) => LastName(EN).fake_with_rng(&mut rng),
+        }
+    }
+}
+
+impl Transformer for PersonNameTransformer {
+    fn id(&self) -> &str {
+        "person-name"
+    }
+
+    fn description(&self) -> &str {
+        "Generate the same first or last name for the same value, following the gender column (string only). [Alice]->[Emma]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "kind",
+                TransformerOptionKind::String,
+                false,
+                "`first_name` (default) or `last_name`",
+            ),
+            TransformerOption::new(
+                "gender_column",
+                TransformerOptionKind::String,
+                false,
+                "column of the same row holding the gender - E.g. `F`, `female`, `M` or `male`",
+            ),
+        ]
+    }
+
+    fn set_secret(&mut self, secret: &str) {
+        self.secret = secret.to_string();
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        self.transform_with_row(column, &[])
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                let new_value = if value.is_empty() {
+                    value
+                } else {
+                    self.fake_name(value.as_str(), self.gender(row))
+                };
+
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{
+        PersonNameKind, PersonNameTransformer, PersonNameTransformerOptions, FEMALE_FIRST_NAMES,
+        MALE_FIRST_NAMES,
+    };
+
+    fn row(first_name: &str, gender: &str) -> Vec<Column> {
+        vec![
+            Column::StringValue("first_name".to_string(), first_name.to_string()),
+            Column::StringValue("gender".to_string(), gender.to_string()),
+        ]
+    }
+
+    fn transform(transformer: &PersonNameTransformer, first_name: &str, gender: &str) -> String {
+        let row = row(first_name, gender);
+        let transformed_column = transformer.transform_with_row(row[0].clone(), &row);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn first_name_follows_gender() {
+        let transformer = get_transformer(PersonNameTransformerOptions {
+            kind: None,
+            gender_column: Some("gender".to_string()),
+        });
+
+        for first_name in ["Jane", "Joan", "Mary", "Patricia"] {
+            let fake_name = transform(&transformer, first_name, "F");
+            assert!(FEMALE_FIRST_NAMES.contains(&fake_name.as_str()));
+
+            let fake_name = transform(&transformer, first_name, "female");
+            assert!(FEMALE_FIRST_NAMES.contains(&fake_name.as_str()));
+        }
+
+        for first_name in ["John", "Robert", "Michael", "Richard"] {
+            let fake_name = transform(&transformer, first_name, "M");
+            assert!(MALE_FIRST_NAMES.contains(&fake_name.as_str()));
+
+            let fake_name = transform(&transformer, first_name, "Male");
+            assert!(MALE_FIRST_NAMES.contains(&fake_name.as_str()));
+        }
+    }
+
+    #[test]
+    fn same_value_gives_same_name() {
+        let transformer = get_transformer(PersonNameTransformerOptions {
+            kind: None,
+            gender_column: Some("gender".to_string()),
+        });
+
+        assert_eq!(
+            transform(&transformer, "Jane", "F"),
+            transform(&transformer, "Jane", "F")
+        );
+
+        let transformer = get_transformer(PersonNameTransformerOptions {
+            kind: Some(PersonNameKind::LastName),
+            gender_column: None,
+        });
+
+        let column = Column::StringValue("last_name".to_string(), "Doe".to_string());
+        let last_name = transformer.transform(column.clone());
+        assert_eq!(
+            last_name.string_value().unwrap(),
+            transformer.transform(column).string_value().unwrap()
+        );
+        assert_ne!(last_name.string_value().unwrap(), "");
+    }
+
+    #[test]
+    fn name_depends_on_the_secret() {
+        let transform = |secret: &str| {
+            let mut transformer = get_transformer(PersonNameTransformerOptions {
+                kind: Some(PersonNameKind::LastName),
+                gender_column: None,
+            });
+            transformer.set_secret(secret);
+            let column = Column::StringValue("last_name".to_string(), "Doe".to_string());
+            transformer
+                .transform(column)
+                .string_value()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(transform("secret"), transform("secret"));
+        assert_ne!(transform("secret"), transform("another secret"));
+    }
+
+    #[test]
+    fn unknown_gender_and_null_value() {
+        let transformer = get_transformer(PersonNameTransformerOptions {
+            kind: None,
+            gender_column: Some("gender".to_string()),
+        });
+
+        // no gender column in the row
+        let column = Column::StringValue("first_name".to_string(), "Jane".to_string());
+        let transformed_column = transformer.transform(column);
+        assert!(!transformed_column.string_value().unwrap().is_empty());
+
+        let column = Column::None("first_name".to_string());
+        let transformed_column = transformer.transform_with_row(column, &row("", "F"));
+        assert!(matches!(transformed_column, Column::None(_)));
+    }
+
+    fn get_transformer(options: PersonNameTransformerOptions) -> PersonNameTransformer {
+        PersonNameTransformer::new("github", "users", "first_name", options)
+    }
+}
@@ -0,0 +1,209 @@
+use crate::transformer::{ColumnType, Transformer, TransformerOption};
+use crate::types::Column;
+
+/// casing pattern of a string value
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum CasePattern {
+    /// E.g. `JOHN DOE`
+    Upper,
+    /// E.g. `john doe`
+    Lower,
+    /// E.g. `John Doe`
+    Title,
+    /// any other pattern (E.g. `McDonald`) - the transformed value is kept as is
+    Mixed,
+}
+
+impl CasePattern {
+    fn of(value: &str) -> Self {
+        let has_upper = value.chars().any(char::is_uppercase);
+        let has_lower = value.chars().any(char::is_lowercase);
+
+        match (has_upper, has_lower) {
+            (true, false) => CasePattern::Upper,
+            (false, true) => CasePattern::Lower,
+            (true, true) if is_title_case(value) => CasePattern::Title,
+            _ => CasePattern::Mixed,
+        }
+    }
+
+    fn apply(&self, value: String) -> String {
+        match self {
+            CasePattern::Upper => value.to_uppercase(),
+            CasePattern::Lower => value.to_lowercase(),
+            CasePattern::Title => to_title_case(value.as_str()),
+            CasePattern::Mixed => value,
+        }
+    }
+}
+
+/// every word starts with an uppercase letter followed by lowercase letters
+fn is_title_case(value: &str) -> bool {
+    value.split_whitespace().all(|word| {
+        let mut chars = word.chars().filter(|c| c.is_alphabetic());
+        match chars.next() {
+            Some(first_char) => first_char.is_uppercase() && chars.all(|c| !c.is_uppercase()),
+            None => true,
+        }
+    })
+}
+
+fn to_title_case(value: &str) -> String {
+    let mut title_case = String::with_capacity(value.len());
+    let mut is_word_start = true;
+
+    for c in value.chars() {
+        if c.is_whitespace() {
+            is_word_start = true;
+            title_case.push(c);
+        } else if is_word_start && c.is_alphabetic() {
+            is_word_start = false;
+            title_case.extend(c.to_uppercase());
+        } else {
+            title_case.extend(c.to_lowercase());
+        }
+    }
+
+    title_case
+}
+
+/// Transformer wrapper giving the transformed string the casing pattern of the original one -
+/// E.g. a `first-name` transformer turns `JOHN` into `PAUL` instead of `Paul`.
+pub struct PreserveCaseTransformer {
+    transformer: Box<dyn Transformer>,
+}
+
+impl PreserveCaseTransformer {
+    pub fn new(transformer: Box<dyn Transformer>) -> Self {
+        PreserveCaseTransformer { transformer }
+    }
+
+    fn preserve_case(original_column: &Column, column: Column) -> Column {
+        match (original_column, column) {
+            (Column::StringValue(_, original_value), Column::StringValue(column_name, value)) => {
+                let value = CasePattern::of(original_value.as_str()).apply(value);
+                Column::StringValue(column_name, value)
+            }
+            (_, column) => column,
+        }
+    }
+}
+
+impl Transformer for PreserveCaseTransformer {
+    fn id(&self) -> &str {
+        self.transformer.id()
+    }
+
+    fn description(&self) -> &str {
+        self.transformer.description()
+    }
+
+    fn database_name(&self) -> &str {
+        self.transformer.database_name()
+    }
+
+    fn table_name(&self) -> &str {
+        self.transformer.table_name()
+    }
+
+    fn column_name(&self) -> &str {
+        self.transformer.column_name()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        self.transformer.expected_type()
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        self.transformer.option_schema()
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        let transformed_column = self.transformer.transform(column.clone());
+        Self::preserve_case(&column, transformed_column)
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        let transformed_column = self.transformer.transform_with_row(column.clone(), row);
+        Self::preserve_case(&column, transformed_column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transformer::preserve_case::PreserveCaseTransformer;
+    use crate::transformer::transient::TransientTransformer;
+    use crate::transformer::Transformer;
+    use crate::types::Column;
+
+    /// returns the same fake value whatever the original one
+    struct ConstantTransformer(&'static str);
+
+    impl Transformer for ConstantTransformer {
+        fn id(&self) -> &str {
+            "constant"
+        }
+
+        fn description(&self) -> &str {
+            ""
+        }
+
+        fn database_name(&self) -> &str {
+            "public"
+        }
+
+        fn table_name(&self) -> &str {
+            "users"
+        }
+
+        fn column_name(&self) -> &str {
+            "name"
+        }
+
+        fn transform(&self, column: Column) -> Column {
+            Column::StringValue(column.name().to_string(), self.0.to_string())
+        }
+    }
+
+    fn transform(value: &str) -> String {
+        let transformer = PreserveCaseTransformer::new(Box::new(ConstantTransformer("paul SMITH")));
+        let column = Column::StringValue("name".to_string(), value.to_string());
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn upper_case() {
+        assert_eq!(transform("JOHN DOE"), "PAUL SMITH");
+        assert_eq!(transform("JOHN-42"), "PAUL SMITH");
+    }
+
+    #[test]
+    fn lower_case() {
+        assert_eq!(transform("john doe"), "paul smith");
+    }
+
+    #[test]
+    fn title_case() {
+        assert_eq!(transform("John Doe"), "Paul Smith");
+        assert_eq!(transform("John"), "Paul Smith");
+    }
+
+    #[test]
+    fn mixed_case_is_kept() {
+        assert_eq!(transform("McDonald"), "paul SMITH");
+        assert_eq!(transform("42"), "paul SMITH");
+    }
+
+    #[test]
+    fn other_values_are_not_changed() {
+        let transformer = PreserveCaseTransformer::new(Box::new(TransientTransformer::default()));
+
+        let column = Column::None("name".to_string());
+        assert!(transformer.transform(column).is_null());
+
+        let column = Column::NumberValue("age".to_string(), 42);
+        let transformed_column = transformer.transform(column);
+        assert_eq!(transformed_column.number_value(), Some(&42));
+    }
+}
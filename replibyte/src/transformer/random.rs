@@ -76,6 +76,9 @@ impl Transformer for RandomTransformer {
             Column::CharValue(column_name, _) => {
                 Column::CharValue(column_name, random.gen::<char>())
             }
+            Column::BooleanValue(column_name, _) => {
+                Column::BooleanValue(column_name, random.gen::<bool>())
+            }
             Column::None(column_name) => Column::None(column_name),
         }
     }
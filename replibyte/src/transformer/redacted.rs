@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::transformer::Transformer;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
 use crate::types::Column;
 
 /// This struct is dedicated to redact a string with a specific character (default to '*').
@@ -12,6 +12,7 @@ pub struct RedactedTransformer {
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(default)]
 pub struct RedactedTransformerOptions {
     pub character: char,
     pub width: u8,
@@ -77,6 +78,27 @@ impl Transformer for RedactedTransformer {
         self.column_name.as_str()
     }
 
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "character",
+                TransformerOptionKind::Char,
+                false,
+                "character replacing the redacted characters - default to `*`",
+            ),
+            TransformerOption::new(
+                "width",
+                TransformerOptionKind::Integer,
+                false,
+                "number of redacted characters - default to 10",
+            ),
+        ]
+    }
+
     fn transform(&self, column: Column) -> Column {
         match column {
             Column::StringValue(column_name, value) => {
@@ -0,0 +1,237 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReplibyteError;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+const DEFAULT_MASK: &str = "[REDACTED]";
+
+/// default patterns - the SSN one goes before the phone number one since a SSN looks like a phone number
+const DEFAULT_PATTERNS: [&str; 3] = [
+    // email
+    r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+    // SSN
+    r"\b\d{3}-\d{2}-\d{4}\b",
+    // phone number
+    r"(?:\+\d{1,3}[ .-]?)?\(?\d{3}\)?[ .-]?\d{3}[ .-]?\d{4}\b",
+];
+
+/// This struct is dedicated to redacting the PII found by regex patterns within a text.
+pub struct RegexRedactTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    regexes: Vec<Regex>,
+    mask: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct RegexRedactTransformerOptions {
+    /// patterns to redact - default to email, phone number and SSN patterns
+    pub patterns: Option<Vec<String>>,
+    /// replacement of the matches - default to `[REDACTED]`
+    pub mask: Option<String>,
+}
+
+impl RegexRedactTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: RegexRedactTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        match Self::try_new(database_name, table_name, column_name, options) {
+            Ok(transformer) => transformer,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// fail when a pattern is not a valid regex
+    pub fn try_new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: RegexRedactTransformerOptions,
+    ) -> Result<Self, ReplibyteError>
+    where
+        S: Into<String>,
+    {
+        let patterns = match options.patterns {
+            Some(patterns) => patterns,
+            None => DEFAULT_PATTERNS.iter().map(|x| x.to_string()).collect(),
+        };
+
+        let regexes = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern.as_str()).map_err(|err| {
+                    ReplibyteError::Transform(format!(
+                        "invalid regex-redact pattern '{}': {}",
+                        pattern, err
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RegexRedactTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            regexes,
+            mask: options.mask.unwrap_or_else(|| DEFAULT_MASK.to_string()),
+        })
+    }
+}
+
+impl Default for RegexRedactTransformer {
+    fn default() -> Self {
+        RegexRedactTransformer::new("", "", "", RegexRedactTransformerOptions::default())
+    }
+}
+
+impl Transformer for RegexRedactTransformer {
+    fn id(&self) -> &str {
+        "regex-redact"
+    }
+
+    fn description(&self) -> &str {
+        "Redact the emails, phone numbers and SSNs found in a text (string only). [call me at 555-123-4567]->[call me at [REDACTED]]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "patterns",
+                TransformerOptionKind::StringList,
+                false,
+                "patterns to redact - default to email, phone number and SSN patterns",
+            ),
+            TransformerOption::new(
+                "mask",
+                TransformerOptionKind::String,
+                false,
+                "replacement of the matches - default to `[REDACTED]`",
+            ),
+        ]
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                let new_value = self.regexes.iter().fold(value, |value, regex| {
+                    regex
+                        .replace_all(value.as_str(), self.mask.as_str())
+                        .into_owned()
+                });
+
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ReplibyteError;
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{RegexRedactTransformer, RegexRedactTransformerOptions};
+
+    #[test]
+    fn redact_default_patterns() {
+        let transformer = get_transformer(RegexRedactTransformerOptions::default());
+        let column = Column::StringValue(
+            "notes".to_string(),
+            "Contact john.doe@example.com or +1 555-123-4567, SSN 123-45-6789 - thanks!"
+                .to_string(),
+        );
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_eq!(
+            transformed_value,
+            "Contact [REDACTED] or [REDACTED], SSN [REDACTED] - thanks!"
+        );
+    }
+
+    #[test]
+    fn redact_custom_patterns_and_mask() {
+        let transformer = get_transformer(RegexRedactTransformerOptions {
+            patterns: Some(vec![r"ORDER-\d+".to_string()]),
+            mask: Some("***".to_string()),
+        });
+        let column = Column::StringValue(
+            "notes".to_string(),
+            "ORDER-42 and ORDER-43 shipped to john.doe@example.com".to_string(),
+        );
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+
+        assert_eq!(
+            transformed_value,
+            "*** and *** shipped to john.doe@example.com"
+        );
+    }
+
+    #[test]
+    fn keep_text_without_pii() {
+        let transformer = get_transformer(RegexRedactTransformerOptions::default());
+        let column = Column::StringValue("notes".to_string(), "nothing to hide".to_string());
+        let transformed_column = transformer.transform(column);
+        let transformed_value = transformed_column.string_value().unwrap();
+        assert_eq!(transformed_value, "nothing to hide");
+
+        let column = Column::None("notes".to_string());
+        let transformed_column = transformer.transform(column);
+        assert!(matches!(transformed_column, Column::None(_)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_pattern() {
+        let _ = get_transformer(RegexRedactTransformerOptions {
+            patterns: Some(vec!["(".to_string()]),
+            mask: None,
+        });
+    }
+
+    #[test]
+    fn invalid_pattern_error() {
+        let result = RegexRedactTransformer::try_new(
+            "github",
+            "users",
+            "notes",
+            RegexRedactTransformerOptions {
+                patterns: Some(vec!["(".to_string()]),
+                mask: None,
+            },
+        );
+
+        assert!(matches!(result, Err(ReplibyteError::Transform(_))));
+    }
+
+    fn get_transformer(options: RegexRedactTransformerOptions) -> RegexRedactTransformer {
+        RegexRedactTransformer::new("github", "users", "notes", options)
+    }
+}
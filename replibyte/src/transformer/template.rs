@@ -0,0 +1,360 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReplibyteError;
+use crate::transformer::keyed_hash::hmac_sha256;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+/// hexadecimal digits of `{hash}` without length - 64 bits
+const DEFAULT_HASH_LENGTH: usize = 16;
+/// hexadecimal digits of a HMAC-SHA256
+const MAX_HASH_LENGTH: usize = 64;
+
+/// This struct is dedicated to replacing a string by a value built from a template -
+/// E.g. `user_{seq}@test.com` or `ACME-{hash:4}`.
+pub struct TemplateTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    parts: Vec<TemplatePart>,
+    seq: AtomicU64,
+    secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct TemplateTransformerOptions {
+    /// text with placeholders - `{seq}`, `{seq:<digits>}`, `{hash}`, `{hash:<length>}` and `{orig_len}`.
+    /// `{{` and `}}` are the braces
+    pub template: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum TemplatePart {
+    Text(String),
+    /// 1, 2, 3... in the order of the values - zero padded to `digits` digits
+    Seq(usize),
+    /// first hexadecimal digits of the HMAC-SHA256 of the original value keyed by the secret -
+    /// the same value always gives the same hash
+    Hash(usize),
+    /// number of chars of the original value
+    OrigLen,
+}
+
+impl TemplateTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: TemplateTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        match Self::try_new(database_name, table_name, column_name, options) {
+            Ok(transformer) => transformer,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// fail when the template has an unknown placeholder or an unmatched brace
+    pub fn try_new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: TemplateTransformerOptions,
+    ) -> Result<Self, ReplibyteError>
+    where
+        S: Into<String>,
+    {
+        let parts = parse_template(options.template.as_str()).map_err(|err| {
+            ReplibyteError::Transform(format!("invalid template '{}': {}", options.template, err))
+        })?;
+
+        Ok(TemplateTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            parts,
+            seq: AtomicU64::new(0),
+            secret: String::new(),
+        })
+    }
+
+    fn render(&self, value: &str) -> String {
+        let mut rendered = String::new();
+
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => rendered.push_str(text),
+                TemplatePart::Seq(digits) => {
+                    let seq = self.seq.fetch_add(1, Ordering::Relaxed) + 1;
+                    rendered.push_str(format!("{:0digits$}", seq, digits = digits).as_str());
+                }
+                TemplatePart::Hash(length) => {
+                    let hash = hmac_sha256(self.secret.as_bytes(), value.as_bytes())
+                        .iter()
+                        .map(|byte| format!("{:02x}", byte))
+                        .collect::<String>();
+                    rendered.push_str(&hash[..*length]);
+                }
+                TemplatePart::OrigLen => {
+                    rendered.push_str(value.chars().count().to_string().as_str())
+                }
+            }
+        }
+
+        rendered
+    }
+}
+
+impl Default for TemplateTransformer {
+    fn default() -> Self {
+        TemplateTransformer::new("", "", "", TemplateTransformerOptions::default())
+    }
+}
+
+fn parse_template(template: &str) -> Result<Vec<TemplatePart>, String> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                let _ = chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                let _ = chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(format!("unclosed placeholder '{{{}'", placeholder)),
+                    }
+                }
+
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+
+                parts.push(parse_placeholder(placeholder.as_str())?);
+            }
+            '}' => return Err("unmatched '}' - use '}}' for a brace".to_string()),
+            c => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+
+    Ok(parts)
+}
+
+/// `name` or `name:<number>`
+fn parse_placeholder(placeholder: &str) -> Result<TemplatePart, String> {
+    let (name, number) = match placeholder.split_once(':') {
+        Some((name, number)) => match number.parse::<usize>() {
+            Ok(number) => (name, Some(number)),
+            Err(_) => return Err(format!("invalid number in '{{{}}}'", placeholder)),
+        },
+        None => (placeholder, None),
+    };
+
+    match (name, number) {
+        ("seq", digits) => Ok(TemplatePart::Seq(digits.unwrap_or(0))),
+        ("hash", length) => match length.unwrap_or(DEFAULT_HASH_LENGTH) {
+            length if length == 0 || length > MAX_HASH_LENGTH => Err(format!(
+                "the length of '{{{}}}' must be between 1 and {}",
+                placeholder, MAX_HASH_LENGTH
+            )),
+            length => Ok(TemplatePart::Hash(length)),
+        },
+        ("orig_len", None) => Ok(TemplatePart::OrigLen),
+        _ => Err(format!(
+            "unknown placeholder '{{{}}}' - use {{seq}}, {{hash}} or {{orig_len}}",
+            placeholder
+        )),
+    }
+}
+
+impl Transformer for TemplateTransformer {
+    fn id(&self) -> &str {
+        "template"
+    }
+
+    fn description(&self) -> &str {
+        "Replace the value by the `template` with a counter ({seq}), a hash of the value ({hash}) or its length ({orig_len}) (string only). [john@acme.com]->[user_42@test.com]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![TransformerOption::new(
+            "template",
+            TransformerOptionKind::String,
+            true,
+            "text with placeholders - `{seq}`, `{seq:<digits>}`, `{hash}`, `{hash:<length>}` and `{orig_len}`",
+        )]
+    }
+
+    fn set_secret(&mut self, secret: &str) {
+        self.secret = secret.to_string();
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                Column::StringValue(column_name, self.render(value.as_str()))
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ReplibyteError;
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{TemplateTransformer, TemplateTransformerOptions};
+
+    fn template_transformer(template: &str) -> TemplateTransformer {
+        TemplateTransformer::new(
+            "public",
+            "users",
+            "email",
+            TemplateTransformerOptions {
+                template: template.to_string(),
+            },
+        )
+    }
+
+    fn transform(transformer: &TemplateTransformer, value: &str) -> String {
+        let column = Column::StringValue("email".to_string(), value.to_string());
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn seq_placeholder() {
+        let transformer = template_transformer("user_{seq}@test.com");
+        assert_eq!(transform(&transformer, "john@acme.com"), "user_1@test.com");
+        assert_eq!(transform(&transformer, "jane@acme.com"), "user_2@test.com");
+        assert_eq!(transform(&transformer, "john@acme.com"), "user_3@test.com");
+
+        let transformer = template_transformer("ACME-{seq:4}");
+        assert_eq!(transform(&transformer, "1234"), "ACME-0001");
+        assert_eq!(transform(&transformer, "5678"), "ACME-0002");
+    }
+
+    #[test]
+    fn hash_placeholder() {
+        let transformer = template_transformer("user_{hash}");
+        let john = transform(&transformer, "john@acme.com");
+        assert_eq!(john.len(), "user_".len() + 16);
+        assert!(john[5..].chars().all(|c| c.is_ascii_hexdigit()));
+        // the same value gives the same hash
+        assert_eq!(transform(&transformer, "john@acme.com"), john);
+        assert_ne!(transform(&transformer, "jane@acme.com"), john);
+
+        // HMAC-SHA256 of "abc" without secret
+        let transformer = template_transformer("{hash:8}-{hash:64}");
+        assert_eq!(
+            transform(&transformer, "abc"),
+            "fd7adb15-fd7adb152c05ef80dccf50a1fa4c05d5a3ec6da95575fc312ae7c5d091836351"
+        );
+    }
+
+    #[test]
+    fn hash_depends_on_the_secret() {
+        let mut transformer = template_transformer("{hash:64}");
+        transformer.set_secret("secret");
+        assert_eq!(
+            transform(&transformer, "abc"),
+            "9946dad4e00e913fc8be8e5d3f7e110a4a9e832f83fb09c345285d78638d8a0e"
+        );
+    }
+
+    #[test]
+    fn orig_len_placeholder() {
+        let transformer = template_transformer("{orig_len} chars");
+        assert_eq!(transform(&transformer, "john"), "4 chars");
+        assert_eq!(transform(&transformer, "zoë"), "3 chars");
+        assert_eq!(transform(&transformer, ""), "0 chars");
+    }
+
+    #[test]
+    fn escaped_braces() {
+        let transformer = template_transformer("{{seq}} {{{seq}}}");
+        assert_eq!(transform(&transformer, "john"), "{seq} {1}");
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for template in [
+            "{seq",
+            "seq}",
+            "{id}",
+            "{seq:four}",
+            "{hash:0}",
+            "{hash:65}",
+            "{orig_len:2}",
+        ] {
+            let result = TemplateTransformer::try_new(
+                "public",
+                "users",
+                "email",
+                TemplateTransformerOptions {
+                    template: template.to_string(),
+                },
+            );
+
+            assert!(
+                matches!(result, Err(ReplibyteError::Transform(_))),
+                "{}",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn null_value() {
+        let transformer = template_transformer("user_{seq}");
+        let column = Column::None("email".to_string());
+        let transformed_column = transformer.transform(column);
+        assert!(transformed_column.is_null());
+
+        // NULL does not take a number
+        assert_eq!(transform(&transformer, "john"), "user_1");
+    }
+
+    #[test]
+    fn number_value() {
+        let transformer = template_transformer("user_{seq}");
+        let column = Column::NumberValue("email".to_string(), 42);
+        let transformed_column = transformer.transform(column);
+        assert_eq!(transformed_column.number_value(), Some(&42));
+    }
+}
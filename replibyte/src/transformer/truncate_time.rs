@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+/// This struct is dedicated to reducing the precision of dates and timestamps - E.g. truncated to the day.
+/// The format of the value is kept: the truncated components are set to their lowest value.
+pub struct TruncateTimeTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    options: TruncateTimeTransformerOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(default)]
+pub struct TruncateTimeTransformerOptions {
+    pub granularity: TimeGranularity,
+}
+
+/// the most precise component kept
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeGranularity {
+    Year,
+    Month,
+    #[default]
+    Day,
+    Hour,
+    Minute,
+}
+
+impl TruncateTimeTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: TruncateTimeTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        TruncateTimeTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            options,
+        }
+    }
+}
+
+impl Default for TruncateTimeTransformer {
+    fn default() -> Self {
+        TruncateTimeTransformer::new("", "", "", TruncateTimeTransformerOptions::default())
+    }
+}
+
+impl Transformer for TruncateTimeTransformer {
+    fn id(&self) -> &str {
+        "truncate-time"
+    }
+
+    fn description(&self) -> &str {
+        "Truncate dates and timestamps to a coarse granularity, keeping their format (string only). [2022-03-04 10:11:12.345+01]->[2022-03-04 00:00:00.000+01]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Date
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![TransformerOption::new(
+            "granularity",
+            TransformerOptionKind::String,
+            false,
+            "most precise component kept: `year`, `month`, `day`, `hour` or `minute` - default to `day`",
+        )]
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                let new_value =
+                    truncate_time(value.as_str(), self.options.granularity).unwrap_or(value);
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+/// truncate a date (`2022-03-04`), a time (`10:11:12`) or a timestamp (`2022-03-04 10:11:12.345+01`, `2022-03-04T10:11Z`...) -
+/// None when the value is in none of these formats
+fn truncate_time(value: &str, granularity: TimeGranularity) -> Option<String> {
+    let mut chars = value.chars().collect::<Vec<_>>();
+
+    let has_date = matches_pattern(&chars, "dddd-dd-dd");
+    let time_start = match has_date {
+        true if chars.len() == 10 => None,
+        true if matches!(chars.get(10), Some(' ') | Some('T')) => Some(11),
+        true => return None,
+        false => Some(0),
+    };
+
+    // the components to reset - from the most precise one kept
+    let (reset_month_and_day, reset_day, reset_hour, reset_minute) = match granularity {
+        TimeGranularity::Year => (true, true, true, true),
+        TimeGranularity::Month => (false, true, true, true),
+        TimeGranularity::Day => (false, false, true, true),
+        TimeGranularity::Hour => (false, false, false, true),
+        TimeGranularity::Minute => (false, false, false, false),
+    };
+
+    if has_date {
+        if reset_month_and_day {
+            replace(&mut chars, 5, "01");
+        }
+        if reset_day {
+            replace(&mut chars, 8, "01");
+        }
+    }
+
+    if let Some(time_start) = time_start {
+        if !matches_pattern(&chars[time_start..], "dd:dd") {
+            return None;
+        }
+
+        if reset_hour {
+            replace(&mut chars, time_start, "00");
+        }
+        if reset_minute {
+            replace(&mut chars, time_start + 3, "00");
+        }
+
+        // seconds and fractional seconds - the timezone is kept
+        let mut idx = time_start + 5;
+        if chars.get(idx) == Some(&':') && matches_pattern(&chars[idx + 1..], "dd") {
+            replace(&mut chars, idx + 1, "00");
+            idx += 3;
+
+            if chars.get(idx) == Some(&'.') {
+                idx += 1;
+                while let Some(c) = chars.get_mut(idx).filter(|c| c.is_ascii_digit()) {
+                    *c = '0';
+                    idx += 1;
+                }
+            }
+        }
+    }
+
+    Some(chars.into_iter().collect())
+}
+
+/// `d` matches any digit
+fn matches_pattern(chars: &[char], pattern: &str) -> bool {
+    chars.len() >= pattern.len()
+        && pattern.chars().zip(chars).all(|(p, c)| match p {
+            'd' => c.is_ascii_digit(),
+            p => p == *c,
+        })
+}
+
+fn replace(chars: &mut [char], start: usize, value: &str) {
+    for (i, c) in value.chars().enumerate() {
+        chars[start + i] = c;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{TimeGranularity, TruncateTimeTransformer, TruncateTimeTransformerOptions};
+
+    fn truncate(value: &str, granularity: TimeGranularity) -> String {
+        let transformer = TruncateTimeTransformer::new(
+            "public",
+            "users",
+            "created_at",
+            TruncateTimeTransformerOptions { granularity },
+        );
+
+        transformer
+            .transform(Column::StringValue(
+                "created_at".to_string(),
+                value.to_string(),
+            ))
+            .string_value()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn truncate_to_the_day() {
+        for (value, expected_value) in [
+            ("2022-03-04 10:11:12", "2022-03-04 00:00:00"),
+            ("2022-03-04 10:11:12.123456", "2022-03-04 00:00:00.000000"),
+            ("2022-03-04 10:11:12.123+01", "2022-03-04 00:00:00.000+01"),
+            ("2022-03-04T10:11:12Z", "2022-03-04T00:00:00Z"),
+            ("2022-03-04T10:11+02:00", "2022-03-04T00:00+02:00"),
+            ("2022-03-04", "2022-03-04"),
+            ("10:11:12", "00:00:00"),
+        ] {
+            assert_eq!(truncate(value, TimeGranularity::Day), expected_value);
+        }
+    }
+
+    #[test]
+    fn truncate_to_the_hour() {
+        for (value, expected_value) in [
+            ("2022-03-04 10:11:12", "2022-03-04 10:00:00"),
+            ("2022-03-04 10:11:12.123+01", "2022-03-04 10:00:00.000+01"),
+            ("2022-03-04T10:11:12Z", "2022-03-04T10:00:00Z"),
+            ("2022-03-04", "2022-03-04"),
+            ("10:11:12", "10:00:00"),
+        ] {
+            assert_eq!(truncate(value, TimeGranularity::Hour), expected_value);
+        }
+    }
+
+    #[test]
+    fn truncate_to_other_granularities() {
+        let value = "2022-03-04 10:11:12.5";
+
+        assert_eq!(
+            truncate(value, TimeGranularity::Year),
+            "2022-01-01 00:00:00.0"
+        );
+        assert_eq!(
+            truncate(value, TimeGranularity::Month),
+            "2022-03-01 00:00:00.0"
+        );
+        assert_eq!(
+            truncate(value, TimeGranularity::Minute),
+            "2022-03-04 10:11:00.0"
+        );
+    }
+
+    #[test]
+    fn other_values_are_kept() {
+        let transformer = TruncateTimeTransformer::default();
+
+        let column = Column::None("created_at".to_string());
+        assert!(transformer.transform(column).is_null());
+
+        for value in ["", "yesterday", "2022/03/04", "2022-03-04 noon"] {
+            assert_eq!(truncate(value, TimeGranularity::Day), value);
+        }
+    }
+}
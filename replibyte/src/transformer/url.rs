@@ -0,0 +1,218 @@
+use ::url::Url;
+use fake::faker::internet::raw::DomainSuffix;
+use fake::faker::lorem::raw::Word;
+use fake::locales::EN;
+use fake::Fake;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+/// max number of path segments of a fully random URL
+const MAX_RANDOM_PATH_DEPTH: usize = 3;
+
+/// This struct is dedicated to replacing a URL by a fake one.
+pub struct UrlTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    preserve_path: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct UrlTransformerOptions {
+    /// keep the scheme, the path depth and the file extension - default to true
+    pub preserve_path: Option<bool>,
+}
+
+impl UrlTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: UrlTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        UrlTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            preserve_path: options.preserve_path.unwrap_or(true),
+        }
+    }
+
+    /// the query and the fragment are dropped - they often hold identifiers
+    fn fake_url(&self, url: &Url) -> String {
+        let domain = format!(
+            "{}.{}",
+            Word(EN).fake::<String>(),
+            DomainSuffix(EN).fake::<String>()
+        );
+
+        let path = if self.preserve_path {
+            url.path_segments()
+                .map(|segments| segments.map(fake_path_segment).collect::<Vec<_>>())
+                .unwrap_or_default()
+        } else {
+            let depth = rand::thread_rng().gen_range(0..=MAX_RANDOM_PATH_DEPTH);
+            (0..depth)
+                .map(|_| Word(EN).fake::<String>())
+                .collect::<Vec<_>>()
+        };
+
+        let scheme = match self.preserve_path {
+            true => url.scheme(),
+            false => "https",
+        };
+
+        format!("{}://{}/{}", scheme, domain, path.join("/"))
+    }
+}
+
+/// a fake word keeping the extension of the segment - E.g. `avatar.png` -> `dolor.png`
+fn fake_path_segment(segment: &str) -> String {
+    if segment.is_empty() {
+        // trailing slash
+        return String::new();
+    }
+
+    match segment.rsplit_once('.') {
+        Some((name, extension)) if !name.is_empty() && !extension.is_empty() => {
+            format!("{}.{}", Word(EN).fake::<String>(), extension)
+        }
+        _ => Word(EN).fake::<String>(),
+    }
+}
+
+impl Default for UrlTransformer {
+    fn default() -> Self {
+        UrlTransformer::new("", "", "", UrlTransformerOptions::default())
+    }
+}
+
+impl Transformer for UrlTransformer {
+    fn id(&self) -> &str {
+        "url"
+    }
+
+    fn description(&self) -> &str {
+        "Generate a URL keeping the scheme and the path depth (string only). [https://acme.com/users/42/avatar.png]->[https://dolor.net/sit/amet/ipsum.png]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![TransformerOption::new(
+            "preserve_path",
+            TransformerOptionKind::Boolean,
+            false,
+            "keep the scheme, the path depth and the file extension (default) - a random https URL otherwise",
+        )]
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => match Url::parse(value.as_str()) {
+                Ok(url) if url.has_host() => Column::StringValue(column_name, self.fake_url(&url)),
+                // not a URL (or without host, E.g. `mailto:`) - kept as is
+                _ => Column::StringValue(column_name, value),
+            },
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::url::Url;
+
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{UrlTransformer, UrlTransformerOptions};
+
+    fn transform(transformer: &UrlTransformer, url: &str) -> String {
+        let column = Column::StringValue("url".to_string(), url.to_string());
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn preserve_path() {
+        let transformer = UrlTransformer::default();
+        let original_url = "http://acme.com/users/42/avatar.png?token=secret#top";
+
+        for _ in 0..100 {
+            let url = Url::parse(transform(&transformer, original_url).as_str()).unwrap();
+
+            assert_eq!(url.scheme(), "http");
+            assert_ne!(url.host_str(), Some("acme.com"));
+            assert!(url.query().is_none());
+            assert!(url.fragment().is_none());
+
+            let segments = url.path_segments().unwrap().collect::<Vec<_>>();
+            assert_eq!(segments.len(), 3);
+            assert!(segments[2].ends_with(".png"));
+        }
+
+        let url = Url::parse(transform(&transformer, "https://acme.com/").as_str()).unwrap();
+        assert_eq!(url.path(), "/");
+    }
+
+    #[test]
+    fn random_url() {
+        let transformer = UrlTransformer::new(
+            "public",
+            "users",
+            "website",
+            UrlTransformerOptions {
+                preserve_path: Some(false),
+            },
+        );
+
+        for _ in 0..100 {
+            let url =
+                Url::parse(transform(&transformer, "ftp://acme.com/users/42/avatar.png").as_str())
+                    .unwrap();
+
+            assert_eq!(url.scheme(), "https");
+            assert_ne!(url.host_str(), Some("acme.com"));
+            assert!(url.path_segments().unwrap().count() <= 3);
+            assert!(!url.path().ends_with(".png"));
+        }
+    }
+
+    #[test]
+    fn invalid_url_is_kept() {
+        let transformer = UrlTransformer::default();
+        assert_eq!(transform(&transformer, "not a url"), "not a url");
+        assert_eq!(
+            transform(&transformer, "mailto:john@acme.com"),
+            "mailto:john@acme.com"
+        );
+    }
+
+    #[test]
+    fn null_value() {
+        let transformer = UrlTransformer::default();
+        let column = Column::None("url".to_string());
+        let transformed_column = transformer.transform(column);
+        assert!(transformed_column.is_null());
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use fake::faker::name::raw::{FirstName, LastName};
+use fake::locales::EN;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::transformer::keyed_hash::keyed_seed;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+const MIN_USERNAME_LENGTH: usize = 3;
+const MAX_USERNAME_LENGTH: usize = 30;
+
+/// This struct is dedicated to replacing a username or a `@handle` by a fake one of a similar length -
+/// the same key (the original value or the `key_column` value, E.g. the user id) always gives the same username
+/// and two keys never get the same username.
+#[derive(Default)]
+pub struct UsernameTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    options: UsernameTransformerOptions,
+    usernames: Mutex<Usernames>,
+    secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct UsernameTransformerOptions {
+    /// column of the same row identifying the user - E.g. `user_id`
+    pub key_column: Option<String>,
+}
+
+/// the usernames given to the keys - to resolve the collisions
+#[derive(Default)]
+struct Usernames {
+    by_key: HashMap<String, String>,
+    taken: HashSet<String>,
+}
+
+impl UsernameTransformer {
+    pub fn new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: UsernameTransformerOptions,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        UsernameTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            options,
+            usernames: Mutex::new(Usernames::default()),
+            secret: String::new(),
+        }
+    }
+
+    /// the value of the key column - None when the row has no key column or the key is NULL
+    fn key(&self, row: &[Column]) -> Option<String> {
+        let key_column = self.options.key_column.as_ref()?;
+
+        row.iter()
+            .find(|column| column.name() == key_column.as_str())
+            .and_then(|column| match column {
+                Column::NumberValue(_, value) => Some(value.to_string()),
+                Column::FloatNumberValue(_, value) => Some(value.to_string()),
+                Column::StringValue(_, value) => Some(value.clone()),
+                Column::CharValue(_, value) => Some(value.to_string()),
+                Column::BooleanValue(_, value) => Some(value.to_string()),
+                Column::None(_) => None,
+            })
+    }
+
+    fn username(&self, key: &str, length: usize) -> String {
+        let mut usernames = self.usernames.lock().unwrap();
+
+        if let Some(username) = usernames.by_key.get(key) {
+            return username.clone();
+        }
+
+        // another key got the same username - add a number until it is unique
+        let fake_username = fake_username(self.secret.as_str(), key, length);
+        let mut username = fake_username.clone();
+        let mut suffix = 2;
+
+        while usernames.taken.contains(&username) {
+            username = format!("{}{}", fake_username, suffix);
+            suffix += 1;
+        }
+
+        let _ = usernames.taken.insert(username.clone());
+        let _ = usernames.by_key.insert(key.to_string(), username.clone());
+
+        username
+    }
+}
+
+/// `<first name>_<last name>` cut or completed with digits to `length` chars - only made of `[a-z0-9_]`
+fn fake_username(secret: &str, key: &str, length: usize) -> String {
+    // the username only depends on the secret and the key - it is the same in every table
+    let mut rng = StdRng::seed_from_u64(keyed_seed(secret, &[key.as_bytes()]));
+    let first_name: String = FirstName(EN).fake_with_rng(&mut rng);
+    let last_name: String = LastName(EN).fake_with_rng(&mut rng);
+
+    let length = length.clamp(MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH);
+    let mut username = format!("{}_{}", first_name, last_name)
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .map(|c| c.to_ascii_lowercase())
+        .take(length)
+        .collect::<String>()
+        .trim_end_matches('_')
+        .to_string();
+
+    while username.len() < length {
+        username.push(char::from(b'0' + rng.gen_range(0..10)));
+    }
+
+    username
+}
+
+impl Transformer for UsernameTransformer {
+    fn id(&self) -> &str {
+        "username"
+    }
+
+    fn description(&self) -> &str {
+        "Generate a unique username of a similar length for the same value or key column (string only). [@jdoe42]->[@paul_sm]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![TransformerOption::new(
+            "key_column",
+            TransformerOptionKind::String,
+            false,
+            "column of the same row identifying the user - E.g. `user_id`",
+        )]
+    }
+
+    fn set_secret(&mut self, secret: &str) {
+        self.secret = secret.to_string();
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        self.transform_with_row(column, &[])
+    }
+
+    fn transform_with_row(&self, column: Column, row: &[Column]) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => {
+                let new_value = if value.is_empty() {
+                    value
+                } else {
+                    // keep the `@` of the handles
+                    let (prefix, name) = match value.strip_prefix('@') {
+                        Some(name) => ("@", name),
+                        None => ("", value.as_str()),
+                    };
+
+                    let length = name.chars().count();
+                    let key = self.key(row).unwrap_or_else(|| name.to_string());
+                    format!("{}{}", prefix, self.username(key.as_str(), length))
+                };
+
+                Column::StringValue(column_name, new_value)
+            }
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{UsernameTransformer, UsernameTransformerOptions};
+
+    fn transform(transformer: &UsernameTransformer, value: &str) -> String {
+        let column = Column::StringValue("username".to_string(), value.to_string());
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn usernames_are_unique_and_stable() {
+        let transformer = UsernameTransformer::default();
+
+        // short usernames collide often
+        let originals = (0..2_000).map(|i| format!("u{}", i)).collect::<Vec<_>>();
+        let usernames = originals
+            .iter()
+            .map(|original| transform(&transformer, original))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            usernames.iter().collect::<HashSet<_>>().len(),
+            originals.len()
+        );
+
+        for (original, username) in originals.iter().zip(&usernames) {
+            assert_eq!(&transform(&transformer, original), username);
+            assert!(username
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'));
+        }
+    }
+
+    #[test]
+    fn handle_of_similar_length() {
+        let transformer = UsernameTransformer::default();
+
+        let handle = transform(&transformer, "@john_doe_1984");
+        assert!(handle.starts_with('@'));
+        assert_ne!(handle, "@john_doe_1984");
+        assert_eq!(handle.len(), "@john_doe_1984".len());
+
+        assert_eq!(transform(&transformer, "jd").len(), 3);
+        assert_eq!(transform(&transformer, "").len(), 0);
+    }
+
+    #[test]
+    fn username_depends_on_the_secret() {
+        let mut transformer = UsernameTransformer::default();
+        transformer.set_secret("secret");
+        let username = transform(&transformer, "john_doe_1984");
+
+        let mut same_transformer = UsernameTransformer::default();
+        same_transformer.set_secret("secret");
+        assert_eq!(transform(&same_transformer, "john_doe_1984"), username);
+
+        let mut another_transformer = UsernameTransformer::default();
+        another_transformer.set_secret("another secret");
+        assert_ne!(transform(&another_transformer, "john_doe_1984"), username);
+    }
+
+    #[test]
+    fn same_key_gives_same_username() {
+        let transformer = UsernameTransformer::new(
+            "public",
+            "users",
+            "username",
+            UsernameTransformerOptions {
+                key_column: Some("id".to_string()),
+            },
+        );
+
+        let row = |id: i128, username: &str| {
+            vec![
+                Column::NumberValue("id".to_string(), id),
+                Column::StringValue("username".to_string(), username.to_string()),
+            ]
+        };
+
+        let transform_row = |row: Vec<Column>| {
+            let transformed_column = transformer.transform_with_row(row[1].clone(), &row);
+            transformed_column.string_value().unwrap().to_string()
+        };
+
+        let username = transform_row(row(42, "jdoe"));
+        // the user renamed
+        assert_eq!(transform_row(row(42, "john.doe")), username);
+        // another user with the same username
+        assert_ne!(transform_row(row(43, "jdoe")), username);
+    }
+
+    #[test]
+    fn null_value() {
+        let transformer = UsernameTransformer::default();
+        let column = Column::None("username".to_string());
+        assert!(matches!(transformer.transform(column), Column::None(_)));
+    }
+}
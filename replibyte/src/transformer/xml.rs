@@ -0,0 +1,707 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReplibyteError;
+use crate::transformer::transient::TransientTransformer;
+use crate::transformer::{ColumnType, Transformer, TransformerOption, TransformerOptionKind};
+use crate::types::Column;
+
+/// This struct is dedicated to replacing the texts and the attributes selected in an XML document by the ones
+/// of another transformer - E.g. the `<name>` of a customer profile. The rest of the document is kept as is.
+pub struct XmlTransformer {
+    database_name: String,
+    table_name: String,
+    column_name: String,
+    selectors: Vec<Selector>,
+    transformer: Box<dyn Transformer>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct XmlTransformerOptions {
+    /// paths of the elements and attributes to transform - E.g. `/customer/name`, `//email` or `/customer/@ssn`
+    pub selectors: Vec<String>,
+    /// name of the transformer applied to the selected values - a transformer without options, E.g. `first-name`
+    pub transformer: String,
+}
+
+/// an element of a selector path - E.g. `//email` is a descendant step named `email`
+#[derive(Debug, PartialEq)]
+struct Step {
+    /// element name or `*` for any element
+    name: String,
+    /// the element is at any depth below the previous step (`//`) instead of being its child (`/`)
+    descendant: bool,
+}
+
+#[derive(Debug, PartialEq)]
+struct Selector {
+    steps: Vec<Step>,
+    /// the attribute of the last element (`@name`) - its text otherwise
+    attribute: Option<String>,
+}
+
+impl Selector {
+    fn matches(&self, path: &[&str], attribute: Option<&str>) -> bool {
+        self.attribute.as_deref() == attribute && steps_match(self.steps.as_slice(), path)
+    }
+}
+
+fn steps_match(steps: &[Step], path: &[&str]) -> bool {
+    let (step, steps) = match steps.split_first() {
+        Some(step) => step,
+        None => return path.is_empty(),
+    };
+
+    let step_matches = |name: &str| step.name == "*" || step.name == name;
+
+    match step.descendant {
+        true => (0..path.len())
+            .any(|idx| step_matches(path[idx]) && steps_match(steps, &path[idx + 1..])),
+        false => match path.split_first() {
+            Some((name, path)) => step_matches(name) && steps_match(steps, path),
+            None => false,
+        },
+    }
+}
+
+/// `/a/b`, `//b`, `/a//b`, `/a/*/c` and `/a/@attr` or `//@attr` - the other XPath expressions are not supported
+fn parse_selector(selector: &str) -> Result<Selector, String> {
+    let mut rest = selector
+        .strip_prefix('/')
+        .ok_or_else(|| "a selector starts with '/'".to_string())?;
+    let mut steps = vec![];
+    let mut attribute = None;
+
+    loop {
+        let descendant = match rest.strip_prefix('/') {
+            Some(r) => {
+                rest = r;
+                true
+            }
+            None => false,
+        };
+
+        let (step, next) = match rest.split_once('/') {
+            Some((step, next)) => (step, Some(next)),
+            None => (rest, None),
+        };
+
+        if let Some(attribute_name) = step.strip_prefix('@') {
+            if next.is_some() || !is_name(attribute_name) {
+                return Err("the attribute must be the last step of the selector".to_string());
+            }
+
+            if descendant {
+                steps.push(Step {
+                    name: "*".to_string(),
+                    descendant,
+                });
+            }
+
+            attribute = Some(attribute_name.to_string());
+            break;
+        }
+
+        if step != "*" && !is_name(step) {
+            return Err(format!("invalid element name '{}'", step));
+        }
+
+        steps.push(Step {
+            name: step.to_string(),
+            descendant,
+        });
+
+        match next {
+            Some(next) => rest = next,
+            None => break,
+        }
+    }
+
+    if steps.is_empty() {
+        return Err("the selector has no element".to_string());
+    }
+
+    Ok(Selector { steps, attribute })
+}
+
+impl XmlTransformer {
+    /// fail when a selector is not supported
+    pub fn try_new<S>(
+        database_name: S,
+        table_name: S,
+        column_name: S,
+        options: XmlTransformerOptions,
+        transformer: Box<dyn Transformer>,
+    ) -> Result<Self, ReplibyteError>
+    where
+        S: Into<String>,
+    {
+        let selectors = options
+            .selectors
+            .iter()
+            .map(|selector| {
+                parse_selector(selector.as_str()).map_err(|err| {
+                    ReplibyteError::Transform(format!("invalid selector '{}': {}", selector, err))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(XmlTransformer {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+            column_name: column_name.into(),
+            selectors,
+            transformer,
+        })
+    }
+
+    fn is_selected(&self, path: &[&str], attribute: Option<&str>) -> bool {
+        self.selectors
+            .iter()
+            .any(|selector| selector.matches(path, attribute))
+    }
+
+    fn transform_value(&self, value: &str) -> String {
+        let column = Column::StringValue(self.column_name.clone(), value.to_string());
+
+        match self.transformer.transform(column) {
+            Column::StringValue(_, value) => value,
+            Column::CharValue(_, value) => value.to_string(),
+            _ => value.to_string(),
+        }
+    }
+
+    /// the leading and trailing whitespaces of the text are kept - E.g. an indented document
+    fn transform_text(&self, text: &str) -> Option<String> {
+        let value = text.trim();
+        if value.is_empty() {
+            return Some(text.to_string());
+        }
+
+        let start_idx = text.len() - text.trim_start().len();
+        let end_idx = start_idx + value.len();
+
+        Some(format!(
+            "{}{}{}",
+            &text[..start_idx],
+            escape(self.transform_value(unescape(value)?.as_str()).as_str()),
+            &text[end_idx..]
+        ))
+    }
+
+    /// rewrite the attributes of a start tag - E.g. ` id="42" ssn='123'`
+    fn transform_attributes(&self, path: &[&str], attributes: &str) -> Option<String> {
+        let mut transformed_attributes = String::with_capacity(attributes.len());
+        let mut rest = attributes;
+
+        loop {
+            let trimmed_rest = rest.trim_start();
+            let whitespaces = &rest[..rest.len() - trimmed_rest.len()];
+            transformed_attributes.push_str(whitespaces);
+            rest = trimmed_rest;
+
+            if rest.is_empty() {
+                return Some(transformed_attributes);
+            }
+
+            // the attributes are separated by whitespaces
+            if whitespaces.is_empty() {
+                return None;
+            }
+
+            let (name, value_rest) = rest.split_once('=')?;
+            let name = name.trim_end();
+            if !is_name(name) {
+                return None;
+            }
+
+            let value_rest = value_rest.trim_start();
+            let quote = value_rest.chars().next()?;
+            if quote != '"' && quote != '\'' {
+                return None;
+            }
+
+            let value_end_idx = value_rest[1..].find(quote)? + 1;
+            let value = &value_rest[1..value_end_idx];
+            let attribute_len = rest.len() - value_rest.len() + value_end_idx + 1;
+
+            if self.is_selected(path, Some(name)) {
+                let transformed_value = self.transform_value(unescape(value)?.as_str());
+                transformed_attributes.push_str(&rest[..rest.len() - value_rest.len()]);
+                transformed_attributes.push(quote);
+                transformed_attributes.push_str(escape(transformed_value.as_str()).as_str());
+                transformed_attributes.push(quote);
+            } else {
+                transformed_attributes.push_str(&rest[..attribute_len]);
+            }
+
+            rest = &rest[attribute_len..];
+        }
+    }
+
+    /// rewrite the selected texts and attributes of `xml` - None when it is not a well-formed document
+    fn transform_xml(&self, xml: &str) -> Option<String> {
+        let mut transformed_xml = String::with_capacity(xml.len());
+        // names of the open elements
+        let mut path: Vec<&str> = vec![];
+        let mut has_root = false;
+        let mut idx = 0;
+
+        while idx < xml.len() {
+            let rest = &xml[idx..];
+
+            if !rest.starts_with('<') {
+                let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+
+                if path.is_empty() {
+                    // nothing but whitespaces around the root element
+                    if !text.trim().is_empty() {
+                        return None;
+                    }
+                    transformed_xml.push_str(text);
+                } else if self.is_selected(path.as_slice(), None) {
+                    transformed_xml.push_str(self.transform_text(text)?.as_str());
+                } else {
+                    transformed_xml.push_str(text);
+                }
+
+                idx += text.len();
+                continue;
+            }
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let len = "<!--".len() + comment.find("-->")? + "-->".len();
+                transformed_xml.push_str(&rest[..len]);
+                idx += len;
+                continue;
+            }
+
+            if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let cdata = &cdata[..cdata.find("]]>")?];
+                let len = "<![CDATA[".len() + cdata.len() + "]]>".len();
+
+                if path.is_empty() {
+                    return None;
+                }
+
+                // the transformed value is written as an escaped text
+                match self.is_selected(path.as_slice(), None) {
+                    true => transformed_xml
+                        .push_str(escape(self.transform_value(cdata).as_str()).as_str()),
+                    false => transformed_xml.push_str(&rest[..len]),
+                }
+
+                idx += len;
+                continue;
+            }
+
+            if rest.starts_with("<?") {
+                let len = rest.find("?>")? + "?>".len();
+                transformed_xml.push_str(&rest[..len]);
+                idx += len;
+                continue;
+            }
+
+            if rest.starts_with("<!") {
+                // E.g. `<!DOCTYPE note [<!ENTITY ...>]>` - only before the root element
+                if has_root {
+                    return None;
+                }
+
+                let len = declaration_end_idx(rest)? + 1;
+                transformed_xml.push_str(&rest[..len]);
+                idx += len;
+                continue;
+            }
+
+            if let Some(end_tag) = rest.strip_prefix("</") {
+                let end_idx = end_tag.find('>')?;
+                if path.pop()? != end_tag[..end_idx].trim_end() {
+                    return None;
+                }
+
+                let len = "</".len() + end_idx + 1;
+                transformed_xml.push_str(&rest[..len]);
+                idx += len;
+                continue;
+            }
+
+            // start tag - a second root element is not allowed
+            if path.is_empty() && has_root {
+                return None;
+            }
+            has_root = true;
+
+            let end_idx = tag_end_idx(rest)?;
+            let (tag, is_empty_element) = match rest[1..end_idx].strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (&rest[1..end_idx], false),
+            };
+
+            let name_len = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            let name = &tag[..name_len];
+            if !is_name(name) {
+                return None;
+            }
+
+            path.push(name);
+            transformed_xml.push('<');
+            transformed_xml.push_str(name);
+            transformed_xml.push_str(
+                self.transform_attributes(path.as_slice(), &tag[name_len..])?
+                    .as_str(),
+            );
+
+            if is_empty_element {
+                let _ = path.pop();
+                transformed_xml.push('/');
+            }
+
+            transformed_xml.push('>');
+            idx += end_idx + 1;
+        }
+
+        match has_root && path.is_empty() {
+            true => Some(transformed_xml),
+            false => None,
+        }
+    }
+}
+
+impl Default for XmlTransformer {
+    fn default() -> Self {
+        XmlTransformer {
+            database_name: String::default(),
+            table_name: String::default(),
+            column_name: String::default(),
+            selectors: vec![],
+            transformer: Box::new(TransientTransformer::default()),
+        }
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+}
+
+/// index of the `>` closing the tag - the `>` of the quoted attribute values are skipped
+fn tag_end_idx(tag: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (idx, c) in tag.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(idx),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// index of the `>` closing a declaration - the `>` of its internal subset (`[...]`) are skipped
+fn declaration_end_idx(declaration: &str) -> Option<usize> {
+    let mut depth = 0usize;
+
+    for (idx, c) in declaration.char_indices().skip(1) {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            '>' if depth == 0 => return Some(idx),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// replace the predefined entities and the character references - None for an unknown entity
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(idx) = rest.find('&') {
+        unescaped.push_str(&rest[..idx]);
+
+        let end_idx = idx + rest[idx..].find(';')?;
+        let entity = &rest[idx + 1..end_idx];
+
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse::<u32>().ok()?,
+                };
+                char::from_u32(code)?
+            }
+        };
+
+        unescaped.push(c);
+        rest = &rest[end_idx + 1..];
+    }
+
+    unescaped.push_str(rest);
+    Some(unescaped)
+}
+
+impl Transformer for XmlTransformer {
+    fn id(&self) -> &str {
+        "xml"
+    }
+
+    fn description(&self) -> &str {
+        "Apply the `transformer` to the elements and attributes of an XML document matched by the `selectors` - the rest of the document is kept, an invalid document is kept as is (string only). [<name>John</name>]->[<name>Paul</name>]"
+    }
+
+    fn database_name(&self) -> &str {
+        self.database_name.as_str()
+    }
+
+    fn table_name(&self) -> &str {
+        self.table_name.as_str()
+    }
+
+    fn column_name(&self) -> &str {
+        self.column_name.as_str()
+    }
+
+    fn expected_type(&self) -> ColumnType {
+        ColumnType::Text
+    }
+
+    fn option_schema(&self) -> Vec<TransformerOption> {
+        vec![
+            TransformerOption::new(
+                "selectors",
+                TransformerOptionKind::StringList,
+                true,
+                "paths of the elements and attributes to transform - E.g. `/customer/name`, `//email` or `/customer/@ssn`",
+            ),
+            TransformerOption::new(
+                "transformer",
+                TransformerOptionKind::String,
+                true,
+                "name of the transformer applied to the selected values - a transformer without options",
+            ),
+        ]
+    }
+
+    fn set_secret(&mut self, secret: &str) {
+        // the selected values are pseudonymized by the nested transformer
+        self.transformer.set_secret(secret);
+    }
+
+    fn transform(&self, column: Column) -> Column {
+        match column {
+            Column::StringValue(column_name, value) => match self.transform_xml(value.as_str()) {
+                Some(transformed_value) => Column::StringValue(column_name, transformed_value),
+                None => Column::StringValue(column_name, value),
+            },
+            column => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::error::ReplibyteError;
+    use crate::transformer::map::{MapTransformer, MapTransformerOptions};
+    use crate::transformer::username::UsernameTransformer;
+    use crate::{transformer::Transformer, types::Column};
+
+    use super::{XmlTransformer, XmlTransformerOptions};
+
+    fn xml_transformer(selectors: &[&str]) -> XmlTransformer {
+        let mapping = HashMap::from([
+            ("John".to_string(), "Paul".to_string()),
+            ("123-45-6789".to_string(), "000-00-0000".to_string()),
+            ("a&b".to_string(), "<c>".to_string()),
+        ]);
+
+        XmlTransformer::try_new(
+            "public",
+            "customers",
+            "profile",
+            XmlTransformerOptions {
+                selectors: selectors.iter().map(|s| s.to_string()).collect(),
+                transformer: "map".to_string(),
+            },
+            Box::new(MapTransformer::new(
+                "public",
+                "customers",
+                "profile",
+                MapTransformerOptions {
+                    mapping,
+                    default: None,
+                },
+            )),
+        )
+        .unwrap()
+    }
+
+    fn transform(transformer: &XmlTransformer, value: &str) -> String {
+        let column = Column::StringValue("profile".to_string(), value.to_string());
+        let transformed_column = transformer.transform(column);
+        transformed_column.string_value().unwrap().to_string()
+    }
+
+    #[test]
+    fn element_text_selection() {
+        let transformer = xml_transformer(&["/customer/name"]);
+        assert_eq!(
+            transform(
+                &transformer,
+                "<?xml version=\"1.0\"?>\n<customer id=\"1\">\n  <name> John </name>\n  <note>John</note>\n</customer>\n"
+            ),
+            "<?xml version=\"1.0\"?>\n<customer id=\"1\">\n  <name> Paul </name>\n  <note>John</note>\n</customer>\n"
+        );
+
+        // at any depth
+        let transformer = xml_transformer(&["//name"]);
+        assert_eq!(
+            transform(
+                &transformer,
+                "<customers><customer><name>John</name></customer><name><![CDATA[John]]></name></customers>"
+            ),
+            "<customers><customer><name>Paul</name></customer><name>Paul</name></customers>"
+        );
+
+        // the entities are unescaped then escaped again
+        let transformer = xml_transformer(&["/customer/*"]);
+        assert_eq!(
+            transform(
+                &transformer,
+                "<customer><note>a&amp;b</note><!-- John --></customer>"
+            ),
+            "<customer><note>&lt;c&gt;</note><!-- John --></customer>"
+        );
+    }
+
+    #[test]
+    fn attribute_selection() {
+        let transformer = xml_transformer(&["/customer/@ssn"]);
+        assert_eq!(
+            transform(
+                &transformer,
+                "<customer ssn=\"123-45-6789\" name='John'><child ssn=\"123-45-6789\"/></customer>"
+            ),
+            "<customer ssn=\"000-00-0000\" name='John'><child ssn=\"123-45-6789\"/></customer>"
+        );
+
+        let transformer = xml_transformer(&["//@name"]);
+        assert_eq!(
+            transform(
+                &transformer,
+                "<customer name = 'John'><child name=\"a&amp;b\">John</child></customer>"
+            ),
+            "<customer name = 'Paul'><child name=\"&lt;c&gt;\">John</child></customer>"
+        );
+    }
+
+    #[test]
+    fn nested_transformer_uses_the_secret() {
+        let keyed_transformer = |secret: &str| {
+            let mut transformer = XmlTransformer::try_new(
+                "public",
+                "customers",
+                "profile",
+                XmlTransformerOptions {
+                    selectors: vec!["/customer/username".to_string()],
+                    transformer: "username".to_string(),
+                },
+                Box::new(UsernameTransformer::default()),
+            )
+            .unwrap();
+            transformer.set_secret(secret);
+            transformer
+        };
+
+        let xml = "<customer><username>john_doe_1984</username></customer>";
+        let transformed_xml = transform(&keyed_transformer("secret"), xml);
+
+        assert_ne!(transformed_xml, xml);
+        assert_eq!(
+            transform(&keyed_transformer("secret"), xml),
+            transformed_xml
+        );
+        assert_ne!(
+            transform(&keyed_transformer("another secret"), xml),
+            transformed_xml
+        );
+    }
+
+    #[test]
+    fn invalid_xml_value() {
+        let transformer = xml_transformer(&["//name"]);
+
+        for value in [
+            "John",
+            "",
+            "<name>John",
+            "<name>John</nom>",
+            "<name>John</name><name>John</name>",
+            "<name>John &unknown;</name>",
+            "<customer name=John><name>John</name></customer>",
+        ] {
+            assert_eq!(transform(&transformer, value), value);
+        }
+    }
+
+    #[test]
+    fn null_value() {
+        let transformer = xml_transformer(&["//name"]);
+        let column = Column::None("profile".to_string());
+        let transformed_column = transformer.transform(column);
+        assert!(transformed_column.is_null());
+    }
+
+    #[test]
+    fn invalid_selectors() {
+        for selector in [
+            "name",
+            "/",
+            "/customer/",
+            "/@ssn",
+            "/customer/@ssn/name",
+            "/customer[1]",
+        ] {
+            let result = XmlTransformer::try_new(
+                "public",
+                "customers",
+                "profile",
+                XmlTransformerOptions {
+                    selectors: vec![selector.to_string()],
+                    transformer: "map".to_string(),
+                },
+                Box::new(MapTransformer::default()),
+            );
+
+            assert!(
+                matches!(result, Err(ReplibyteError::Transform(_))),
+                "{}",
+                selector
+            );
+        }
+    }
+}
@@ -29,12 +29,15 @@ pub struct InsertIntoQuery {
     pub columns: Vec<Column>,
 }
 
+/// the value of a column - NULL is `None` and an empty string is a `StringValue`, the transformers keep them distinct
 #[derive(Clone)]
 pub enum Column {
     NumberValue(String, i128),
     FloatNumberValue(String, f64),
     StringValue(String, String),
     CharValue(String, char),
+    BooleanValue(String, bool),
+    /// NULL
     None(String),
 }
 
@@ -45,10 +48,20 @@ impl Column {
             Column::FloatNumberValue(name, _) => name.as_str(),
             Column::StringValue(name, _) => name.as_str(),
             Column::CharValue(name, _) => name.as_str(),
+            Column::BooleanValue(name, _) => name.as_str(),
             Column::None(name) => name.as_str(),
         }
     }
 
+    pub fn is_null(&self) -> bool {
+        matches!(self, Column::None(_))
+    }
+
+    /// an empty string is not NULL - E.g. it satisfies a NOT NULL constraint
+    pub fn is_empty_string(&self) -> bool {
+        matches!(self, Column::StringValue(_, value) if value.is_empty())
+    }
+
     pub fn number_value(&self) -> Option<&i128> {
         match self {
             Column::NumberValue(_, value) => Some(value),
@@ -76,4 +89,11 @@ impl Column {
             _ => None,
         }
     }
+
+    pub fn boolean_value(&self) -> Option<&bool> {
+        match self {
+            Column::BooleanValue(_, value) => Some(value),
+            _ => None,
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use prettytable::{format, Table};
 use std::io::{Error, ErrorKind};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use which::which;
 
 pub fn epoch_millis() -> u128 {
@@ -10,6 +10,27 @@ pub fn epoch_millis() -> u128 {
         .as_millis()
 }
 
+/// the UTC year, month and day of a timestamp in milliseconds - E.g. `(2022, 3, 19)` for `1647706359405`
+pub fn utc_date(epoch_millis: u128) -> (i64, u32, u32) {
+    // days to civil date - http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (epoch_millis / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month as u32, day as u32)
+}
+
 pub fn table() -> Table {
     // Create the table
     let mut table = Table::new();
@@ -31,6 +52,81 @@ pub fn to_human_readable_unit(bytes: usize) -> String {
     }
 }
 
+/// converts a size with a unit (E.g. `50GB`, `100 MB`, `1024`) into Bytes
+pub fn from_human_readable_unit(size: &str) -> Result<usize, Error> {
+    let size = size.trim();
+    let unit_idx = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+
+    let (value, unit) = size.split_at(unit_idx);
+
+    let value = value.parse::<usize>().map_err(|_| {
+        Error::new(
+            ErrorKind::Other,
+            format!("invalid size '{}' - E.g. 500MB or 50GB", size),
+        )
+    })?;
+
+    let multiplier: usize = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        unit => {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("invalid size unit '{}' - use B, KB, MB, GB or TB", unit),
+            ))
+        }
+    };
+
+    Ok(value * multiplier)
+}
+
+/// converts a duration with a unit (E.g. `30s`, `15m`, `6h`, `1d`) into a Duration
+pub fn from_human_readable_duration(duration: &str) -> Result<Duration, Error> {
+    let duration = duration.trim();
+    let unit_idx = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+
+    let (value, unit) = duration.split_at(unit_idx);
+
+    let value = value.parse::<u64>().map_err(|_| {
+        Error::new(
+            ErrorKind::Other,
+            format!("invalid duration '{}' - E.g. 30m or 6h", duration),
+        )
+    })?;
+
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        unit => {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("invalid duration unit '{}' - use s, m, h or d", unit),
+            ))
+        }
+    };
+
+    if value == 0 {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "invalid duration '{}' - it must be greater than 0",
+                duration
+            ),
+        ));
+    }
+
+    Ok(Duration::from_secs(value * multiplier))
+}
+
 /// check for binary presence in PATH
 pub fn binary_exists(binary_name: &str) -> Result<(), Error> {
     let _ = which(binary_name).map_err(|_| {
@@ -42,3 +138,56 @@ pub fn binary_exists(binary_name: &str) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::utils::{from_human_readable_duration, from_human_readable_unit, utc_date};
+
+    #[test]
+    fn test_from_human_readable_unit() {
+        assert_eq!(from_human_readable_unit("1024").unwrap(), 1024);
+        assert_eq!(from_human_readable_unit("10B").unwrap(), 10);
+        assert_eq!(from_human_readable_unit("500MB").unwrap(), 500_000_000);
+        assert_eq!(from_human_readable_unit("50 GB").unwrap(), 50_000_000_000);
+        assert_eq!(from_human_readable_unit("2tb").unwrap(), 2_000_000_000_000);
+        assert!(from_human_readable_unit("").is_err());
+        assert!(from_human_readable_unit("GB").is_err());
+        assert!(from_human_readable_unit("50 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_from_human_readable_duration() {
+        assert_eq!(
+            from_human_readable_duration("30s").unwrap(),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            from_human_readable_duration("15m").unwrap(),
+            Duration::from_secs(900)
+        );
+        assert_eq!(
+            from_human_readable_duration("6H").unwrap(),
+            Duration::from_secs(21_600)
+        );
+        assert_eq!(
+            from_human_readable_duration("1 d").unwrap(),
+            Duration::from_secs(86_400)
+        );
+        assert!(from_human_readable_duration("").is_err());
+        assert!(from_human_readable_duration("6").is_err());
+        assert!(from_human_readable_duration("0h").is_err());
+        assert!(from_human_readable_duration("2 weeks").is_err());
+    }
+
+    #[test]
+    fn test_utc_date() {
+        assert_eq!(utc_date(0), (1970, 1, 1));
+        assert_eq!(utc_date(1647706359405), (2022, 3, 19));
+        assert_eq!(utc_date(1705276800000), (2024, 1, 15));
+        // leap days
+        assert_eq!(utc_date(951782400000), (2000, 2, 29));
+        assert_eq!(utc_date(1709251199999), (2024, 2, 29));
+    }
+}